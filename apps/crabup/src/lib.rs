@@ -61,6 +61,11 @@ pub enum Command {
         #[command(subcommand)]
         action: ServiceAction,
     },
+    /// Discord gateway service commands.
+    Discord {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
     /// Search service commands.
     Search {
         #[command(subcommand)]
@@ -138,6 +143,7 @@ impl Cli {
             Command::Daemon { action } => action.run(&registry::DAEMON),
             Command::Telegram { action } => action.run(&registry::TELEGRAM),
             Command::Wechat { action } => action.run(&registry::WECHAT),
+            Command::Discord { action } => action.run(&registry::DISCORD),
             Command::Search { action } => action.run(&registry::SEARCH),
             Command::Outlook { action } => action.run(&registry::OUTLOOK),
         }
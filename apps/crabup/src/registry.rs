@@ -42,6 +42,13 @@ pub const WECHAT: Entry = Entry {
     description: "WeChat gateway for Crabtalk",
 };
 
+pub const DISCORD: Entry = Entry {
+    short: "discord",
+    krate: "crabtalk-discord",
+    label: Some("ai.crabtalk.discord"),
+    description: "Discord gateway for Crabtalk",
+};
+
 pub const SEARCH: Entry = Entry {
     short: "search",
     krate: "crabtalk-search",
@@ -63,7 +70,9 @@ pub const CRON: Entry = Entry {
     description: "Cron scheduler for Crabtalk",
 };
 
-const TABLE: &[&Entry] = &[&DAEMON, &TUI, &TELEGRAM, &WECHAT, &SEARCH, &OUTLOOK, &CRON];
+const TABLE: &[&Entry] = &[
+    &DAEMON, &TUI, &TELEGRAM, &WECHAT, &DISCORD, &SEARCH, &OUTLOOK, &CRON,
+];
 
 impl Entry {
     /// Look up a table entry by short name.
@@ -3,12 +3,15 @@
 use anyhow::Result;
 use clap::Parser;
 use crabtalkd::Cli;
+use tracing_subscriber::{
+    Layer, filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    if cli.foreground && cli.verbose > 0 {
+    let level = if cli.foreground && cli.verbose > 0 {
         let level = match cli.verbose {
             1 => "crabtalk=info",
             2 => "crabtalk=debug",
@@ -16,22 +19,25 @@ async fn main() -> Result<()> {
         };
         // SAFETY: called in main before spawning any threads.
         unsafe { std::env::set_var("RUST_LOG", level) };
-        let level = parse_level(level);
-        tracing_subscriber::fmt()
-            .with_max_level(level)
+        Some(parse_level(level))
+    } else {
+        std::env::var("RUST_LOG").ok().map(|val| parse_level(&val))
+    };
+    let fmt = level.map(|level| {
+        tracing_subscriber::fmt::layer()
             .without_time()
             .with_target(false)
-            .init();
-    } else if let Ok(val) = std::env::var("RUST_LOG") {
-        let level = parse_level(&val);
-        tracing_subscriber::fmt()
-            .with_max_level(level)
-            .without_time()
-            .with_target(false)
-            .init();
-    }
+            .with_filter(LevelFilter::from_level(level))
+    });
+    let registry = tracing_subscriber::registry().with(fmt);
+    #[cfg(feature = "otlp")]
+    let registry = registry.with(crabtalkd::otlp::layer()?.with_filter(LevelFilter::INFO));
+    registry.init();
 
-    cli.run().await
+    let result = cli.run().await;
+    #[cfg(feature = "otlp")]
+    crabtalkd::otlp::shutdown();
+    result
 }
 
 /// Extract the most specific level from a filter string like "crabtalk=debug".
@@ -15,6 +15,18 @@ pub async fn start() -> Result<()> {
     std::fs::write(&*TCP_PORT_FILE, tcp_port.to_string())?;
     tracing::info!(tcp_port, "TCP transport listening");
 
+    let openai_join = crabtalk::setup_openai(
+        handle.daemon.clone(),
+        &handle.config.openai,
+        &handle.shutdown_tx,
+    )
+    .await?;
+    let eviction_join = crabtalk::setup_eviction(
+        handle.daemon.clone(),
+        &handle.config.sessions,
+        &handle.shutdown_tx,
+    );
+
     handle.wait_until_ready().await?;
     tracing::info!("daemon ready");
 
@@ -30,6 +42,12 @@ pub async fn start() -> Result<()> {
         tracing::info!(path = %socket_path.display(), "removed socket");
     }
     let _ = tokio::time::timeout(timeout, tcp_join).await;
+    if let Some(join) = openai_join {
+        let _ = tokio::time::timeout(timeout, join).await;
+    }
+    if let Some(join) = eviction_join {
+        let _ = tokio::time::timeout(timeout, join).await;
+    }
     let _ = std::fs::remove_file(&*TCP_PORT_FILE);
     Ok(())
 }
@@ -12,6 +12,8 @@ use wcore::protocol::{
 
 pub mod attach;
 pub mod foreground;
+#[cfg(feature = "otlp")]
+pub mod otlp;
 
 /// Crabtalk — AI agent platform.
 #[derive(Parser, Debug)]
@@ -37,7 +39,12 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Run the daemon in the foreground (what launchd/systemd invokes).
-    Run,
+    Run {
+        /// Validate config, LLM endpoint, memory, skills, MCP servers
+        /// and agents, print a report, and exit without serving.
+        #[arg(long)]
+        check: bool,
+    },
     /// Scaffold config and prompt for LLM endpoint on first run.
     Setup,
     /// Hot-reload daemon config.
@@ -71,7 +78,8 @@ impl Cli {
         };
 
         match command {
-            Command::Run => foreground::start().await,
+            Command::Run { check: true } => preflight().await,
+            Command::Run { check: false } => foreground::start().await,
             Command::Setup => ensure_config(),
             Command::Reload => {
                 let mut conn = connect(self.tcp).await?;
@@ -135,10 +143,8 @@ impl Cli {
                         plugin_event::Event::Warning(w) => {
                             eprintln!("  warning: {}", w.message);
                         }
-                        plugin_event::Event::Done(d) => {
-                            if !d.error.is_empty() {
-                                anyhow::bail!("{}", d.error);
-                            }
+                        plugin_event::Event::Done(d) if !d.error.is_empty() => {
+                            anyhow::bail!("{}", d.error);
                         }
                         _ => {}
                     }
@@ -161,6 +167,17 @@ fn ensure_config() -> Result<()> {
     Ok(())
 }
 
+/// Run the preflight check and exit non-zero if any probe failed.
+async fn preflight() -> Result<()> {
+    let report = crabtalk::check(&wcore::paths::CONFIG_DIR).await;
+    print!("{report}");
+    let failed = report.items.iter().filter(|i| i.result.is_err()).count();
+    if failed > 0 {
+        anyhow::bail!("{failed} check(s) failed");
+    }
+    Ok(())
+}
+
 /// Stream daemon events to stdout, buffering text/thinking deltas.
 async fn stream_events(mut conn: Transport) -> Result<()> {
     use wcore::protocol::message::{
@@ -0,0 +1,44 @@
+//! OTLP trace export, behind the `otlp` feature.
+//!
+//! Off unless `OTEL_EXPORTER_OTLP_ENDPOINT` is set. The exporter reads
+//! the standard `OTEL_EXPORTER_OTLP_*` variables (endpoint, headers,
+//! timeout) itself and ships spans over HTTP/protobuf.
+
+use anyhow::Result;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
+use std::sync::OnceLock;
+use tracing::Subscriber;
+use tracing_subscriber::{Layer, registry::LookupSpan};
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// A layer exporting every span to the configured collector, or `None`
+/// when no endpoint is configured.
+pub fn layer<S>() -> Result<Option<impl Layer<S>>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_none() {
+        return Ok(None);
+    }
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name("crabtalkd").build())
+        .build();
+    let tracer = provider.tracer("crabtalkd");
+    let _ = PROVIDER.set(provider);
+    Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer)))
+}
+
+/// Flush spans still queued for export. Call before the process exits.
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get()
+        && let Err(e) = provider.shutdown()
+    {
+        tracing::warn!("otlp shutdown: {e}");
+    }
+}
@@ -0,0 +1,206 @@
+//! Discord REST API client and the payload types shared with the gateway.
+
+use anyhow::{Context, Result, bail};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Base URL of the versioned REST API.
+const API_BASE: &str = "https://discord.com/api/v10";
+
+/// Retries after a `429 Too Many Requests` before a send gives up.
+const MAX_RETRIES: u32 = 3;
+
+// ── Payload types ───────────────────────────────────────────────────
+
+/// A Discord user. IDs are snowflakes, sent as strings.
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    pub id: String,
+    #[serde(default)]
+    pub username: String,
+    /// Display name, when the user set one.
+    #[serde(default)]
+    pub global_name: Option<String>,
+    #[serde(default)]
+    pub bot: bool,
+}
+
+impl User {
+    /// The name shown in clients: the display name, else the username.
+    pub fn display_name(&self) -> &str {
+        self.global_name.as_deref().unwrap_or(&self.username)
+    }
+}
+
+/// A file attached to a message.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    pub filename: String,
+    /// CDN URL the file can be fetched from.
+    pub url: String,
+    /// MIME type, when Discord could tell.
+    #[serde(default)]
+    pub content_type: Option<String>,
+}
+
+/// The message another message replies to.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessageReference {
+    #[serde(default)]
+    pub message_id: Option<String>,
+}
+
+/// A message, as carried by `MESSAGE_CREATE` and returned by sends.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Message {
+    pub id: String,
+    pub channel_id: String,
+    /// Set for messages in a server; absent in DMs.
+    #[serde(default)]
+    pub guild_id: Option<String>,
+    pub author: User,
+    #[serde(default)]
+    pub content: String,
+    /// Users mentioned in the message.
+    #[serde(default)]
+    pub mentions: Vec<User>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub message_reference: Option<MessageReference>,
+    /// The message replied to, when Discord resolved it.
+    #[serde(default)]
+    pub referenced_message: Option<Box<Message>>,
+}
+
+#[derive(Deserialize)]
+struct GatewayBot {
+    url: String,
+}
+
+#[derive(Serialize)]
+struct CreateMessage<'a> {
+    content: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_reference: Option<Reference<'a>>,
+    allowed_mentions: AllowedMentions,
+}
+
+#[derive(Serialize)]
+struct Reference<'a> {
+    message_id: &'a str,
+    fail_if_not_exists: bool,
+}
+
+/// Replies never ping: agent output could otherwise mention
+/// `@everyone` or any user it names.
+#[derive(Serialize)]
+struct AllowedMentions {
+    parse: [&'static str; 0],
+    replied_user: bool,
+}
+
+#[derive(Deserialize)]
+struct RateLimited {
+    retry_after: f64,
+}
+
+// ── Requests ────────────────────────────────────────────────────────
+
+fn authorization(token: &str) -> String {
+    format!("Bot {token}")
+}
+
+/// The bot's own user.
+pub async fn current_user(client: &Client, token: &str) -> Result<User> {
+    let resp = client
+        .get(format!("{API_BASE}/users/@me"))
+        .header("Authorization", authorization(token))
+        .send()
+        .await
+        .context("users/@me request failed")?;
+    if !resp.status().is_success() {
+        bail!("users/@me failed: {}", resp.status());
+    }
+    resp.json().await.context("invalid users/@me response")
+}
+
+/// The WebSocket URL to open the gateway on.
+pub async fn gateway_url(client: &Client, token: &str) -> Result<String> {
+    let resp = client
+        .get(format!("{API_BASE}/gateway/bot"))
+        .header("Authorization", authorization(token))
+        .send()
+        .await
+        .context("gateway/bot request failed")?;
+    if !resp.status().is_success() {
+        bail!("gateway/bot failed: {}", resp.status());
+    }
+    let gateway: GatewayBot = resp.json().await.context("invalid gateway/bot response")?;
+    Ok(gateway.url)
+}
+
+/// Post `content` to `channel_id`, as a reply to `reply_to` when given.
+/// A rate-limited send waits as long as Discord asks and is retried.
+pub async fn send_message(
+    client: &Client,
+    token: &str,
+    channel_id: &str,
+    content: &str,
+    reply_to: Option<&str>,
+) -> Result<Message> {
+    let body = CreateMessage {
+        content,
+        message_reference: reply_to.map(|message_id| Reference {
+            message_id,
+            fail_if_not_exists: false,
+        }),
+        allowed_mentions: AllowedMentions {
+            parse: [],
+            replied_user: false,
+        },
+    };
+    let url = format!("{API_BASE}/channels/{channel_id}/messages");
+    let mut retries = 0;
+    loop {
+        let resp = client
+            .post(&url)
+            .header("Authorization", authorization(token))
+            .json(&body)
+            .send()
+            .await
+            .context("create message request failed")?;
+        let status = resp.status();
+        if status == StatusCode::TOO_MANY_REQUESTS && retries < MAX_RETRIES {
+            let wait = resp
+                .json::<RateLimited>()
+                .await
+                .map_or(1.0, |r| r.retry_after);
+            tracing::debug!(channel_id, wait, "rate limited, retrying send");
+            tokio::time::sleep(Duration::from_secs_f64(wait.max(0.0))).await;
+            retries += 1;
+            continue;
+        }
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            bail!("create message failed: {status} {text}");
+        }
+        return resp.json().await.context("invalid create message response");
+    }
+}
+
+/// Show "typing…" in `channel_id` for about ten seconds.
+pub async fn trigger_typing(client: &Client, token: &str, channel_id: &str) -> Result<()> {
+    let resp = client
+        .post(format!("{API_BASE}/channels/{channel_id}/typing"))
+        .header("Authorization", authorization(token))
+        .send()
+        .await
+        .context("typing request failed")?;
+    if !resp.status().is_success() {
+        bail!("typing failed: {}", resp.status());
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Discord bot configuration.
+
+use crate::{Channel, MAX_MESSAGE_LEN};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Discord bot configuration.
+///
+/// Loaded from `~/.crabtalk/config/discord.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscordConfig {
+    /// Bot token from the Discord developer portal.
+    pub token: String,
+    /// Optional whitelist of Discord user IDs.
+    ///
+    /// When non-empty only messages from these users are processed;
+    /// everyone else is silently ignored. When empty or omitted the
+    /// bot responds to all users.
+    #[serde(default)]
+    pub allowed_users: Vec<i64>,
+    /// In servers, only answer messages that mention or reply to the
+    /// bot. DMs are always answered.
+    #[serde(default = "default_require_mention")]
+    pub require_mention: bool,
+    /// Longest reply message in characters; longer replies are split.
+    /// Capped at Discord's own limit of 2000, which is the default.
+    #[serde(default = "default_max_message_len")]
+    pub max_message_len: usize,
+}
+
+impl Channel for DiscordConfig {
+    fn max_message_len(&self) -> Option<usize> {
+        Some(self.max_message_len.clamp(1, MAX_MESSAGE_LEN))
+    }
+}
+
+fn default_require_mention() -> bool {
+    true
+}
+
+fn default_max_message_len() -> usize {
+    MAX_MESSAGE_LEN
+}
+
+impl DiscordConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("invalid TOML in {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self).context("failed to serialize DiscordConfig")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
@@ -0,0 +1,264 @@
+//! Discord Gateway client — receives events over a WebSocket.
+//!
+//! The gateway says hello with a heartbeat interval, the client
+//! identifies (or resumes a dropped session), and events then arrive as
+//! dispatches numbered by a sequence the heartbeat echoes back.
+
+use crate::{GatewayMessage, api};
+use anyhow::{Context, Result, bail};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::{self, protocol::CloseFrame};
+
+/// Events the bot subscribes to: server messages, DMs and message
+/// content. Message content is privileged and must be enabled for the
+/// bot in the developer portal.
+pub const INTENTS: u64 = (1 << 9) | (1 << 12) | (1 << 15);
+
+/// Query selecting the gateway version and encoding.
+const GATEWAY_QUERY: &str = "/?v=10&encoding=json";
+
+/// Longest wait between reconnect attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+const OP_DISPATCH: u8 = 0;
+const OP_HEARTBEAT: u8 = 1;
+const OP_IDENTIFY: u8 = 2;
+const OP_RESUME: u8 = 6;
+const OP_RECONNECT: u8 = 7;
+const OP_INVALID_SESSION: u8 = 9;
+const OP_HELLO: u8 = 10;
+const OP_HEARTBEAT_ACK: u8 = 11;
+
+#[derive(Deserialize)]
+struct Payload {
+    op: u8,
+    #[serde(default)]
+    d: Value,
+    #[serde(default)]
+    s: Option<u64>,
+    #[serde(default)]
+    t: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Ready {
+    session_id: String,
+    resume_gateway_url: String,
+    user: api::User,
+}
+
+/// State kept across connections so a dropped one can resume.
+#[derive(Default)]
+struct Session {
+    id: Option<String>,
+    resume_url: Option<String>,
+    seq: Option<u64>,
+}
+
+/// How a connection ended.
+enum Closed {
+    /// Reconnect and resume the session.
+    Resume,
+    /// Reconnect with a fresh session.
+    Identify,
+    /// Stop for good: the receiver is gone or the gateway refused us.
+    Stop,
+}
+
+/// Gateway loop: receives Discord messages and forwards them as
+/// [`GatewayMessage`]s, reconnecting with backoff whenever the
+/// connection drops.
+///
+/// `me` is the bot's own user, used to tell whether server messages are
+/// addressed to it. When unknown it is taken from the `READY` event.
+pub async fn gateway_loop(
+    http: reqwest::Client,
+    token: String,
+    mut me: Option<api::User>,
+    tx: mpsc::UnboundedSender<GatewayMessage>,
+) {
+    let mut session = Session::default();
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        let url = match session.resume_url.clone() {
+            Some(url) if session.id.is_some() => url,
+            _ => match api::gateway_url(&http, &token).await {
+                Ok(url) => url,
+                Err(e) => {
+                    tracing::error!("failed to get gateway url: {e:#}");
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+            },
+        };
+        match connect(&url, &token, &mut session, &mut me, &tx).await {
+            Ok(Closed::Stop) => return,
+            Ok(Closed::Resume) => {
+                tracing::info!("gateway connection closed, resuming");
+                backoff = Duration::from_secs(1);
+            }
+            Ok(Closed::Identify) => {
+                tracing::info!("gateway session invalidated, identifying again");
+                session = Session::default();
+                tokio::time::sleep(backoff).await;
+            }
+            Err(e) => {
+                tracing::error!("gateway connection failed: {e:#}");
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+/// Run one gateway connection until it closes.
+async fn connect(
+    url: &str,
+    token: &str,
+    session: &mut Session,
+    me: &mut Option<api::User>,
+    tx: &mpsc::UnboundedSender<GatewayMessage>,
+) -> Result<Closed> {
+    let (ws, _) = tokio_tungstenite::connect_async(format!("{url}{GATEWAY_QUERY}"))
+        .await
+        .context("websocket connect failed")?;
+    let (mut sink, mut stream) = ws.split();
+
+    let hello = match stream.next().await {
+        Some(Ok(tungstenite::Message::Text(text))) => serde_json::from_str::<Payload>(&text)?,
+        Some(Ok(other)) => bail!("expected hello, got {other:?}"),
+        Some(Err(e)) => return Err(e.into()),
+        None => bail!("gateway closed before hello"),
+    };
+    if hello.op != OP_HELLO {
+        bail!("expected hello, got op {}", hello.op);
+    }
+    let interval = hello.d["heartbeat_interval"]
+        .as_u64()
+        .map(Duration::from_millis)
+        .context("hello without heartbeat_interval")?;
+
+    let greeting = match (&session.id, session.seq) {
+        (Some(session_id), Some(seq)) => json!({
+            "op": OP_RESUME,
+            "d": { "token": token, "session_id": session_id, "seq": seq },
+        }),
+        _ => json!({
+            "op": OP_IDENTIFY,
+            "d": {
+                "token": token,
+                "intents": INTENTS,
+                "properties": { "os": std::env::consts::OS, "browser": "crabtalk", "device": "crabtalk" },
+            },
+        }),
+    };
+    sink.send(text(&greeting)).await?;
+
+    let mut heartbeat = tokio::time::interval(interval);
+    heartbeat.reset();
+    let mut acked = true;
+    loop {
+        tokio::select! {
+            _ = heartbeat.tick() => {
+                // No ack since the last beat: the connection is dead.
+                if !acked {
+                    return Ok(Closed::Resume);
+                }
+                acked = false;
+                sink.send(text(&json!({ "op": OP_HEARTBEAT, "d": session.seq }))).await?;
+            }
+            frame = stream.next() => {
+                let payload = match frame {
+                    Some(Ok(tungstenite::Message::Text(text))) => {
+                        serde_json::from_str::<Payload>(&text)?
+                    }
+                    Some(Ok(tungstenite::Message::Close(frame))) => return Ok(closed_by(frame)),
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e.into()),
+                    None => return Ok(Closed::Resume),
+                };
+                if payload.s.is_some() {
+                    session.seq = payload.s;
+                }
+                match payload.op {
+                    OP_DISPATCH if !dispatch(&payload, session, me, tx) => {
+                        return Ok(Closed::Stop);
+                    }
+                    OP_HEARTBEAT => {
+                        sink.send(text(&json!({ "op": OP_HEARTBEAT, "d": session.seq }))).await?;
+                    }
+                    OP_HEARTBEAT_ACK => acked = true,
+                    OP_RECONNECT => return Ok(Closed::Resume),
+                    OP_INVALID_SESSION => {
+                        return Ok(if payload.d.as_bool() == Some(true) {
+                            Closed::Resume
+                        } else {
+                            Closed::Identify
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Handle a dispatch event, returning `false` once the receiver is gone.
+fn dispatch(
+    payload: &Payload,
+    session: &mut Session,
+    me: &mut Option<api::User>,
+    tx: &mpsc::UnboundedSender<GatewayMessage>,
+) -> bool {
+    match payload.t.as_deref() {
+        Some("READY") => match Ready::deserialize(&payload.d) {
+            Ok(ready) => {
+                tracing::info!(user = %ready.user.username, "gateway ready");
+                session.id = Some(ready.session_id);
+                session.resume_url = Some(ready.resume_gateway_url);
+                *me = Some(ready.user);
+            }
+            Err(e) => tracing::warn!("invalid READY event: {e}"),
+        },
+        Some("MESSAGE_CREATE") => match api::Message::deserialize(&payload.d) {
+            Ok(msg) => {
+                if let Some(msg) = crate::convert_message(&msg, me.as_ref())
+                    && tx.send(msg).is_err()
+                {
+                    tracing::info!("channel dropped, stopping discord gateway loop");
+                    return false;
+                }
+            }
+            Err(e) => tracing::warn!("invalid MESSAGE_CREATE event: {e}"),
+        },
+        _ => {}
+    }
+    true
+}
+
+/// Decide how to go on after the gateway closed the connection.
+fn closed_by(frame: Option<CloseFrame>) -> Closed {
+    let Some(frame) = frame else {
+        return Closed::Resume;
+    };
+    let code = u16::from(frame.code);
+    match code {
+        // Bad token, sharding or intents: retrying cannot help.
+        4004 | 4010..=4014 => {
+            tracing::error!(code, reason = %frame.reason, "gateway refused the bot");
+            Closed::Stop
+        }
+        // The session is gone; resuming would be refused.
+        1000 | 1001 | 4007 | 4009 => Closed::Identify,
+        _ => Closed::Resume,
+    }
+}
+
+fn text(payload: &Value) -> tungstenite::Message {
+    tungstenite::Message::text(payload.to_string())
+}
@@ -0,0 +1,93 @@
+//! Crabtalk Discord gateway — Discord Gateway and REST API adapter.
+
+pub mod api;
+pub mod config;
+pub mod gateway;
+pub mod serve;
+
+pub use sdk::*;
+
+/// Maximum length of a single Discord message, in characters.
+pub const MAX_MESSAGE_LEN: usize = 2000;
+
+/// Milliseconds from the Unix epoch to the first Discord snowflake.
+const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+
+/// Convert a Discord message to a [`GatewayMessage`]. Snowflake IDs fit
+/// an `i64`; a message whose IDs do not parse is dropped.
+///
+/// `me` is the bot's own user, used to tell whether a server message is
+/// addressed to it. When unknown, every message counts as addressed.
+/// Mentions of the bot are stripped from the content.
+pub fn convert_message(msg: &api::Message, me: Option<&api::User>) -> Option<GatewayMessage> {
+    let message_id = snowflake(&msg.id)?;
+    let is_group = msg.guild_id.is_some();
+    let addressed = !is_group || me.is_none_or(|me| is_addressed(msg, me));
+    let content = match me {
+        Some(me) => strip_mention(&msg.content, &me.id),
+        None => msg.content.clone(),
+    };
+
+    let attachments = msg
+        .attachments
+        .iter()
+        .map(|a| Attachment {
+            kind: attachment_kind(a.content_type.as_deref()),
+            url: a.url.clone(),
+            name: Some(a.filename.clone()),
+        })
+        .collect();
+
+    let reply_to = msg
+        .message_reference
+        .as_ref()
+        .and_then(|r| r.message_id.as_deref())
+        .and_then(snowflake);
+
+    Some(GatewayMessage {
+        chat_id: snowflake(&msg.channel_id)?,
+        message_id,
+        sender_id: snowflake(&msg.author.id)?,
+        sender_name: msg.author.display_name().to_owned(),
+        is_bot: msg.author.bot,
+        is_group,
+        addressed,
+        content,
+        attachments,
+        reply_to,
+        timestamp: ((message_id as u64 >> 22) + DISCORD_EPOCH_MS) / 1000,
+    })
+}
+
+/// Whether a server message is directed at the bot: it mentions the bot
+/// or replies to one of its messages.
+fn is_addressed(msg: &api::Message, me: &api::User) -> bool {
+    msg.mentions.iter().any(|u| u.id == me.id)
+        || msg
+            .referenced_message
+            .as_ref()
+            .is_some_and(|r| r.author.id == me.id)
+}
+
+/// Remove `<@id>` and `<@!id>` mentions of `id` from `content`.
+fn strip_mention(content: &str, id: &str) -> String {
+    content
+        .replace(&format!("<@{id}>"), "")
+        .replace(&format!("<@!{id}>"), "")
+        .trim()
+        .to_owned()
+}
+
+/// Map a MIME type to an [`AttachmentKind`]; unknown types are files.
+fn attachment_kind(content_type: Option<&str>) -> AttachmentKind {
+    match content_type.and_then(|t| t.split_once('/')).map(|(t, _)| t) {
+        Some("image") => AttachmentKind::Image,
+        Some("audio") => AttachmentKind::Audio,
+        Some("video") => AttachmentKind::Video,
+        _ => AttachmentKind::File,
+    }
+}
+
+fn snowflake(id: &str) -> Option<i64> {
+    id.parse().ok()
+}
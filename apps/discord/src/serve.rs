@@ -0,0 +1,345 @@
+//! Discord gateway serve logic.
+
+use crate::config::DiscordConfig;
+use crate::{
+    AttachmentKind, Channel, Connect, GatewayMessage, KnownBots, StreamAccumulator, StreamResult,
+    api, attachment_summary,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+use wcore::protocol::message::{
+    ClientMessage, ReplyToAsk, ServerMessage, StreamMsg, server_message,
+};
+
+/// How often the typing indicator is refreshed. Discord clears it after
+/// about ten seconds.
+const TYPING_REFRESH: Duration = Duration::from_secs(8);
+
+/// Run the Discord gateway service.
+pub async fn run(node_client: impl Connect, config: &DiscordConfig) -> anyhow::Result<()> {
+    let client = Arc::new(node_client);
+
+    let agents_dir = wcore::paths::CONFIG_DIR.join(wcore::paths::AGENTS_DIR);
+    let default_agent = crate::resolve_default_agent(&agents_dir);
+    tracing::info!(agent = %default_agent, "discord gateway starting");
+
+    let known_bots: KnownBots = Arc::new(tokio::sync::RwLock::new(HashSet::new()));
+
+    if config.token.is_empty() {
+        tracing::warn!(platform = "discord", "token is empty, skipping");
+    } else {
+        spawn_discord(config, default_agent, client, known_bots).await;
+    }
+
+    tokio::signal::ctrl_c().await?;
+    tracing::info!("discord gateway shutting down");
+    Ok(())
+}
+
+async fn spawn_discord<C: Connect>(
+    config: &DiscordConfig,
+    agent: String,
+    client: Arc<C>,
+    known_bots: KnownBots,
+) {
+    let http = reqwest::Client::new();
+
+    let me = match api::current_user(&http, &config.token).await {
+        Ok(me) => {
+            let bot_sender = format!("dc:{}", me.id);
+            tracing::info!(platform = "discord", %bot_sender, "registered bot identity");
+            known_bots.write().await.insert(bot_sender);
+            Some(me)
+        }
+        Err(e) => {
+            tracing::warn!(
+                platform = "discord",
+                "failed to resolve bot identity: {e:#}"
+            );
+            None
+        }
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel::<GatewayMessage>();
+    let listen_http = http.clone();
+    let token = config.token.clone();
+    tokio::spawn(async move {
+        crate::gateway::gateway_loop(listen_http, token, me, tx).await;
+    });
+
+    let allowed: HashSet<i64> = config.allowed_users.iter().copied().collect();
+    if !allowed.is_empty() {
+        tracing::info!(
+            platform = "discord",
+            count = allowed.len(),
+            "user whitelist active"
+        );
+    }
+    tokio::spawn(discord_loop(
+        rx,
+        http,
+        config.clone(),
+        agent,
+        client,
+        known_bots,
+        allowed,
+    ));
+    tracing::info!(platform = "discord", "channel transport started");
+}
+
+/// Per-channel stream state, tracked while a stream is in flight.
+struct ChatStream {
+    handle: JoinHandle<StreamResult>,
+    reply_tx: mpsc::UnboundedSender<String>,
+}
+
+impl ChatStream {
+    fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+/// Reap a finished ChatStream, returning whether it succeeded.
+async fn reap_chat(chat: ChatStream) -> bool {
+    matches!(chat.handle.await, Ok(StreamResult::Ok))
+}
+
+async fn discord_loop<C: Connect>(
+    mut rx: mpsc::UnboundedReceiver<GatewayMessage>,
+    http: reqwest::Client,
+    config: DiscordConfig,
+    agent: String,
+    client: Arc<C>,
+    known_bots: KnownBots,
+    allowed_users: HashSet<i64>,
+) {
+    let config = Arc::new(config);
+    let mut chats: HashMap<i64, ChatStream> = HashMap::new();
+
+    while let Some(msg) = rx.recv().await {
+        let chat_id = msg.chat_id;
+        let content = msg.content.clone();
+        let sender = format!("dc:{}", msg.sender_id);
+
+        if known_bots.read().await.contains(&sender) {
+            tracing::debug!(%sender, chat_id, "dropping message from known bot");
+            continue;
+        }
+
+        if !allowed_users.is_empty() && !allowed_users.contains(&msg.sender_id) {
+            tracing::debug!(
+                sender_id = msg.sender_id,
+                chat_id,
+                "dropping message from non-allowed user"
+            );
+            continue;
+        }
+
+        if config.require_mention && !msg.addressed {
+            tracing::debug!(chat_id, "dropping server message not addressed to the bot");
+            continue;
+        }
+
+        tracing::info!(agent = %agent, chat_id, "discord dispatch");
+
+        // Check if there's an active stream for this channel.
+        if let Some(chat_stream) = chats.get(&chat_id) {
+            if chat_stream.is_finished() {
+                let chat_stream = chats.remove(&chat_id).unwrap();
+                reap_chat(chat_stream).await;
+            } else {
+                // Stream in flight — forward message. If ask_user is pending,
+                // dc_stream will route it as ReplyToAsk. Otherwise it's dropped.
+                let _ = chat_stream.reply_tx.send(content);
+                continue;
+            }
+        }
+
+        // Attachment-only messages become a turn about the attachments;
+        // anything else empty is dropped.
+        let content = match attachment_summary(&msg.attachments) {
+            Some(summary) if content.trim().is_empty() => summary,
+            Some(summary) => format!("{content}\n{summary}"),
+            None if content.trim().is_empty() => {
+                tracing::debug!(chat_id, "dropping empty message");
+                continue;
+            }
+            None => content,
+        };
+        // Discord serves attachments from public CDN URLs, which the
+        // model can fetch directly.
+        let images: Vec<String> = msg
+            .attachments
+            .iter()
+            .filter(|a| a.kind == AttachmentKind::Image)
+            .map(|a| a.url.clone())
+            .collect();
+        let reply_to = msg.is_group.then_some(msg.message_id);
+
+        let (reply_tx, reply_rx) = mpsc::unbounded_channel();
+        let handle = {
+            let http = http.clone();
+            let config = config.clone();
+            let client = client.clone();
+            let agent = agent.clone();
+            tokio::spawn(async move {
+                dc_stream(
+                    &http,
+                    config.as_ref(),
+                    client.as_ref(),
+                    &agent,
+                    chat_id,
+                    reply_to,
+                    &content,
+                    images,
+                    &sender,
+                    reply_rx,
+                )
+                .await
+            })
+        };
+
+        chats.insert(chat_id, ChatStream { handle, reply_tx });
+    }
+
+    tracing::info!(platform = "discord", "channel loop ended");
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn dc_stream(
+    http: &reqwest::Client,
+    config: &DiscordConfig,
+    client: &impl Connect,
+    agent: &str,
+    chat_id: i64,
+    reply_to: Option<i64>,
+    content: &str,
+    images: Vec<String>,
+    sender: &str,
+    mut reply_rx: mpsc::UnboundedReceiver<String>,
+) -> StreamResult {
+    let token = config.token.as_str();
+    let channel_id = chat_id.to_string();
+    let typing = Typing::start(http.clone(), token.to_owned(), channel_id.clone());
+    let client_msg = ClientMessage::from(StreamMsg {
+        agent: agent.to_string(),
+        content: content.to_string(),
+        sender: Some(sender.to_string()),
+        cwd: None,
+        guest: None,
+        tool_choice: None,
+        instructions: None,
+        images,
+        interactive: false,
+    });
+    let mut server_rx = client.send(client_msg).await;
+    let mut acc = StreamAccumulator::new();
+
+    loop {
+        tokio::select! {
+            server_msg = server_rx.recv() => {
+                match server_msg {
+                    Some(ServerMessage { msg: Some(server_message::Msg::Stream(event)) }) => {
+                        acc.push(&event);
+
+                        // Handle ask_user: send question text, accept free-text reply.
+                        if let Some(questions) = acc.take_pending_questions() {
+                            let question_text = questions
+                                .iter()
+                                .map(|q| format!("{}: {}", q.header, q.question))
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            for chunk in config.split(&question_text) {
+                                if let Err(e) = api::send_message(http, token, &channel_id, &chunk, None).await {
+                                    tracing::warn!(agent, "failed to send question: {e:#}");
+                                    break;
+                                }
+                            }
+                        }
+
+                        if acc.done {
+                            break;
+                        }
+                    }
+                    Some(ServerMessage { msg: Some(server_message::Msg::Error(err)) }) => {
+                        acc.set_error(err.message);
+                        break;
+                    }
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+            reply = reply_rx.recv() => {
+                if let Some(reply_content) = reply {
+                    // Free-text reply for ask_user.
+                    let reply_msg = ClientMessage::from(ReplyToAsk {
+                        agent: agent.to_string(),
+                        sender: sender.to_string(),
+                        content: reply_content,
+                    });
+                    let _ = client.send(reply_msg).await;
+                }
+            }
+        }
+    }
+    drop(typing);
+
+    let reply_to = reply_to.map(|id| id.to_string());
+    if let Some(err) = acc.error() {
+        tracing::warn!(agent, chat_id, "stream error: {err}");
+        let err_text = format!("Error: {err}");
+        let err_text = config.split(&err_text).swap_remove(0);
+        if let Err(e) =
+            api::send_message(http, token, &channel_id, &err_text, reply_to.as_deref()).await
+        {
+            tracing::warn!(agent, "failed to send error to channel: {e:#}");
+        }
+        return StreamResult::Failed;
+    }
+
+    let final_text = acc.render();
+    if !final_text.is_empty() {
+        // Only the first chunk replies to the message; the rest follow it.
+        let mut reply_to = reply_to.as_deref();
+        for chunk in config.split(&final_text) {
+            if let Err(e) = api::send_message(http, token, &channel_id, &chunk, reply_to).await {
+                tracing::warn!(agent, "failed to send reply: {e:#}");
+                break;
+            }
+            reply_to = None;
+        }
+    }
+
+    if acc.agent.is_some() {
+        StreamResult::Ok
+    } else {
+        StreamResult::Failed
+    }
+}
+
+/// Keeps the typing indicator alive in a channel until dropped.
+struct Typing(JoinHandle<()>);
+
+impl Typing {
+    /// Trigger typing every few seconds. Stops on the first failure.
+    fn start(http: reqwest::Client, token: String, channel_id: String) -> Self {
+        Self(tokio::spawn(async move {
+            while api::trigger_typing(&http, &token, &channel_id)
+                .await
+                .is_ok()
+            {
+                tokio::time::sleep(TYPING_REFRESH).await;
+            }
+        }))
+    }
+}
+
+impl Drop for Typing {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
@@ -0,0 +1,107 @@
+//! Tests for converting Discord messages to gateway messages.
+
+use crabtalk_discord::{
+    AttachmentKind,
+    api::{Message, User},
+    convert_message,
+};
+
+fn bot() -> User {
+    serde_json::from_value(serde_json::json!({
+        "id": "42",
+        "username": "crab",
+        "bot": true,
+    }))
+    .unwrap()
+}
+
+fn message(fields: serde_json::Value) -> Message {
+    let mut msg = serde_json::json!({
+        "id": "175928847299117063",
+        "channel_id": "100",
+        "author": { "id": "7", "username": "alice", "global_name": "Alice" },
+        "content": "hello",
+    });
+    for (key, value) in fields.as_object().unwrap() {
+        msg[key] = value.clone();
+    }
+    serde_json::from_value(msg).unwrap()
+}
+
+#[test]
+fn direct_messages_are_always_addressed() {
+    let msg = message(serde_json::json!({
+        "message_reference": { "message_id": "99" },
+    }));
+    let converted = convert_message(&msg, Some(&bot())).unwrap();
+    assert_eq!(converted.chat_id, 100);
+    assert_eq!(converted.message_id, 175928847299117063);
+    assert_eq!(converted.sender_id, 7);
+    assert_eq!(converted.sender_name, "Alice");
+    assert!(!converted.is_group);
+    assert!(converted.addressed);
+    assert_eq!(converted.reply_to, Some(99));
+    // The creation time is encoded in the snowflake.
+    assert_eq!(converted.timestamp, 1462015105);
+}
+
+#[test]
+fn server_messages_need_a_mention_or_reply() {
+    let me = bot();
+    let plain = message(serde_json::json!({ "guild_id": "1" }));
+    let converted = convert_message(&plain, Some(&me)).unwrap();
+    assert!(converted.is_group);
+    assert!(!converted.addressed);
+
+    let mention = message(serde_json::json!({
+        "guild_id": "1",
+        "content": "<@42> what's up <@!42>",
+        "mentions": [{ "id": "42", "username": "crab" }],
+    }));
+    let converted = convert_message(&mention, Some(&me)).unwrap();
+    assert!(converted.addressed);
+    assert_eq!(converted.content, "what's up");
+
+    let reply = message(serde_json::json!({
+        "guild_id": "1",
+        "message_reference": { "message_id": "5" },
+        "referenced_message": {
+            "id": "5",
+            "channel_id": "100",
+            "author": { "id": "42", "username": "crab", "bot": true },
+        },
+    }));
+    assert!(convert_message(&reply, Some(&me)).unwrap().addressed);
+
+    // Without the bot's identity there is nothing to check against.
+    assert!(convert_message(&plain, None).unwrap().addressed);
+}
+
+#[test]
+fn attachments_are_kept_by_kind() {
+    let msg = message(serde_json::json!({
+        "attachments": [
+            { "id": "1", "filename": "cat.png", "url": "https://cdn/cat.png", "content_type": "image/png" },
+            { "id": "2", "filename": "notes.txt", "url": "https://cdn/notes.txt" },
+            { "id": "3", "filename": "talk.ogg", "url": "https://cdn/talk.ogg", "content_type": "audio/ogg" },
+        ],
+    }));
+    let converted = convert_message(&msg, Some(&bot())).unwrap();
+    let kinds: Vec<_> = converted.attachments.iter().map(|a| a.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            AttachmentKind::Image,
+            AttachmentKind::File,
+            AttachmentKind::Audio
+        ]
+    );
+    assert_eq!(converted.attachments[0].url, "https://cdn/cat.png");
+    assert_eq!(converted.attachments[1].name.as_deref(), Some("notes.txt"));
+}
+
+#[test]
+fn unparsable_ids_are_dropped() {
+    let msg = message(serde_json::json!({ "channel_id": "general" }));
+    assert!(convert_message(&msg, None).is_none());
+}
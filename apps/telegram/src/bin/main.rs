@@ -84,6 +84,12 @@ fn ensure_config() -> anyhow::Result<()> {
         let config = TelegramConfig {
             token,
             allowed_users: vec![],
+            routes: vec![],
+            require_mention: true,
+            webhook: None,
+            parse_mode: Default::default(),
+            rate_limit: Default::default(),
+            max_message_len: crabtalk_telegram::MAX_MESSAGE_LEN,
         };
         config.save(&path)?;
         println!("saved config to {}", path.display());
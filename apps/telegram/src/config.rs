@@ -1,5 +1,6 @@
 //! Telegram bot configuration.
 
+use crate::{Channel, MAX_MESSAGE_LEN};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -18,6 +19,159 @@ pub struct TelegramConfig {
     /// bot responds to all users.
     #[serde(default)]
     pub allowed_users: Vec<i64>,
+    /// Per-chat overrides, matched by chat ID.
+    #[serde(default, rename = "route", skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<ChatRoute>,
+    /// In groups, only answer messages that mention or reply to the bot.
+    /// Private chats are always answered.
+    #[serde(default = "default_require_mention")]
+    pub require_mention: bool,
+    /// Receive updates by webhook instead of long polling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<WebhookConfig>,
+    /// Markup replies are sent with: `"MarkdownV2"` (default) or `"HTML"`.
+    #[serde(default)]
+    pub parse_mode: ParseMode,
+    /// Outgoing send limits.
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    /// Longest reply message in characters; longer replies are split.
+    /// Capped at Telegram's own limit of 4096, which is the default.
+    #[serde(default = "default_max_message_len")]
+    pub max_message_len: usize,
+}
+
+impl Channel for TelegramConfig {
+    fn max_message_len(&self) -> Option<usize> {
+        Some(self.max_message_len.clamp(1, MAX_MESSAGE_LEN))
+    }
+}
+
+/// Outgoing send limits, declared as a `[rate_limit]` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Sends per second across all chats. 0 disables the limit.
+    #[serde(default = "default_global_per_sec")]
+    pub global_per_sec: f64,
+    /// Sends per second to a single chat. 0 disables the limit.
+    #[serde(default = "default_per_chat_per_sec")]
+    pub per_chat_per_sec: f64,
+    /// Retries after a `429 Too Many Requests` before giving up.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            global_per_sec: default_global_per_sec(),
+            per_chat_per_sec: default_per_chat_per_sec(),
+            max_retries: default_max_retries(),
+        }
+    }
+}
+
+fn default_global_per_sec() -> f64 {
+    30.0
+}
+
+fn default_per_chat_per_sec() -> f64 {
+    1.0
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+/// Telegram markup for outgoing replies. Agent markdown is converted and
+/// escaped for the chosen mode before sending.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParseMode {
+    #[default]
+    MarkdownV2,
+    #[serde(rename = "HTML")]
+    Html,
+}
+
+impl From<ParseMode> for teloxide::types::ParseMode {
+    fn from(mode: ParseMode) -> Self {
+        match mode {
+            ParseMode::MarkdownV2 => Self::MarkdownV2,
+            ParseMode::Html => Self::Html,
+        }
+    }
+}
+
+/// Webhook receive mode, declared as a `[webhook]` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Public HTTPS URL Telegram posts updates to. It must reach `bind`,
+    /// e.g. through a reverse proxy. Telegram accepts ports 443, 80, 88
+    /// and 8443 only.
+    pub url: String,
+    /// Local address the webhook server listens on.
+    #[serde(default = "default_webhook_bind")]
+    pub bind: String,
+    /// Value Telegram sends in the `X-Telegram-Bot-Api-Secret-Token`
+    /// header; requests without it are rejected. 1-256 characters from
+    /// `A-Z`, `a-z`, `0-9`, `_` and `-`. A random one is generated at
+    /// startup when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_token: Option<String>,
+}
+
+fn default_require_mention() -> bool {
+    true
+}
+
+fn default_max_message_len() -> usize {
+    MAX_MESSAGE_LEN
+}
+
+fn default_webhook_bind() -> String {
+    "0.0.0.0:8443".to_owned()
+}
+
+impl WebhookConfig {
+    /// Resolve into teloxide's webhook options, validating the URL,
+    /// bind address and secret.
+    pub fn options(&self) -> Result<teloxide::update_listeners::webhooks::Options> {
+        let url = url::Url::parse(&self.url)
+            .with_context(|| format!("invalid webhook url '{}'", self.url))?;
+        let bind = self
+            .bind
+            .parse()
+            .with_context(|| format!("invalid webhook bind address '{}'", self.bind))?;
+        let mut options = teloxide::update_listeners::webhooks::Options::new(bind, url);
+        if let Some(secret) = &self.secret_token {
+            let valid = (1..=256).contains(&secret.len())
+                && secret
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+            if !valid {
+                anyhow::bail!(
+                    "invalid webhook secret_token: 1-256 characters from A-Z, a-z, 0-9, _ and -"
+                );
+            }
+            options = options.secret_token(secret.clone());
+        }
+        Ok(options)
+    }
+}
+
+/// Per-chat routing, declared as `[[route]]` tables.
+///
+/// Chats without a route go to the default agent with no prefix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatRoute {
+    /// Telegram chat ID (negative for groups).
+    pub chat_id: i64,
+    /// Agent that answers this chat instead of the default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Prompt prefix injected ahead of each message for that turn only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
 }
 
 impl TelegramConfig {
@@ -0,0 +1,74 @@
+//! Attachment downloads.
+//!
+//! Resolves the `file_id`s recorded in [`Attachment::url`] into bytes via
+//! `getFile` and the Bot API file endpoint.
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use sdk::{Attachment, AttachmentKind};
+use teloxide::{ApiError, RequestError, net::Download, prelude::*, types::FileId};
+
+/// Largest file the Bot API lets bots download, in bytes.
+pub const MAX_DOWNLOAD_BYTES: u32 = 20 * 1024 * 1024;
+
+/// Largest image forwarded to the model, in bytes. Keeps the base64
+/// payload well inside the daemon's frame limit.
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Download the file behind `file_id`.
+///
+/// Fails with a clear error for files over [`MAX_DOWNLOAD_BYTES`], which
+/// Telegram refuses to serve to bots.
+pub async fn download(bot: &Bot, file_id: &str) -> Result<Vec<u8>> {
+    let file = match bot.get_file(FileId(file_id.to_owned())).await {
+        Ok(file) => file,
+        Err(RequestError::Api(ApiError::Unknown(msg))) if msg.contains("file is too big") => {
+            bail!("telegram file {file_id} exceeds the 20 MB bot download limit")
+        }
+        Err(e) => return Err(e).with_context(|| format!("getFile failed for {file_id}")),
+    };
+    if file.size > MAX_DOWNLOAD_BYTES {
+        bail!(
+            "telegram file {file_id} is {} bytes, over the 20 MB bot download limit",
+            file.size
+        );
+    }
+
+    let mut bytes = Vec::with_capacity(file.size as usize);
+    bot.download_file(&file.path, &mut bytes)
+        .await
+        .with_context(|| format!("failed to download telegram file {file_id}"))?;
+    Ok(bytes)
+}
+
+/// Download every attachment, in order. Stops at the first failure.
+pub async fn download_all(bot: &Bot, attachments: &[Attachment]) -> Result<Vec<Vec<u8>>> {
+    let mut out = Vec::with_capacity(attachments.len());
+    for attachment in attachments {
+        out.push(download(bot, &attachment.url).await?);
+    }
+    Ok(out)
+}
+
+/// Download the image attachments as `data:` URIs for the model. Telegram
+/// re-encodes photos as JPEG. Images that fail to download or exceed
+/// [`MAX_IMAGE_BYTES`] are skipped with a warning.
+pub async fn image_data_urls(bot: &Bot, attachments: &[Attachment]) -> Vec<String> {
+    let mut urls = Vec::new();
+    for attachment in attachments
+        .iter()
+        .filter(|a| a.kind == AttachmentKind::Image)
+    {
+        match download(bot, &attachment.url).await {
+            Ok(bytes) if bytes.len() > MAX_IMAGE_BYTES => {
+                tracing::warn!(size = bytes.len(), "skipping image over the size limit");
+            }
+            Ok(bytes) => {
+                let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+                urls.push(format!("data:image/jpeg;base64,{data}"));
+            }
+            Err(e) => tracing::warn!("failed to fetch image: {e:#}"),
+        }
+    }
+    urls
+}
@@ -2,29 +2,72 @@
 
 pub mod command;
 pub mod config;
+pub mod file;
+pub mod limit;
 pub mod markdown;
 pub mod serve;
+pub mod typing;
 
 use futures_util::StreamExt;
 pub use sdk::*;
+use std::collections::{HashSet, VecDeque};
 use teloxide::prelude::*;
-use teloxide::types::{CallbackQuery, ChatKind, UpdateKind};
-use teloxide::update_listeners::{AsUpdateStream, polling_default};
+use teloxide::types::{CallbackQuery, ChatKind, Me, MessageEntityKind, UpdateId, UpdateKind};
+use teloxide::update_listeners::{UpdateListener, polling_default, webhooks};
 use tokio::sync::mpsc;
 
+/// Maximum length of a single Telegram message, in characters.
+pub const MAX_MESSAGE_LEN: usize = 4096;
+
+/// Update IDs remembered for deduplication. Telegram redelivers a
+/// webhook update it saw no timely answer for, usually soon after.
+const SEEN_UPDATES: usize = 1024;
+
 /// Long-poll loop: receives Telegram updates and forwards them as [`GatewayMessage`]s.
-pub async fn poll_loop(bot: Bot, tx: mpsc::UnboundedSender<GatewayMessage>) {
-    let mut listener = polling_default(bot).await;
+///
+/// `me` is the bot's own identity, used to tell whether group messages are
+/// addressed to it. When unknown, every message counts as addressed.
+pub async fn poll_loop(bot: Bot, me: Option<Me>, tx: mpsc::UnboundedSender<GatewayMessage>) {
+    forward_updates(polling_default(bot).await, me, tx).await;
+}
+
+/// Webhook loop: registers `options.url` with `setWebhook` and serves it
+/// on `options.address`, failing if either step does. The returned
+/// future forwards updates as [`GatewayMessage`]s; spawn it. Requests
+/// without the secret token header are rejected.
+pub async fn webhook_loop(
+    bot: Bot,
+    me: Option<Me>,
+    options: webhooks::Options,
+    tx: mpsc::UnboundedSender<GatewayMessage>,
+) -> anyhow::Result<impl Future<Output = ()> + Send + 'static> {
+    let listener = webhooks::axum(bot, options).await?;
+    Ok(forward_updates(listener, me, tx))
+}
+
+async fn forward_updates<L>(
+    mut listener: L,
+    me: Option<Me>,
+    tx: mpsc::UnboundedSender<GatewayMessage>,
+) where
+    L: UpdateListener,
+    L::Err: std::fmt::Display,
+{
+    let mut seen = SeenUpdates::default();
     let stream = listener.as_stream();
     futures_util::pin_mut!(stream);
 
     while let Some(result) = stream.next().await {
         match result {
             Ok(update) => {
-                if let Some(msg) = convert_update(update)
+                if !seen.insert(update.id) {
+                    tracing::debug!(update_id = update.id.0, "dropping redelivered update");
+                    continue;
+                }
+                if let Some(msg) = convert_update(update, me.as_ref())
                     && tx.send(msg).is_err()
                 {
-                    tracing::info!("channel handle dropped, stopping poll loop");
+                    tracing::info!("channel handle dropped, stopping update loop");
                     return;
                 }
             }
@@ -35,11 +78,34 @@ pub async fn poll_loop(bot: Bot, tx: mpsc::UnboundedSender<GatewayMessage>) {
     }
 }
 
+/// The most recent [`SEEN_UPDATES`] update IDs.
+#[derive(Default)]
+struct SeenUpdates {
+    order: VecDeque<UpdateId>,
+    ids: HashSet<UpdateId>,
+}
+
+impl SeenUpdates {
+    /// Record `id`, returning `false` if it was already seen.
+    fn insert(&mut self, id: UpdateId) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > SEEN_UPDATES
+            && let Some(old) = self.order.pop_front()
+        {
+            self.ids.remove(&old);
+        }
+        true
+    }
+}
+
 /// Convert a teloxide `Update` to a `GatewayMessage`.
-fn convert_update(update: Update) -> Option<GatewayMessage> {
+fn convert_update(update: Update, me: Option<&Me>) -> Option<GatewayMessage> {
     match update.kind {
         UpdateKind::CallbackQuery(cq) => convert_callback_query(cq),
-        UpdateKind::Message(msg) => convert_message(msg),
+        UpdateKind::Message(msg) => convert_message(msg, me),
         _ => None,
     }
 }
@@ -62,6 +128,8 @@ fn convert_callback_query(cq: CallbackQuery) -> Option<GatewayMessage> {
         sender_name,
         is_bot,
         is_group,
+        // Button presses only reach the bot that sent the keyboard.
+        addressed: true,
         content: data,
         attachments: Vec::new(),
         reply_to: None,
@@ -70,14 +138,15 @@ fn convert_callback_query(cq: CallbackQuery) -> Option<GatewayMessage> {
 }
 
 /// Convert a regular `Message` update into a `GatewayMessage`.
-fn convert_message(msg: teloxide::types::Message) -> Option<GatewayMessage> {
+fn convert_message(msg: teloxide::types::Message, me: Option<&Me>) -> Option<GatewayMessage> {
     let chat_id = msg.chat.id.0;
     let sender = msg.from.as_ref();
     let sender_id = sender.map(|u| u.id.0 as i64).unwrap_or(0);
     let sender_name = sender.map(|u| u.first_name.clone()).unwrap_or_default();
     let is_bot = sender.is_some_and(|u| u.is_bot);
     let is_group = matches!(msg.chat.kind, ChatKind::Public(_));
-    let content = msg.text().unwrap_or("").to_owned();
+    let content = msg.text().or(msg.caption()).unwrap_or("").to_owned();
+    let addressed = !is_group || me.is_none_or(|me| is_addressed(&msg, me));
 
     let mut attachments = Vec::new();
     if let Some(photos) = msg.photo()
@@ -97,7 +166,7 @@ fn convert_message(msg: teloxide::types::Message) -> Option<GatewayMessage> {
         });
     }
 
-    let reply_to = msg.reply_to_message().map(|r| r.id.0);
+    let reply_to = msg.reply_to_message().map(|r| i64::from(r.id.0));
 
     Some(GatewayMessage {
         chat_id,
@@ -106,9 +175,41 @@ fn convert_message(msg: teloxide::types::Message) -> Option<GatewayMessage> {
         sender_name,
         is_bot,
         is_group,
+        addressed,
         content,
         attachments,
         reply_to,
         timestamp: msg.date.timestamp() as u64,
     })
 }
+
+/// Whether a group message is directed at the bot: it @-mentions the bot,
+/// text-mentions it, names it in a `/command@bot`, or replies to one of
+/// its messages. A bare `/command` counts too, since Telegram delivers
+/// those to every bot in the group.
+fn is_addressed(msg: &teloxide::types::Message, me: &Me) -> bool {
+    if msg
+        .reply_to_message()
+        .and_then(|r| r.from.as_ref())
+        .is_some_and(|u| u.id == me.id)
+    {
+        return true;
+    }
+    let username = me.username();
+    let entities = msg
+        .parse_entities()
+        .or_else(|| msg.parse_caption_entities())
+        .unwrap_or_default();
+    entities.iter().any(|e| match e.kind() {
+        MessageEntityKind::Mention => e
+            .text()
+            .strip_prefix('@')
+            .is_some_and(|name| name.eq_ignore_ascii_case(username)),
+        MessageEntityKind::TextMention { user } => user.id == me.id,
+        MessageEntityKind::BotCommand => match e.text().split_once('@') {
+            Some((_, name)) => name.eq_ignore_ascii_case(username),
+            None => true,
+        },
+        _ => false,
+    })
+}
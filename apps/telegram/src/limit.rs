@@ -0,0 +1,138 @@
+//! Outgoing rate limiting.
+//!
+//! Telegram allows roughly 30 messages per second per bot and one per
+//! second per chat, answering bursts past that with `429 Too Many
+//! Requests` and a `retry_after`. [`RateLimiter`] spaces sends with token
+//! buckets and retries the ones that still get a 429.
+
+use crate::config::RateLimitConfig;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use teloxide::{RequestError, types::ChatId};
+
+/// Per-chat buckets kept before idle ones are dropped.
+const MAX_CHAT_BUCKETS: usize = 1024;
+
+/// Token bucket refilling at `rate` tokens per second, holding at most
+/// `rate` tokens (and never less than one).
+struct Bucket {
+    tokens: f64,
+    rate: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64) -> Self {
+        Self {
+            tokens: rate.max(1.0),
+            rate,
+            last: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate.max(1.0));
+        self.last = now;
+    }
+
+    /// Take a token, or return how long until one is available.
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= self.rate.max(1.0)
+    }
+}
+
+/// Limiter shared by every send on one bot.
+pub struct RateLimiter {
+    global: Option<Mutex<Bucket>>,
+    per_chat: Option<f64>,
+    chats: Mutex<HashMap<ChatId, Bucket>>,
+    max_retries: u32,
+}
+
+impl RateLimiter {
+    /// A rate of zero disables that bucket.
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let rate = |r: f64| (r > 0.0).then_some(r);
+        Self {
+            global: rate(config.global_per_sec).map(|r| Mutex::new(Bucket::new(r))),
+            per_chat: rate(config.per_chat_per_sec),
+            chats: Mutex::new(HashMap::new()),
+            max_retries: config.max_retries,
+        }
+    }
+
+    /// Wait until both the global and `chat_id`'s bucket allow a send.
+    pub async fn acquire(&self, chat_id: ChatId) {
+        if let Some(rate) = self.per_chat {
+            while let Some(wait) = self.take_chat(chat_id, rate) {
+                tokio::time::sleep(wait).await;
+            }
+        }
+        if let Some(global) = &self.global {
+            loop {
+                let wait = global.lock().unwrap().take(Instant::now());
+                match wait {
+                    Some(wait) => tokio::time::sleep(wait).await,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    fn take_chat(&self, chat_id: ChatId, rate: f64) -> Option<Duration> {
+        let now = Instant::now();
+        let mut chats = self.chats.lock().unwrap();
+        if chats.len() >= MAX_CHAT_BUCKETS && !chats.contains_key(&chat_id) {
+            chats.retain(|_, b| {
+                b.refill(now);
+                !b.is_full()
+            });
+        }
+        chats
+            .entry(chat_id)
+            .or_insert_with(|| Bucket::new(rate))
+            .take(now)
+    }
+
+    /// Run the request built by `send` once the limiter allows it. On a
+    /// 429, sleep for the `retry_after` Telegram asked for and try again,
+    /// up to `max_retries` times.
+    pub async fn send<T, F, Fut>(&self, chat_id: ChatId, mut send: F) -> Result<T, RequestError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, RequestError>>,
+    {
+        let mut retries = 0;
+        loop {
+            self.acquire(chat_id).await;
+            match send().await {
+                Err(RequestError::RetryAfter(after)) if retries < self.max_retries => {
+                    retries += 1;
+                    tracing::warn!(
+                        chat_id = chat_id.0,
+                        retry_after = after.seconds(),
+                        retries,
+                        "telegram rate limited, retrying"
+                    );
+                    tokio::time::sleep(after.duration()).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
@@ -1,11 +1,13 @@
-//! Telegram MarkdownV2 helpers.
+//! Telegram formatting helpers.
 //!
-//! Provides send/edit wrappers that try `MarkdownV2` parse mode first and
-//! fall back to plain text on parse errors.
+//! Converts LLM markdown into Telegram's `MarkdownV2` or `HTML` markup and
+//! provides send/edit wrappers that fall back to plain text on parse errors.
 
+use crate::{config::ParseMode, limit::RateLimiter, split_message};
+use std::future::IntoFuture;
 use teloxide::{
     prelude::*,
-    types::{MessageId, ParseMode, ReplyParameters},
+    types::{MessageId, ReplyParameters},
 };
 
 /// Characters that must be escaped in MarkdownV2 text (outside code spans).
@@ -13,6 +15,9 @@ const SPECIAL_CHARS: &[char] = &[
     '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!',
 ];
 
+/// Code fence delimiter.
+const FENCE: &str = "```";
+
 /// Escape special characters for Telegram MarkdownV2.
 pub fn escape_markdown_v2(text: &str) -> String {
     let mut out = String::with_capacity(text.len() + text.len() / 4);
@@ -25,50 +30,281 @@ pub fn escape_markdown_v2(text: &str) -> String {
     out
 }
 
-/// Send a new message with MarkdownV2, falling back to plain text on error.
+/// Escape text inside a MarkdownV2 code span or block, where only `` ` ``
+/// and `\` are special.
+fn escape_code_v2(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('`', "\\`")
+}
+
+/// Escape text for Telegram HTML.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// A piece of LLM markdown that maps onto a Telegram entity.
+enum Span<'a> {
+    Text(&'a str),
+    Bold(&'a str),
+    Code(&'a str),
+    Block { lang: &'a str, body: &'a str },
+}
+
+/// Split markdown into code blocks, inline code, bold and plain text.
+/// Everything else is left as text and escaped. An unclosed fence runs
+/// to the end of the input.
+fn spans(text: &str) -> Vec<Span<'_>> {
+    let mut out = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let fence = find_fence(rest);
+        let (prose, block) = match fence {
+            Some(i) => (&rest[..i], Some(&rest[i + FENCE.len()..])),
+            None => (rest, None),
+        };
+        inline_spans(prose, &mut out);
+        let Some(block) = block else { break };
+
+        let (lang, body_start) = match block.find('\n') {
+            Some(nl) => (block[..nl].trim(), &block[nl + 1..]),
+            None => ("", block),
+        };
+        let (body, next) = match find_fence(body_start) {
+            Some(end) => {
+                let after = &body_start[end + FENCE.len()..];
+                (
+                    &body_start[..end],
+                    after.strip_prefix('\n').unwrap_or(after),
+                )
+            }
+            None => (body_start, ""),
+        };
+        out.push(Span::Block {
+            lang,
+            body: body.strip_suffix('\n').unwrap_or(body),
+        });
+        rest = next;
+    }
+    out
+}
+
+/// Byte offset of the next fence that starts a line.
+fn find_fence(text: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(i) = text[from..].find(FENCE) {
+        let at = from + i;
+        if at == 0 || text.as_bytes()[at - 1] == b'\n' {
+            return Some(at);
+        }
+        from = at + FENCE.len();
+    }
+    None
+}
+
+/// Split prose into inline code, `**bold**` and plain text.
+fn inline_spans<'a>(mut text: &'a str, out: &mut Vec<Span<'a>>) {
+    while !text.is_empty() {
+        let code = text.find('`');
+        let bold = text.find("**");
+        let next = match (code, bold) {
+            (Some(c), Some(b)) => Some(c.min(b)),
+            (c, b) => c.or(b),
+        };
+        let Some(start) = next else {
+            out.push(Span::Text(text));
+            return;
+        };
+        let (delim, make): (&str, fn(&'a str) -> Span<'a>) = if Some(start) == code {
+            ("`", Span::Code)
+        } else {
+            ("**", Span::Bold)
+        };
+        let inner = &text[start + delim.len()..];
+        match inner.find(delim).filter(|&end| end > 0) {
+            Some(end) => {
+                if start > 0 {
+                    out.push(Span::Text(&text[..start]));
+                }
+                out.push(make(&inner[..end]));
+                text = &inner[end + delim.len()..];
+            }
+            None => {
+                // Unpaired delimiter: keep it as literal text.
+                let literal = start + delim.len();
+                out.push(Span::Text(&text[..literal]));
+                text = &text[literal..];
+            }
+        }
+    }
+}
+
+/// Convert LLM markdown into markup for `mode`. Code fences become code
+/// blocks, `` `code` `` becomes inline code, `**bold**` becomes bold and
+/// everything else is escaped.
+pub fn render(text: &str, mode: ParseMode) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 4);
+    for span in spans(text) {
+        match (mode, span) {
+            (ParseMode::MarkdownV2, Span::Text(t)) => out.push_str(&escape_markdown_v2(t)),
+            (ParseMode::MarkdownV2, Span::Bold(t)) => {
+                out.push('*');
+                out.push_str(&escape_markdown_v2(t));
+                out.push('*');
+            }
+            (ParseMode::MarkdownV2, Span::Code(t)) => {
+                out.push('`');
+                out.push_str(&escape_code_v2(t));
+                out.push('`');
+            }
+            (ParseMode::MarkdownV2, Span::Block { lang, body }) => {
+                out.push_str(FENCE);
+                out.push_str(lang);
+                out.push('\n');
+                out.push_str(&escape_code_v2(body));
+                out.push('\n');
+                out.push_str(FENCE);
+                out.push('\n');
+            }
+            (ParseMode::Html, Span::Text(t)) => out.push_str(&escape_html(t)),
+            (ParseMode::Html, Span::Bold(t)) => {
+                out.push_str("<b>");
+                out.push_str(&escape_html(t));
+                out.push_str("</b>");
+            }
+            (ParseMode::Html, Span::Code(t)) => {
+                out.push_str("<code>");
+                out.push_str(&escape_html(t));
+                out.push_str("</code>");
+            }
+            (ParseMode::Html, Span::Block { lang, body }) => {
+                if lang.is_empty() {
+                    out.push_str("<pre>");
+                    out.push_str(&escape_html(body));
+                    out.push_str("</pre>\n");
+                } else {
+                    out.push_str("<pre><code class=\"language-");
+                    out.push_str(&escape_html(lang));
+                    out.push_str("\">");
+                    out.push_str(&escape_html(body));
+                    out.push_str("</code></pre>\n");
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Split `text` into chunks of at most `max` characters, each with its
+/// code fences balanced. The split leaves room for the fence that
+/// [`balance_fences`] reopens and closes in every chunk.
+pub fn split_fenced(text: &str, max: usize) -> Vec<String> {
+    let longest_lang = text
+        .lines()
+        .filter_map(|line| line.strip_prefix(FENCE))
+        .map(|lang| lang.trim().chars().count())
+        .max();
+    let Some(lang) = longest_lang else {
+        return split_message(text, max);
+    };
+    let room = 2 * (FENCE.len() + lang + 2);
+    balance_fences(split_message(text, max.saturating_sub(room)))
+}
+
+/// Close code fences left open at a chunk boundary and reopen them at the
+/// start of the next chunk, so each message renders on its own.
+///
+/// Chunks grow by up to two fence lines past the split limit; split with
+/// [`split_fenced`] to stay under it.
+pub fn balance_fences(chunks: Vec<String>) -> Vec<String> {
+    let mut out = Vec::with_capacity(chunks.len());
+    let mut open: Option<String> = None;
+    for chunk in chunks {
+        let mut chunk = match &open {
+            Some(lang) => format!("{FENCE}{lang}\n{chunk}"),
+            None => chunk,
+        };
+        open = None;
+        for line in chunk.lines() {
+            if let Some(lang) = line.strip_prefix(FENCE) {
+                open = match open {
+                    Some(_) => None,
+                    None => Some(lang.trim().to_owned()),
+                };
+            }
+        }
+        if open.is_some() {
+            chunk.push('\n');
+            chunk.push_str(FENCE);
+        }
+        out.push(chunk);
+    }
+    out
+}
+
+/// Send a new message formatted for `mode`, falling back to plain text
+/// on error. Both attempts go through `limiter`.
 pub async fn send_md(
     bot: &Bot,
+    limiter: &RateLimiter,
     chat_id: ChatId,
     text: &str,
     reply_to: Option<MessageId>,
+    mode: ParseMode,
 ) -> Result<teloxide::types::Message, teloxide::RequestError> {
-    let escaped = escape_markdown_v2(text);
-    let mut req = bot
-        .send_message(chat_id, &escaped)
-        .parse_mode(ParseMode::MarkdownV2);
-    if let Some(mid) = reply_to {
-        req = req.reply_parameters(ReplyParameters::new(mid));
-    }
-    match req.await {
+    let rendered = render(text, mode);
+    let send = |text: &str, mode: Option<ParseMode>| {
+        let mut req = bot.send_message(chat_id, text);
+        if let Some(mode) = mode {
+            req = req.parse_mode(mode.into());
+        }
+        if let Some(mid) = reply_to {
+            req = req.reply_parameters(ReplyParameters::new(mid));
+        }
+        req
+    };
+    match limiter
+        .send(chat_id, || send(&rendered, Some(mode)).into_future())
+        .await
+    {
         Ok(msg) => Ok(msg),
         Err(e) => {
-            tracing::debug!("MarkdownV2 send failed, falling back to plain: {e}");
-            let mut req = bot.send_message(chat_id, text);
-            if let Some(mid) = reply_to {
-                req = req.reply_parameters(ReplyParameters::new(mid));
-            }
-            req.await
+            tracing::debug!("{mode:?} send failed, falling back to plain: {e}");
+            limiter
+                .send(chat_id, || send(text, None).into_future())
+                .await
         }
     }
 }
 
-/// Edit an existing message with MarkdownV2, falling back to plain text on error.
+/// Edit an existing message formatted for `mode`, falling back to plain
+/// text on error. Both attempts go through `limiter`.
 pub async fn edit_md(
     bot: &Bot,
+    limiter: &RateLimiter,
     chat_id: ChatId,
     message_id: MessageId,
     text: &str,
+    mode: ParseMode,
 ) -> Result<teloxide::types::Message, teloxide::RequestError> {
-    let escaped = escape_markdown_v2(text);
-    match bot
-        .edit_message_text(chat_id, message_id, &escaped)
-        .parse_mode(ParseMode::MarkdownV2)
+    let rendered = render(text, mode);
+    match limiter
+        .send(chat_id, || {
+            bot.edit_message_text(chat_id, message_id, &rendered)
+                .parse_mode(mode.into())
+                .into_future()
+        })
         .await
     {
         Ok(msg) => Ok(msg),
         Err(e) => {
-            tracing::debug!("MarkdownV2 edit failed, falling back to plain: {e}");
-            bot.edit_message_text(chat_id, message_id, text).await
+            tracing::debug!("{mode:?} edit failed, falling back to plain: {e}");
+            limiter
+                .send(chat_id, || {
+                    bot.edit_message_text(chat_id, message_id, text)
+                        .into_future()
+                })
+                .await
         }
     }
 }
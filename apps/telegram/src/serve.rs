@@ -1,20 +1,23 @@
 //! Telegram gateway serve logic.
 
-use crate::config::TelegramConfig;
+use crate::config::{ChatRoute, ParseMode, TelegramConfig, WebhookConfig};
+use crate::limit::RateLimiter;
+use crate::typing::Typing;
 use crate::{
-    COMMAND_HINT, GatewayMessage, KnownBots, NodeClient, StreamAccumulator, StreamResult,
-    attachment_summary, parse_command,
+    COMMAND_HINT, Channel, Connect, GatewayMessage, KnownBots, MAX_MESSAGE_LEN, StreamAccumulator,
+    StreamResult, attachment_summary, markdown::split_fenced, parse_command, split_message,
 };
-use std::{collections::HashMap, sync::Arc};
+use anyhow::Context;
+use std::{collections::HashMap, future::IntoFuture, sync::Arc};
 use teloxide::prelude::*;
-use teloxide::types::{ChatAction, InlineKeyboardButton, InlineKeyboardMarkup};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
 use tokio::sync::mpsc;
 use wcore::protocol::message::{
     AskQuestion, ClientMessage, ReplyToAsk, ServerMessage, StreamMsg, server_message,
 };
 
 /// Run the Telegram gateway service.
-pub async fn run(node_client: NodeClient, config: &TelegramConfig) -> anyhow::Result<()> {
+pub async fn run(node_client: impl Connect, config: &TelegramConfig) -> anyhow::Result<()> {
     let client = Arc::new(node_client);
 
     let agents_dir = wcore::paths::CONFIG_DIR.join(wcore::paths::AGENTS_DIR);
@@ -27,14 +30,7 @@ pub async fn run(node_client: NodeClient, config: &TelegramConfig) -> anyhow::Re
     if config.token.is_empty() {
         tracing::warn!(platform = "telegram", "token is empty, skipping");
     } else {
-        spawn_telegram(
-            &config.token,
-            &config.allowed_users,
-            default_agent,
-            client,
-            known_bots,
-        )
-        .await;
+        spawn_telegram(config, default_agent, client, known_bots).await?;
     }
 
     tokio::signal::ctrl_c().await?;
@@ -42,34 +38,52 @@ pub async fn run(node_client: NodeClient, config: &TelegramConfig) -> anyhow::Re
     Ok(())
 }
 
-async fn spawn_telegram(
-    token: &str,
-    allowed_users: &[i64],
+async fn spawn_telegram<C: Connect>(
+    config: &TelegramConfig,
     agent: String,
-    client: Arc<NodeClient>,
+    client: Arc<C>,
     known_bots: KnownBots,
-) {
-    let bot = Bot::new(token);
-
-    match bot.get_me().await {
+) -> anyhow::Result<()> {
+    let webhook = config
+        .webhook
+        .as_ref()
+        .map(WebhookConfig::options)
+        .transpose()?;
+    let bot = Bot::new(&config.token);
+
+    let me = match bot.get_me().await {
         Ok(me) => {
             let bot_sender = format!("tg:{}", me.id.0);
             tracing::info!(platform = "telegram", %bot_sender, "registered bot identity");
             known_bots.write().await.insert(bot_sender);
+            Some(me)
         }
         Err(e) => {
             tracing::warn!(platform = "telegram", "failed to resolve bot identity: {e}");
+            None
         }
-    }
+    };
 
     let (tx, rx) = mpsc::unbounded_channel::<GatewayMessage>();
 
-    let poll_bot = bot.clone();
-    tokio::spawn(async move {
-        crate::poll_loop(poll_bot, tx).await;
-    });
+    let listen_bot = bot.clone();
+    match webhook {
+        Some(options) => {
+            let url = options.url.clone();
+            let updates = crate::webhook_loop(listen_bot, me, options, tx)
+                .await
+                .context("telegram webhook setup failed")?;
+            tracing::info!(platform = "telegram", %url, "receiving updates by webhook");
+            tokio::spawn(updates);
+        }
+        None => {
+            tokio::spawn(async move {
+                crate::poll_loop(listen_bot, me, tx).await;
+            });
+        }
+    }
 
-    let allowed: std::collections::HashSet<i64> = allowed_users.iter().copied().collect();
+    let allowed: std::collections::HashSet<i64> = config.allowed_users.iter().copied().collect();
     if !allowed.is_empty() {
         tracing::info!(
             platform = "telegram",
@@ -77,8 +91,26 @@ async fn spawn_telegram(
             "user whitelist active"
         );
     }
-    tokio::spawn(telegram_loop(rx, bot, agent, client, known_bots, allowed));
+    let routes: HashMap<i64, ChatRoute> = config
+        .routes
+        .iter()
+        .map(|r| (r.chat_id, r.clone()))
+        .collect();
+    tokio::spawn(telegram_loop(
+        rx,
+        bot,
+        agent,
+        client,
+        known_bots,
+        allowed,
+        routes,
+        config.require_mention,
+        config.parse_mode,
+        Channel::max_message_len(config).unwrap_or(MAX_MESSAGE_LEN),
+        Arc::new(RateLimiter::new(&config.rate_limit)),
+    ));
     tracing::info!(platform = "telegram", "channel transport started");
+    Ok(())
 }
 
 /// Per-chat stream state, tracked while a stream is in flight.
@@ -98,13 +130,19 @@ async fn reap_chat(chat: ChatStream) -> bool {
     matches!(chat.handle.await, Ok(StreamResult::Ok))
 }
 
-async fn telegram_loop(
+#[allow(clippy::too_many_arguments)]
+async fn telegram_loop<C: Connect>(
     mut rx: mpsc::UnboundedReceiver<GatewayMessage>,
     bot: Bot,
     agent: String,
-    client: Arc<NodeClient>,
+    client: Arc<C>,
     known_bots: KnownBots,
     allowed_users: std::collections::HashSet<i64>,
+    routes: HashMap<i64, ChatRoute>,
+    require_mention: bool,
+    parse_mode: ParseMode,
+    max_len: usize,
+    limiter: Arc<RateLimiter>,
 ) {
     let mut chats: HashMap<i64, ChatStream> = HashMap::new();
 
@@ -127,6 +165,11 @@ async fn telegram_loop(
             continue;
         }
 
+        if require_mention && !msg.addressed {
+            tracing::debug!(chat_id, "dropping group message not addressed to the bot");
+            continue;
+        }
+
         // Slash commands are always dispatched immediately.
         if content.starts_with('/') {
             match parse_command(&content) {
@@ -138,7 +181,11 @@ async fn telegram_loop(
                 }
                 None => {
                     tracing::warn!(chat_id, content, "unrecognised bot command");
-                    if let Err(e) = bot.send_message(ChatId(chat_id), COMMAND_HINT).await {
+                    let hint = limiter.send(ChatId(chat_id), || {
+                        bot.send_message(ChatId(chat_id), COMMAND_HINT)
+                            .into_future()
+                    });
+                    if let Err(e) = hint.await {
                         tracing::warn!("failed to send command hint: {e}");
                     }
                 }
@@ -146,6 +193,11 @@ async fn telegram_loop(
             continue;
         }
 
+        let route = routes.get(&chat_id);
+        let agent = route
+            .and_then(|r| r.agent.clone())
+            .unwrap_or_else(|| agent.clone());
+        let prompt = route.and_then(|r| r.prompt.clone());
         tracing::info!(agent = %agent, chat_id, "telegram dispatch");
 
         // Check if there's an active stream for this chat.
@@ -162,27 +214,40 @@ async fn telegram_loop(
             }
         }
 
+        // Attachment-only messages (a photo without a caption) become a
+        // turn about the attachments; anything else empty is dropped.
         let content = match attachment_summary(&msg.attachments) {
+            Some(summary) if content.trim().is_empty() => summary,
             Some(summary) => format!("{content}\n{summary}"),
+            None if content.trim().is_empty() => {
+                tracing::debug!(chat_id, "dropping empty message");
+                continue;
+            }
             None => content,
         };
 
         // Spawn the stream as a background task.
+        let attachments = msg.attachments;
         let (reply_tx, reply_rx) = mpsc::unbounded_channel();
         let handle = {
             let bot = bot.clone();
             let client = client.clone();
-            let agent = agent.clone();
+            let limiter = limiter.clone();
             tokio::spawn(async move {
                 tg_stream(
                     &bot,
-                    &client,
+                    &limiter,
+                    client.as_ref(),
                     &agent,
                     chat_id,
                     msg.message_id,
                     msg.is_group,
                     &content,
+                    &attachments,
                     &sender,
+                    prompt,
+                    parse_mode,
+                    max_len,
                     reply_rx,
                 )
                 .await
@@ -198,17 +263,24 @@ async fn telegram_loop(
 #[allow(clippy::too_many_arguments)]
 async fn tg_stream(
     bot: &Bot,
-    client: &NodeClient,
+    limiter: &RateLimiter,
+    client: &impl Connect,
     agent: &str,
     chat_id: i64,
     reply_to_msg_id: i64,
     is_group: bool,
     content: &str,
+    attachments: &[crate::Attachment],
     sender: &str,
+    instructions: Option<String>,
+    parse_mode: ParseMode,
+    max_len: usize,
     mut reply_rx: mpsc::UnboundedReceiver<String>,
 ) -> StreamResult {
     use std::time::Duration;
 
+    let typing = Typing::start(bot.clone(), ChatId(chat_id));
+    let images = crate::file::image_data_urls(bot, attachments).await;
     let client_msg = ClientMessage::from(StreamMsg {
         agent: agent.to_string(),
         content: content.to_string(),
@@ -216,6 +288,9 @@ async fn tg_stream(
         cwd: None,
         guest: None,
         tool_choice: None,
+        instructions,
+        images,
+        interactive: false,
     });
     let mut server_rx = client.send(client_msg).await;
     let mut acc = StreamAccumulator::new();
@@ -226,20 +301,6 @@ async fn tg_stream(
     let mut pending_ask_questions: Option<Vec<AskQuestion>> = None;
     let mut multi_select_state: HashMap<usize, Vec<usize>> = HashMap::new();
 
-    let typing_bot = bot.clone();
-    let typing_handle = tokio::spawn(async move {
-        loop {
-            if typing_bot
-                .send_chat_action(ChatId(chat_id), ChatAction::Typing)
-                .await
-                .is_err()
-            {
-                break;
-            }
-            tokio::time::sleep(Duration::from_secs(4)).await;
-        }
-    });
-
     loop {
         tokio::select! {
             server_msg = server_rx.recv() => {
@@ -249,18 +310,18 @@ async fn tg_stream(
 
                         // When ask_user fires, flush text and send inline keyboard.
                         if let Some(questions) = acc.take_pending_questions() {
-                            let rendered = acc.render();
+                            let rendered = render_preview(&acc, max_len);
                             if !rendered.is_empty() && rendered.len() != last_sent_len {
                                 let reply_to = is_group.then_some(teloxide::types::MessageId(reply_to_msg_id as i32));
                                 match msg_id {
                                     None => {
-                                        if let Ok(sent) = crate::markdown::send_md(bot, ChatId(chat_id), &rendered, reply_to).await {
+                                        if let Ok(sent) = crate::markdown::send_md(bot, limiter, ChatId(chat_id), &rendered, reply_to, parse_mode).await {
                                             msg_id = Some(sent.id);
                                             last_sent_len = rendered.len();
                                         }
                                     }
                                     Some(mid) => {
-                                        if crate::markdown::edit_md(bot, ChatId(chat_id), mid, &rendered).await.is_ok() {
+                                        if crate::markdown::edit_md(bot, limiter, ChatId(chat_id), mid, &rendered, parse_mode).await.is_ok() {
                                             last_sent_len = rendered.len();
                                         }
                                     }
@@ -270,11 +331,12 @@ async fn tg_stream(
                             for (qi, q) in questions.iter().enumerate() {
                                 let keyboard = build_ask_keyboard(qi, q);
                                 let text = format!("📋 {}\n{}", q.header, q.question);
-                                if let Err(e) = bot
-                                    .send_message(ChatId(chat_id), text)
-                                    .reply_markup(keyboard)
-                                    .await
-                                {
+                                let sent = limiter.send(ChatId(chat_id), || {
+                                    bot.send_message(ChatId(chat_id), text.clone())
+                                        .reply_markup(keyboard.clone())
+                                        .into_future()
+                                });
+                                if let Err(e) = sent.await {
                                     tracing::warn!(agent, "failed to send ask keyboard: {e}");
                                 }
                             }
@@ -304,6 +366,7 @@ async fn tg_stream(
                             questions,
                             &mut multi_select_state,
                             bot,
+                            limiter,
                             ChatId(chat_id),
                         ).await
                     } else {
@@ -328,14 +391,14 @@ async fn tg_stream(
                 }
             }
             _ = debounce.tick() => {
-                let rendered = acc.render();
+                let rendered = render_preview(&acc, max_len);
                 if rendered.is_empty() || rendered.len() == last_sent_len {
                     continue;
                 }
                 let reply_to = is_group.then_some(teloxide::types::MessageId(reply_to_msg_id as i32));
                 match msg_id {
                     None => {
-                        match crate::markdown::send_md(bot, ChatId(chat_id), &rendered, reply_to).await {
+                        match crate::markdown::send_md(bot, limiter, ChatId(chat_id), &rendered, reply_to, parse_mode).await {
                             Ok(sent) => {
                                 msg_id = Some(sent.id);
                                 last_sent_len = rendered.len();
@@ -344,7 +407,7 @@ async fn tg_stream(
                         }
                     }
                     Some(mid) => {
-                        if let Err(e) = crate::markdown::edit_md(bot, ChatId(chat_id), mid, &rendered).await {
+                        if let Err(e) = crate::markdown::edit_md(bot, limiter, ChatId(chat_id), mid, &rendered, parse_mode).await {
                             tracing::debug!(agent, "edit failed (may be same text): {e}");
                         } else {
                             last_sent_len = rendered.len();
@@ -355,12 +418,16 @@ async fn tg_stream(
         }
     }
 
-    typing_handle.abort();
+    drop(typing);
 
     if let Some(err) = acc.error() {
         tracing::warn!(agent, chat_id, "stream error: {err}");
         let err_text = format!("Error: {err}");
-        if let Err(e) = bot.send_message(ChatId(chat_id), err_text).await {
+        let sent = limiter.send(ChatId(chat_id), || {
+            bot.send_message(ChatId(chat_id), err_text.clone())
+                .into_future()
+        });
+        if let Err(e) = sent.await {
             tracing::warn!(agent, "failed to send error to chat: {e}");
         }
         return StreamResult::Failed;
@@ -368,10 +435,13 @@ async fn tg_stream(
 
     let final_text = acc.render();
     if !final_text.is_empty() {
+        let chunks = split_fenced(&final_text, max_len);
+        let (first, rest) = chunks.split_first().expect("split_message yields a chunk");
         match msg_id {
-            Some(mid) if final_text.len() != last_sent_len => {
+            Some(mid) if first.len() != last_sent_len => {
                 if let Err(e) =
-                    crate::markdown::edit_md(bot, ChatId(chat_id), mid, &final_text).await
+                    crate::markdown::edit_md(bot, limiter, ChatId(chat_id), mid, first, parse_mode)
+                        .await
                 {
                     tracing::debug!(agent, "final edit failed: {e}");
                 }
@@ -379,14 +449,30 @@ async fn tg_stream(
             None => {
                 let reply_to =
                     is_group.then_some(teloxide::types::MessageId(reply_to_msg_id as i32));
-                if let Err(e) =
-                    crate::markdown::send_md(bot, ChatId(chat_id), &final_text, reply_to).await
+                if let Err(e) = crate::markdown::send_md(
+                    bot,
+                    limiter,
+                    ChatId(chat_id),
+                    first,
+                    reply_to,
+                    parse_mode,
+                )
+                .await
                 {
                     tracing::warn!(agent, "failed to send reply: {e}");
                 }
             }
             _ => {}
         }
+        // Overflow goes out as follow-up messages in order.
+        for chunk in rest {
+            if let Err(e) =
+                crate::markdown::send_md(bot, limiter, ChatId(chat_id), chunk, None, parse_mode)
+                    .await
+            {
+                tracing::warn!(agent, "failed to send reply chunk: {e}");
+            }
+        }
     }
 
     if acc.agent.is_some() {
@@ -396,6 +482,12 @@ async fn tg_stream(
     }
 }
 
+/// Render the accumulator for an in-flight preview, clipped to the
+/// first message-sized chunk. The full text is split on the final send.
+fn render_preview(acc: &StreamAccumulator, max_len: usize) -> String {
+    split_message(&acc.render(), max_len).swap_remove(0)
+}
+
 /// Build an inline keyboard for a single question.
 fn build_ask_keyboard(question_idx: usize, q: &AskQuestion) -> InlineKeyboardMarkup {
     let mut rows: Vec<Vec<InlineKeyboardButton>> = q
@@ -435,6 +527,7 @@ async fn handle_ask_callback(
     questions: &[AskQuestion],
     multi_state: &mut HashMap<usize, Vec<usize>>,
     bot: &Bot,
+    limiter: &RateLimiter,
     chat_id: ChatId,
 ) -> Option<String> {
     let parts: Vec<&str> = data.split(':').collect();
@@ -447,7 +540,11 @@ async fn handle_ask_callback(
 
     if parts[2] == "other" {
         // Ask the user to type a reply. For now, signal that we need free text.
-        if let Err(e) = bot.send_message(chat_id, "Please type your answer:").await {
+        let prompt = limiter.send(chat_id, || {
+            bot.send_message(chat_id, "Please type your answer:")
+                .into_future()
+        });
+        if let Err(e) = prompt.await {
             tracing::warn!("failed to send other prompt: {e}");
         }
         return None;
@@ -479,8 +576,11 @@ async fn handle_ask_callback(
         if let Some(opt) = q.options.get(oi) {
             let selected = entry.contains(&oi);
             let mark = if selected { "☑" } else { "☐" };
-            let _ = bot
-                .send_message(chat_id, format!("{mark} {}", opt.label))
+            let text = format!("{mark} {}", opt.label);
+            let _ = limiter
+                .send(chat_id, || {
+                    bot.send_message(chat_id, text.clone()).into_future()
+                })
                 .await;
         }
         None
@@ -0,0 +1,39 @@
+//! Typing indicator.
+//!
+//! Telegram clears a chat action after about five seconds, so it has to
+//! be re-sent for as long as the agent is working.
+
+use std::time::Duration;
+use teloxide::{prelude::*, types::ChatAction};
+use tokio::task::JoinHandle;
+
+/// How often the typing action is refreshed.
+const TYPING_REFRESH: Duration = Duration::from_secs(4);
+
+/// Show "typing…" in `chat_id` once.
+pub async fn send_typing(bot: &Bot, chat_id: ChatId) -> Result<(), teloxide::RequestError> {
+    bot.send_chat_action(chat_id, ChatAction::Typing)
+        .await
+        .map(drop)
+}
+
+/// Keeps the typing indicator alive until dropped.
+pub struct Typing(JoinHandle<()>);
+
+impl Typing {
+    /// Start sending the typing action every few seconds. Stops on the
+    /// first failed send.
+    pub fn start(bot: Bot, chat_id: ChatId) -> Self {
+        Self(tokio::spawn(async move {
+            while send_typing(&bot, chat_id).await.is_ok() {
+                tokio::time::sleep(TYPING_REFRESH).await;
+            }
+        }))
+    }
+}
+
+impl Drop for Typing {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
@@ -0,0 +1,114 @@
+//! Tests for the markdown to Telegram markup conversion.
+
+use crabtalk_telegram::{
+    MAX_MESSAGE_LEN,
+    config::ParseMode,
+    markdown::{balance_fences, escape_markdown_v2, render, split_fenced},
+};
+
+fn v2(text: &str) -> String {
+    render(text, ParseMode::MarkdownV2)
+}
+
+fn html(text: &str) -> String {
+    render(text, ParseMode::Html)
+}
+
+#[test]
+fn fences_become_code_blocks() {
+    let text = "before\n```rust\nlet x = 1;\n```\nafter";
+    assert_eq!(v2(text), "before\n```rust\nlet x = 1;\n```\nafter");
+    assert_eq!(
+        html(text),
+        "before\n<pre><code class=\"language-rust\">let x = 1;</code></pre>\nafter"
+    );
+}
+
+#[test]
+fn unclosed_fence_runs_to_the_end() {
+    let text = "```\nfn main() {}";
+    assert_eq!(v2(text), "```\nfn main() {}\n```\n");
+    assert_eq!(html(text), "<pre>fn main() {}</pre>\n");
+}
+
+#[test]
+fn code_escapes_only_backticks_and_backslashes() {
+    assert_eq!(v2("run `C:\\tmp` now."), "run `C:\\\\tmp` now\\.");
+    assert_eq!(v2("```\na `b` c.d\n```"), "```\na \\`b\\` c.d\n```\n");
+}
+
+#[test]
+fn bold_and_inline_code_map_to_entities() {
+    assert_eq!(v2("**bold** and `x_y`"), "*bold* and `x_y`");
+    assert_eq!(
+        html("**bold** and `x_y`"),
+        "<b>bold</b> and <code>x_y</code>"
+    );
+}
+
+#[test]
+fn unpaired_bold_stays_literal() {
+    assert_eq!(v2("2 ** 3 is 8."), "2 \\*\\* 3 is 8\\.");
+    assert_eq!(html("2 ** 3 is 8."), "2 ** 3 is 8.");
+    assert_eq!(v2("a `tick"), "a \\`tick");
+}
+
+#[test]
+fn markdown_v2_escapes_special_characters() {
+    assert_eq!(
+        escape_markdown_v2("a_b (c) [d]!"),
+        "a\\_b \\(c\\) \\[d\\]\\!"
+    );
+}
+
+#[test]
+fn html_escapes_markup_everywhere() {
+    assert_eq!(html("a < b && c > d"), "a &lt; b &amp;&amp; c &gt; d");
+    assert_eq!(
+        html("**<b>** `<T>`"),
+        "<b>&lt;b&gt;</b> <code>&lt;T&gt;</code>"
+    );
+    assert_eq!(
+        html("```<x>\n1 < 2\n```"),
+        "<pre><code class=\"language-&lt;x&gt;\">1 &lt; 2</code></pre>\n"
+    );
+}
+
+#[test]
+fn fence_split_across_chunks_is_closed_and_reopened() {
+    let chunks = vec![
+        "intro\n```rust\nlet a = 1;".to_owned(),
+        "let b = 2;\n```\ndone".to_owned(),
+        "plain".to_owned(),
+    ];
+    let balanced = balance_fences(chunks);
+    assert_eq!(
+        balanced,
+        vec![
+            "intro\n```rust\nlet a = 1;\n```",
+            "```rust\nlet b = 2;\n```\ndone",
+            "plain",
+        ]
+    );
+    assert_eq!(v2(&balanced[1]), "```rust\nlet b = 2;\n```\ndone");
+}
+
+#[test]
+fn fenced_block_straddling_the_limit_stays_under_it() {
+    let body = "let answer = compute(41) + 1;\n".repeat(200);
+    let text = format!("intro\n\n```rust\n{body}```\nafter");
+    let chunks = split_fenced(&text, MAX_MESSAGE_LEN);
+    assert!(chunks.len() > 1);
+    for chunk in &chunks {
+        assert!(chunk.chars().count() <= MAX_MESSAGE_LEN, "{}", chunk.len());
+        assert_eq!(chunk.matches("```").count() % 2, 0, "{chunk}");
+    }
+}
+
+#[test]
+fn unfenced_text_splits_at_the_limit() {
+    let text = "word ".repeat(1000);
+    let chunks = split_fenced(&text, MAX_MESSAGE_LEN);
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks[0].chars().count() > MAX_MESSAGE_LEN - 10);
+}
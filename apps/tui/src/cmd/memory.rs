@@ -0,0 +1,183 @@
+//! `crabtalk memory` — back up, seed and search the daemon's memory.
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use wcore::protocol::{api::Client, message::MemoryEntryInfo};
+
+/// Manage memory.
+#[derive(Args, Debug)]
+pub struct Memory {
+    #[command(subcommand)]
+    pub command: MemoryCmd,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MemoryCmd {
+    /// Write every memory entry to a file. Use `-` for stdout.
+    Export {
+        /// Output path.
+        file: PathBuf,
+        /// File format.
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+    /// Load memory entries from a file. Use `-` for stdin.
+    Import {
+        /// Input path.
+        file: PathBuf,
+        /// File format.
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// Overwrite entries whose name already exists.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show the entries that best match a query, ranked.
+    Search {
+        /// Search query.
+        query: String,
+        /// Maximum results; 0 uses the daemon default.
+        #[arg(long, default_value_t = 0)]
+        limit: u32,
+        /// Hide results scoring below this.
+        #[arg(long, default_value_t = 0.0)]
+        threshold: f64,
+    },
+}
+
+/// On-disk layout for export and import.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A JSON array of entries with kind, aliases, timestamps, access
+    /// counts and expiry. Re-importing it reproduces recall ranking.
+    Json,
+    /// One `name=content` line per entry. Only names and content survive.
+    Env,
+}
+
+/// Entries fetched per `ListMemory` round-trip during export.
+const EXPORT_PAGE: u32 = 500;
+
+impl Memory {
+    pub async fn run(self, tcp: bool) -> Result<()> {
+        let mut runner = super::connect(tcp).await?;
+        match self.command {
+            MemoryCmd::Export { file, format } => {
+                let mut entries = Vec::new();
+                let mut cursor = None;
+                loop {
+                    let page = runner.list_memory(cursor, EXPORT_PAGE).await?;
+                    entries.extend(page.entries);
+                    match page.next_cursor {
+                        Some(next) => cursor = Some(next),
+                        None => break,
+                    }
+                }
+                let out = match format {
+                    Format::Json => serde_json::to_string_pretty(&entries)? + "\n",
+                    Format::Env => to_env(&entries),
+                };
+                if file.as_os_str() == "-" {
+                    print!("{out}");
+                } else {
+                    std::fs::write(&file, out)
+                        .with_context(|| format!("failed to write {}", file.display()))?;
+                    eprintln!("exported {} entries to {}", entries.len(), file.display());
+                }
+            }
+            MemoryCmd::Import {
+                file,
+                format,
+                force,
+            } => {
+                let text = super::read_path_or_stdin(&file)?;
+                let entries = match format {
+                    Format::Json => serde_json::from_str(&text)
+                        .with_context(|| format!("invalid memory JSON in {}", file.display()))?,
+                    Format::Env => from_env(&text)?,
+                };
+                let result = runner.import_memory(entries, force).await?;
+                println!("imported {} entries", result.imported);
+                if !result.skipped.is_empty() {
+                    eprintln!(
+                        "warning: skipped {} existing entries (use --force to overwrite): {}",
+                        result.skipped.len(),
+                        result.skipped.join(", ")
+                    );
+                }
+            }
+            MemoryCmd::Search {
+                query,
+                limit,
+                threshold,
+            } => {
+                let recall = runner.recall_memory(query, limit, threshold).await?;
+                if recall.hits.is_empty() {
+                    eprintln!("no matching entries");
+                }
+                for hit in recall.hits {
+                    let Some(entry) = hit.entry else { continue };
+                    println!("{:>8.3}  {} ({})", hit.score, entry.name, entry.kind);
+                    for line in entry.content.lines() {
+                        println!("          {line}");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Render entries as `name=content` lines, escaping backslashes and
+/// newlines so each entry stays on one line.
+fn to_env(entries: &[MemoryEntryInfo]) -> String {
+    let mut out = String::new();
+    for e in entries {
+        let content = e.content.replace('\\', "\\\\").replace('\n', "\\n");
+        out.push_str(&format!("{}={content}\n", e.name));
+    }
+    out
+}
+
+/// Parse `name=content` lines. Blank lines and `#` comments are skipped.
+fn from_env(text: &str) -> Result<Vec<MemoryEntryInfo>> {
+    let mut entries = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, content) = line
+            .split_once('=')
+            .with_context(|| format!("line {}: expected name=content", i + 1))?;
+        entries.push(MemoryEntryInfo {
+            name: name.trim().to_owned(),
+            content: unescape(content.trim()),
+            aliases: Vec::new(),
+            kind: "note".to_owned(),
+            created_at: 0,
+            access_count: 0,
+            expires_at: None,
+            pinned: false,
+        });
+    }
+    Ok(entries)
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
@@ -3,10 +3,12 @@
 use crate::repl::runner::Runner;
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use wcore::protocol::api::Client;
 #[cfg(feature = "daemon")]
 pub mod agent;
 pub mod console;
 pub mod mcp;
+pub mod memory;
 
 /// Crabtalk TUI — interactive agent client.
 #[derive(Parser, Debug)]
@@ -45,6 +47,11 @@ pub enum Command {
     Agent(agent::Agent),
     /// Manage MCP servers (create, list, delete).
     Mcp(mcp::Mcp),
+    /// Export or import memory entries.
+    Memory(memory::Memory),
+    /// Print the raw JSON of the daemon's latest LLM call. Needs
+    /// `debug = true` under `[llm]`.
+    LastExchange,
     /// Resume a previous conversation.
     Resume {
         /// Conversation file to resume. If omitted, shows a conversation picker.
@@ -120,6 +127,16 @@ impl Cli {
             }
             Some(Command::Agent(cmd)) => cmd.run(self.tcp).await,
             Some(Command::Mcp(cmd)) => cmd.run(self.tcp).await,
+            Some(Command::Memory(cmd)) => cmd.run(self.tcp).await,
+            Some(Command::LastExchange) => {
+                let mut runner = connect(self.tcp).await?;
+                let exchange = runner.get_llm_exchange().await?;
+                println!(
+                    "request:\n{}\n\nresponse:\n{}",
+                    exchange.request, exchange.response
+                );
+                Ok(())
+            }
             #[cfg(feature = "daemon")]
             Some(Command::Pull { plugin, force }) => {
                 let daemon = crabtalkd::Cli {
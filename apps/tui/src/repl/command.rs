@@ -2,7 +2,7 @@
 
 use anyhow::Result;
 
-pub const SLASH_COMMANDS: &[&str] = &["/clear", "/exit", "/help", "/resume"];
+pub const SLASH_COMMANDS: &[&str] = &["/clear", "/edit", "/exit", "/help", "/resume"];
 
 /// Collect matching `/command` and `/skill` names for the typed prefix.
 pub fn collect_candidates(line: &str, pos: usize, skill_names: &[String]) -> Vec<String> {
@@ -42,6 +42,8 @@ pub enum SlashResult {
     Clear,
     /// Open the conversation console.
     Resume,
+    /// Compose the next message in `$EDITOR`.
+    Edit,
 }
 
 /// Dispatch a slash command.
@@ -56,14 +58,18 @@ pub async fn handle_slash(line: &str) -> Result<SlashResult> {
     };
     match cmd {
         "clear" => return Ok(SlashResult::Clear),
+        "edit" => return Ok(SlashResult::Edit),
         "exit" => return Ok(SlashResult::Exit),
         "help" => {
             println!("Available commands:");
             println!("  /clear   — start a new conversation");
+            println!("  /edit    — compose a message in $EDITOR");
             println!("  /exit    — exit the REPL");
             println!("  /help    — show this help");
             println!("  /resume  — open conversation console");
             println!("  /<skill> — run a skill");
+            println!();
+            println!("End a line with \\ or open a \"\"\" block to keep typing across lines.");
         }
         "resume" => return Ok(SlashResult::Resume),
         _ => {
@@ -73,3 +79,26 @@ pub async fn handle_slash(line: &str) -> Result<SlashResult> {
     }
     Ok(SlashResult::Handled)
 }
+
+/// Open `$VISUAL` / `$EDITOR` (falling back to `vi`) on a scratch file and
+/// return what was saved, trimmed. The terminal must be out of raw mode.
+pub fn compose_in_editor() -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_owned());
+    let path = std::env::temp_dir().join(format!("crabtalk-{}.md", std::process::id()));
+    std::fs::write(&path, "")?;
+    // The editor may carry its own flags, e.g. `code --wait`.
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status();
+    let content = std::fs::read_to_string(&path).unwrap_or_default();
+    let _ = std::fs::remove_file(&path);
+    if !status?.success() {
+        anyhow::bail!("{editor} exited with an error");
+    }
+    Ok(content.trim().to_owned())
+}
@@ -12,6 +12,9 @@ use ratatui::{
 
 const MAX_DROPDOWN_VISIBLE: usize = 5;
 
+/// Opens and closes a fenced multi-line block.
+const FENCE: &str = "\"\"\"";
+
 /// Command history backed by a Vec.
 pub struct History {
     entries: Vec<String>,
@@ -130,6 +133,54 @@ impl InputBuffer {
         &self.lines[0]
     }
 
+    /// Enter continues instead of submitting when the cursor sits after a
+    /// trailing `\`, or while a `"""` block is still open.
+    fn continues(&self) -> bool {
+        let (row, col) = self.cursor;
+        let line = &self.lines[row];
+        if line.ends_with('\\') && col == line.chars().count() {
+            return true;
+        }
+        let content = self.content();
+        content
+            .trim_start()
+            .strip_prefix(FENCE)
+            .is_some_and(|rest| !rest.contains(FENCE))
+    }
+
+    /// Break the line at the cursor, dropping a trailing `\` continuation.
+    fn continue_line(&mut self) {
+        let (row, col) = self.cursor;
+        if col > 0 && self.lines[row].ends_with('\\') && col == self.lines[row].chars().count() {
+            self.lines[row].pop();
+            self.cursor.1 -= 1;
+        }
+        self.insert_newline();
+    }
+
+    /// The message to send: the buffer, or the body of a closed `"""` block.
+    fn message(&self) -> String {
+        let content = self.content();
+        let Some(body) = content.trim().strip_prefix(FENCE) else {
+            return content;
+        };
+        match body.find(FENCE) {
+            Some(end) => body[..end].trim_matches('\n').to_owned(),
+            None => content,
+        }
+    }
+
+    /// Insert `text` at the cursor, splitting lines on newlines.
+    fn insert_str(&mut self, text: &str) {
+        for ch in text.chars() {
+            match ch {
+                '\n' => self.insert_newline(),
+                '\r' => {}
+                ch => self.handle_key(event::KeyCode::Char(ch)),
+            }
+        }
+    }
+
     fn insert_newline(&mut self) {
         let (row, col) = self.cursor;
         let byte_pos = tui::char_to_byte(&self.lines[row], col);
@@ -335,11 +386,13 @@ impl InputState {
             event::KeyCode::Enter => {
                 if key.modifiers.contains(event::KeyModifiers::SHIFT) {
                     self.buf.insert_newline();
+                } else if self.buf.continues() {
+                    self.buf.continue_line();
                 } else {
-                    let content = self.buf.content();
-                    self.history.push(&content);
+                    self.history.push(&self.buf.content());
+                    let message = self.buf.message();
                     self.buf = InputBuffer::new();
-                    return InputAction::Submit(content);
+                    return InputAction::Submit(message);
                 }
             }
             event::KeyCode::Up => {
@@ -376,6 +429,13 @@ impl InputState {
         InputAction::Noop
     }
 
+    /// Insert pasted text as-is — embedded newlines never submit.
+    pub fn paste(&mut self, text: &str) {
+        self.close_dropdown();
+        self.buf.insert_str(text);
+        self.history.reset_cursor();
+    }
+
     fn handle_dropdown_key(&mut self, key: event::KeyEvent) -> InputAction {
         match key.code {
             event::KeyCode::Up => {
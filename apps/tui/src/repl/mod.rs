@@ -3,13 +3,18 @@
 use crate::repl::{
     ask::{AskAction, AskState},
     chat::ChatEntry,
-    command::{SlashResult, handle_slash},
+    command::{SlashResult, compose_in_editor, handle_slash},
     input::{History, InputAction, InputState},
     render::MarkdownRenderer,
-    runner::{ConnectionInfo, OutputChunk, Runner, send_reply},
+    runner::{ConnectionInfo, OutputChunk, Runner, send_decision, send_reply},
 };
 use anyhow::Result;
-use crossterm::event::{Event, EventStream, KeyCode, KeyModifiers};
+use crossterm::{
+    event::{
+        DisableBracketedPaste, EnableBracketedPaste, Event, EventStream, KeyCode, KeyModifiers,
+    },
+    execute,
+};
 use futures_util::StreamExt;
 use ratatui::{
     layout::{Constraint, Layout},
@@ -19,7 +24,10 @@ use ratatui::{
 };
 use std::{collections::VecDeque, path::PathBuf, pin::pin, time::Duration};
 use tokio::sync::mpsc;
-use wcore::protocol::api::Client;
+use wcore::protocol::{
+    api::Client,
+    message::{AskOption, AskQuestion, CancelMsg},
+};
 
 mod ask;
 pub mod chat;
@@ -99,6 +107,7 @@ impl ChatRepl {
             ask_state: None,
             ask_agent: None,
             ask_sender: None,
+            confirm_call: None,
         };
 
         // Push welcome banner as first chat entry.
@@ -108,8 +117,11 @@ impl ChatRepl {
         )]));
 
         let mut terminal = crate::tui::setup()?;
+        // Pastes arrive whole, so their newlines don't submit.
+        execute!(std::io::stdout(), EnableBracketedPaste)?;
         let result = run_event_loop(&mut terminal, &mut app).await;
 
+        execute!(std::io::stdout(), DisableBracketedPaste)?;
         crate::tui::teardown(&mut terminal)?;
 
         // Save history back.
@@ -182,6 +194,8 @@ struct App {
     ask_agent: Option<String>,
     /// Sender for the pending ask reply.
     ask_sender: Option<String>,
+    /// Tool call id when the ask modal is a tool confirmation.
+    confirm_call: Option<String>,
 }
 
 // ── Event loop ───────────────────────────────────────────────────
@@ -258,8 +272,24 @@ async fn run_event_loop(
                                 AskAction::Noop => {}
                                 AskAction::Cancelled => {
                                     app.ask_state = None;
-                                    app.ask_agent = None;
-                                    app.ask_sender = None;
+                                    let agent = app.ask_agent.take();
+                                    let sender = app.ask_sender.take();
+                                    if let (Some(call_id), Some(agent), Some(sender)) = (app.confirm_call.take(), agent, sender) {
+                                        let conn_info = app.conn_info.clone();
+                                        tokio::spawn(async move {
+                                            let _ = send_decision(&conn_info, agent, sender, call_id, false).await;
+                                        });
+                                    }
+                                }
+                                AskAction::Submitted(answers) if app.confirm_call.is_some() => {
+                                    let approve = answers.values().any(|a| a == CONFIRM_APPROVE);
+                                    if let (Some(call_id), Some(agent), Some(sender)) = (app.confirm_call.take(), app.ask_agent.take(), app.ask_sender.take()) {
+                                        let conn_info = app.conn_info.clone();
+                                        tokio::spawn(async move {
+                                            let _ = send_decision(&conn_info, agent, sender, call_id, approve).await;
+                                        });
+                                    }
+                                    app.ask_state = None;
                                 }
                                 AskAction::Submitted(answers) => {
                                     let reply = serde_json::to_string(&answers).unwrap_or_default();
@@ -290,11 +320,22 @@ async fn run_event_loop(
                             continue;
                         }
 
-                        // Ctrl+C during streaming: cancel stream.
+                        // Ctrl+C during streaming: cancel the turn and stop
+                        // rendering it.
                         if key.modifiers.contains(KeyModifiers::CONTROL)
                             && key.code == KeyCode::Char('c')
                             && app.streaming
                         {
+                            let conn_info = app.conn_info.clone();
+                            let req = CancelMsg {
+                                agent: app.agent.clone(),
+                                sender: app.os_user.clone(),
+                            };
+                            tokio::spawn(async move {
+                                if let Ok(mut runner) = Runner::connect_from(&conn_info).await {
+                                    let _ = runner.cancel(req).await;
+                                }
+                            });
                             app.renderer.finish();
                             chunk_rx = None;
                             app.streaming = false;
@@ -348,6 +389,32 @@ async fn run_event_loop(
                                             }
                                             *terminal = crate::tui::setup()?;
                                         }
+                                        SlashResult::Edit => {
+                                            crate::tui::teardown(terminal)?;
+                                            let composed = compose_in_editor();
+                                            *terminal = crate::tui::setup()?;
+                                            match composed {
+                                                Ok(message) if !message.is_empty() => {
+                                                    app.renderer.buffer.push(ChatEntry::Text(vec![
+                                                        Line::from(Span::styled(
+                                                            format!(" {message} "),
+                                                            Style::new().bg(Color::Indexed(236)),
+                                                        )),
+                                                        Line::raw(""),
+                                                    ]));
+                                                    send_or_queue(app, &mut chunk_rx, message);
+                                                }
+                                                Ok(_) => {}
+                                                Err(e) => {
+                                                    app.renderer.buffer.push(ChatEntry::Text(vec![
+                                                        Line::from(Span::styled(
+                                                            format!("  {e}"),
+                                                            Style::new().add_modifier(Modifier::DIM),
+                                                        )),
+                                                    ]));
+                                                }
+                                            }
+                                        }
                                         SlashResult::Clear => {
                                             app.renderer.buffer.clear();
                                             app.renderer = MarkdownRenderer::new();
@@ -386,6 +453,10 @@ async fn run_event_loop(
                             }
                         }
                     }
+                    Some(Ok(Event::Paste(text))) if app.ask_state.is_none() => {
+                        app.input.paste(&text);
+                        app.dirty = true;
+                    }
                     Some(Ok(Event::Resize(_, _))) => {
                         app.dirty = true;
                     }
@@ -494,6 +565,19 @@ fn handle_chunk(chunk: OutputChunk, app: &mut App) {
             app.ask_agent = Some(agent);
             app.ask_sender = Some(sender);
         }
+        OutputChunk::ConfirmTool {
+            call_id,
+            name,
+            arguments,
+            agent,
+            sender,
+        } => {
+            app.renderer.finish();
+            app.ask_state = Some(AskState::new(&[confirm_question(&name, &arguments)]));
+            app.ask_agent = Some(agent);
+            app.ask_sender = Some(sender);
+            app.confirm_call = Some(call_id);
+        }
         // Boundary markers — the renderer infers transitions from delta
         // arrival, so Start markers are inert. ThinkingEnd above is the
         // exception because it lets us flush thinking eagerly.
@@ -503,6 +587,28 @@ fn handle_chunk(chunk: OutputChunk, app: &mut App) {
     app.scroll = 0;
 }
 
+/// Option label that approves a tool confirmation.
+const CONFIRM_APPROVE: &str = "Approve";
+
+/// A single-choice question asking whether to run a tool call.
+fn confirm_question(name: &str, arguments: &str) -> AskQuestion {
+    AskQuestion {
+        question: format!("Run {name} {arguments}?"),
+        header: "Confirm".to_owned(),
+        options: vec![
+            AskOption {
+                label: CONFIRM_APPROVE.to_owned(),
+                description: "Run the tool call".to_owned(),
+            },
+            AskOption {
+                label: "Deny".to_owned(),
+                description: "Refuse it and tell the agent".to_owned(),
+            },
+        ],
+        multi_select: false,
+    }
+}
+
 // ── Drawing ──────────────────────────────────────────────────────
 
 fn draw(frame: &mut ratatui::Frame, app: &App) {
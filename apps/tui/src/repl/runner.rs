@@ -12,8 +12,8 @@ use wcore::protocol::{
     message::{
         ActiveConversationInfo, AgentEventMsg, AskQuestion, ClientMessage, InstallPluginMsg,
         KillMsg, ListActiveConversationsMsg, PluginEvent, ReplyToAsk, ServerMessage, StreamMsg,
-        SubscribeEvents, UninstallPluginMsg, client_message, plugin_event, server_message,
-        stream_event,
+        SubscribeEvents, ToolDecisionMsg, UninstallPluginMsg, client_message, plugin_event,
+        server_message, stream_event,
     },
 };
 
@@ -43,6 +43,14 @@ pub enum OutputChunk {
         agent: String,
         sender: String,
     },
+    /// A tool call is waiting for approval. Carries the call and agent identity.
+    ConfirmTool {
+        call_id: String,
+        name: String,
+        arguments: String,
+        agent: String,
+        sender: String,
+    },
 }
 
 pub use transport::Transport;
@@ -137,6 +145,9 @@ impl Runner {
                 cwd,
                 guest: None,
                 tool_choice: None,
+                instructions: None,
+                images: Vec::new(),
+                interactive: true,
             }))
             .take_while(|r| {
                 std::future::ready(!matches!(
@@ -179,6 +190,15 @@ impl Runner {
                             agent: state.0.clone(),
                             sender: state.1.clone(),
                         })),
+                        Some(stream_event::Event::ConfirmTool(c)) => {
+                            Some(Ok(OutputChunk::ConfirmTool {
+                                call_id: c.call_id.clone(),
+                                name: c.name.clone(),
+                                arguments: c.arguments.clone(),
+                                agent: state.0.clone(),
+                                sender: state.1.clone(),
+                            }))
+                        }
                         Some(stream_event::Event::UserSteered(_)) => None,
                         Some(stream_event::Event::End(end)) if !end.error.is_empty() => {
                             Some(Err(anyhow::anyhow!("{}", end.error)))
@@ -397,6 +417,27 @@ pub async fn send_reply(
         sender,
         content,
     });
+    send_once(conn_info, msg).await
+}
+
+/// Send a `ToolDecision` to the daemon on a temporary connection.
+pub async fn send_decision(
+    conn_info: &ConnectionInfo,
+    agent: String,
+    sender: String,
+    call_id: String,
+    approve: bool,
+) -> Result<()> {
+    let msg = ClientMessage::from(ToolDecisionMsg {
+        agent,
+        sender,
+        call_id,
+        approve,
+    });
+    send_once(conn_info, msg).await
+}
+
+async fn send_once(conn_info: &ConnectionInfo, msg: ClientMessage) -> Result<()> {
     match conn_info {
         #[cfg(unix)]
         ConnectionInfo::Uds(path) => {
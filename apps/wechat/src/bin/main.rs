@@ -42,6 +42,7 @@ fn ensure_config() -> anyhow::Result<()> {
             token,
             base_url,
             allowed_users: vec![],
+            max_message_len: None,
         };
         config.save(&path)?;
         println!("saved config to {}", path.display());
@@ -1,5 +1,6 @@
 //! WeChat bot configuration.
 
+use crate::Channel;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -20,6 +21,16 @@ pub struct WechatConfig {
     /// everyone else is silently ignored.
     #[serde(default)]
     pub allowed_users: Vec<String>,
+    /// Longest reply message in characters; longer replies are split.
+    /// Unset sends each reply whole.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_message_len: Option<usize>,
+}
+
+impl Channel for WechatConfig {
+    fn max_message_len(&self) -> Option<usize> {
+        self.max_message_len
+    }
 }
 
 impl WechatConfig {
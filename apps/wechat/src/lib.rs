@@ -143,6 +143,7 @@ pub async fn poll_loop(
                         sender_name: msg.from_user_id.clone(),
                         is_bot: false,
                         is_group: false,
+                        addressed: true,
                         content: text,
                         attachments: vec![],
                         reply_to: None,
@@ -2,7 +2,7 @@
 
 use crate::config::WechatConfig;
 use crate::{
-    ContextTokens, GatewayMessage, NodeClient, StreamAccumulator, StreamResult, UserIdMap,
+    Channel, Connect, ContextTokens, GatewayMessage, StreamAccumulator, StreamResult, UserIdMap,
 };
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::mpsc;
@@ -11,7 +11,7 @@ use wcore::protocol::message::{
 };
 
 /// Run the WeChat gateway service.
-pub async fn run(node_client: NodeClient, config: &WechatConfig) -> anyhow::Result<()> {
+pub async fn run(node_client: impl Connect, config: &WechatConfig) -> anyhow::Result<()> {
     let client = Arc::new(node_client);
 
     let agents_dir = wcore::paths::CONFIG_DIR.join(wcore::paths::AGENTS_DIR);
@@ -29,7 +29,7 @@ pub async fn run(node_client: NodeClient, config: &WechatConfig) -> anyhow::Resu
     Ok(())
 }
 
-async fn spawn_wechat(wc: &WechatConfig, agent: String, client: Arc<NodeClient>) {
+async fn spawn_wechat<C: Connect>(wc: &WechatConfig, agent: String, client: Arc<C>) {
     let (tx, rx) = mpsc::unbounded_channel::<GatewayMessage>();
     let ctx_tokens: ContextTokens = Arc::new(parking_lot::Mutex::new(HashMap::new()));
     let user_ids: UserIdMap = Arc::new(parking_lot::Mutex::new(HashMap::new()));
@@ -55,7 +55,15 @@ async fn spawn_wechat(wc: &WechatConfig, agent: String, client: Arc<NodeClient>)
     let base_url = wc.base_url.clone();
     let token = wc.token.clone();
     tokio::spawn(wechat_loop(
-        rx, agent, client, ctx_tokens, user_ids, allowed, base_url, token,
+        rx,
+        agent,
+        client,
+        ctx_tokens,
+        user_ids,
+        allowed,
+        base_url,
+        token,
+        wc.clone(),
     ));
     tracing::info!(platform = "wechat", "channel transport started");
 }
@@ -77,22 +85,28 @@ async fn reap_chat(chat: ChatStream) -> bool {
 }
 
 #[allow(clippy::too_many_arguments)]
-async fn wechat_loop(
+async fn wechat_loop<C: Connect>(
     mut rx: mpsc::UnboundedReceiver<GatewayMessage>,
     agent: String,
-    client: Arc<NodeClient>,
+    client: Arc<C>,
     ctx_tokens: ContextTokens,
     user_ids: UserIdMap,
     allowed_users: std::collections::HashSet<String>,
     base_url: String,
     token: String,
+    channel: WechatConfig,
 ) {
+    let channel = Arc::new(channel);
     let mut chats: HashMap<i64, ChatStream> = HashMap::new();
     let http = reqwest::Client::new();
 
     while let Some(msg) = rx.recv().await {
         let chat_id = msg.chat_id;
         let content = msg.content.clone();
+        if content.trim().is_empty() {
+            tracing::debug!(chat_id, "dropping empty message");
+            continue;
+        }
 
         // User whitelist check (using original user ID from reverse map).
         if !allowed_users.is_empty() {
@@ -130,10 +144,11 @@ async fn wechat_loop(
             let ctx_tokens = ctx_tokens.clone();
             let user_ids = user_ids.clone();
             let sender = sender.clone();
+            let channel = channel.clone();
             tokio::spawn(async move {
                 wx_stream(
                     &http,
-                    &client,
+                    client.as_ref(),
                     &agent,
                     chat_id,
                     &content,
@@ -143,6 +158,7 @@ async fn wechat_loop(
                     &token,
                     &ctx_tokens,
                     &user_ids,
+                    channel.as_ref(),
                 )
                 .await
             })
@@ -157,7 +173,7 @@ async fn wechat_loop(
 #[allow(clippy::too_many_arguments)]
 async fn wx_stream(
     http: &reqwest::Client,
-    client: &NodeClient,
+    client: &impl Connect,
     agent: &str,
     chat_id: i64,
     content: &str,
@@ -167,6 +183,7 @@ async fn wx_stream(
     token: &str,
     ctx_tokens: &ContextTokens,
     user_ids: &UserIdMap,
+    channel: &impl Channel,
 ) -> StreamResult {
     tracing::info!(agent, chat_id, %sender, "starting stream");
     let client_msg = ClientMessage::from(StreamMsg {
@@ -176,6 +193,9 @@ async fn wx_stream(
         cwd: None,
         guest: None,
         tool_choice: None,
+        instructions: None,
+        images: Vec::new(),
+        interactive: false,
     });
     let mut server_rx = client.send(client_msg).await;
     let mut acc = StreamAccumulator::new();
@@ -258,11 +278,17 @@ async fn wx_stream(
             .get(to_user.as_deref().unwrap_or(""))
             .cloned();
         if let (Some(to), Some(ct)) = (to_user, ctx) {
-            if let Err(e) =
-                crate::api::send_message(http, base_url, token, &to, &ct, &final_text).await
-            {
-                tracing::warn!(agent, chat_id, "failed to send reply: {e}");
-            } else {
+            let mut sent = true;
+            for chunk in channel.split(&final_text) {
+                if let Err(e) =
+                    crate::api::send_message(http, base_url, token, &to, &ct, &chunk).await
+                {
+                    tracing::warn!(agent, chat_id, "failed to send reply: {e}");
+                    sent = false;
+                    break;
+                }
+            }
+            if sent {
                 tracing::info!(agent, chat_id, "reply sent");
             }
         } else {
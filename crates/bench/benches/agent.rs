@@ -5,7 +5,7 @@ use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
 use futures_util::StreamExt;
 use std::{future::Future, pin::Pin, sync::Arc};
 use wcore::{
-    AgentBuilder, AgentConfig, ToolDispatcher, ToolFuture,
+    AgentBuilder, AgentConfig, CancellationToken, ToolDispatcher, ToolFuture,
     model::{HistoryEntry, Model},
     testing::provider::{TestProvider, text_chunks, tool_chunks},
 };
@@ -21,10 +21,12 @@ where
     fn dispatch<'a>(
         &'a self,
         _name: &'a str,
+        _call_id: &'a str,
         _args: &'a str,
         _agent: &'a str,
         _sender: &'a str,
         _conversation_id: Option<u64>,
+        _cancel: Option<CancellationToken>,
     ) -> ToolFuture<'a> {
         (self.0)()
     }
@@ -61,7 +63,7 @@ fn bench_agent_no_tools(c: &mut Criterion) {
             |(agent, mut history)| {
                 rt.block_on(async {
                     let mut stream =
-                        std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+                        std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
                     while stream.next().await.is_some() {}
                 });
             },
@@ -95,7 +97,7 @@ fn bench_agent_with_tools(c: &mut Criterion) {
             |(agent, mut history)| {
                 rt.block_on(async {
                     let mut stream =
-                        std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+                        std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
                     while stream.next().await.is_some() {}
                 });
             },
@@ -1,10 +1,14 @@
-//! Protocol codec benchmarks: encode, decode, and framed roundtrip.
+//! Protocol codec benchmarks: encode, decode, framed roundtrip, and
+//! compressed roundtrip of a large memory listing.
 
-use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
 use prost::Message;
 use wcore::protocol::{
-    codec::{read_message, write_message},
-    message::{ClientMessage, Ping, SendMsg, client_message},
+    codec::{Compression, read_message, write_message, write_message_with},
+    message::{
+        ClientMessage, MemoryEntryInfo, MemoryList, Ping, SendMsg, ServerMessage, client_message,
+        server_message,
+    },
 };
 
 fn make_message(content_size: usize) -> ClientMessage {
@@ -83,5 +87,69 @@ fn bench_roundtrip(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_encode, bench_decode, bench_roundtrip);
+fn make_memory_list(n: usize) -> ServerMessage {
+    let entries = (0..n)
+        .map(|i| MemoryEntryInfo {
+            name: format!("project-note-{i}"),
+            content: format!(
+                "The user prefers concise answers and works on the crabtalk daemon; note {i}."
+            ),
+            aliases: vec![format!("note-{i}")],
+            kind: "note".into(),
+            created_at: 1_700_000_000 + i as u64,
+            access_count: 0,
+            expires_at: None,
+            pinned: false,
+        })
+        .collect();
+    ServerMessage {
+        msg: Some(server_message::Msg::MemoryList(MemoryList {
+            entries,
+            next_cursor: None,
+            total: n as u64,
+        })),
+    }
+}
+
+fn bench_compressed_roundtrip(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let msg = make_memory_list(5000);
+    let mut group = c.benchmark_group("codec_memory_list_5000");
+    for (label, compression) in [
+        ("plain", None),
+        ("gzip", Some(Compression::Gzip)),
+        ("zstd", Some(Compression::Zstd)),
+    ] {
+        // Throughput is measured in wire bytes, so each variant's report
+        // carries the size of its frame.
+        let mut frame = Vec::new();
+        rt.block_on(write_message_with(&mut frame, &msg, compression))
+            .unwrap();
+        group.throughput(Throughput::Bytes(frame.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(label), &msg, |b, msg| {
+            b.iter(|| {
+                rt.block_on(async {
+                    let mut frame = Vec::new();
+                    write_message_with(&mut frame, msg, compression)
+                        .await
+                        .unwrap();
+                    let _: ServerMessage = read_message(&mut frame.as_slice()).await.unwrap();
+                })
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_encode,
+    bench_decode,
+    bench_roundtrip,
+    bench_compressed_roundtrip
+);
 criterion_main!(benches);
@@ -3,6 +3,9 @@ use std::io::Result;
 fn main() -> Result<()> {
     prost_build::Config::new()
         .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+        // Memory exports written before this field existed still import.
+        .field_attribute("MemoryEntryInfo.access_count", "#[serde(default)]")
+        .field_attribute("MemoryEntryInfo.pinned", "#[serde(default)]")
         .compile_protos(&["proto/crabtalk.proto"], &["proto/"])?;
     Ok(())
 }
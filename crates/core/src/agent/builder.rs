@@ -1,7 +1,11 @@
 //! Fluent builder for constructing an [`Agent`].
 
 use crate::{
-    agent::{Agent, config::AgentConfig, tool::ToolDispatcher},
+    agent::{
+        Agent, DEFAULT_MAX_PARALLEL_TOOLS,
+        config::AgentConfig,
+        tool::{ToolDispatcher, ToolTimeouts},
+    },
     model::Model,
 };
 use crabllm_core::{Provider, Tool};
@@ -16,6 +20,9 @@ pub struct AgentBuilder<P: Provider + 'static> {
     model: Model<P>,
     tools: Vec<Tool>,
     dispatcher: Option<Arc<dyn ToolDispatcher>>,
+    max_parallel_tools: usize,
+    tool_timeouts: Arc<ToolTimeouts>,
+    max_tool_calls: usize,
 }
 
 impl<P: Provider + 'static> AgentBuilder<P> {
@@ -26,6 +33,9 @@ impl<P: Provider + 'static> AgentBuilder<P> {
             model,
             tools: Vec::new(),
             dispatcher: None,
+            max_parallel_tools: DEFAULT_MAX_PARALLEL_TOOLS,
+            tool_timeouts: Arc::default(),
+            max_tool_calls: 0,
         }
     }
 
@@ -47,6 +57,26 @@ impl<P: Provider + 'static> AgentBuilder<P> {
         self
     }
 
+    /// Cap how many tool calls from one model round run at once
+    /// (default [`DEFAULT_MAX_PARALLEL_TOOLS`]). 0 = no cap.
+    pub fn max_parallel_tools(mut self, max: usize) -> Self {
+        self.max_parallel_tools = max;
+        self
+    }
+
+    /// Limit how long each tool call may run. No limit by default.
+    pub fn tool_timeouts(mut self, timeouts: Arc<ToolTimeouts>) -> Self {
+        self.tool_timeouts = timeouts;
+        self
+    }
+
+    /// Cap the tool calls one turn may make; 0 (the default) removes the
+    /// cap. A round that would go past it ends the turn with an error.
+    pub fn max_tool_calls(mut self, max: usize) -> Self {
+        self.max_tool_calls = max;
+        self
+    }
+
     /// Build the [`Agent`].
     pub fn build(self) -> Agent<P> {
         Agent {
@@ -54,6 +84,9 @@ impl<P: Provider + 'static> AgentBuilder<P> {
             model: self.model,
             tools: self.tools,
             dispatcher: self.dispatcher,
+            max_parallel_tools: self.max_parallel_tools,
+            tool_timeouts: self.tool_timeouts,
+            max_tool_calls: self.max_tool_calls,
         }
     }
 }
@@ -1,7 +1,8 @@
 //! Context compaction — summarize conversation history and replace it.
 
 use crate::model::HistoryEntry;
-use crabllm_core::{ChatCompletionRequest, Message, Provider, Role};
+use crabllm_core::{ChatCompletionRequest, Message, Provider, Role, Usage};
+use std::time::Duration;
 
 pub(crate) const COMPACT_PROMPT: &str = include_str!("../../prompts/compact.md");
 
@@ -10,8 +11,27 @@ impl<P: Provider + 'static> super::Agent<P> {
     ///
     /// Builds the base compact prompt, lets the `compact_hook` (if any) enrich
     /// it, then sends the history with the enriched prompt as system message.
-    /// Returns the summary text, or `None` if the model produces no content.
+    /// Returns the summary text, or `None` if the model produces no content,
+    /// the call fails, or it exceeds [`AgentConfig::compact_timeout`].
+    ///
+    /// [`AgentConfig::compact_timeout`]: crate::AgentConfig::compact_timeout
+    #[tracing::instrument(
+        name = "compact",
+        skip_all,
+        fields(agent = %self.config.name, messages = history.len())
+    )]
     pub async fn compact(&self, history: &[HistoryEntry]) -> Option<String> {
+        self.compact_with_usage(history)
+            .await
+            .map(|(summary, _)| summary)
+    }
+
+    /// [`Agent::compact`](super::Agent::compact), also returning the
+    /// tokens the summary call spent.
+    pub(crate) async fn compact_with_usage(
+        &self,
+        history: &[HistoryEntry],
+    ) -> Option<(String, Usage)> {
         let model_name = self.config.model.clone();
         let prompt = COMPACT_PROMPT.to_owned();
 
@@ -57,8 +77,25 @@ impl<P: Provider + 'static> super::Agent<P> {
             anthropic_max_tokens: None,
             extra: Default::default(),
         };
-        match self.model.send_ct(request).await {
-            Ok(response) => response.content().map(|s| s.to_owned()),
+        let send = self.model.send_ct(request);
+        let result = match self.config.compact_timeout.filter(|&secs| secs > 0) {
+            Some(secs) => match tokio::time::timeout(Duration::from_secs(secs), send).await {
+                Ok(result) => result,
+                Err(_) => {
+                    tracing::warn!(
+                        timeout_secs = secs,
+                        "compaction LLM call timed out, continuing with uncompacted history"
+                    );
+                    return None;
+                }
+            },
+            None => send.await,
+        };
+        match result {
+            Ok(response) => {
+                let usage = response.usage.clone().unwrap_or_default();
+                response.content().map(|s| (s.to_owned(), usage))
+            }
             Err(e) => {
                 tracing::warn!("compaction LLM call failed: {e}");
                 None
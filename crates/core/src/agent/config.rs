@@ -15,6 +15,12 @@ const DEFAULT_COMPACT_THRESHOLD: usize = 100_000;
 /// Default max byte length for tool results during compaction.
 const DEFAULT_COMPACT_TOOL_MAX_LEN: usize = 1024;
 
+/// Default timeout in seconds for the compaction LLM call.
+const DEFAULT_COMPACT_TIMEOUT: u64 = 60;
+
+/// Default retries after malformed tool-call arguments.
+const DEFAULT_TOOL_ARG_RETRIES: u32 = 1;
+
 /// Serializable agent configuration.
 ///
 /// Contains all parameters for an agent: identity, system prompt, model,
@@ -48,12 +54,40 @@ pub struct AgentConfig {
     /// Whether to enable thinking/reasoning mode.
     #[serde(default)]
     pub thinking: bool,
+    /// Fixed sampling seed forwarded to the provider. With identical
+    /// inputs, a seeded request should reproduce the same output on
+    /// providers that honor it — useful when chasing nondeterministic
+    /// replies. Greedy decoding (temperature 0) is already deterministic
+    /// and doesn't need one. None = provider default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Nucleus sampling cutoff forwarded to the provider. None = provider
+    /// default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    /// Sequences that end the reply when generated; the sequence itself
+    /// is not included. Empty = none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// Penalty on tokens by how often they already appeared. Ignored by
+    /// providers without one (e.g. Anthropic). None = provider default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f64>,
+    /// Penalty on tokens that already appeared at all. Ignored by
+    /// providers without one (e.g. Anthropic). None = provider default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f64>,
     /// Skill names this agent can access. Empty = all skills (crabtalk default).
     #[serde(default)]
     pub skills: Vec<String>,
     /// MCP server names this agent can access. Empty = all MCPs (crabtalk default).
     #[serde(default)]
     pub mcps: Vec<String>,
+    /// Abort the turn when the model calls a tool this agent wasn't
+    /// given, instead of answering the call with an error result. Such a
+    /// call usually means a schema or registration bug. Defaults to false.
+    #[serde(default)]
+    pub strict_tools: bool,
     /// Computed tool whitelist. Empty = all tools. Not serialized.
     #[serde(skip)]
     pub tools: Vec<String>,
@@ -66,6 +100,39 @@ pub struct AgentConfig {
     /// Longer results are truncated before sending to the compaction LLM.
     #[serde(default = "default_compact_tool_max_len")]
     pub compact_tool_max_len: usize,
+    /// Timeout in seconds for the compaction LLM call. A slow or hung
+    /// provider skips the summary and the turn proceeds with the raw
+    /// (possibly over-limit) history. None or 0 = wait indefinitely.
+    /// Defaults to 60.
+    #[serde(default = "default_compact_timeout")]
+    pub compact_timeout: Option<u64>,
+    /// Wall-clock budget in seconds for a whole turn — every model call
+    /// and tool dispatch in the loop together. When it runs out the turn
+    /// stops with `TurnTimeout` and whatever text it produced so far.
+    /// The provider's per-call timeout still bounds each request on its
+    /// own; this caps their sum. None = no limit (the default).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_timeout: Option<u64>,
+    /// Rounds a turn may retry after the model sends tool-call
+    /// arguments that are not valid JSON. Each bad call gets an error
+    /// result carrying the parse error instead of being dispatched; one
+    /// more malformed round than this ends the turn. Defaults to 1.
+    #[serde(default = "default_tool_arg_retries")]
+    pub tool_arg_retries: u32,
+    /// Whether `{{name}}` placeholders in the system prompt are filled in
+    /// at the start of each turn. Off by default so literal braces in
+    /// existing prompts are sent as written.
+    #[serde(default)]
+    pub prompt_vars: PromptVars,
+    /// The system prompt as written, before hooks add their fragments.
+    /// Set by the runtime for agents with [`PromptVars`] on; only this
+    /// text is rendered each turn.
+    #[serde(skip)]
+    pub prompt_template: String,
+    /// Constrain the final reply to JSON matching a schema. A reply that
+    /// does not parse as JSON gets one retry. None = free-form text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
     /// Hook configuration for this agent (bash deny rules, memory recall
     /// limit, etc.). Each agent owns its own hook state — there is no
     /// global override.
@@ -73,6 +140,44 @@ pub struct AgentConfig {
     pub hooks: HooksConfig,
 }
 
+/// How `{{name}}` placeholders in an agent's system prompt are handled.
+/// See [`render_prompt`](super::template::render_prompt) for the syntax.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptVars {
+    /// Send the prompt as written.
+    #[default]
+    Off,
+    /// Fill in known variables; unknown ones render empty.
+    Lenient,
+    /// Fill in known variables; an unknown one fails the turn.
+    Strict,
+}
+
+/// JSON schema an agent's final reply must match.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseFormat {
+    /// The schema, as a JSON Schema object.
+    pub schema: serde_json::Value,
+    /// How the schema reaches the provider. Defaults to `native`.
+    #[serde(default)]
+    pub mode: ResponseMode,
+}
+
+/// How a [`ResponseFormat`] is enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseMode {
+    /// Sent as the OpenAI `json_schema` response format.
+    #[default]
+    Native,
+    /// For providers without structured output (e.g. Anthropic): the
+    /// model is forced to call a synthetic tool whose parameters are the
+    /// schema, and the call's arguments become the reply. Other tools
+    /// are unavailable while the call is forced.
+    Tool,
+}
+
 fn default_max_iterations() -> usize {
     DEFAULT_MAX_ITERATIONS
 }
@@ -85,6 +190,14 @@ fn default_compact_tool_max_len() -> usize {
     DEFAULT_COMPACT_TOOL_MAX_LEN
 }
 
+fn default_compact_timeout() -> Option<u64> {
+    Some(DEFAULT_COMPACT_TIMEOUT)
+}
+
+fn default_tool_arg_retries() -> u32 {
+    DEFAULT_TOOL_ARG_RETRIES
+}
+
 impl Default for AgentConfig {
     fn default() -> Self {
         Self {
@@ -96,11 +209,23 @@ impl Default for AgentConfig {
             max_iterations: DEFAULT_MAX_ITERATIONS,
             tool_choice: ToolChoice::Auto,
             thinking: false,
+            seed: None,
+            top_p: None,
+            stop: Vec::new(),
+            frequency_penalty: None,
+            presence_penalty: None,
             skills: Vec::new(),
             mcps: Vec::new(),
+            strict_tools: false,
             tools: Vec::new(),
             compact_threshold: default_compact_threshold(),
             compact_tool_max_len: DEFAULT_COMPACT_TOOL_MAX_LEN,
+            compact_timeout: default_compact_timeout(),
+            turn_timeout: None,
+            tool_arg_retries: DEFAULT_TOOL_ARG_RETRIES,
+            prompt_vars: PromptVars::Off,
+            prompt_template: String::new(),
+            response_format: None,
             hooks: HooksConfig::default(),
         }
     }
@@ -140,4 +265,40 @@ impl AgentConfig {
         self.thinking = enabled;
         self
     }
+
+    /// Pin the sampling seed for reproducible generations.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the nucleus sampling cutoff.
+    pub fn top_p(mut self, top_p: f64) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Set the stop sequences.
+    pub fn stop(mut self, stop: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.stop = stop.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the frequency penalty.
+    pub fn frequency_penalty(mut self, penalty: f64) -> Self {
+        self.frequency_penalty = Some(penalty);
+        self
+    }
+
+    /// Set the presence penalty.
+    pub fn presence_penalty(mut self, penalty: f64) -> Self {
+        self.presence_penalty = Some(penalty);
+        self
+    }
+
+    /// The stop sequences in request form; `None` when there are none so
+    /// the field is left out of the request.
+    pub fn stop_sequences(&self) -> Option<crabllm_core::Stop> {
+        (!self.stop.is_empty()).then(|| crabllm_core::Stop::Multiple(self.stop.clone()))
+    }
 }
@@ -57,8 +57,9 @@ pub enum AgentEvent {
     ToolCallsComplete,
     /// User steering message injected at turn boundary.
     UserSteered { content: String },
-    /// Context was compacted — carries the compaction summary.
-    Compact { summary: String },
+    /// Context was compacted — carries the compaction summary and how
+    /// many history entries it replaced.
+    Compact { summary: String, replaced: usize },
     /// Agent finished with final response.
     Done(AgentResponse),
 }
@@ -97,6 +98,9 @@ pub struct AgentResponse {
     pub stop_reason: AgentStopReason,
     /// The requested model name (from config, not the API-echoed value).
     pub model: String,
+    /// Tokens spent on every provider call in the turn, summed. Unlike
+    /// the per-step usage, this includes compaction calls.
+    pub usage: Usage,
 }
 
 impl AgentResponse {
@@ -108,8 +112,38 @@ impl AgentResponse {
             iterations: 0,
             stop_reason: AgentStopReason::Error(msg.into()),
             model: String::new(),
+            usage: Usage::default(),
+        }
+    }
+}
+
+/// Add one provider call's `round` usage onto `total`. Optional counts
+/// stay `None` until some call reports them.
+pub fn add_usage(total: &mut Usage, round: &Usage) {
+    fn add(total: &mut Option<u32>, round: Option<u32>) {
+        if let Some(n) = round {
+            *total = Some(total.unwrap_or(0) + n);
         }
     }
+    total.prompt_tokens += round.prompt_tokens;
+    total.completion_tokens += round.completion_tokens;
+    total.total_tokens += round.total_tokens;
+    add(
+        &mut total.prompt_cache_hit_tokens,
+        round.prompt_cache_hit_tokens,
+    );
+    add(
+        &mut total.prompt_cache_miss_tokens,
+        round.prompt_cache_miss_tokens,
+    );
+    if let Some(reasoning) = round
+        .completion_tokens_details
+        .as_ref()
+        .and_then(|d| d.reasoning_tokens)
+    {
+        let details = total.completion_tokens_details.get_or_insert_default();
+        add(&mut details.reasoning_tokens, Some(reasoning));
+    }
 }
 
 /// Why the agent stopped executing.
@@ -121,6 +155,10 @@ pub enum AgentStopReason {
     MaxIterations,
     /// No tool calls and no text response.
     NoAction,
+    /// The turn ran past `AgentConfig::turn_timeout`.
+    TurnTimeout,
+    /// The caller cancelled the turn's token.
+    Cancelled,
     /// Error during execution.
     Error(String),
 }
@@ -131,6 +169,8 @@ impl std::fmt::Display for AgentStopReason {
             Self::TextResponse => write!(f, "text_response"),
             Self::MaxIterations => write!(f, "max_iterations"),
             Self::NoAction => write!(f, "no_action"),
+            Self::TurnTimeout => write!(f, "turn_timeout"),
+            Self::Cancelled => write!(f, "cancelled"),
             Self::Error(msg) => write!(f, "error: {msg}"),
         }
     }
@@ -11,23 +11,56 @@ use crate::model::{HistoryEntry, MessageBuilder, Model};
 use anyhow::Result;
 use async_stream::stream;
 pub use builder::AgentBuilder;
-pub use config::AgentConfig;
+pub use config::{AgentConfig, PromptVars, ResponseFormat, ResponseMode};
 use crabllm_core::{ChatCompletionRequest, Provider, Role, Tool, ToolCall, ToolChoice, Usage};
-use event::{AgentEvent, AgentResponse, AgentStep, AgentStopReason};
+use event::{AgentEvent, AgentResponse, AgentStep, AgentStopReason, add_usage};
 use futures_core::Stream;
-use futures_util::{StreamExt, future::join_all, stream::FuturesUnordered};
+use futures_util::{StreamExt, stream::iter as stream_iter};
 pub use id::AgentId;
-use std::sync::Arc;
-use tokio::sync::{mpsc, watch};
-pub use tool::{AsTool, ToolDispatcher};
+use std::{future::Future, sync::Arc, time::Duration};
+use tokio::{
+    sync::{mpsc, watch},
+    time::Instant,
+};
+use tokio_util::sync::CancellationToken;
+pub use tool::{AsTool, ToolDispatcher, ToolTimeouts};
+use tracing::Instrument;
+
+/// Default cap on tool calls from one model round running at once.
+pub const DEFAULT_MAX_PARALLEL_TOOLS: usize = 8;
+
+/// Synthetic tool the model is forced to call under
+/// [`ResponseMode::Tool`]; its arguments are the reply.
+const RESPONSE_TOOL: &str = "respond";
 
 mod builder;
 mod compact;
 pub mod config;
 pub mod event;
 mod id;
+pub mod template;
 pub mod tool;
 
+/// Pop a trailing text-only assistant entry off `history`. Such an
+/// entry is a prefill: the model continues it instead of replying after
+/// it.
+fn take_prefill(history: &mut Vec<HistoryEntry>) -> Option<String> {
+    let last = history.last()?;
+    if *last.role() != Role::Assistant || !last.tool_calls().is_empty() || last.text().is_empty() {
+        return None;
+    }
+    history.pop().map(|e| e.text().to_owned())
+}
+
+/// Join a prefill onto the front of the model's continuation.
+fn prepend_prefill(message: &mut crabllm_core::Message, prefill: Option<String>) {
+    let Some(mut text) = prefill else { return };
+    if let Some(rest) = message.content.as_ref().and_then(|v| v.as_str()) {
+        text.push_str(rest);
+    }
+    message.content = Some(serde_json::Value::String(text));
+}
+
 /// A neutral placeholder assistant message returned by `step()` when the
 /// provider yields zero choices. Used only as a step record so callers see
 /// an empty AgentStep instead of a panic; nothing is appended to history.
@@ -53,6 +86,32 @@ fn last_sender(history: &[HistoryEntry]) -> String {
         .unwrap_or_default()
 }
 
+/// Await `fut` unless `deadline` passes or `cancel` fires first, in
+/// which case `fut` is dropped and the reason the turn stops is returned.
+/// A `None` deadline or token never interrupts.
+async fn within<F: Future>(
+    deadline: Option<Instant>,
+    cancel: Option<&CancellationToken>,
+    fut: F,
+) -> Result<F::Output, AgentStopReason> {
+    let timed = async {
+        match deadline {
+            Some(at) => tokio::time::timeout_at(at, fut)
+                .await
+                .map_err(|_| AgentStopReason::TurnTimeout),
+            None => Ok(fut.await),
+        }
+    };
+    match cancel {
+        Some(token) => tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(AgentStopReason::Cancelled),
+            out = timed => out,
+        },
+        None => timed.await,
+    }
+}
+
 /// Borrow the inner string from a tool-dispatch result regardless of
 /// success/error. The LLM wire format (crabllm-core `Message`) has no
 /// `is_error` flag, so the agent collapses both arms to a plain string
@@ -64,6 +123,33 @@ fn tool_output_text(result: &Result<String, String>) -> &str {
     }
 }
 
+/// Span for one model call. Token counts are recorded once the call
+/// finishes; see [`record_usage`].
+fn llm_span(model: &str, iteration: usize) -> tracing::Span {
+    tracing::info_span!(
+        "llm",
+        model,
+        iteration,
+        prompt_tokens = tracing::field::Empty,
+        completion_tokens = tracing::field::Empty,
+    )
+}
+
+fn record_usage(span: &tracing::Span, usage: &Usage) {
+    span.record("prompt_tokens", usage.prompt_tokens);
+    span.record("completion_tokens", usage.completion_tokens);
+}
+
+/// Parse error of a call's JSON arguments, or `None` when they parse.
+/// Empty arguments pass: some providers send `""` for a call with no
+/// parameters.
+fn invalid_arguments(args: &str) -> Option<serde_json::Error> {
+    if args.trim().is_empty() {
+        return None;
+    }
+    serde_json::from_str::<serde::de::IgnoredAny>(args).err()
+}
+
 /// An immutable agent definition.
 ///
 /// Generic over `P: crabllm_core::Provider` — holds a `Model<P>` wrapper
@@ -77,10 +163,18 @@ pub struct Agent<P: Provider + 'static> {
     pub config: AgentConfig,
     /// The model wrapper for LLM calls.
     model: Model<P>,
-    /// Tool schemas advertised to the LLM. Set once at build time.
+    /// Tool schemas advertised to the LLM. Set at build time; per-turn
+    /// clones may be widened with [`Agent::extend_tools`].
     tools: Vec<Tool>,
     /// Dispatcher for tool calls. None = no tools.
     dispatcher: Option<Arc<dyn ToolDispatcher>>,
+    /// Tool calls from one model round run at most this many at a time.
+    /// 0 = no cap.
+    max_parallel_tools: usize,
+    /// How long each tool call may run.
+    tool_timeouts: Arc<ToolTimeouts>,
+    /// Most tool calls one turn may make. 0 = no cap.
+    max_tool_calls: usize,
 }
 
 impl<P: Provider + 'static> Clone for Agent<P> {
@@ -90,11 +184,74 @@ impl<P: Provider + 'static> Clone for Agent<P> {
             model: self.model.clone(),
             tools: self.tools.clone(),
             dispatcher: self.dispatcher.clone(),
+            max_parallel_tools: self.max_parallel_tools,
+            tool_timeouts: self.tool_timeouts.clone(),
+            max_tool_calls: self.max_tool_calls,
         }
     }
 }
 
 impl<P: Provider + 'static> Agent<P> {
+    /// Point the agent at a different model. Clones taken earlier keep
+    /// the model they were cloned with.
+    pub fn set_model(&mut self, model: Model<P>) {
+        self.model = model;
+    }
+
+    /// Advertise extra tool schemas, skipping names already present.
+    /// Meant for a per-turn clone — the registered agent is unaffected.
+    pub fn extend_tools(&mut self, tools: Vec<Tool>) {
+        for tool in tools {
+            if !self
+                .tools
+                .iter()
+                .any(|t| t.function.name == tool.function.name)
+            {
+                self.tools.push(tool);
+            }
+        }
+    }
+
+    /// Whether `name` is among the tool schemas this agent advertises.
+    /// An agent with no schemas leaves the decision to its dispatcher,
+    /// matching the "empty = all tools" whitelist convention.
+    fn offers(&self, name: &str) -> bool {
+        self.tools.is_empty() || self.tools.iter().any(|t| t.function.name == name)
+    }
+
+    /// The first call naming a tool this agent wasn't given, as the
+    /// error that aborts a strict agent's turn.
+    fn strict_violation(&self, calls: &[ToolCall]) -> Option<String> {
+        if !self.config.strict_tools {
+            return None;
+        }
+        calls
+            .iter()
+            .find(|tc| !self.offers(&tc.function.name))
+            .map(|tc| format!("model called unavailable tool '{}'", tc.function.name))
+    }
+
+    /// The error that ends a turn when running `calls` on top of the
+    /// `made` calls so far would exceed `max_tool_calls`.
+    fn call_limit_violation(&self, made: usize, calls: &[ToolCall]) -> Option<String> {
+        let cap = self.max_tool_calls;
+        if cap == 0 || made + calls.len() <= cap {
+            return None;
+        }
+        let last = calls.last().map_or("", |tc| tc.function.name.as_str());
+        Some(format!(
+            "tool call limit of {cap} reached after {made} calls (last requested: '{last}')"
+        ))
+    }
+
+    /// How many of `calls` tool calls may run at once.
+    fn parallel_limit(&self, calls: usize) -> usize {
+        match self.max_parallel_tools {
+            0 => calls.max(1),
+            cap => cap.min(calls.max(1)),
+        }
+    }
+
     /// Resolve the model name from agent config.
     fn model_name(&self) -> String {
         self.config.model.clone()
@@ -120,35 +277,90 @@ impl<P: Provider + 'static> Agent<P> {
         }
         messages.extend(history.iter().map(|e| e.to_wire_message()));
 
-        let tool_choice = tool_choice_override
+        let mut tools = self.tools.clone();
+        let mut tool_choice = tool_choice_override
             .cloned()
             .unwrap_or_else(|| self.config.tool_choice.clone());
+        let mut extra = serde_json::Map::new();
+        match &self.config.response_format {
+            Some(format) if format.mode == ResponseMode::Tool => {
+                tools.push(Tool {
+                    kind: crabllm_core::ToolType::Function,
+                    function: crabllm_core::FunctionDef {
+                        name: RESPONSE_TOOL.to_owned(),
+                        description: Some("Give your final reply.".to_owned()),
+                        parameters: Some(format.schema.clone()),
+                    },
+                    strict: None,
+                });
+                tool_choice = ToolChoice::Function {
+                    name: RESPONSE_TOOL.to_owned(),
+                };
+            }
+            Some(format) => {
+                extra.insert(
+                    "response_format".to_owned(),
+                    serde_json::json!({
+                        "type": "json_schema",
+                        "json_schema": { "name": "response", "schema": format.schema },
+                    }),
+                );
+            }
+            None => {}
+        }
 
         ChatCompletionRequest {
             model: model_name,
             messages,
             temperature: None,
-            top_p: None,
+            top_p: self.config.top_p,
             max_tokens: None,
             stream: None,
-            stop: None,
-            tools: if self.tools.is_empty() {
-                None
-            } else {
-                Some(self.tools.clone())
-            },
+            stop: self.config.stop_sequences(),
+            tools: if tools.is_empty() { None } else { Some(tools) },
             tool_choice: Some(tool_choice),
-            frequency_penalty: None,
-            presence_penalty: None,
-            seed: None,
+            frequency_penalty: self.config.frequency_penalty,
+            presence_penalty: self.config.presence_penalty,
+            seed: self.config.seed,
             user: None,
             reasoning_effort: self.config.thinking.then(|| "high".to_string()),
             thinking: None,
             anthropic_max_tokens: None,
-            extra: Default::default(),
+            extra,
         }
     }
 
+    /// Under [`ResponseMode::Tool`], turn the forced call to the response
+    /// tool into a plain reply carrying its arguments, so it ends the turn
+    /// instead of being dispatched.
+    fn unwrap_response_tool(&self, message: &mut crabllm_core::Message) {
+        let Some(ResponseFormat {
+            mode: ResponseMode::Tool,
+            ..
+        }) = &self.config.response_format
+        else {
+            return;
+        };
+        let Some(call) = message
+            .tool_calls
+            .as_ref()
+            .and_then(|calls| calls.iter().find(|tc| tc.function.name == RESPONSE_TOOL))
+        else {
+            return;
+        };
+        message.content = Some(serde_json::Value::String(call.function.arguments.clone()));
+        message.tool_calls = None;
+    }
+
+    /// Why `reply` fails this agent's [`ResponseFormat`], if it has one.
+    /// Only parsing is checked; the schema itself is left to the provider.
+    fn invalid_json_reply(&self, reply: Option<&str>) -> Option<String> {
+        self.config.response_format.as_ref()?;
+        serde_json::from_str::<serde_json::Value>(reply.unwrap_or_default())
+            .err()
+            .map(|e| e.to_string())
+    }
+
     /// Perform a single LLM round: send request, dispatch tools, return step.
     ///
     /// Composes a [`ChatCompletionRequest`] from config state (system prompt +
@@ -160,39 +372,53 @@ impl<P: Provider + 'static> Agent<P> {
         conversation_id: Option<u64>,
     ) -> Result<AgentStep> {
         let request = self.build_request(history, None);
-        let response = self.model.send_ct(request).await?;
-        let tool_calls: Vec<ToolCall> = response.tool_calls().to_vec();
+        let span = llm_span(&request.model, 0);
+        let response = self.model.send_ct(request).instrument(span.clone()).await?;
         let finish_reason = response.finish_reason().cloned();
         let usage = response.usage.clone().unwrap_or_default();
+        record_usage(&span, &usage);
 
         // If the provider returned zero choices, there is no message to record
         // — match the old `step()` behavior of not appending anything in that
         // case, instead of bloating history with a synthetic empty assistant
         // entry on flaky providers.
-        let Some(message) = response.message().cloned() else {
+        let Some(mut message) = response.message().cloned() else {
             return Ok(AgentStep {
                 message: empty_assistant_message(),
                 usage,
                 finish_reason,
-                tool_calls,
+                tool_calls: response.tool_calls().to_vec(),
                 tool_results: Vec::new(),
             });
         };
+        self.unwrap_response_tool(&mut message);
+        let tool_calls: Vec<ToolCall> = message.tool_calls.clone().unwrap_or_default();
 
+        if let Some(e) = self.strict_violation(&tool_calls) {
+            anyhow::bail!(e);
+        }
         history.push(HistoryEntry::from_message(message.clone()));
 
         let mut tool_results = Vec::new();
         if !tool_calls.is_empty() {
             let sender = last_sender(history);
-            let outputs = join_all(tool_calls.iter().map(|tc| {
-                self.dispatch_tool(
-                    &tc.function.name,
-                    &tc.function.arguments,
-                    &sender,
-                    conversation_id,
-                )
-            }))
-            .await;
+            let dispatches: Vec<_> = tool_calls
+                .iter()
+                .map(|tc| {
+                    self.dispatch_tool(
+                        &tc.function.name,
+                        &tc.id,
+                        &tc.function.arguments,
+                        &sender,
+                        conversation_id,
+                        None,
+                    )
+                })
+                .collect();
+            let outputs: Vec<_> = stream_iter(dispatches)
+                .buffered(self.parallel_limit(tool_calls.len()))
+                .collect()
+                .await;
             for (tc, result) in tool_calls.iter().zip(outputs) {
                 let entry =
                     HistoryEntry::tool(tool_output_text(&result), tc.id.clone(), &tc.function.name);
@@ -213,24 +439,75 @@ impl<P: Provider + 'static> Agent<P> {
     /// Dispatch a single tool call via the configured [`ToolDispatcher`].
     ///
     /// Returns `Ok(output)` for normal tool output or `Err(message)` for a
-    /// failure. If no dispatcher is configured, returns an `Err` describing
-    /// the misconfiguration; otherwise the dispatcher's verdict is forwarded
-    /// unchanged.
+    /// failure. Only tools this agent advertises are dispatched — a call
+    /// naming any other tool gets an `Err`, even if the dispatcher could
+    /// run it. So does a call whose arguments are not valid JSON; the
+    /// parse error tells the model what to fix. If no dispatcher is
+    /// configured, returns an `Err` describing the misconfiguration. A
+    /// call the dispatcher does not [approve](ToolDispatcher::approve)
+    /// gets its refusal. Otherwise the dispatcher's verdict is forwarded
+    /// unchanged, unless the call outlives its [`ToolTimeouts`] limit —
+    /// then the handler future is dropped and the model is told the tool
+    /// timed out. The limit starts once the call is approved.
+    #[tracing::instrument(
+        name = "tool",
+        skip_all,
+        fields(tool = name, call_id, agent = %self.config.name, is_error)
+    )]
     async fn dispatch_tool(
         &self,
         name: &str,
+        call_id: &str,
         args: &str,
         sender: &str,
         conversation_id: Option<u64>,
+        cancel: Option<CancellationToken>,
     ) -> Result<String, String> {
+        if !self.offers(name) {
+            return Err(format!("tool not available: {name}"));
+        }
+        if let Some(e) = invalid_arguments(args) {
+            return Err(format!(
+                "invalid JSON arguments for {name}: {e}. Call the tool again with valid JSON."
+            ));
+        }
         let Some(dispatcher) = &self.dispatcher else {
             return Err(format!(
                 "tool '{name}' called but no tool dispatcher configured"
             ));
         };
         dispatcher
-            .dispatch(name, args, &self.config.name, sender, conversation_id)
-            .await
+            .approve(
+                name,
+                call_id,
+                args,
+                &self.config.name,
+                sender,
+                conversation_id,
+                cancel.clone(),
+            )
+            .await?;
+        let call = dispatcher.dispatch(
+            name,
+            call_id,
+            args,
+            &self.config.name,
+            sender,
+            conversation_id,
+            cancel,
+        );
+        let result = match self.tool_timeouts.for_tool(name) {
+            Some(limit) => tokio::time::timeout(limit, call).await.unwrap_or_else(|_| {
+                tracing::warn!(tool = name, call_id, ?limit, "tool call timed out");
+                Err(format!(
+                    "tool '{name}' timed out after {}s",
+                    limit.as_secs_f64()
+                ))
+            }),
+            None => call.await,
+        };
+        tracing::Span::current().record("is_error", result.is_err());
+        result
     }
 
     /// Determine the stop reason for a step with no tool calls.
@@ -258,9 +535,10 @@ impl<P: Provider + 'static> Agent<P> {
         events: mpsc::UnboundedSender<AgentEvent>,
         conversation_id: Option<u64>,
         tool_choice: Option<ToolChoice>,
+        cancel: Option<CancellationToken>,
     ) -> AgentResponse {
         let mut stream =
-            std::pin::pin!(self.run_stream(history, conversation_id, None, tool_choice));
+            std::pin::pin!(self.run_stream(history, conversation_id, None, tool_choice, cancel));
         let mut response = None;
         while let Some(event) = stream.next().await {
             if let AgentEvent::Done(ref resp) = event {
@@ -275,6 +553,7 @@ impl<P: Provider + 'static> Agent<P> {
             stop_reason: AgentStopReason::Error("stream ended without Done".into()),
             steps: vec![],
             model: self.model_name(),
+            usage: Usage::default(),
         })
     }
 
@@ -282,20 +561,39 @@ impl<P: Provider + 'static> Agent<P> {
     ///
     /// Uses the model's streaming API so text deltas are yielded token-by-token.
     /// Tool call responses are dispatched after the stream completes (arguments
-    /// arrive incrementally and must be fully accumulated first).
+    /// arrive incrementally and must be fully accumulated first). `cancel` is
+    /// handed to every tool handler so long-running tools can stop when the
+    /// caller aborts the turn. Cancelling it also ends the turn with
+    /// [`AgentStopReason::Cancelled`]: the in-flight model stream or tool
+    /// futures are dropped, and abandoned calls get an error result.
+    ///
+    /// A trailing text-only assistant entry in `history` is a prefill: it is
+    /// sent as the last message of the first request and becomes the start
+    /// of the reply rather than a separate history entry.
     pub fn run_stream<'a>(
         &'a self,
         history: &'a mut Vec<HistoryEntry>,
         conversation_id: Option<u64>,
         mut steer_rx: Option<watch::Receiver<Option<String>>>,
         tool_choice: Option<ToolChoice>,
+        cancel: Option<CancellationToken>,
     ) -> impl Stream<Item = AgentEvent> + 'a {
         stream! {
             let mut steps = Vec::new();
+            // Sent only with the first request; the reply absorbs it.
+            let mut prefill = take_prefill(history);
             let max = self.config.max_iterations;
             let model_name = self.model_name();
+            let deadline = self
+                .config
+                .turn_timeout
+                .map(|secs| Instant::now() + Duration::from_secs(secs));
+            let mut malformed_rounds = 0;
+            let mut calls_made = 0;
+            let mut json_retried = false;
+            let mut turn_usage = Usage::default();
 
-            for _ in 0..max {
+            for iteration in 0..max {
                 // Check for pending steering message before the next model call.
                 // Scope the borrow so the !Send guard is dropped before yield.
                 let steer_content = steer_rx.as_mut().and_then(|rx| {
@@ -307,7 +605,12 @@ impl<P: Provider + 'static> Agent<P> {
                     yield AgentEvent::UserSteered { content };
                 }
 
-                let request = self.build_request(history, tool_choice.as_ref());
+                let mut request = self.build_request(history, tool_choice.as_ref());
+                if let Some(text) = &prefill {
+                    request
+                        .messages
+                        .push(HistoryEntry::assistant(text, None, None).to_wire_message());
+                }
 
                 // Stream from the model, yielding text deltas as they arrive.
                 let mut builder = MessageBuilder::new(Role::Assistant);
@@ -323,10 +626,31 @@ impl<P: Provider + 'static> Agent<P> {
                 #[derive(PartialEq)]
                 enum OpenSegment { None, Text, Thinking }
                 let mut open = OpenSegment::None;
+                // Set when the deadline passes or the turn is cancelled.
+                let mut interrupted: Option<AgentStopReason> = None;
+
+                // The prefill is the head of the reply, so stream it first.
+                if let Some(text) = &prefill {
+                    yield AgentEvent::TextStart;
+                    yield AgentEvent::TextDelta(text.clone());
+                    open = OpenSegment::Text;
+                }
 
+                let span = llm_span(&request.model, iteration);
                 {
                     let mut chunk_stream = std::pin::pin!(self.model.stream_ct(request));
-                    while let Some(result) = chunk_stream.next().await {
+                    loop {
+                        let next = chunk_stream.next().instrument(span.clone());
+                        let next = match within(deadline, cancel.as_ref(), next).await {
+                            Ok(next) => next,
+                            Err(reason) => {
+                                interrupted = Some(reason);
+                                break;
+                            }
+                        };
+                        let Some(result) = next else {
+                            break;
+                        };
                         match result {
                             Ok(chunk) => {
                                 // Process text portion. Match existing behavior:
@@ -391,6 +715,26 @@ impl<P: Provider + 'static> Agent<P> {
                         stop_reason: AgentStopReason::Error(e),
                         steps,
                         model: model_name.clone(),
+                        usage: turn_usage.clone(),
+                    });
+                    return;
+                }
+                if let Some(stop_reason) = interrupted {
+                    let mut partial = builder.build();
+                    prepend_prefill(&mut partial, prefill.take());
+                    let partial = partial
+                        .content
+                        .as_ref()
+                        .and_then(|v| v.as_str())
+                        .filter(|s| !s.is_empty())
+                        .map(|s| s.to_owned());
+                    yield AgentEvent::Done(AgentResponse {
+                        final_response: partial,
+                        iterations: steps.len(),
+                        stop_reason,
+                        steps,
+                        model: model_name.clone(),
+                        usage: turn_usage.clone(),
                     });
                     return;
                 }
@@ -398,7 +742,9 @@ impl<P: Provider + 'static> Agent<P> {
                 // Build the accumulated message. `MessageBuilder::build`
                 // already drops degenerate (id-less or name-less) tool call
                 // fragments, so any tool_calls present here are well-formed.
-                let message = builder.build();
+                let mut message = builder.build();
+                prepend_prefill(&mut message, prefill.take());
+                self.unwrap_response_tool(&mut message);
                 let tool_calls: Vec<ToolCall> =
                     message.tool_calls.clone().unwrap_or_default();
                 let content = message
@@ -408,6 +754,9 @@ impl<P: Provider + 'static> Agent<P> {
                     .filter(|s| !s.is_empty())
                     .map(|s| s.to_owned());
                 let usage = last_usage.unwrap_or_default();
+                record_usage(&span, &usage);
+                add_usage(&mut turn_usage, &usage);
+                drop(span);
                 let has_tool_calls = !tool_calls.is_empty();
 
                 // If the stream produced neither text nor any usable tool
@@ -425,15 +774,43 @@ impl<P: Provider + 'static> Agent<P> {
                         stop_reason: AgentStopReason::NoAction,
                         steps,
                         model: model_name.clone(),
+                        usage: turn_usage.clone(),
                     });
                     return;
                 }
 
+                // Checked before the message lands in history so an aborted
+                // turn never leaves a call without a result behind.
+                if let Some(e) = self.strict_violation(&tool_calls) {
+                    yield AgentEvent::Done(AgentResponse {
+                        final_response: None,
+                        iterations: steps.len(),
+                        stop_reason: AgentStopReason::Error(e),
+                        steps,
+                        model: model_name.clone(),
+                        usage: turn_usage.clone(),
+                    });
+                    return;
+                }
+                if let Some(e) = self.call_limit_violation(calls_made, &tool_calls) {
+                    yield AgentEvent::Done(AgentResponse {
+                        final_response: None,
+                        iterations: steps.len(),
+                        stop_reason: AgentStopReason::Error(e),
+                        steps,
+                        model: model_name.clone(),
+                        usage: turn_usage.clone(),
+                    });
+                    return;
+                }
+                calls_made += tool_calls.len();
+
                 history.push(HistoryEntry::from_message(message.clone()));
 
-                // Dispatch tool calls concurrently.
+                // Dispatch tool calls concurrently, at most
+                // `max_parallel_tools` at a time.
                 //
-                // `FuturesUnordered` polls each dispatch future to completion
+                // `buffer_unordered` polls each dispatch future to completion
                 // independently so `ToolResult` events fire in completion
                 // order (fast tools don't wait on slow siblings in the UI).
                 // Outputs are buffered by the original call index so history
@@ -445,19 +822,22 @@ impl<P: Provider + 'static> Agent<P> {
                     let sender = last_sender(history);
                     yield AgentEvent::ToolCallsStart(tool_calls.clone());
 
-                    let mut pending: FuturesUnordered<_> = tool_calls
+                    let limit = self.parallel_limit(tool_calls.len());
+                    let dispatches: Vec<_> = tool_calls
                         .iter()
                         .enumerate()
                         .map(|(idx, tc)| {
                             let fut = self.dispatch_tool(
                                 &tc.function.name,
+                                &tc.id,
                                 &tc.function.arguments,
                                 &sender,
                                 conversation_id,
+                                cancel.clone(),
                             );
                             // `start` is captured inside the async block so
                             // it measures actual polled runtime, not the time
-                            // since `FuturesUnordered` was built.
+                            // spent queued behind the parallelism cap.
                             async move {
                                 let start = std::time::Instant::now();
                                 let out = fut.await;
@@ -465,10 +845,23 @@ impl<P: Provider + 'static> Agent<P> {
                             }
                         })
                         .collect();
+                    // Collected up front: a lazy map closure held across
+                    // awaits trips the `Send` check on callers' futures.
+                    let mut pending = stream_iter(dispatches).buffer_unordered(limit);
 
                     let mut buffered: Vec<Option<Result<String, String>>> =
                         vec![None; tool_calls.len()];
-                    while let Some((idx, output, duration_ms)) = pending.next().await {
+                    loop {
+                        let next = match within(deadline, cancel.as_ref(), pending.next()).await {
+                            Ok(next) => next,
+                            Err(reason) => {
+                                interrupted = Some(reason);
+                                break;
+                            }
+                        };
+                        let Some((idx, output, duration_ms)) = next else {
+                            break;
+                        };
                         let call_id = tool_calls[idx].id.clone();
                         // Clone into the event; the owned Result lands in
                         // `buffered[idx]` so the drain-loop tail can append
@@ -480,9 +873,20 @@ impl<P: Provider + 'static> Agent<P> {
                         };
                         buffered[idx] = Some(output);
                     }
+                    // Tools still running at the deadline or on cancel are
+                    // dropped; their calls still need a result for the provider to accept
+                    // the history.
+                    drop(pending);
 
                     for (tc, out) in tool_calls.iter().zip(buffered.into_iter()) {
-                        let out = out.expect("FuturesUnordered drained every slot");
+                        let out = out.unwrap_or_else(|| {
+                            Err(match interrupted {
+                                Some(AgentStopReason::Cancelled) => {
+                                    "turn cancelled before this tool finished".to_owned()
+                                }
+                                _ => "turn timed out before this tool finished".to_owned(),
+                            })
+                        });
                         let entry = HistoryEntry::tool(
                             tool_output_text(&out),
                             tc.id.clone(),
@@ -495,12 +899,91 @@ impl<P: Provider + 'static> Agent<P> {
                     yield AgentEvent::ToolCallsComplete;
                 }
 
+                // A malformed call got an error result telling the model
+                // why; it may retry that many rounds before the turn ends.
+                let malformed = tool_calls
+                    .iter()
+                    .any(|tc| invalid_arguments(&tc.function.arguments).is_some());
+                if malformed && interrupted.is_none() {
+                    if malformed_rounds >= self.config.tool_arg_retries {
+                        steps.push(AgentStep {
+                            message,
+                            usage,
+                            finish_reason,
+                            tool_calls,
+                            tool_results,
+                        });
+                        yield AgentEvent::Done(AgentResponse {
+                            final_response: content,
+                            iterations: steps.len(),
+                            stop_reason: AgentStopReason::Error(format!(
+                                "tool call arguments still not valid JSON after {malformed_rounds} {}",
+                                if malformed_rounds == 1 { "retry" } else { "retries" }
+                            )),
+                            steps,
+                            model: model_name.clone(),
+                            usage: turn_usage.clone(),
+                        });
+                        return;
+                    }
+                    malformed_rounds += 1;
+                }
+
+                if let Some(stop_reason) = interrupted {
+                    steps.push(AgentStep {
+                        message,
+                        usage,
+                        finish_reason,
+                        tool_calls,
+                        tool_results,
+                    });
+                    yield AgentEvent::Done(AgentResponse {
+                        final_response: content,
+                        iterations: steps.len(),
+                        stop_reason,
+                        steps,
+                        model: model_name.clone(),
+                        usage: turn_usage.clone(),
+                    });
+                    return;
+                }
+
                 // Auto-compaction: check token estimate after each step.
                 if let Some(threshold) = self.config.compact_threshold
                     && Self::estimate_tokens(history) > threshold
                 {
-                    if let Some(summary) = self.compact(history).await {
-                        yield AgentEvent::Compact { summary: summary.clone() };
+                    // The summary call counts against the turn's deadline
+                    // and stops on cancel like any other model call.
+                    let compacted =
+                        match within(deadline, cancel.as_ref(), self.compact_with_usage(history))
+                            .await
+                        {
+                            Ok(compacted) => compacted,
+                            Err(stop_reason) => {
+                                steps.push(AgentStep {
+                                    message,
+                                    usage,
+                                    finish_reason,
+                                    tool_calls,
+                                    tool_results,
+                                });
+                                yield AgentEvent::Done(AgentResponse {
+                                    final_response: content,
+                                    iterations: steps.len(),
+                                    stop_reason,
+                                    steps,
+                                    model: model_name.clone(),
+                                    usage: turn_usage.clone(),
+                                });
+                                return;
+                            }
+                        };
+                    if let Some((summary, compact_usage)) = compacted {
+                        add_usage(&mut turn_usage, &compact_usage);
+                        yield AgentEvent::Compact {
+                            summary: summary.clone(),
+                            replaced: history.len(),
+                        };
                         *history = vec![HistoryEntry::user(&summary)];
                         yield AgentEvent::TextStart;
                         yield AgentEvent::TextDelta(
@@ -522,14 +1005,31 @@ impl<P: Provider + 'static> Agent<P> {
                 if !step.tool_calls.is_empty() {
                     steps.push(step);
                 } else {
-                    let stop_reason = Self::stop_reason(&step);
+                    let mut stop_reason = Self::stop_reason(&step);
                     steps.push(step);
+                    if let Some(e) = self.invalid_json_reply(content.as_deref()) {
+                        if !json_retried {
+                            json_retried = true;
+                            history.push(
+                                HistoryEntry::user(format!(
+                                    "Your output was invalid JSON ({e}). Reply again with \
+                                     only JSON matching the requested schema."
+                                ))
+                                .auto_injected(),
+                            );
+                            continue;
+                        }
+                        stop_reason = AgentStopReason::Error(format!(
+                            "reply is still not valid JSON after a retry: {e}"
+                        ));
+                    }
                     yield AgentEvent::Done(AgentResponse {
                         final_response: content,
                         iterations: steps.len(),
                         stop_reason,
                         steps,
                         model: model_name.clone(),
+                        usage: turn_usage.clone(),
                     });
                     return;
                 }
@@ -547,6 +1047,7 @@ impl<P: Provider + 'static> Agent<P> {
                 stop_reason: AgentStopReason::MaxIterations,
                 steps,
                 model: model_name,
+                usage: turn_usage,
             });
         }
     }
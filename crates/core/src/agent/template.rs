@@ -0,0 +1,40 @@
+//! System prompt variables — `{{name}}` placeholders filled in per turn.
+//!
+//! A placeholder is `{{`, a name, and `}}`; whitespace around the name is
+//! ignored, so `{{ date }}` and `{{date}}` match the same variable. A
+//! backslash right before `{{` keeps the braces literal: `\{{date}}`
+//! renders as `{{date}}`. A `{{` with no closing `}}` is left as written.
+
+use std::collections::BTreeMap;
+
+/// Substitute `vars` into `template`. An unknown variable renders empty,
+/// or with `strict` fails with its name.
+pub fn render_prompt(
+    template: &str,
+    vars: &BTreeMap<String, String>,
+    strict: bool,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        if rest[..start].ends_with('\\') {
+            out.push_str(&rest[..start - 1]);
+            out.push_str("{{");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + len].trim();
+        match vars.get(name) {
+            Some(value) => out.push_str(value),
+            None if strict => return Err(name.to_owned()),
+            None => {}
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
@@ -3,36 +3,134 @@
 //! [`ToolRegistry`] stores `crabllm_core::Tool` schemas by name — no
 //! handlers, no closures. [`ToolDispatcher`] is the trait Agents call to
 //! execute a tool call; [`ToolHandler`] is the per-tool async closure
-//! type stored in a [`ToolEntry`].
+//! type stored in a [`ToolEntry`]. Handlers receive the turn's
+//! [`CancellationToken`] in [`ToolDispatch::cancel`] — long-running tools
+//! select on it to stop early when the turn is aborted.
 
 use crate::model::HistoryEntry;
 use crabllm_core::{FunctionDef, Tool, ToolType};
 use heck::ToSnakeCase;
 use schemars::JsonSchema;
-use std::{collections::BTreeMap, future::Future, pin::Pin, sync::Arc};
+use std::{collections::BTreeMap, future::Future, pin::Pin, sync::Arc, time::Duration};
+use tokio_util::sync::CancellationToken;
 
 /// Boxed future returned by a [`ToolDispatcher::dispatch`] call.
 pub type ToolFuture<'a> = Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>>;
 
+/// Boxed future returned by a [`ToolDispatcher::approve`] call: `Ok` to
+/// run the tool, `Err` with the message the model gets instead.
+pub type ApprovalFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// Box a handler that returns structured data into a [`ToolFuture`].
+///
+/// The counterpart of `Box::pin` for free-form handlers: the value is
+/// serialized by [`json_output`], so every structured tool reaches the
+/// model in the same envelope.
+pub fn json_tool<'a>(
+    fut: impl Future<Output = Result<serde_json::Value, String>> + Send + 'a,
+) -> ToolFuture<'a> {
+    Box::pin(async move { json_output(fut.await) })
+}
+
+/// [`json_tool`] for handlers written with their natural types: any
+/// `Serialize` value on success and any `Display` error on failure, e.g.
+/// `anyhow::Result<Report>`. The error is rendered with `{:#}` so an
+/// anyhow context chain survives, then both arms share the envelope of
+/// [`json_output`]. A value that fails to serialize becomes the error.
+pub fn try_json_tool<'a, T, E>(
+    fut: impl Future<Output = Result<T, E>> + Send + 'a,
+) -> ToolFuture<'a>
+where
+    T: serde::Serialize,
+    E: std::fmt::Display,
+{
+    Box::pin(async move {
+        let result = match fut.await {
+            Ok(value) => serde_json::to_value(value).map_err(|e| e.to_string()),
+            Err(error) => Err(format!("{error:#}")),
+        };
+        json_output(result)
+    })
+}
+
+/// Wrap a structured tool result in the stable envelope, serialized
+/// compactly: `{"ok":true,"result":<value>}` on success and
+/// `{"ok":false,"error":"<message>"}` on failure. A failure stays `Err`
+/// so clients still see it as one.
+pub fn json_output(result: Result<serde_json::Value, String>) -> Result<String, String> {
+    match result {
+        Ok(value) => Ok(serde_json::json!({ "ok": true, "result": value }).to_string()),
+        Err(error) => Err(serde_json::json!({ "ok": false, "error": error }).to_string()),
+    }
+}
+
 /// Dynamic tool dispatch surface.
 ///
 /// The Agent holds an `Arc<dyn ToolDispatcher>` and calls `dispatch` for
 /// every tool call the model emits. Implementors look the tool up by
-/// name, enforce scope, and invoke the registered handler.
+/// name, enforce scope, and invoke the registered handler. `call_id` is
+/// the model-assigned id of the call; `cancel` is the token of the
+/// running turn. Both are forwarded to the handler untouched.
 pub trait ToolDispatcher: Send + Sync + 'static {
+    #[allow(clippy::too_many_arguments)]
     fn dispatch<'a>(
         &'a self,
         name: &'a str,
+        call_id: &'a str,
         args: &'a str,
         agent: &'a str,
         sender: &'a str,
         conversation_id: Option<u64>,
+        cancel: Option<CancellationToken>,
     ) -> ToolFuture<'a>;
+
+    /// Hold a call until it is cleared to run, e.g. by a person. The
+    /// agent awaits this before [`dispatch`](Self::dispatch) and outside
+    /// the tool's [`ToolTimeouts`] limit, so waiting is not charged to the
+    /// tool. Default: every call is cleared at once.
+    #[allow(clippy::too_many_arguments)]
+    fn approve<'a>(
+        &'a self,
+        _name: &'a str,
+        _call_id: &'a str,
+        _args: &'a str,
+        _agent: &'a str,
+        _sender: &'a str,
+        _conversation_id: Option<u64>,
+        _cancel: Option<CancellationToken>,
+    ) -> ApprovalFuture<'a> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+/// How long a tool call may run before the agent gives up on it.
+///
+/// A call that outlives its limit is dropped — the handler future is
+/// cancelled, not left running — and the model gets an error result.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolTimeouts {
+    /// Limit for every tool without an override. `None` = no limit.
+    pub default: Option<Duration>,
+    /// Per-tool overrides by name. A zero duration lifts the limit for
+    /// that tool.
+    pub per_tool: BTreeMap<String, Duration>,
+}
+
+impl ToolTimeouts {
+    /// The limit that applies to `name`, if any.
+    pub fn for_tool(&self, name: &str) -> Option<Duration> {
+        match self.per_tool.get(name) {
+            Some(limit) => (!limit.is_zero()).then_some(*limit),
+            None => self.default,
+        }
+    }
 }
 
 /// Arguments passed to a tool handler during dispatch.
 #[derive(Clone)]
 pub struct ToolDispatch {
+    /// Model-assigned id of this call (empty when the caller has none).
+    pub call_id: String,
     /// JSON-encoded arguments string.
     pub args: String,
     /// Name of the agent making this call.
@@ -41,9 +139,28 @@ pub struct ToolDispatch {
     pub sender: String,
     /// Conversation ID, if running within a conversation.
     pub conversation_id: Option<u64>,
+    /// Cancelled when the turn is aborted. `None` for runs nobody can
+    /// abort (stateless calls, tests). Handlers are free to ignore it.
+    pub cancel: Option<CancellationToken>,
 }
 
-/// A type-erased async tool handler.
+impl ToolDispatch {
+    /// Resolves once the turn is aborted; never resolves without a token.
+    pub async fn cancelled(&self) {
+        match &self.cancel {
+            Some(token) => token.cancelled().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Whether the turn has been aborted.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|t| t.is_cancelled())
+    }
+}
+
+/// A type-erased async tool handler. The turn's cancellation token rides
+/// in [`ToolDispatch::cancel`], so handlers that ignore it need no changes.
 pub type ToolHandler = Arc<
     dyn Fn(ToolDispatch) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send>>
         + Send
@@ -137,6 +254,12 @@ impl ToolRegistry {
 /// Trait to convert a type into a `crabllm_core::Tool`. The tool's
 /// description is read from the `///` doc comment on the struct —
 /// schemars puts it in the schema's top-level `description` field.
+///
+/// Parameters are the struct's fields. `Option<_>` fields and fields
+/// with `#[serde(default)]` or `#[serde(default = "path")]` are left
+/// out of `required`; a `default = "path"` value is also published as
+/// the property's `default`, and serde applies it when the model omits
+/// the argument.
 pub trait AsTool {
     /// Convert the type into a `crabllm_core::Tool` (the enveloped
     /// `{kind, function}` wire shape).
@@ -173,10 +296,12 @@ impl ToolDispatcher for () {
     fn dispatch<'a>(
         &'a self,
         name: &'a str,
+        _call_id: &'a str,
         _args: &'a str,
         _agent: &'a str,
         _sender: &'a str,
         _conversation_id: Option<u64>,
+        _cancel: Option<CancellationToken>,
     ) -> ToolFuture<'a> {
         Box::pin(async move { Err(format!("tool not registered: {name}")) })
     }
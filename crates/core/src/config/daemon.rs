@@ -1,6 +1,9 @@
 //! Daemon configuration loaded from `config.toml`.
 
-use crate::config::{LlmConfig, system::TasksConfig};
+use crate::config::{
+    DaemonHooksConfig, LlmConfig, OpenAiConfig, env,
+    system::{LimitsConfig, SessionsConfig, TasksConfig, ToolsConfig},
+};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -20,9 +23,28 @@ pub struct DaemonConfig {
     /// Task executor pool configuration (`[tasks]`).
     #[serde(default)]
     pub tasks: TasksConfig,
+    /// OpenAI-compatible HTTP server (`[openai]`).
+    #[serde(default)]
+    pub openai: OpenAiConfig,
+    /// Request limits (`[limits]`).
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    /// Idle conversation eviction (`[sessions]`).
+    #[serde(default)]
+    pub sessions: SessionsConfig,
+    /// Tool dispatch policy (`[tools]`).
+    #[serde(default)]
+    pub tools: ToolsConfig,
+    /// Hook settings shared by every agent (`[hooks]`).
+    #[serde(default)]
+    pub hooks: DaemonHooksConfig,
     /// Environment variables passed to all MCP server processes.
     #[serde(default)]
     pub env: BTreeMap<String, String>,
+    /// Values for `{{name}}` system prompt placeholders (`[prompt_vars]`),
+    /// on top of the runtime's built-ins.
+    #[serde(default)]
+    pub prompt_vars: BTreeMap<String, String>,
 }
 
 impl DaemonConfig {
@@ -31,9 +53,28 @@ impl DaemonConfig {
         Ok(toml::from_str(toml_str)?)
     }
 
-    /// Load configuration from a file path.
+    /// Load configuration from a file path. `${ENV_VAR}` placeholders are
+    /// kept as written; call [`DaemonConfig::resolve_env`] before use.
     pub fn load(path: &std::path::Path) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
         Self::from_toml(&content)
     }
+
+    /// Resolve `${ENV_VAR}` placeholders from the process environment.
+    ///
+    /// Interpolated fields: `llm.base_url`, `llm.api_key`, `llm.keys[].key`,
+    /// `openai.api_key`, and the values of `[env]`. Fails naming the field
+    /// when a referenced variable is unset.
+    pub fn resolve_env(&mut self) -> Result<()> {
+        env::resolve("llm.base_url", &mut self.llm.base_url)?;
+        env::resolve("llm.api_key", &mut self.llm.api_key)?;
+        for (i, key) in self.llm.keys.iter_mut().enumerate() {
+            env::resolve(&format!("llm.keys[{i}].key"), &mut key.key)?;
+        }
+        env::resolve("openai.api_key", &mut self.openai.api_key)?;
+        for (name, value) in &mut self.env {
+            env::resolve(&format!("env.{name}"), value)?;
+        }
+        Ok(())
+    }
 }
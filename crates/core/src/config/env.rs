@@ -0,0 +1,39 @@
+//! `${ENV_VAR}` interpolation for config string fields.
+//!
+//! Configs stay secret-free on disk: a field like `api_key =
+//! "${OPENAI_API_KEY}"` is resolved from the process environment when the
+//! daemon consumes the config. Loading and saving keep the placeholders,
+//! so a round trip through storage never writes a secret back out.
+
+use anyhow::{Result, bail};
+
+/// Replace every `${NAME}` in `value` with the environment variable
+/// `NAME`. Strings without a placeholder pass through unchanged. Fails if
+/// a referenced variable is unset or a placeholder is unterminated.
+pub fn interpolate(value: &str) -> Result<String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            bail!("unterminated '${{' in {value:?}");
+        };
+        let name = &after[..end];
+        match std::env::var(name) {
+            Ok(resolved) => out.push_str(&resolved),
+            Err(_) => bail!("environment variable {name} is not set (referenced as ${{{name}}})"),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Interpolate `value` in place, naming `field` in the error.
+pub(crate) fn resolve(field: &str, value: &mut String) -> Result<()> {
+    if value.contains("${") {
+        *value = interpolate(value).map_err(|e| anyhow::anyhow!("{field}: {e}"))?;
+    }
+    Ok(())
+}
@@ -1,6 +1,8 @@
 //! Per-agent hook configuration — bash deny rules, memory recall
 //! tuning. Each agent owns its own `HooksConfig` directly on
-//! [`crate::AgentConfig`]; there is no global override.
+//! [`crate::AgentConfig`]; there is no global override. Settings of
+//! state every agent shares, like the memory store, live in
+//! [`DaemonHooksConfig`] instead.
 
 use serde::{Deserialize, Serialize};
 
@@ -28,12 +30,59 @@ pub struct BashConfig {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct MemoryConfig {
+    /// Turn memory off for this agent: no auto-recall, no memory prompt,
+    /// no memory tools.
+    pub disabled: bool,
     /// Maximum entries returned by auto-recall (default 5).
     pub recall_limit: usize,
+    /// How many recent user messages feed the auto-recall query, newest
+    /// first (default 1). Raise it so a terse follow-up ("yes, do that")
+    /// still recalls by the topic set a turn or two earlier.
+    pub recall_window: usize,
+    /// Rough token budget for what auto-recall injects (~4 chars per
+    /// token). Entries go in by rank until it runs out; the one that
+    /// crosses it is cut at a word boundary. 0 (the default) injects all
+    /// `recall_limit` entries in full.
+    pub recall_max_tokens: usize,
+    /// Withhold the `remember` and `forget` tools. Auto-recall and the
+    /// `recall` tool still work.
+    pub read_only: bool,
+    /// Hold `remember` and `forget` writes until the turn ends and commit
+    /// them only if it finishes with a text response; a turn that errors,
+    /// times out, or is killed drops them. Staged writes are invisible to
+    /// `recall` for the rest of the turn. Off by default: writes land as
+    /// soon as the tool runs.
+    pub transactional: bool,
 }
 
 impl Default for MemoryConfig {
     fn default() -> Self {
-        Self { recall_limit: 5 }
+        Self {
+            disabled: false,
+            recall_limit: 5,
+            recall_window: 1,
+            recall_max_tokens: 0,
+            read_only: false,
+            transactional: false,
+        }
     }
 }
+
+/// Daemon-wide hook settings (`[hooks]` in `config.toml`). These tune
+/// state shared by every agent, so they have no per-agent counterpart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct DaemonHooksConfig {
+    /// The shared memory store (`[hooks.memory]`).
+    pub memory: MemoryStoreConfig,
+}
+
+/// Memory store configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MemoryStoreConfig {
+    /// Weight of the access boost in recall ranking: each score is scaled
+    /// by `1 + ln(1 + access_count) * access_weight`. Unset keeps the
+    /// store's default of 0.1; 0 ranks on relevance alone.
+    pub access_weight: Option<f64>,
+}
@@ -13,8 +13,62 @@ pub struct LlmConfig {
     /// `http://localhost:4000/v1` or `https://api.openai.com/v1`.
     #[serde(default)]
     pub base_url: String,
-    /// Bearer token for the endpoint. Supports `${ENV_VAR}` interpolation
-    /// at load time (resolved by the daemon builder).
+    /// Bearer token for the endpoint. Supports `${ENV_VAR}` interpolation,
+    /// resolved by [`crate::DaemonConfig::resolve_env`].
     #[serde(default)]
     pub api_key: String,
+    /// Key pool (`[[llm.keys]]`) for spreading load across several keys
+    /// on the same endpoint. Takes precedence over `api_key` when set.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<ApiKey>,
+    /// Capture the raw request and response of the most recent chat call
+    /// for debugging; `crabtalk last-exchange` prints it. Off by default:
+    /// captures hold prompt contents.
+    #[serde(default)]
+    pub debug: bool,
+    /// Retries of a rate-limited (429), 5xx or timed-out chat call, with
+    /// jittered exponential backoff. Other 4xx fail at once. Unset uses
+    /// the default of 2; 0 disables retry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    /// Seconds a chat call, or opening a stream, may take before it is
+    /// abandoned as timed out. Unset uses 120; 0 disables the limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Seconds an open stream may go without a chunk before it is
+    /// abandoned. Unset uses 60; 0 disables the limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<u64>,
+}
+
+/// One key in the pool. Requests are spread across keys by weighted
+/// round-robin; a key that gets rate limited (429) sits out a cooldown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Bearer token. Supports `${ENV_VAR}` interpolation like `api_key`.
+    pub key: String,
+    /// Relative share of requests (default 1).
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl LlmConfig {
+    /// The effective key pool: `keys` when set, otherwise `api_key` as a
+    /// single weight-1 key. Empty when the endpoint needs no auth.
+    pub fn key_pool(&self) -> Vec<ApiKey> {
+        if !self.keys.is_empty() {
+            return self.keys.clone();
+        }
+        if self.api_key.is_empty() {
+            return Vec::new();
+        }
+        vec![ApiKey {
+            key: self.api_key.clone(),
+            weight: 1,
+        }]
+    }
 }
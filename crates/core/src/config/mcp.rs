@@ -1,5 +1,7 @@
 //! MCP server configuration.
 
+use crate::config::env;
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
@@ -24,6 +26,19 @@ pub struct McpServerConfig {
     pub auth: bool,
 }
 
+impl McpServerConfig {
+    /// Resolve `${ENV_VAR}` placeholders in `env` values and `url`.
+    pub fn resolve_env(&mut self) -> Result<()> {
+        for (name, value) in &mut self.env {
+            env::resolve(&format!("mcp '{}' env.{name}", self.name), value)?;
+        }
+        if let Some(url) = &mut self.url {
+            env::resolve(&format!("mcp '{}' url", self.name), url)?;
+        }
+        Ok(())
+    }
+}
+
 impl Default for McpServerConfig {
     fn default() -> Self {
         Self {
@@ -1,18 +1,21 @@
 //! Shared configuration types used across crates.
 
 pub mod daemon;
+pub mod env;
 pub mod hooks;
 pub mod llm;
 pub mod manifest;
 pub mod mcp;
+pub mod openai;
 pub mod system;
 
 pub use daemon::DaemonConfig;
-pub use hooks::{BashConfig, HooksConfig, MemoryConfig};
-pub use llm::LlmConfig;
+pub use hooks::{BashConfig, DaemonHooksConfig, HooksConfig, MemoryConfig, MemoryStoreConfig};
+pub use llm::{ApiKey, LlmConfig};
 pub use manifest::{
     PackageMeta, ResolvedDirs, Setup, check_skill_conflicts, external_source_name, load_agents_dir,
     load_agents_dirs, repo_slug, resolve_dirs, scan_skill_names,
 };
 pub use mcp::McpServerConfig;
-pub use system::TasksConfig;
+pub use openai::OpenAiConfig;
+pub use system::{LimitsConfig, SessionsConfig, TasksConfig, ToolsConfig};
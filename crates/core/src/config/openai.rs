@@ -0,0 +1,27 @@
+//! OpenAI-compatible HTTP server configuration.
+
+use serde::{Deserialize, Serialize};
+
+/// OpenAI-compatible `/v1/chat/completions` server (`[openai]` in
+/// `config.toml`). Off by default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenAiConfig {
+    /// Whether the daemon serves the endpoint (default false).
+    pub enabled: bool,
+    /// Listen address (default `127.0.0.1:6688`).
+    pub bind: String,
+    /// Bearer token clients must present. Empty disables the check.
+    /// Supports `${ENV_VAR}` interpolation.
+    pub api_key: String,
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1:6688".to_owned(),
+            api_key: String::new(),
+        }
+    }
+}
@@ -1,6 +1,8 @@
-//! Task executor pool configuration.
+//! Task executor pool, request limit, and session eviction configuration.
 
+use crate::agent::ToolTimeouts;
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
 
 /// Task executor pool configuration (`[tasks]` in `config.toml`).
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,3 +25,120 @@ impl Default for TasksConfig {
         }
     }
 }
+
+/// Request limits (`[limits]` in `config.toml`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Largest message content in bytes (default 256 KiB), for protocol
+    /// sends, steers, relays and turn instructions, and for each message
+    /// sent to the OpenAI endpoint. Larger content is refused with a 413
+    /// before any processing. 0 disables the check.
+    pub max_content_bytes: usize,
+    /// Most memory notes kept (default 0 = no cap). Past it, the least
+    /// recalled notes are dropped as new ones are written. Pinned notes
+    /// and compaction archives are never dropped.
+    pub memory_entries: usize,
+    /// Deepest chain of `delegate` calls one conversation may start
+    /// (default 4). A delegated agent that would go deeper, or delegate
+    /// back to an agent earlier in its chain, gets an error result
+    /// instead. Delegating to itself is allowed. 0 lifts the depth cap;
+    /// cycles are refused either way.
+    pub delegate_depth: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_content_bytes: 256 * 1024,
+            memory_entries: 0,
+            delegate_depth: 4,
+        }
+    }
+}
+
+/// Idle conversation eviction (`[sessions]` in `config.toml`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionsConfig {
+    /// Seconds since a conversation's last finished turn before it is
+    /// dropped from memory (default 3600). Its history stays on disk and
+    /// is resumed by the next message from the same sender. 0 disables
+    /// eviction.
+    pub idle_timeout: u64,
+    /// Seconds between eviction sweeps (default 60).
+    pub sweep_interval: u64,
+    /// Agents whose conversations are never evicted.
+    pub pinned: Vec<String>,
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout: 3600,
+            sweep_interval: 60,
+            pinned: Vec::new(),
+        }
+    }
+}
+
+/// Tool dispatch policy (`[tools]` in `config.toml`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolsConfig {
+    /// Tools that wait for the client to approve each call before
+    /// running. A denied or unanswered call returns an error to the
+    /// model instead.
+    pub confirm: Vec<String>,
+    /// Seconds a `confirm` call waits for the client's answer before it
+    /// is denied (default 300).
+    pub confirm_timeout: u64,
+    /// Most tool calls from one model round that run at once (default 8).
+    /// Lower it for rate-limited tools. 0 runs them all at once.
+    pub max_parallel: usize,
+    /// Seconds any one tool call may run before it is abandoned and the
+    /// model told it timed out (default 0 = no limit).
+    pub timeout: u64,
+    /// Per-tool overrides of `timeout`, in seconds. 0 lifts the limit
+    /// for that tool.
+    pub timeouts: BTreeMap<String, u64>,
+    /// Most tool calls one turn may make before it ends with an error
+    /// (default 0 = no cap beyond the agent's `max_iterations`).
+    pub max_calls: usize,
+    /// Pure tools whose successful results are reused for identical
+    /// arguments within a conversation. Only list tools whose output
+    /// depends on nothing but their arguments.
+    pub cacheable: Vec<String>,
+    /// Most cached results kept per conversation (default 128). The
+    /// oldest is dropped first. 0 turns the cache off.
+    pub cache_entries: usize,
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            confirm: Vec::new(),
+            confirm_timeout: 300,
+            max_parallel: crate::agent::DEFAULT_MAX_PARALLEL_TOOLS,
+            timeout: 0,
+            timeouts: BTreeMap::new(),
+            max_calls: 0,
+            cacheable: Vec::new(),
+            cache_entries: 128,
+        }
+    }
+}
+
+impl ToolsConfig {
+    /// The configured limits as the agent applies them.
+    pub fn tool_timeouts(&self) -> ToolTimeouts {
+        ToolTimeouts {
+            default: (self.timeout > 0).then(|| Duration::from_secs(self.timeout)),
+            per_tool: self
+                .timeouts
+                .iter()
+                .map(|(name, secs)| (name.clone(), Duration::from_secs(*secs)))
+                .collect(),
+        }
+    }
+}
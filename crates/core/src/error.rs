@@ -0,0 +1,63 @@
+//! Typed runtime errors.
+//!
+//! Runtime methods still return `anyhow::Result`, but the failures a
+//! caller can act on are raised as [`RuntimeError`] so they survive the
+//! trip: `err.downcast_ref::<RuntimeError>()` recovers the variant, and
+//! the protocol layer maps it to a status code via [`RuntimeError::code`].
+
+/// Runtime failure a caller can match on instead of string-matching.
+#[derive(Debug, thiserror::Error)]
+pub enum RuntimeError {
+    /// No agent with this name is registered.
+    #[error("agent '{0}' not registered")]
+    AgentNotRegistered(String),
+    /// No conversation matches the given id, handle, or identity.
+    #[error("conversation {0} not found")]
+    ConversationNotFound(String),
+    /// Steering targets a conversation that isn't streaming.
+    #[error("no active stream for conversation {0}")]
+    NoActiveStream(u64),
+    /// No tool call with this id is waiting on a confirmation.
+    #[error("no tool call '{0}' awaiting confirmation")]
+    NoPendingToolCall(String),
+    /// Compaction produced no summary.
+    #[error("compact failed for {0}")]
+    Compaction(String),
+    /// A relay chain hit the hop limit — likely a loop.
+    #[error("relay refused after {0} hops")]
+    RelayHopsExceeded(u32),
+    /// The user message is empty or whitespace only.
+    #[error("message is empty")]
+    EmptyMessage,
+    /// Message content exceeds the configured size limit.
+    #[error("message is {size} bytes, over the {limit}-byte limit")]
+    ContentTooLarge { size: usize, limit: usize },
+    /// The agent's system prompt alone fills the model's context window.
+    #[error(
+        "agent '{agent}' system prompt is ~{tokens} tokens, over the {limit}-token context window"
+    )]
+    PromptTooLarge {
+        agent: String,
+        tokens: usize,
+        limit: usize,
+    },
+    /// A strict agent's system prompt names a variable nobody provided.
+    #[error("agent '{agent}' system prompt uses unknown variable '{name}'")]
+    MissingPromptVar { agent: String, name: String },
+}
+
+impl RuntimeError {
+    /// Protocol status code for this error (HTTP-style).
+    pub fn code(&self) -> u32 {
+        match self {
+            Self::AgentNotRegistered(_)
+            | Self::ConversationNotFound(_)
+            | Self::NoPendingToolCall(_) => 404,
+            Self::NoActiveStream(_) => 409,
+            Self::EmptyMessage | Self::MissingPromptVar { .. } => 400,
+            Self::ContentTooLarge { .. } | Self::PromptTooLarge { .. } => 413,
+            Self::Compaction(_) => 500,
+            Self::RelayHopsExceeded(_) => 508,
+        }
+    }
+}
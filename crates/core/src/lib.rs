@@ -7,29 +7,38 @@
 //! - [`ToolDispatcher`]: Agent-side tool dispatch trait.
 //! - [`model`]: Unified LLM interface types and traits.
 //! - [`storage`]: Unified persistence trait and domain types.
+//! - [`redact`]: Secret and PII scrubbing for persisted history.
+//! - [`RuntimeError`]: Typed runtime failures with protocol status codes.
 //! - Agent event types: [`AgentEvent`], [`AgentStep`], [`AgentResponse`], [`AgentStopReason`].
 
 pub use agent::{
-    Agent, AgentBuilder, AgentConfig, AgentId,
+    Agent, AgentBuilder, AgentConfig, AgentId, PromptVars, ResponseFormat, ResponseMode,
     event::{AgentEvent, AgentResponse, AgentStep, AgentStopReason},
     tool::{
-        BeforeRunHook, ToolDispatch, ToolDispatcher, ToolEntry, ToolFuture, ToolHandler,
-        ToolRegistry,
+        ApprovalFuture, BeforeRunHook, ToolDispatch, ToolDispatcher, ToolEntry, ToolFuture,
+        ToolHandler, ToolRegistry, ToolTimeouts, json_output, json_tool, try_json_tool,
     },
 };
 pub use config::{
-    BashConfig, DaemonConfig, HooksConfig, LlmConfig, McpServerConfig, MemoryConfig, PackageMeta,
-    ResolvedDirs, Setup, TasksConfig, check_skill_conflicts, external_source_name, load_agents_dir,
-    load_agents_dirs, repo_slug, resolve_dirs, scan_skill_names,
+    ApiKey, BashConfig, DaemonConfig, DaemonHooksConfig, HooksConfig, LimitsConfig, LlmConfig,
+    McpServerConfig, MemoryConfig, MemoryStoreConfig, OpenAiConfig, PackageMeta, ResolvedDirs,
+    SessionsConfig, Setup, TasksConfig, ToolsConfig, check_skill_conflicts, external_source_name,
+    load_agents_dir, load_agents_dirs, repo_slug, resolve_dirs, scan_skill_names,
 };
+pub use error::RuntimeError;
+pub use redact::RedactionConfig;
 pub use storage::{ConversationMeta, EventLine, sender_slug};
+pub use tokio_util::sync::CancellationToken;
 
 pub mod agent;
 pub mod config;
+pub mod error;
 pub mod model;
 pub mod paths;
 pub mod protocol;
+pub mod redact;
 pub mod storage;
 #[cfg(feature = "testing")]
 pub mod testing;
+pub mod trace;
 pub mod utils;
@@ -45,6 +45,12 @@ pub struct HistoryEntry {
     #[serde(skip)]
     pub auto_injected: bool,
 
+    /// Skills preprocessing loaded into this message (runtime-only). Only
+    /// these grant their declared tools; a `<skill>` tag typed by the
+    /// sender grants nothing.
+    #[serde(skip)]
+    pub skills: Vec<String>,
+
     /// The wire-level message sent to providers.
     pub message: Message,
 }
@@ -67,6 +73,25 @@ impl HistoryEntry {
         entry
     }
 
+    /// Create a new user entry with images after the text. Each image is
+    /// an `http(s)` URL or a `data:<mime>;base64,<data>` URI. The content
+    /// becomes OpenAI-style `text` and `image_url` parts, which providers
+    /// map to their own image blocks. No images gives a plain text entry.
+    pub fn user_with_images(content: impl Into<String>, images: &[String]) -> Self {
+        if images.is_empty() {
+            return Self::user(content);
+        }
+        let mut parts = vec![serde_json::json!({ "type": "text", "text": content.into() })];
+        parts.extend(
+            images
+                .iter()
+                .map(|url| serde_json::json!({ "type": "image_url", "image_url": { "url": url } })),
+        );
+        let mut message = Message::user("");
+        message.content = Some(serde_json::Value::Array(parts));
+        Self::from_message(message)
+    }
+
     /// Create a new assistant entry.
     ///
     /// Preserves the `content: null` vs empty-string discrimination:
@@ -111,6 +136,7 @@ impl HistoryEntry {
             agent: String::new(),
             sender: String::new(),
             auto_injected: false,
+            skills: Vec::new(),
             message,
         }
     }
@@ -126,9 +152,60 @@ impl HistoryEntry {
         &self.message.role
     }
 
-    /// The text content of the message, or `""` if absent / empty / non-string.
+    /// The text content of the message, or `""` if absent / empty. For
+    /// multi-part content this is the first text part.
     pub fn text(&self) -> &str {
-        self.message.content_str().unwrap_or("")
+        match &self.message.content {
+            Some(serde_json::Value::Array(parts)) => parts
+                .iter()
+                .filter(|p| p["type"] == "text")
+                .find_map(|p| p["text"].as_str())
+                .unwrap_or(""),
+            _ => self.message.content_str().unwrap_or(""),
+        }
+    }
+
+    /// Image URLs attached by [`HistoryEntry::user_with_images`], in order.
+    pub fn images(&self) -> impl Iterator<Item = &str> {
+        let parts = match &self.message.content {
+            Some(serde_json::Value::Array(parts)) => parts.as_slice(),
+            _ => &[],
+        };
+        parts
+            .iter()
+            .filter(|p| p["type"] == "image_url")
+            .filter_map(|p| p["image_url"]["url"].as_str())
+    }
+
+    /// Replace every inline `data:` image with an [`INLINE_IMAGE_PLACEHOLDER`]
+    /// text part, keeping URL images. Returns whether anything changed.
+    pub fn strip_inline_images(&mut self) -> bool {
+        let Some(serde_json::Value::Array(parts)) = &mut self.message.content else {
+            return false;
+        };
+        let mut stripped = false;
+        for part in parts.iter_mut() {
+            let inline = part["type"] == "image_url"
+                && part["image_url"]["url"]
+                    .as_str()
+                    .is_some_and(|url| url.starts_with("data:"));
+            if inline {
+                *part = serde_json::json!({ "type": "text", "text": INLINE_IMAGE_PLACEHOLDER });
+                stripped = true;
+            }
+        }
+        stripped
+    }
+
+    /// Replace the text, keeping any images.
+    fn set_text(&mut self, text: String) {
+        if let Some(serde_json::Value::Array(parts)) = &mut self.message.content
+            && let Some(part) = parts.iter_mut().find(|p| p["type"] == "text")
+        {
+            part["text"] = serde_json::Value::String(text);
+            return;
+        }
+        self.message.content = Some(serde_json::Value::String(text));
     }
 
     /// The reasoning content, or empty if absent.
@@ -146,8 +223,10 @@ impl HistoryEntry {
         self.message.tool_call_id.as_deref().unwrap_or("")
     }
 
-    /// Estimate the number of tokens in this entry (~4 chars per token).
+    /// Estimate the number of tokens in this entry (~4 chars per token,
+    /// plus [`IMAGE_TOKENS`] per image).
     pub fn estimate_tokens(&self) -> usize {
+        let images = self.images().count() * IMAGE_TOKENS;
         let chars = self.text().len()
             + self.reasoning().len()
             + self.tool_call_id().len()
@@ -156,7 +235,42 @@ impl HistoryEntry {
                 .iter()
                 .map(|tc| tc.function.name.len() + tc.function.arguments.len())
                 .sum::<usize>();
-        (chars / 4).max(1)
+        (chars / 4).max(1) + images
+    }
+
+    /// Copy of this entry with its text cut to roughly `max` tokens under
+    /// the ~4-chars-per-token estimate. See
+    /// [`HistoryEntry::truncate_to_tokens_with`].
+    pub fn truncate_to_tokens(&self, max: usize) -> Self {
+        self.truncate_to_tokens_with(max, estimate_text_tokens)
+    }
+
+    /// Copy of this entry with its text cut so that `estimate` puts it,
+    /// marker included, at no more than `max` tokens. The cut lands on a
+    /// UTF-8 boundary and is followed by [`TRUNCATION_MARKER`]. Text
+    /// already within budget is left unchanged. Tool calls and other
+    /// fields are never touched.
+    pub fn truncate_to_tokens_with(&self, max: usize, estimate: impl Fn(&str) -> usize) -> Self {
+        let text = self.text();
+        if estimate(text) <= max {
+            return self.clone();
+        }
+        // Largest prefix that still fits with the marker appended. The
+        // estimate grows with the prefix, so a binary search over byte
+        // offsets finds it in O(log n) estimator calls.
+        let fits = |end: usize| {
+            let end = text.floor_char_boundary(end);
+            estimate(&format!("{}{TRUNCATION_MARKER}", &text[..end])) <= max
+        };
+        let (mut lo, mut hi) = (0, text.len());
+        while lo < hi {
+            let mid = (lo + hi).div_ceil(2);
+            if fits(mid) { lo = mid } else { hi = mid - 1 }
+        }
+        let end = text.floor_char_boundary(lo);
+        let mut entry = self.clone();
+        entry.set_text(format!("{}{TRUNCATION_MARKER}", &text[..end]));
+        entry
     }
 
     /// Project to a `crabllm_core::Message` for sending to a provider.
@@ -181,6 +295,23 @@ impl HistoryEntry {
     }
 }
 
+/// Flat token estimate for one attached image, whatever its size. Close
+/// to what a high-detail 1024px image costs on OpenAI-style endpoints.
+pub const IMAGE_TOKENS: usize = 765;
+
+/// Stands in for an inline image once its turn is over, so base64 data
+/// is not kept or persisted.
+pub const INLINE_IMAGE_PLACEHOLDER: &str = "[image]";
+
+/// Appended to text cut by [`HistoryEntry::truncate_to_tokens`].
+pub const TRUNCATION_MARKER: &str = "…[truncated]";
+
+/// Estimate the tokens in a string (~4 bytes per token), the same
+/// heuristic [`HistoryEntry::estimate_tokens`] uses.
+pub fn estimate_text_tokens(text: &str) -> usize {
+    text.len() / 4
+}
+
 /// Estimate total tokens across a slice of entries.
 pub fn estimate_history_tokens(entries: &[HistoryEntry]) -> usize {
     entries.iter().map(|e| e.estimate_tokens()).sum()
@@ -337,6 +468,11 @@ impl<P: Provider + 'static> Model<P> {
         Self { inner: provider }
     }
 
+    /// The wrapped provider.
+    pub fn provider(&self) -> &P {
+        &self.inner
+    }
+
     /// Send a non-streaming chat completion request.
     pub async fn send_ct(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse> {
         let mut req = request;
@@ -1,16 +1,18 @@
 //! Client trait — transport primitives plus typed provided methods.
 
 use crate::protocol::message::{
-    AgentInfo, AgentList, ClientMessage, ConversationHistory, ConversationInfo, ConversationList,
-    CreateAgentMsg, DaemonStats, DeleteAgentMsg, DeleteConversationMsg, DeleteMcpMsg, ErrorMsg,
-    GetAgentMsg, GetConversationHistoryMsg, GetStats, InstallPluginMsg, ListAgentsMsg,
-    ListConversationsMsg, ListMcpsMsg, ListModelsMsg, ListPluginsMsg, ListSkillsMsg,
-    ListSubscriptionsMsg, McpInfo, McpList, ModelInfo, ModelList, Ping, PluginEvent, PluginInfo,
-    PluginList, PluginSearchList, PublishEventMsg, RenameAgentMsg, SearchPluginsMsg, SendMsg,
-    SendResponse, ServerMessage, ServiceLogOutput, ServiceLogsMsg, SetActiveModelMsg, SkillInfo,
-    SkillList, StartServiceMsg, StopServiceMsg, StreamEvent, StreamMsg, SubscribeEventMsg,
-    SubscriptionInfo, SubscriptionList, UninstallPluginMsg, UnsubscribeEventMsg, UpdateAgentMsg,
-    UpsertMcpMsg, client_message, plugin_event, server_message, stream_event,
+    AgentInfo, AgentList, CancelMsg, ClientMessage, ConversationHistory, ConversationInfo,
+    ConversationList, CreateAgentMsg, DaemonStats, DeleteAgentMsg, DeleteConversationMsg,
+    DeleteMcpMsg, ErrorMsg, GetAgentMsg, GetConversationHistoryMsg, GetLlmExchange, GetStats,
+    ImportMemoryMsg, InstallPluginMsg, ListAgentsMsg, ListConversationsMsg, ListMcpsMsg,
+    ListMemoryMsg, ListModelsMsg, ListPluginsMsg, ListSkillsMsg, ListSubscriptionsMsg, LlmExchange,
+    McpInfo, McpList, MemoryEntryInfo, MemoryImported, MemoryList, MemoryRecall, ModelInfo,
+    ModelList, Ping, PluginEvent, PluginInfo, PluginList, PluginSearchList, PublishEventMsg,
+    RecallMemoryMsg, RelayMsg, RenameAgentMsg, SearchPluginsMsg, SendMsg, SendResponse,
+    ServerMessage, ServiceLogOutput, ServiceLogsMsg, SetActiveModelMsg, SkillInfo, SkillList,
+    StartServiceMsg, StopServiceMsg, StreamEvent, StreamMsg, SubscribeEventMsg, SubscriptionInfo,
+    SubscriptionList, UninstallPluginMsg, UnsubscribeEventMsg, UpdateAgentMsg, UpsertMcpMsg,
+    client_message, plugin_event, server_message, stream_event,
 };
 use anyhow::Result;
 use futures_core::Stream;
@@ -48,6 +50,14 @@ pub trait Client: Send {
         async move { SendResponse::try_from(self.request(req.into()).await?) }
     }
 
+    /// Relay one agent's output to another and receive the reply.
+    fn relay(
+        &mut self,
+        req: RelayMsg,
+    ) -> impl std::future::Future<Output = Result<SendResponse>> + Send {
+        async move { SendResponse::try_from(self.request(req.into()).await?) }
+    }
+
     /// Send a message to an agent and receive a streamed response.
     fn stream(
         &mut self,
@@ -67,6 +77,23 @@ pub trait Client: Send {
             .map(|r| r.and_then(stream_event::Event::try_from))
     }
 
+    /// Abort the turn running on the conversation `req` names. Its stream
+    /// ends as usual, with the history the turn built so far kept. Errors
+    /// when no turn is running.
+    fn cancel(&mut self, req: CancelMsg) -> impl std::future::Future<Output = Result<()>> + Send {
+        async move {
+            match self.request(req.into()).await? {
+                ServerMessage {
+                    msg: Some(server_message::Msg::Pong(_)),
+                } => Ok(()),
+                ServerMessage {
+                    msg: Some(server_message::Msg::Error(ErrorMsg { code, message })),
+                } => anyhow::bail!("server error ({code}): {message}"),
+                other => anyhow::bail!("unexpected response: {other:?}"),
+            }
+        }
+    }
+
     /// Ping the server (keepalive).
     fn ping(&mut self) -> impl std::future::Future<Output = Result<()>> + Send {
         async move {
@@ -111,6 +138,30 @@ pub trait Client: Send {
         }
     }
 
+    /// Fetch the raw JSON of the daemon's most recent chat call.
+    fn get_llm_exchange(
+        &mut self,
+    ) -> impl std::future::Future<Output = Result<LlmExchange>> + Send {
+        async move {
+            match self
+                .request(ClientMessage {
+                    msg: Some(client_message::Msg::GetLlmExchange(GetLlmExchange {})),
+                })
+                .await?
+            {
+                ServerMessage {
+                    msg: Some(server_message::Msg::LlmExchange(exchange)),
+                } => Ok(exchange),
+                ServerMessage {
+                    msg: Some(server_message::Msg::Error(ErrorMsg { code, message })),
+                } => {
+                    anyhow::bail!("server error ({code}): {message}")
+                }
+                other => anyhow::bail!("unexpected response: {other:?}"),
+            }
+        }
+    }
+
     /// List all registered agents.
     fn list_agents(&mut self) -> impl std::future::Future<Output = Result<Vec<AgentInfo>>> + Send {
         async move {
@@ -624,6 +675,98 @@ pub trait Client: Send {
         }
     }
 
+    /// Fetch one page of memory entries. Pass the previous page's
+    /// `next_cursor` to continue; a `limit` of 0 uses the server default.
+    fn list_memory(
+        &mut self,
+        cursor: Option<String>,
+        limit: u32,
+    ) -> impl std::future::Future<Output = Result<MemoryList>> + Send {
+        async move {
+            match self
+                .request(ClientMessage {
+                    msg: Some(client_message::Msg::ListMemory(ListMemoryMsg {
+                        cursor,
+                        limit,
+                    })),
+                })
+                .await?
+            {
+                ServerMessage {
+                    msg: Some(server_message::Msg::MemoryList(list)),
+                } => Ok(list),
+                ServerMessage {
+                    msg: Some(server_message::Msg::Error(ErrorMsg { code, message })),
+                } => {
+                    anyhow::bail!("server error ({code}): {message}")
+                }
+                other => anyhow::bail!("unexpected response: {other:?}"),
+            }
+        }
+    }
+
+    /// Rank memory entries against `query`, best first. A `limit` of 0
+    /// uses the server default; hits scoring below `threshold` are dropped.
+    fn recall_memory(
+        &mut self,
+        query: String,
+        limit: u32,
+        threshold: f64,
+    ) -> impl std::future::Future<Output = Result<MemoryRecall>> + Send {
+        async move {
+            match self
+                .request(ClientMessage {
+                    msg: Some(client_message::Msg::RecallMemory(RecallMemoryMsg {
+                        query,
+                        limit,
+                        threshold,
+                    })),
+                })
+                .await?
+            {
+                ServerMessage {
+                    msg: Some(server_message::Msg::MemoryRecall(recall)),
+                } => Ok(recall),
+                ServerMessage {
+                    msg: Some(server_message::Msg::Error(ErrorMsg { code, message })),
+                } => {
+                    anyhow::bail!("server error ({code}): {message}")
+                }
+                other => anyhow::bail!("unexpected response: {other:?}"),
+            }
+        }
+    }
+
+    /// Import memory entries. Existing names are reported back as
+    /// skipped unless `force` is set.
+    fn import_memory(
+        &mut self,
+        entries: Vec<MemoryEntryInfo>,
+        force: bool,
+    ) -> impl std::future::Future<Output = Result<MemoryImported>> + Send {
+        async move {
+            match self
+                .request(ClientMessage {
+                    msg: Some(client_message::Msg::ImportMemory(ImportMemoryMsg {
+                        entries,
+                        force,
+                    })),
+                })
+                .await?
+            {
+                ServerMessage {
+                    msg: Some(server_message::Msg::MemoryImported(imported)),
+                } => Ok(imported),
+                ServerMessage {
+                    msg: Some(server_message::Msg::Error(ErrorMsg { code, message })),
+                } => {
+                    anyhow::bail!("server error ({code}): {message}")
+                }
+                other => anyhow::bail!("unexpected response: {other:?}"),
+            }
+        }
+    }
+
     /// List all resolved models with provider and active state.
     fn list_models(&mut self) -> impl std::future::Future<Output = Result<Vec<ModelInfo>>> + Send {
         async move {
@@ -1,13 +1,15 @@
 //! Server trait — one async method per protocol operation.
 
+use crate::RuntimeError;
 use crate::protocol::message::{
-    ActiveConversationInfo, ActiveConversationList, AgentEventMsg, AgentInfo, AgentList,
+    ActiveConversationInfo, ActiveConversationList, AgentEventMsg, AgentInfo, AgentList, CancelMsg,
     ClientMessage, CompactResponse, ConversationHistory, ConversationInfo, ConversationList,
-    CreateAgentMsg, DaemonStats, ErrorMsg, InstallPluginMsg, McpInfo, McpList, ModelInfo,
-    ModelList, PluginEvent, PluginInfo, PluginList, PluginSearchList, Pong, PublishEventMsg,
-    SendMsg, SendResponse, ServerMessage, ServiceLogOutput, SkillInfo, SkillList, SteerSessionMsg,
-    StreamEvent, StreamMsg, SubscribeEventMsg, SubscriptionInfo, SubscriptionList, UpdateAgentMsg,
-    UpsertMcpMsg, client_message, server_message,
+    CreateAgentMsg, DaemonStats, ErrorMsg, ImportMemoryMsg, InstallPluginMsg, ListMemoryMsg,
+    LlmExchange, McpInfo, McpList, MemoryImported, MemoryList, MemoryRecall, ModelInfo, ModelList,
+    PluginEvent, PluginInfo, PluginList, PluginSearchList, Pong, PublishEventMsg, RecallMemoryMsg,
+    RelayMsg, SendMsg, SendResponse, ServerMessage, ServiceLogOutput, SkillInfo, SkillList,
+    SteerSessionMsg, StreamEvent, StreamMsg, SubscribeEventMsg, SubscriptionInfo, SubscriptionList,
+    ToolDecisionMsg, UpdateAgentMsg, UpsertMcpMsg, client_message, server_message,
 };
 use anyhow::Result;
 use futures_core::Stream;
@@ -20,6 +22,15 @@ fn server_error(code: u32, message: String) -> ServerMessage {
     }
 }
 
+/// Construct an error `ServerMessage` from a handler error. Typed
+/// [`RuntimeError`]s keep their status code; anything else is a 500.
+fn error_to_msg(e: anyhow::Error) -> ServerMessage {
+    let code = e
+        .downcast_ref::<RuntimeError>()
+        .map_or(500, RuntimeError::code);
+    server_error(code, e.to_string())
+}
+
 /// Construct a pong `ServerMessage`.
 fn server_pong() -> ServerMessage {
     ServerMessage {
@@ -31,7 +42,7 @@ fn server_pong() -> ServerMessage {
 fn result_to_msg<T: Into<ServerMessage>>(result: Result<T>) -> ServerMessage {
     match result {
         Ok(resp) => resp.into(),
-        Err(e) => server_error(500, e.to_string()),
+        Err(e) => error_to_msg(e),
     }
 }
 
@@ -51,6 +62,13 @@ pub trait Server: Sync {
     /// Handle `Stream` — run agent and stream response events.
     fn stream(&self, req: StreamMsg) -> impl Stream<Item = Result<StreamEvent>> + Send;
 
+    /// Handle `Relay` — run `to_agent` statelessly on `from_agent`'s
+    /// output and return the reply.
+    fn relay(
+        &self,
+        req: RelayMsg,
+    ) -> impl std::future::Future<Output = Result<SendResponse>> + Send;
+
     /// Handle `Ping` — keepalive.
     fn ping(&self) -> impl std::future::Future<Output = Result<()>> + Send;
 
@@ -75,6 +93,9 @@ pub trait Server: Sync {
     /// Handle `GetStats` — return daemon-level stats.
     fn get_stats(&self) -> impl std::future::Future<Output = Result<DaemonStats>> + Send;
 
+    /// Handle `GetLlmExchange` — return the most recent captured chat call.
+    fn get_llm_exchange(&self) -> impl std::future::Future<Output = Result<LlmExchange>> + Send;
+
     /// Handle `SubscribeEvent` — create an event bus subscription.
     fn subscribe_event(
         &self,
@@ -96,6 +117,7 @@ pub trait Server: Sync {
     ) -> impl std::future::Future<Output = Result<()>> + Send;
 
     /// Handle `Compact` — compact a conversation's history into a summary.
+    /// An empty summary means there was no conversation to compact.
     fn compact_conversation(
         &self,
         agent: String,
@@ -110,12 +132,22 @@ pub trait Server: Sync {
         content: String,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
 
+    /// Handle `ToolDecision` — approve or deny a tool call waiting on
+    /// confirmation.
+    fn tool_decision(
+        &self,
+        req: ToolDecisionMsg,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
     /// Handle `SteerSession` — inject a user message into an active stream.
     fn steer_session(
         &self,
         req: SteerSessionMsg,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
 
+    /// Handle `Cancel` — abort the turn running on a conversation.
+    fn cancel_turn(&self, req: CancelMsg) -> impl std::future::Future<Output = Result<()>> + Send;
+
     /// Handle `ListAgents` — return all registered agents.
     fn list_agents(&self) -> impl std::future::Future<Output = Result<Vec<AgentInfo>>> + Send;
 
@@ -168,6 +200,25 @@ pub trait Server: Sync {
     /// Handle `ListSkills` — return all available skills with enabled state.
     fn list_skills(&self) -> impl std::future::Future<Output = Result<Vec<SkillInfo>>> + Send;
 
+    /// Handle `ListMemory` — return one page of memory entries.
+    fn list_memory(
+        &self,
+        req: ListMemoryMsg,
+    ) -> impl std::future::Future<Output = Result<MemoryList>> + Send;
+
+    /// Handle `ImportMemory` — write entries, skipping existing names
+    /// unless forced.
+    fn import_memory(
+        &self,
+        req: ImportMemoryMsg,
+    ) -> impl std::future::Future<Output = Result<MemoryImported>> + Send;
+
+    /// Handle `RecallMemory` — rank memory entries against a query.
+    fn recall_memory(
+        &self,
+        req: RecallMemoryMsg,
+    ) -> impl std::future::Future<Output = Result<MemoryRecall>> + Send;
+
     /// Handle `ListModels` — return all resolved models with provider and active state.
     fn list_models(&self) -> impl std::future::Future<Output = Result<Vec<ModelInfo>>> + Send;
 
@@ -258,10 +309,18 @@ pub trait Server: Sync {
                         yield result_to_msg(result);
                     }
                 }
+                client_message::Msg::Relay(relay_msg) => {
+                    yield result_to_msg(self.relay(relay_msg).await);
+                }
+                client_message::Msg::Hello(_) => {
+                    // The transport answers the handshake before dispatch;
+                    // reaching here means it was bypassed.
+                    yield server_error(400, "hello is handled by the transport".to_string());
+                }
                 client_message::Msg::Ping(_) => {
                     yield match self.ping().await {
                         Ok(()) => server_pong(),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::ListActiveConversations(_req) => {
@@ -269,7 +328,7 @@ pub trait Server: Sync {
                         Ok(conversations) => ServerMessage {
                             msg: Some(server_message::Msg::ActiveConversations(ActiveConversationList { conversations })),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::Kill(kill_msg) => {
@@ -279,7 +338,7 @@ pub trait Server: Sync {
                             404,
                             format!("conversation not found for agent='{}' sender='{}'", kill_msg.agent, kill_msg.sender),
                         ),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::GetConfig(_) => {
@@ -295,7 +354,7 @@ pub trait Server: Sync {
                 client_message::Msg::Reload(_) => {
                     yield match self.reload().await {
                         Ok(()) => server_pong(),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::ReplyToAsk(msg) => {
@@ -304,10 +363,22 @@ pub trait Server: Sync {
                         Err(e) => server_error(404, e.to_string()),
                     };
                 }
+                client_message::Msg::ToolDecision(req) => {
+                    yield match self.tool_decision(req).await {
+                        Ok(()) => server_pong(),
+                        Err(e) => error_to_msg(e),
+                    };
+                }
                 client_message::Msg::SteerSession(req) => {
                     yield match self.steer_session(req).await {
                         Ok(()) => server_pong(),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
+                    };
+                }
+                client_message::Msg::Cancel(req) => {
+                    yield match self.cancel_turn(req).await {
+                        Ok(()) => server_pong(),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::GetStats(_) => {
@@ -315,7 +386,15 @@ pub trait Server: Sync {
                         Ok(stats) => ServerMessage {
                             msg: Some(server_message::Msg::Stats(stats)),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
+                    };
+                }
+                client_message::Msg::GetLlmExchange(_) => {
+                    yield match self.get_llm_exchange().await {
+                        Ok(exchange) => ServerMessage {
+                            msg: Some(server_message::Msg::LlmExchange(exchange)),
+                        },
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::SubscribeEvent(req) => {
@@ -323,14 +402,14 @@ pub trait Server: Sync {
                         Ok(info) => ServerMessage {
                             msg: Some(server_message::Msg::SubscriptionInfo(info)),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::UnsubscribeEvent(req) => {
                     yield match self.unsubscribe_event(req.id).await {
                         Ok(true) => server_pong(),
                         Ok(false) => server_error(404, format!("subscription {} not found", req.id)),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::ListSubscriptions(_) => {
@@ -338,13 +417,13 @@ pub trait Server: Sync {
                         Ok(list) => ServerMessage {
                             msg: Some(server_message::Msg::SubscriptionList(list)),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::PublishEvent(req) => {
                     yield match self.publish_event(req).await {
                         Ok(()) => server_pong(),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::Compact(req) => {
@@ -352,7 +431,7 @@ pub trait Server: Sync {
                         Ok(summary) => ServerMessage {
                             msg: Some(server_message::Msg::Compact(CompactResponse { summary })),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::ListAgents(_) => {
@@ -360,7 +439,7 @@ pub trait Server: Sync {
                         Ok(agents) => ServerMessage {
                             msg: Some(server_message::Msg::AgentList(AgentList { agents })),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::GetAgent(req) => {
@@ -376,7 +455,7 @@ pub trait Server: Sync {
                         Ok(info) => ServerMessage {
                             msg: Some(server_message::Msg::AgentInfo(info)),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::UpdateAgent(req) => {
@@ -384,7 +463,7 @@ pub trait Server: Sync {
                         Ok(info) => ServerMessage {
                             msg: Some(server_message::Msg::AgentInfo(info)),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::DeleteAgent(req) => {
@@ -394,7 +473,7 @@ pub trait Server: Sync {
                             404,
                             format!("agent '{}' not found in local manifest", req.name),
                         ),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::RenameAgent(req) => {
@@ -402,7 +481,7 @@ pub trait Server: Sync {
                         Ok(info) => ServerMessage {
                             msg: Some(server_message::Msg::AgentInfo(info)),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::InstallPlugin(req) => {
@@ -426,7 +505,7 @@ pub trait Server: Sync {
                                 plugins,
                             })),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::SearchPlugins(req) => {
@@ -436,19 +515,19 @@ pub trait Server: Sync {
                                 plugins,
                             })),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::StartService(req) => {
                     yield match self.start_service(req.name, req.force).await {
                         Ok(()) => server_pong(),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::StopService(req) => {
                     yield match self.stop_service(req.name).await {
                         Ok(()) => server_pong(),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::ServiceLogs(req) => {
@@ -458,7 +537,7 @@ pub trait Server: Sync {
                                 ServiceLogOutput { content },
                             )),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::ListSkills(_) => {
@@ -466,7 +545,31 @@ pub trait Server: Sync {
                         Ok(skills) => ServerMessage {
                             msg: Some(server_message::Msg::SkillList(SkillList { skills })),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
+                    };
+                }
+                client_message::Msg::ListMemory(req) => {
+                    yield match self.list_memory(req).await {
+                        Ok(list) => ServerMessage {
+                            msg: Some(server_message::Msg::MemoryList(list)),
+                        },
+                        Err(e) => error_to_msg(e),
+                    };
+                }
+                client_message::Msg::ImportMemory(req) => {
+                    yield match self.import_memory(req).await {
+                        Ok(imported) => ServerMessage {
+                            msg: Some(server_message::Msg::MemoryImported(imported)),
+                        },
+                        Err(e) => error_to_msg(e),
+                    };
+                }
+                client_message::Msg::RecallMemory(req) => {
+                    yield match self.recall_memory(req).await {
+                        Ok(recall) => ServerMessage {
+                            msg: Some(server_message::Msg::MemoryRecall(recall)),
+                        },
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::ListModels(_) => {
@@ -474,7 +577,7 @@ pub trait Server: Sync {
                         Ok(models) => ServerMessage {
                             msg: Some(server_message::Msg::ModelList(ModelList { models })),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::ListConversations(req) => {
@@ -484,7 +587,7 @@ pub trait Server: Sync {
                                 conversations,
                             })),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::GetConversationHistory(req) => {
@@ -493,7 +596,7 @@ pub trait Server: Sync {
                 client_message::Msg::DeleteConversation(req) => {
                     yield match self.delete_conversation(req.file_path).await {
                         Ok(()) => server_pong(),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::ListMcps(_) => {
@@ -501,7 +604,7 @@ pub trait Server: Sync {
                         Ok(mcps) => ServerMessage {
                             msg: Some(server_message::Msg::McpList(McpList { mcps })),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::UpsertMcp(req) => {
@@ -509,20 +612,20 @@ pub trait Server: Sync {
                         Ok(info) => ServerMessage {
                             msg: Some(server_message::Msg::McpInfo(info)),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::DeleteMcp(req) => {
                     yield match self.delete_mcp(req.name.clone()).await {
                         Ok(true) => server_pong(),
                         Ok(false) => server_error(404, format!("mcp '{}' not found", req.name)),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::SetActiveModel(req) => {
                     yield match self.set_active_model(req.model).await {
                         Ok(()) => server_pong(),
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
                 client_message::Msg::Extension(payload) => {
@@ -530,7 +633,7 @@ pub trait Server: Sync {
                         Ok(response) => ServerMessage {
                             msg: Some(server_message::Msg::Extension(response)),
                         },
-                        Err(e) => server_error(500, e.to_string()),
+                        Err(e) => error_to_msg(e),
                     };
                 }
             }
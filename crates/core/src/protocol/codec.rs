@@ -1,16 +1,108 @@
 //! Length-prefixed framing codec for crabtalk wire protocol.
 //!
-//! Wire format: `[u32 BE length][protobuf payload]`. The length is the byte
-//! count of the payload only (not including the 4-byte header). Generic over
-//! `AsyncRead`/`AsyncWrite` — used by both UDS and TCP transports.
+//! Wire format: `[u32 BE header][protobuf payload]`. The low 28 bits of
+//! the header are the byte count of the payload only (not including the
+//! 4-byte header); the high 4 bits name the payload's [`Compression`],
+//! zero for a plain frame. Generic over `AsyncRead`/`AsyncWrite` — used by
+//! both UDS and TCP transports.
+//!
+//! Readers always honour the flag. Writers only compress once a `Hello`
+//! handshake has agreed on an algorithm, and only frames larger than
+//! [`COMPRESS_THRESHOLD`].
 
 use prost::Message;
-use std::io;
+use std::io::{self, Read, Write};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-/// Maximum frame size: 16 MiB.
+/// Maximum frame size: 16 MiB. Applies to the payload both on the wire
+/// and once decompressed.
 pub const MAX_FRAME_SIZE: u32 = 16 * 1024 * 1024;
 
+/// Payloads at or below this size are sent plain even when compression is
+/// on — the saving doesn't pay for the CPU.
+pub const COMPRESS_THRESHOLD: usize = 4 * 1024;
+
+/// Bits of the header below the compression flag.
+const LEN_BITS: u32 = 28;
+const LEN_MASK: u32 = (1 << LEN_BITS) - 1;
+
+/// Per-frame payload compression, negotiated by the `Hello` handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Every supported algorithm, most preferred first.
+    pub const ALL: [Self; 2] = [Self::Zstd, Self::Gzip];
+
+    /// The name used in `Hello` and `HelloReply`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Parse a handshake name; `None` for unsupported algorithms.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    fn flag(self) -> u32 {
+        match self {
+            Self::Gzip => 1,
+            Self::Zstd => 2,
+        }
+    }
+
+    fn from_flag(flag: u32) -> Result<Option<Self>, FrameError> {
+        match flag {
+            0 => Ok(None),
+            1 => Ok(Some(Self::Gzip)),
+            2 => Ok(Some(Self::Zstd)),
+            other => Err(FrameError::Codec(format!(
+                "unknown compression flag {other}"
+            ))),
+        }
+    }
+
+    /// Compress `data`.
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Gzip => {
+                let mut enc =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+                enc.write_all(data)?;
+                enc.finish()
+            }
+            Self::Zstd => zstd::encode_all(data, 0),
+        }
+    }
+
+    /// Decompress `data`, refusing output larger than [`MAX_FRAME_SIZE`].
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>, FrameError> {
+        let limit = u64::from(MAX_FRAME_SIZE) + 1;
+        let mut out = Vec::new();
+        match self {
+            Self::Gzip => flate2::read::GzDecoder::new(data)
+                .take(limit)
+                .read_to_end(&mut out)?,
+            Self::Zstd => zstd::Decoder::new(data)?
+                .take(limit)
+                .read_to_end(&mut out)?,
+        };
+        if out.len() as u64 >= limit {
+            return Err(FrameError::Codec(format!(
+                "{} frame inflates past {MAX_FRAME_SIZE} bytes",
+                self.name()
+            )));
+        }
+        Ok(out)
+    }
+}
+
 /// Errors that can occur during frame read/write.
 #[derive(Debug)]
 pub enum FrameError {
@@ -64,25 +156,51 @@ where
     W: tokio::io::AsyncWrite + Unpin,
     T: Message,
 {
-    let data = msg.encode_to_vec();
+    write_message_with(writer, msg, None).await
+}
+
+/// Write a typed message, compressing the payload with `compression` when
+/// it exceeds [`COMPRESS_THRESHOLD`] and compressing actually shrinks it.
+pub async fn write_message_with<W, T>(
+    writer: &mut W,
+    msg: &T,
+    compression: Option<Compression>,
+) -> Result<(), FrameError>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+    T: Message,
+{
+    let mut data = msg.encode_to_vec();
+    let mut flag = 0;
+    if let Some(c) = compression
+        && data.len() > COMPRESS_THRESHOLD
+    {
+        let packed = c.compress(&data)?;
+        if packed.len() < data.len() {
+            data = packed;
+            flag = c.flag();
+        }
+    }
     let len = data.len() as u32;
     if len > MAX_FRAME_SIZE {
         return Err(FrameError::TooLarge { size: len });
     }
-    writer.write_all(&len.to_be_bytes()).await?;
+    let header = (flag << LEN_BITS) | len;
+    writer.write_all(&header.to_be_bytes()).await?;
     writer.write_all(&data).await?;
     writer.flush().await?;
     Ok(())
 }
 
 /// Read a length-prefixed protobuf frame and deserialize into a typed message.
+/// Compressed frames are decompressed according to their header flag.
 pub async fn read_message<R, T>(reader: &mut R) -> Result<T, FrameError>
 where
     R: tokio::io::AsyncRead + Unpin,
     T: Message + Default,
 {
-    let mut len_buf = [0u8; 4];
-    match reader.read_exact(&mut len_buf).await {
+    let mut header_buf = [0u8; 4];
+    match reader.read_exact(&mut header_buf).await {
         Ok(_) => {}
         Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
             return Err(FrameError::ConnectionClosed);
@@ -90,13 +208,18 @@ where
         Err(e) => return Err(FrameError::Io(e)),
     }
 
-    let len = u32::from_be_bytes(len_buf);
+    let header = u32::from_be_bytes(header_buf);
+    let compression = Compression::from_flag(header >> LEN_BITS)?;
+    let len = header & LEN_MASK;
     if len > MAX_FRAME_SIZE {
         return Err(FrameError::TooLarge { size: len });
     }
 
     let mut buf = vec![0u8; len as usize];
     reader.read_exact(&mut buf).await?;
+    if let Some(c) = compression {
+        buf = c.decompress(&buf)?;
+    }
     let msg = T::decode(&buf[..])?;
     Ok(msg)
 }
@@ -2,9 +2,9 @@
 
 use crate::agent::AgentConfig;
 use crate::protocol::proto::{
-    AgentEventMsg, AgentInfo, ClientMessage, ConversationHistory, PluginEvent, ReplyToAsk, SendMsg,
-    SendResponse, ServerMessage, StreamEvent, StreamMsg, client_message, plugin_event,
-    server_message, stream_event,
+    AgentEventMsg, AgentInfo, CancelMsg, ClientMessage, ConversationHistory, PluginEvent, RelayMsg,
+    ReplyToAsk, SendMsg, SendResponse, ServerMessage, StreamEvent, StreamMsg, ToolDecisionMsg,
+    client_message, plugin_event, server_message, stream_event,
 };
 
 impl From<&AgentConfig> for AgentInfo {
@@ -42,6 +42,14 @@ impl From<StreamMsg> for ClientMessage {
     }
 }
 
+impl From<RelayMsg> for ClientMessage {
+    fn from(msg: RelayMsg) -> Self {
+        Self {
+            msg: Some(client_message::Msg::Relay(msg)),
+        }
+    }
+}
+
 impl From<ReplyToAsk> for ClientMessage {
     fn from(msg: ReplyToAsk) -> Self {
         Self {
@@ -50,6 +58,22 @@ impl From<ReplyToAsk> for ClientMessage {
     }
 }
 
+impl From<ToolDecisionMsg> for ClientMessage {
+    fn from(msg: ToolDecisionMsg) -> Self {
+        Self {
+            msg: Some(client_message::Msg::ToolDecision(msg)),
+        }
+    }
+}
+
+impl From<CancelMsg> for ClientMessage {
+    fn from(msg: CancelMsg) -> Self {
+        Self {
+            msg: Some(client_message::Msg::Cancel(msg)),
+        }
+    }
+}
+
 // ── ServerMessage constructors ───────────────────────────────────
 
 impl From<SendResponse> for ServerMessage {
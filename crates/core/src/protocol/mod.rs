@@ -5,5 +5,8 @@ pub mod codec;
 pub mod message;
 pub mod proto;
 
+/// Relays a single chain may make before the daemon refuses to forward.
+pub const MAX_RELAY_HOPS: u32 = 8;
+
 /// Current protocol version.
 pub const PROTOCOL_VERSION: &str = "0.3";
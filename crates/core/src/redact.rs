@@ -0,0 +1,125 @@
+//! Secret and PII scrubbing for history that leaves the live session.
+//!
+//! [`RedactionConfig`] holds a compiled pattern set. Callers that write
+//! history to disk (audit logs, session export) call
+//! [`HistoryEntry::redacted`] to get a scrubbed copy; the live session
+//! keeps the original.
+
+use crate::model::{HistoryEntry, ToolCall};
+use anyhow::Result;
+use regex::Regex;
+use std::borrow::Cow;
+
+/// Replacement text for every match.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Default patterns: provider API keys, bearer tokens, bot tokens, email
+/// addresses, and card numbers. Each one needs a shape that prose, paths
+/// and ids rarely take, so ordinary text passes through untouched.
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    // OpenAI / Anthropic / DeepSeek style keys: hyphenated prefixes, then
+    // one long random run (`sk-learn-style` words stay).
+    r"\bsk-(?:[A-Za-z0-9]+-)*[A-Za-z0-9_]{20,}",
+    // GitHub tokens.
+    r"\bgh[pousr]_[A-Za-z0-9]{20,}",
+    r"\bgithub_pat_[A-Za-z0-9_]{20,}",
+    // Slack tokens.
+    r"\bxox[abprs]-[A-Za-z0-9-]{10,}",
+    // AWS access key IDs.
+    r"\bAKIA[0-9A-Z]{16}\b",
+    // Google API keys.
+    r"\bAIza[0-9A-Za-z_-]{35}",
+    // Telegram bot tokens.
+    r"\b\d{8,10}:[A-Za-z0-9_-]{35}",
+    // Authorization headers carry a credential whatever its length; a bare
+    // "bearer" needs a token-length run after it.
+    r"(?i)\bauthorization:\s*bearer\s+[A-Za-z0-9._~+/-]+=*",
+    r"(?i)\bbearer\s+[A-Za-z0-9._~+/-]{20,}=*",
+    // Email addresses. The domain starts with a letter, which keeps
+    // `icon@2x.png` out.
+    r"\b[A-Za-z0-9._%+-]+@[A-Za-z][A-Za-z0-9-]*(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b",
+    // Visa, Mastercard and Discover numbers (4-4-4-4) and Amex (4-6-5),
+    // optionally grouped by spaces or dashes. Other digit runs, such as
+    // millisecond timestamps, stay.
+    r"\b(?:4\d{3}|5[1-5]\d{2}|2[2-7]\d{2}|6(?:011|5\d{2}))(?:[ -]?\d{4}){3}\b",
+    r"\b3[47]\d{2}[ -]?\d{6}[ -]?\d{5}\b",
+];
+
+/// A compiled set of redaction patterns.
+#[derive(Debug, Clone)]
+pub struct RedactionConfig {
+    patterns: Vec<Regex>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_PATTERNS).expect("default redaction patterns compile")
+    }
+}
+
+impl RedactionConfig {
+    /// Compile a pattern set. Fails on the first invalid regex.
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| Regex::new(p.as_ref()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Add a pattern on top of the current set (chainable).
+    pub fn with_pattern(mut self, pattern: &str) -> Result<Self> {
+        self.patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Replace every match in `text` with [`REDACTED`]. Borrows when
+    /// nothing matched.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut out = Cow::Borrowed(text);
+        for re in &self.patterns {
+            if let Cow::Owned(replaced) = re.replace_all(&out, REDACTED) {
+                out = Cow::Owned(replaced);
+            }
+        }
+        out
+    }
+}
+
+impl HistoryEntry {
+    /// A copy of this entry with secrets scrubbed from its text (plain or
+    /// every `text` part), reasoning, and tool call arguments. `self` is
+    /// left untouched.
+    pub fn redacted(&self, config: &RedactionConfig) -> HistoryEntry {
+        let mut entry = self.clone();
+        let message = &mut entry.message;
+        match &mut message.content {
+            Some(serde_json::Value::String(content)) => redact_string(content, config),
+            Some(serde_json::Value::Array(parts)) => {
+                for part in parts {
+                    if let Some(serde_json::Value::String(text)) = part.get_mut("text") {
+                        redact_string(text, config);
+                    }
+                }
+            }
+            _ => {}
+        }
+        if let Some(reasoning) = &mut message.reasoning_content {
+            redact_string(reasoning, config);
+        }
+        if let Some(calls) = &mut message.tool_calls {
+            calls.iter_mut().for_each(|call| redact_call(call, config));
+        }
+        entry
+    }
+}
+
+fn redact_call(call: &mut ToolCall, config: &RedactionConfig) {
+    redact_string(&mut call.function.arguments, config);
+}
+
+fn redact_string(text: &mut String, config: &RedactionConfig) {
+    if let Cow::Owned(scrubbed) = config.redact(text) {
+        *text = scrubbed;
+    }
+}
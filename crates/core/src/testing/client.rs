@@ -0,0 +1,123 @@
+//! `TestClient` — in-process implementation of [`Client`] for testing
+//! the channel apps (Telegram, WeChat, cron) without a running daemon.
+//!
+//! Replies are scripted up front: `request` pops one [`ServerMessage`]
+//! per call, `request_stream` pops one batch per call and yields it in
+//! full. Every [`ClientMessage`] sent is recorded so tests can assert on
+//! exactly what a channel delivered to the daemon.
+//!
+//! Errors out when the script runs dry, mirroring
+//! [`TestProvider`](super::provider::TestProvider).
+
+use crate::protocol::{
+    api::Client,
+    message::{
+        ClientMessage, SendResponse, ServerMessage, StreamChunk, StreamEnd, StreamEvent,
+        server_message, stream_event,
+    },
+};
+use anyhow::Result;
+use futures_core::Stream;
+use parking_lot::Mutex;
+use std::{collections::VecDeque, sync::Arc};
+
+/// A scripted client that records every message it sends.
+///
+/// `Clone` is cheap — clones share the script and the recorded requests,
+/// so a test can hand one clone to the code under test and keep another
+/// to inspect.
+#[derive(Clone, Default, Debug)]
+pub struct TestClient {
+    replies: Arc<Mutex<VecDeque<ServerMessage>>>,
+    streams: Arc<Mutex<VecDeque<Vec<ServerMessage>>>>,
+    sent: Arc<Mutex<Vec<ClientMessage>>>,
+}
+
+impl TestClient {
+    /// Create an empty client. Script replies with [`TestClient::push_reply`]
+    /// and [`TestClient::push_stream`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a reply for the next `request` call.
+    pub fn push_reply(&self, msg: ServerMessage) -> &Self {
+        self.replies.lock().push_back(msg);
+        self
+    }
+
+    /// Queue a batch for the next `request_stream` call.
+    pub fn push_stream(&self, batch: Vec<ServerMessage>) -> &Self {
+        self.streams.lock().push_back(batch);
+        self
+    }
+
+    /// Messages sent so far, in call order.
+    pub fn sent(&self) -> Vec<ClientMessage> {
+        self.sent.lock().clone()
+    }
+}
+
+impl Client for TestClient {
+    async fn request(&mut self, msg: ClientMessage) -> Result<ServerMessage> {
+        self.sent.lock().push(msg);
+        self.replies
+            .lock()
+            .pop_front()
+            .ok_or_else(|| anyhow::anyhow!("TestClient: no more scripted replies"))
+    }
+
+    fn request_stream(
+        &mut self,
+        msg: ClientMessage,
+    ) -> impl Stream<Item = Result<ServerMessage>> + Send + '_ {
+        self.sent.lock().push(msg);
+        let batch = self.streams.lock().pop_front();
+        async_stream::stream! {
+            match batch {
+                Some(batch) => {
+                    for msg in batch {
+                        yield Ok(msg);
+                    }
+                }
+                None => yield Err(anyhow::anyhow!("TestClient: no more scripted streams")),
+            }
+        }
+    }
+}
+
+// ── Fixture constructors ──
+
+/// A `Send` reply carrying `content`.
+pub fn response(agent: &str, content: &str) -> ServerMessage {
+    ServerMessage {
+        msg: Some(server_message::Msg::Response(SendResponse {
+            agent: agent.to_owned(),
+            content: content.to_owned(),
+            ..Default::default()
+        })),
+    }
+}
+
+/// A complete `Stream` reply: one text event per chunk, then the end
+/// sentinel.
+pub fn text_stream(agent: &str, chunks: &[&str]) -> Vec<ServerMessage> {
+    let event = |event| ServerMessage {
+        msg: Some(server_message::Msg::Stream(StreamEvent {
+            event: Some(event),
+        })),
+    };
+    let mut batch: Vec<_> = chunks
+        .iter()
+        .map(|c| {
+            event(stream_event::Event::Chunk(StreamChunk {
+                content: (*c).to_owned(),
+            }))
+        })
+        .collect();
+    batch.push(event(stream_event::Event::End(StreamEnd {
+        agent: agent.to_owned(),
+        ..Default::default()
+    })));
+    batch
+}
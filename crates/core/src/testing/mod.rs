@@ -1,12 +1,16 @@
 //! Shared test scaffolding for crabtalk-core and downstream crates.
 //!
 //! [`InMemoryStorage`] for a pluggable [`Storage`] without a filesystem,
-//! [`test_provider`] for a scripted [`Provider`], and [`test_schema`]
-//! for a minimal [`Tool`] schema.
+//! [`test_provider`] for a scripted [`Provider`], [`client`] for a
+//! scripted daemon [`Client`], [`test_schema`] for a minimal [`Tool`]
+//! schema, and [`test_dispatch`] for a tool call to hand a hook.
 
+use crate::ToolDispatch;
+pub use client::TestClient;
 use crabllm_core::{FunctionDef, Tool, ToolType};
 pub use mem::InMemoryStorage;
 
+pub mod client;
 mod mem;
 pub mod provider;
 
@@ -22,3 +26,16 @@ pub fn test_schema(name: &str) -> Tool {
         strict: None,
     }
 }
+
+/// Create a tool call from `agent` with JSON `args`, outside any
+/// conversation. Override the other fields with struct update syntax.
+pub fn test_dispatch(agent: &str, args: impl Into<String>) -> ToolDispatch {
+    ToolDispatch {
+        call_id: String::new(),
+        args: args.into(),
+        agent: agent.to_owned(),
+        sender: String::new(),
+        conversation_id: None,
+        cancel: None,
+    }
+}
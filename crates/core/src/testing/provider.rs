@@ -18,7 +18,7 @@
 use crabllm_core::{
     BoxStream, ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, Choice,
     ChunkChoice, Delta, Error, FinishReason, FunctionCallDelta, Message, Provider, Role, ToolCall,
-    ToolCallDelta, ToolType,
+    ToolCallDelta, ToolType, Usage,
 };
 use parking_lot::Mutex;
 use serde_json::{Map, Value};
@@ -28,11 +28,13 @@ use std::{collections::VecDeque, sync::Arc};
 ///
 /// Thread-safe via `Arc<Mutex<_>>` and `Clone` (cheap — clones share the
 /// same underlying script). The provider trait requires `Send + Sync`, both
-/// are satisfied.
+/// are satisfied. Every request it receives is recorded; read them back
+/// with [`TestProvider::requests`].
 #[derive(Clone, Default, Debug)]
 pub struct TestProvider {
     responses: Arc<Mutex<VecDeque<ChatCompletionResponse>>>,
     chunks: Arc<Mutex<VecDeque<Vec<ChatCompletionChunk>>>>,
+    requests: Arc<Mutex<Vec<ChatCompletionRequest>>>,
 }
 
 impl TestProvider {
@@ -41,6 +43,7 @@ impl TestProvider {
         Self {
             responses: Arc::new(Mutex::new(responses.into())),
             chunks: Arc::new(Mutex::new(VecDeque::new())),
+            requests: Arc::default(),
         }
     }
 
@@ -50,6 +53,7 @@ impl TestProvider {
         Self {
             responses: Arc::new(Mutex::new(VecDeque::new())),
             chunks: Arc::new(Mutex::new(chunks.into())),
+            requests: Arc::default(),
         }
     }
 
@@ -62,15 +66,22 @@ impl TestProvider {
         Self {
             responses: Arc::new(Mutex::new(responses.into())),
             chunks: Arc::new(Mutex::new(chunks.into())),
+            requests: Arc::default(),
         }
     }
+
+    /// Requests received so far, in call order.
+    pub fn requests(&self) -> Vec<ChatCompletionRequest> {
+        self.requests.lock().clone()
+    }
 }
 
 impl Provider for TestProvider {
     async fn chat_completion(
         &self,
-        _request: &ChatCompletionRequest,
+        request: &ChatCompletionRequest,
     ) -> Result<ChatCompletionResponse, Error> {
+        self.requests.lock().push(request.clone());
         let mut responses = self.responses.lock();
         responses.pop_front().ok_or_else(|| {
             Error::Internal("TestProvider: no more scripted responses for chat_completion".into())
@@ -79,8 +90,9 @@ impl Provider for TestProvider {
 
     async fn chat_completion_stream(
         &self,
-        _request: &ChatCompletionRequest,
+        request: &ChatCompletionRequest,
     ) -> Result<BoxStream<'static, Result<ChatCompletionChunk, Error>>, Error> {
+        self.requests.lock().push(request.clone());
         let batch = {
             let mut all = self.chunks.lock();
             all.pop_front()
@@ -197,6 +209,20 @@ pub fn finish_chunk(reason: FinishReason) -> ChatCompletionChunk {
     }
 }
 
+/// A trailing stream chunk reporting token usage, as providers send once
+/// the reply is complete.
+pub fn usage_chunk(prompt_tokens: u32, completion_tokens: u32) -> ChatCompletionChunk {
+    ChatCompletionChunk {
+        usage: Some(Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
 /// Convert a non-streaming `ToolCall` into a streaming `ToolCallDelta`
 /// carrying the full name + args in a single delta. Real LLM streams split
 /// these across many deltas, but the agent's `MessageBuilder::accept`
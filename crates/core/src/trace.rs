@@ -0,0 +1,22 @@
+//! Span helpers for the turn trace tree.
+//!
+//! A turn produces `turn` → `llm` / `tool` → (`delegate` → `turn` …)
+//! spans. Futures attach with [`tracing::Instrument`]; streams, which
+//! `Instrument` does not cover, use [`in_span`].
+
+use futures_core::Stream;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tracing::Span;
+
+/// Poll `stream` inside `span`, so spans opened while producing an item
+/// become its children. The span closes when the stream is dropped.
+pub fn in_span<S: Stream>(span: Span, stream: S) -> impl Stream<Item = S::Item> {
+    let mut stream = Box::pin(stream);
+    futures_util::stream::poll_fn(move |cx: &mut Context<'_>| -> Poll<Option<S::Item>> {
+        let _entered = span.enter();
+        Pin::as_mut(&mut stream).poll_next(cx)
+    })
+}
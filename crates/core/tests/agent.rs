@@ -2,11 +2,12 @@
 
 use crabllm_core::{FinishReason, FunctionCall, Role, ToolCall};
 use crabtalk_core::{
-    AgentBuilder, AgentConfig, AgentEvent, AgentStopReason, ToolDispatcher, ToolFuture,
+    AgentBuilder, AgentConfig, AgentEvent, AgentStopReason, ApprovalFuture, CancellationToken,
+    ResponseFormat, ResponseMode, ToolDispatcher, ToolFuture, ToolTimeouts,
     model::{HistoryEntry, Model},
     testing::provider::{
         TestProvider, finish_chunk, mixed_chunk, text_chunk, text_chunks, text_response,
-        thinking_chunk, tool_chunks, tool_response,
+        thinking_chunk, tool_chunks, tool_response, usage_chunk,
     },
 };
 use futures_util::StreamExt;
@@ -30,10 +31,12 @@ where
     fn dispatch<'a>(
         &'a self,
         name: &'a str,
+        _call_id: &'a str,
         _args: &'a str,
         _agent: &'a str,
         _sender: &'a str,
         _conversation_id: Option<u64>,
+        _cancel: Option<CancellationToken>,
     ) -> ToolFuture<'a> {
         (self.0)(name.to_owned())
     }
@@ -119,6 +122,61 @@ async fn step_no_dispatcher_returns_error_message() {
     );
 }
 
+#[tokio::test]
+async fn step_forwards_seed_to_provider() {
+    let model = TestProvider::new(vec![text_response("a"), text_response("b")]);
+    let seeded = AgentBuilder::new(Model::new(model.clone()))
+        .config(AgentConfig::new("test-agent").seed(42))
+        .build();
+    let unseeded = build_agent_no_tools(model.clone());
+
+    let mut history = vec![HistoryEntry::user("hi")];
+    seeded.step(&mut history.clone(), None).await.unwrap();
+    unseeded.step(&mut history, None).await.unwrap();
+
+    let requests = model.requests();
+    assert_eq!(requests[0].seed, Some(42));
+    assert_eq!(requests[1].seed, None);
+}
+
+#[tokio::test]
+async fn step_forwards_sampling_params() {
+    let model = TestProvider::new(vec![text_response("a"), text_response("b")]);
+    let tuned = AgentBuilder::new(Model::new(model.clone()))
+        .config(
+            AgentConfig::new("test-agent")
+                .top_p(0.9)
+                .stop(["END"])
+                .frequency_penalty(0.5)
+                .presence_penalty(0.25),
+        )
+        .build();
+    let plain = build_agent_no_tools(model.clone());
+
+    let mut history = vec![HistoryEntry::user("hi")];
+    tuned.step(&mut history.clone(), None).await.unwrap();
+    plain.step(&mut history, None).await.unwrap();
+
+    let requests = model.requests();
+    let sent = serde_json::to_value(&requests[0]).unwrap();
+    assert_eq!(sent["top_p"], 0.9);
+    assert_eq!(sent["stop"], serde_json::json!(["END"]));
+    assert_eq!(sent["frequency_penalty"], 0.5);
+    assert_eq!(sent["presence_penalty"], 0.25);
+
+    // Unset fields are left out of the request, not sent as null.
+    let sent = serde_json::to_value(&requests[1]).unwrap();
+    for field in [
+        "top_p",
+        "stop",
+        "frequency_penalty",
+        "presence_penalty",
+        "seed",
+    ] {
+        assert!(sent.get(field).is_none(), "{field} should be omitted");
+    }
+}
+
 #[tokio::test]
 async fn step_send_error_propagates() {
     // Empty script — send() will error.
@@ -147,7 +205,7 @@ async fn run_stream_surfaces_handler_error_end_to_end() {
 
     let mut history = vec![HistoryEntry::user("go")];
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -180,6 +238,84 @@ async fn run_stream_surfaces_handler_error_end_to_end() {
     }
 }
 
+/// Dispatcher that cancels the turn through the token it was handed,
+/// then never finishes.
+struct CancelFromTool;
+
+impl ToolDispatcher for CancelFromTool {
+    fn dispatch<'a>(
+        &'a self,
+        _name: &'a str,
+        _call_id: &'a str,
+        _args: &'a str,
+        _agent: &'a str,
+        _sender: &'a str,
+        _conversation_id: Option<u64>,
+        cancel: Option<CancellationToken>,
+    ) -> ToolFuture<'a> {
+        Box::pin(async move {
+            cancel.ok_or("no cancellation token")?.cancel();
+            std::future::pending().await
+        })
+    }
+}
+
+#[tokio::test]
+async fn run_stream_hands_cancel_token_to_tools() {
+    let calls = vec![make_tool_call("scrape", "{}")];
+    let model = TestProvider::with_chunks(vec![tool_chunks(calls), text_chunks("ack")]);
+
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(AgentConfig::new("test-agent"))
+        .dispatcher(Arc::new(CancelFromTool))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("go")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let cancel = CancellationToken::new();
+    let response = agent.run(&mut history, tx, None, None, Some(cancel)).await;
+
+    assert_eq!(response.stop_reason, AgentStopReason::Cancelled);
+    // The abandoned call still has a result, so the history stays valid.
+    assert_eq!(
+        history.last().unwrap().text(),
+        "turn cancelled before this tool finished"
+    );
+}
+
+#[tokio::test]
+async fn run_stream_turn_timeout_abandons_slow_tools() {
+    let calls = vec![make_tool_call("scrape", "{}")];
+    let model = TestProvider::with_chunks(vec![tool_chunks(calls), text_chunks("ack")]);
+
+    let mut config = AgentConfig::new("test-agent");
+    config.turn_timeout = Some(1);
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(config)
+        .dispatcher(dispatcher(|_| Box::pin(std::future::pending())))
+        .build();
+
+    let started = std::time::Instant::now();
+    let mut history = vec![HistoryEntry::user("go")];
+    let mut done = None;
+    {
+        let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
+        while let Some(event) = stream.next().await {
+            if let AgentEvent::Done(resp) = event {
+                done = Some(resp);
+            }
+        }
+    }
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+    let done = done.expect("stream yields Done");
+    assert_eq!(done.stop_reason, AgentStopReason::TurnTimeout);
+    // The abandoned call still gets a result so the history stays valid.
+    let last = history.last().unwrap();
+    assert_eq!(*last.role(), Role::Tool);
+    assert!(last.text().contains("timed out"));
+}
+
 // --- run_stream() tests ---
 
 #[tokio::test]
@@ -189,7 +325,7 @@ async fn run_stream_text_response() {
     let mut history = vec![HistoryEntry::user("hi")];
 
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -228,7 +364,7 @@ async fn run_stream_tool_call_then_text() {
 
     let mut history = vec![HistoryEntry::user("question")];
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -309,7 +445,7 @@ async fn run_stream_multiple_tool_calls_in_one_step() {
 
     let mut history = vec![HistoryEntry::user("multi")];
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -390,7 +526,7 @@ async fn run_stream_dispatches_tool_calls_concurrently() {
     let mut history = vec![HistoryEntry::user("multi")];
     let start = std::time::Instant::now();
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -447,6 +583,194 @@ async fn run_stream_dispatches_tool_calls_concurrently() {
     );
 }
 
+#[tokio::test]
+async fn run_stream_caps_parallel_tool_calls() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let calls: Vec<ToolCall> = (0..5)
+        .map(|i| ToolCall {
+            index: Some(i),
+            id: format!("call_{i}"),
+            function: FunctionCall {
+                name: format!("t{i}"),
+                arguments: "{}".into(),
+            },
+            ..Default::default()
+        })
+        .collect();
+    let model = TestProvider::with_chunks(vec![tool_chunks(calls), text_chunks("done")]);
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let (r, p) = (running.clone(), peak.clone());
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(AgentConfig::new("test-agent"))
+        .max_parallel_tools(2)
+        .dispatcher(dispatcher(move |name| {
+            let (running, peak) = (r.clone(), p.clone());
+            Box::pin(async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(format!("result:{name}"))
+            })
+        }))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("many")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    let results: Vec<_> = response.steps[0]
+        .tool_results
+        .iter()
+        .map(|r| r.text().to_owned())
+        .collect();
+    assert_eq!(
+        results,
+        [
+            "result:t0",
+            "result:t1",
+            "result:t2",
+            "result:t3",
+            "result:t4"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn tool_timeout_drops_hung_call() {
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        time::Duration,
+    };
+
+    struct SetOnDrop(Arc<AtomicBool>);
+    impl Drop for SetOnDrop {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let calls = vec![
+        make_tool_call("hang", "{}"),
+        ToolCall {
+            index: Some(1),
+            ..make_tool_call("bash", "{}")
+        },
+    ];
+    let model = TestProvider::with_chunks(vec![tool_chunks(calls), text_chunks("done")]);
+
+    let dropped = Arc::new(AtomicBool::new(false));
+    let d = dropped.clone();
+    let timeouts = ToolTimeouts {
+        default: Some(Duration::from_millis(50)),
+        per_tool: [("bash".to_owned(), Duration::ZERO)].into(),
+    };
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(AgentConfig::new("test-agent"))
+        .tool_timeouts(Arc::new(timeouts))
+        .dispatcher(dispatcher(move |name| {
+            if name != "hang" {
+                return Box::pin(async move { Ok(format!("result:{name}")) });
+            }
+            let guard = SetOnDrop(d.clone());
+            Box::pin(async move {
+                let _guard = guard;
+                std::future::pending::<Result<String, String>>().await
+            })
+        }))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("go")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    assert_eq!(response.final_response.as_deref(), Some("done"));
+    let results: Vec<_> = response.steps[0]
+        .tool_results
+        .iter()
+        .map(|r| r.text().to_owned())
+        .collect();
+    assert_eq!(
+        results,
+        ["tool 'hang' timed out after 0.05s", "result:bash"]
+    );
+    assert!(dropped.load(Ordering::SeqCst), "hung handler was leaked");
+}
+
+#[tokio::test]
+async fn tool_timeout_starts_after_approval() {
+    use std::time::Duration;
+
+    /// Approves "slow" after 100ms and refuses "nope"; runs at once.
+    struct Gate;
+    impl ToolDispatcher for Gate {
+        fn dispatch<'a>(
+            &'a self,
+            name: &'a str,
+            _call_id: &'a str,
+            _args: &'a str,
+            _agent: &'a str,
+            _sender: &'a str,
+            _conversation_id: Option<u64>,
+            _cancel: Option<CancellationToken>,
+        ) -> ToolFuture<'a> {
+            Box::pin(async move { Ok(format!("result:{name}")) })
+        }
+
+        fn approve<'a>(
+            &'a self,
+            name: &'a str,
+            _call_id: &'a str,
+            _args: &'a str,
+            _agent: &'a str,
+            _sender: &'a str,
+            _conversation_id: Option<u64>,
+            _cancel: Option<CancellationToken>,
+        ) -> ApprovalFuture<'a> {
+            Box::pin(async move {
+                if name == "nope" {
+                    return Err("denied".to_owned());
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(())
+            })
+        }
+    }
+
+    let calls = vec![
+        make_tool_call("slow", "{}"),
+        ToolCall {
+            index: Some(1),
+            ..make_tool_call("nope", "{}")
+        },
+    ];
+    let model = TestProvider::with_chunks(vec![tool_chunks(calls), text_chunks("done")]);
+    let timeouts = ToolTimeouts {
+        default: Some(Duration::from_millis(50)),
+        per_tool: Default::default(),
+    };
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(AgentConfig::new("test-agent"))
+        .tool_timeouts(Arc::new(timeouts))
+        .dispatcher(Arc::new(Gate))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("go")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    let results: Vec<_> = response.steps[0]
+        .tool_results
+        .iter()
+        .map(|r| r.text().to_owned())
+        .collect();
+    assert_eq!(results, ["result:slow", "denied"]);
+}
+
 #[tokio::test]
 async fn run_stream_max_iterations() {
     let calls = vec![make_tool_call("bash", "{}")];
@@ -466,7 +790,7 @@ async fn run_stream_max_iterations() {
 
     let mut history = vec![HistoryEntry::user("loop")];
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -479,6 +803,136 @@ async fn run_stream_max_iterations() {
     }
 }
 
+#[tokio::test]
+async fn run_stream_max_tool_calls_ends_turn() {
+    let calls = vec![make_tool_call("bash", "{}")];
+    let model = TestProvider::with_chunks(vec![
+        tool_chunks(calls.clone()),
+        tool_chunks(calls.clone()),
+        tool_chunks(vec![make_tool_call("grep", "{}")]),
+    ]);
+
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(AgentConfig::new("test-agent"))
+        .max_tool_calls(2)
+        .dispatcher(dispatcher(|_name| Box::pin(async { Ok("ok".to_owned()) })))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("loop")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    assert_eq!(
+        response.stop_reason,
+        AgentStopReason::Error(
+            "tool call limit of 2 reached after 2 calls (last requested: 'grep')".into()
+        )
+    );
+    assert_eq!(response.iterations, 2);
+    // The refused round leaves no unanswered call behind.
+    assert_eq!(*history.last().unwrap().role(), Role::Tool);
+}
+
+#[tokio::test]
+async fn response_format_retries_invalid_json_once() {
+    let model = TestProvider::with_chunks(vec![
+        text_chunks("sure, here you go"),
+        text_chunks(r#"{"title":"x"}"#),
+    ]);
+    let mut config = AgentConfig::new("test-agent");
+    config.response_format = Some(ResponseFormat {
+        schema: serde_json::json!({"type": "object"}),
+        mode: ResponseMode::Native,
+    });
+    let agent = AgentBuilder::new(Model::new(model.clone()))
+        .config(config)
+        .build();
+
+    let mut history = vec![HistoryEntry::user("extract")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    assert_eq!(response.stop_reason, AgentStopReason::TextResponse);
+    assert_eq!(response.final_response.as_deref(), Some(r#"{"title":"x"}"#));
+    let requests = model.requests();
+    assert_eq!(
+        requests[0].extra["response_format"]["type"],
+        serde_json::json!("json_schema")
+    );
+    let nudge = requests[1].messages.last().unwrap();
+    assert!(
+        nudge
+            .content
+            .as_ref()
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .contains("invalid JSON"),
+        "retry should tell the model why: {nudge:?}"
+    );
+}
+
+#[tokio::test]
+async fn response_format_tool_mode_unwraps_forced_call() {
+    let model = TestProvider::with_chunks(vec![tool_chunks(vec![make_tool_call(
+        "respond",
+        r#"{"title":"x"}"#,
+    )])]);
+    let mut config = AgentConfig::new("test-agent");
+    config.response_format = Some(ResponseFormat {
+        schema: serde_json::json!({"type": "object"}),
+        mode: ResponseMode::Tool,
+    });
+    let agent = AgentBuilder::new(Model::new(model.clone()))
+        .config(config)
+        .dispatcher(dispatcher(|name| panic!("{name} should not be dispatched")))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("extract")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    assert_eq!(response.stop_reason, AgentStopReason::TextResponse);
+    assert_eq!(response.final_response.as_deref(), Some(r#"{"title":"x"}"#));
+    let request = &model.requests()[0];
+    assert_eq!(
+        request.tool_choice,
+        Some(crabllm_core::ToolChoice::Function {
+            name: "respond".into()
+        })
+    );
+    assert!(history.last().unwrap().tool_calls().is_empty());
+}
+
+#[tokio::test]
+async fn run_sums_usage_across_rounds_and_compaction() {
+    let mut tool_round = tool_chunks(vec![make_tool_call("bash", "{}")]);
+    tool_round.push(usage_chunk(100, 10));
+    let mut text_round = text_chunks("done");
+    text_round.push(usage_chunk(50, 5));
+    let summary = crabllm_core::ChatCompletionResponse {
+        usage: usage_chunk(7, 3).usage,
+        ..text_response("summary")
+    };
+    let model = TestProvider::with_both(vec![summary], vec![tool_round, text_round]);
+
+    let mut config = AgentConfig::new("test-agent");
+    config.compact_threshold = Some(20);
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(config)
+        .dispatcher(dispatcher(|_name| Box::pin(async { Ok("x".repeat(200)) })))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("go")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    assert_eq!(response.final_response.as_deref(), Some("done"));
+    assert_eq!(response.usage.prompt_tokens, 157);
+    assert_eq!(response.usage.completion_tokens, 18);
+    assert_eq!(response.usage.total_tokens, 175);
+}
+
 #[tokio::test]
 async fn run_stream_no_content_no_tools_stops_with_no_action() {
     let model = TestProvider::with_chunks(vec![vec![finish_chunk(FinishReason::Stop)]]);
@@ -486,7 +940,7 @@ async fn run_stream_no_content_no_tools_stops_with_no_action() {
     let mut history = vec![HistoryEntry::user("hi")];
 
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -505,7 +959,7 @@ async fn run_stream_error_in_stream() {
     let mut history = vec![HistoryEntry::user("hi")];
 
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -529,7 +983,7 @@ async fn run_stream_thinking_delta() {
     let mut history = vec![HistoryEntry::user("think")];
 
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -573,7 +1027,7 @@ async fn run_stream_text_segment_is_bracketed() {
     let mut history = vec![HistoryEntry::user("ping")];
 
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -605,7 +1059,7 @@ async fn run_stream_thinking_then_text_brackets_atomically() {
     let mut history = vec![HistoryEntry::user("think")];
 
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -637,7 +1091,7 @@ async fn run_stream_chunk_with_both_text_and_reasoning_does_not_overlap() {
     let mut history = vec![HistoryEntry::user("hi")];
 
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -685,7 +1139,7 @@ async fn run_stream_text_then_tools_closes_text_before_tools() {
 
     let mut history = vec![HistoryEntry::user("q")];
     let mut events: Vec<AgentEvent> = Vec::new();
-    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None));
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -725,7 +1179,7 @@ async fn run_forwards_events_through_channel() {
     let mut history = vec![HistoryEntry::user("hi")];
     let (tx, mut rx) = mpsc::unbounded_channel();
 
-    let response = agent.run(&mut history, tx, None, None).await;
+    let response = agent.run(&mut history, tx, None, None, None).await;
     assert_eq!(response.stop_reason, AgentStopReason::TextResponse);
     assert_eq!(response.final_response.as_deref(), Some("done"));
 
@@ -741,3 +1195,279 @@ async fn run_forwards_events_through_channel() {
     assert!(event_count > 0, "events should have been sent");
     assert!(has_done, "Done event should have been sent");
 }
+
+// --- compaction timeout ---
+
+/// Provider whose calls never resolve — stands in for a hung upstream.
+struct StalledProvider;
+
+impl crabllm_core::Provider for StalledProvider {
+    async fn chat_completion(
+        &self,
+        _request: &crabllm_core::ChatCompletionRequest,
+    ) -> Result<crabllm_core::ChatCompletionResponse, crabllm_core::Error> {
+        std::future::pending().await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        _request: &crabllm_core::ChatCompletionRequest,
+    ) -> Result<
+        crabllm_core::BoxStream<
+            'static,
+            Result<crabllm_core::ChatCompletionChunk, crabllm_core::Error>,
+        >,
+        crabllm_core::Error,
+    > {
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn compact_gives_up_after_timeout() {
+    let mut config = AgentConfig::new("test-agent");
+    config.compact_timeout = Some(1);
+    let agent = AgentBuilder::new(Model::new(StalledProvider))
+        .config(config)
+        .build();
+
+    let started = std::time::Instant::now();
+    let summary = agent.compact(&[HistoryEntry::user("hi")]).await;
+    assert!(summary.is_none());
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn compact_timeout_zero_waits() {
+    let mut config = AgentConfig::new("test-agent");
+    config.compact_timeout = Some(0);
+    let agent = AgentBuilder::new(Model::new(StalledProvider))
+        .config(config)
+        .build();
+
+    let history = [HistoryEntry::user("hi")];
+    let waited = tokio::time::timeout(
+        std::time::Duration::from_millis(200),
+        agent.compact(&history),
+    )
+    .await;
+    assert!(waited.is_err(), "a 0 timeout gave up on the summary");
+}
+
+/// Streams scripted replies, but the summary call never resolves.
+struct StalledSummary(TestProvider);
+
+impl crabllm_core::Provider for StalledSummary {
+    async fn chat_completion(
+        &self,
+        _request: &crabllm_core::ChatCompletionRequest,
+    ) -> Result<crabllm_core::ChatCompletionResponse, crabllm_core::Error> {
+        std::future::pending().await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        request: &crabllm_core::ChatCompletionRequest,
+    ) -> Result<
+        crabllm_core::BoxStream<
+            'static,
+            Result<crabllm_core::ChatCompletionChunk, crabllm_core::Error>,
+        >,
+        crabllm_core::Error,
+    > {
+        self.0.chat_completion_stream(request).await
+    }
+}
+
+/// Run a turn whose reply crosses the compaction threshold, returning
+/// its stop reason.
+async fn stop_reason_of_compacting_turn(
+    config: AgentConfig,
+    cancel: Option<CancellationToken>,
+) -> Option<AgentStopReason> {
+    let provider = StalledSummary(TestProvider::with_chunks(vec![text_chunks("done")]));
+    let agent = AgentBuilder::new(Model::new(provider))
+        .config(config)
+        .build();
+    let mut history = vec![HistoryEntry::user("hi")];
+    let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, cancel));
+    while let Some(event) = stream.next().await {
+        if let AgentEvent::Done(resp) = event {
+            return Some(resp.stop_reason);
+        }
+    }
+    None
+}
+
+#[tokio::test]
+async fn mid_turn_compaction_counts_against_the_turn_timeout() {
+    let mut config = AgentConfig::new("test-agent");
+    config.compact_threshold = Some(1);
+    config.compact_timeout = None;
+    config.turn_timeout = Some(1);
+
+    let started = std::time::Instant::now();
+    let stop = stop_reason_of_compacting_turn(config, None).await;
+    assert_eq!(stop, Some(AgentStopReason::TurnTimeout));
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn mid_turn_compaction_stops_on_cancel() {
+    let mut config = AgentConfig::new("test-agent");
+    config.compact_threshold = Some(1);
+    config.compact_timeout = None;
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        cancel.cancel();
+    });
+
+    let stop = stop_reason_of_compacting_turn(config, Some(token)).await;
+    assert_eq!(stop, Some(AgentStopReason::Cancelled));
+}
+
+#[tokio::test]
+async fn run_stream_turn_timeout_bounds_stalled_model() {
+    let mut config = AgentConfig::new("test-agent");
+    config.turn_timeout = Some(1);
+    let agent = AgentBuilder::new(Model::new(StalledProvider))
+        .config(config)
+        .build();
+
+    let mut history = vec![HistoryEntry::user("hi")];
+    let mut stop_reason = None;
+    {
+        let mut stream = std::pin::pin!(agent.run_stream(&mut history, None, None, None, None));
+        while let Some(event) = stream.next().await {
+            if let AgentEvent::Done(resp) = event {
+                stop_reason = Some(resp.stop_reason);
+            }
+        }
+    }
+    assert_eq!(stop_reason, Some(AgentStopReason::TurnTimeout));
+    assert_eq!(history.len(), 1);
+}
+
+#[tokio::test]
+async fn run_stream_cancel_drops_stalled_model() {
+    let agent = AgentBuilder::new(Model::new(StalledProvider))
+        .config(AgentConfig::new("test-agent"))
+        .build();
+    let cancel = CancellationToken::new();
+    let trigger = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        trigger.cancel();
+    });
+
+    let mut history = vec![HistoryEntry::user("hi")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, Some(cancel)).await;
+    assert_eq!(response.stop_reason, AgentStopReason::Cancelled);
+    assert_eq!(history.len(), 1);
+}
+
+#[tokio::test]
+async fn run_stream_refuses_tools_the_agent_was_not_given() {
+    let calls = vec![make_tool_call("bash", "{}")];
+    let model = TestProvider::with_chunks(vec![tool_chunks(calls), text_chunks("ok")]);
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(AgentConfig::new("test-agent"))
+        .tools(vec![crabtalk_core::testing::test_schema("read")])
+        .dispatcher(dispatcher(|name| {
+            Box::pin(async move { Ok(format!("ran {name}")) })
+        }))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("go")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    assert_eq!(response.stop_reason, AgentStopReason::TextResponse);
+    assert_eq!(history[2].text(), "tool not available: bash");
+}
+
+#[tokio::test]
+async fn strict_agent_aborts_on_unavailable_tool() {
+    let calls = vec![make_tool_call("bash", "{}")];
+    let model = TestProvider::with_chunks(vec![tool_chunks(calls)]);
+    let mut config = AgentConfig::new("test-agent");
+    config.strict_tools = true;
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(config)
+        .tools(vec![crabtalk_core::testing::test_schema("read")])
+        .dispatcher(dispatcher(|_| Box::pin(async { Ok("ran".to_owned()) })))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("go")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    assert_eq!(
+        response.stop_reason,
+        AgentStopReason::Error("model called unavailable tool 'bash'".into())
+    );
+    assert_eq!(history.len(), 1, "no dangling tool call left in history");
+}
+
+#[tokio::test]
+async fn malformed_tool_args_get_one_retry_by_default() {
+    let bad = vec![make_tool_call("read", "{\"path\": ")];
+    let good = vec![make_tool_call("read", "{\"path\": \"a.rs\"}")];
+    let model = TestProvider::with_chunks(vec![
+        tool_chunks(bad),
+        tool_chunks(good),
+        text_chunks("done"),
+    ]);
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(AgentConfig::new("test-agent"))
+        .tools(vec![crabtalk_core::testing::test_schema("read")])
+        .dispatcher(dispatcher(|name| {
+            Box::pin(async move { Ok(format!("ran {name}")) })
+        }))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("go")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    assert_eq!(response.stop_reason, AgentStopReason::TextResponse);
+    assert!(
+        history[2]
+            .text()
+            .starts_with("invalid JSON arguments for read: EOF"),
+        "got: {}",
+        history[2].text()
+    );
+    assert_eq!(history[4].text(), "ran read");
+}
+
+#[tokio::test]
+async fn malformed_tool_args_past_the_retry_limit_end_the_turn() {
+    let bad = || vec![make_tool_call("read", "not json")];
+    let model = TestProvider::with_chunks(vec![
+        tool_chunks(bad()),
+        tool_chunks(bad()),
+        text_chunks("unreachable"),
+    ]);
+    let agent = AgentBuilder::new(Model::new(model))
+        .config(AgentConfig::new("test-agent"))
+        .tools(vec![crabtalk_core::testing::test_schema("read")])
+        .dispatcher(dispatcher(|_| {
+            Box::pin(async { panic!("malformed call must not be dispatched") })
+        }))
+        .build();
+
+    let mut history = vec![HistoryEntry::user("go")];
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let response = agent.run(&mut history, tx, None, None, None).await;
+
+    assert_eq!(
+        response.stop_reason,
+        AgentStopReason::Error("tool call arguments still not valid JSON after 1 retry".into())
+    );
+    assert_eq!(response.iterations, 2);
+    assert_eq!(history.len(), 5, "every call still has a result");
+}
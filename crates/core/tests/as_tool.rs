@@ -0,0 +1,43 @@
+//! Tests for AsTool — optional and defaulted parameters in derived schemas.
+
+use crabtalk_core::agent::AsTool;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Search the index.
+#[derive(Deserialize, JsonSchema)]
+#[allow(dead_code)]
+struct Search {
+    /// Query text.
+    query: String,
+    /// Restrict to one kind.
+    kind: Option<String>,
+    /// Maximum hits.
+    #[serde(default = "default_limit")]
+    limit: u32,
+}
+
+fn default_limit() -> u32 {
+    5
+}
+
+#[test]
+fn optional_and_defaulted_params_are_not_required() {
+    let tool = Search::as_tool();
+    assert_eq!(tool.function.name, "search");
+    assert_eq!(
+        tool.function.description.as_deref(),
+        Some("Search the index.")
+    );
+
+    let params = tool.function.parameters.unwrap();
+    assert_eq!(params["required"], serde_json::json!(["query"]));
+    assert_eq!(params["properties"]["limit"]["default"], 5);
+}
+
+#[test]
+fn defaults_apply_when_the_model_omits_a_param() {
+    let args: Search = serde_json::from_str(r#"{"query":"rust"}"#).unwrap();
+    assert_eq!(args.limit, 5);
+    assert!(args.kind.is_none());
+}
@@ -0,0 +1,92 @@
+//! Tests for the scripted in-process `TestClient`.
+
+use crabtalk_core::{
+    protocol::{
+        api::Client,
+        message::{
+            CancelMsg, ErrorMsg, Pong, SendMsg, ServerMessage, StreamMsg, client_message,
+            server_message, stream_event,
+        },
+    },
+    testing::{
+        TestClient,
+        client::{response, text_stream},
+    },
+};
+use futures_util::StreamExt;
+
+#[tokio::test]
+async fn records_sends_and_replies_in_order() {
+    let client = TestClient::new();
+    client.push_reply(response("crab", "hi there"));
+    let mut channel = client.clone();
+
+    let reply = channel
+        .send(SendMsg {
+            agent: "crab".into(),
+            content: "hello".into(),
+            sender: Some("tg:42".into()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(reply.content, "hi there");
+
+    let sent = client.sent();
+    assert_eq!(sent.len(), 1);
+    let Some(client_message::Msg::Send(msg)) = &sent[0].msg else {
+        panic!("expected a send, got {:?}", sent[0]);
+    };
+    assert_eq!(msg.sender.as_deref(), Some("tg:42"));
+
+    assert!(channel.send(SendMsg::default()).await.is_err());
+}
+
+#[tokio::test]
+async fn streams_scripted_batches_until_end() {
+    let mut client = TestClient::new();
+    client.push_stream(text_stream("crab", &["one ", "two"]));
+
+    let events: Vec<_> = client
+        .stream(StreamMsg {
+            agent: "crab".into(),
+            content: "count".into(),
+            ..Default::default()
+        })
+        .collect()
+        .await;
+    let text: String = events
+        .into_iter()
+        .map(|e| match e.unwrap() {
+            stream_event::Event::Chunk(c) => c.content,
+            other => panic!("unexpected event {other:?}"),
+        })
+        .collect();
+    assert_eq!(text, "one two");
+}
+
+#[tokio::test]
+async fn cancel_sends_cancel_msg() {
+    let client = TestClient::new();
+    client.push_reply(ServerMessage {
+        msg: Some(server_message::Msg::Pong(Pong {})),
+    });
+    client.push_reply(ServerMessage {
+        msg: Some(server_message::Msg::Error(ErrorMsg {
+            code: 404,
+            message: "no active stream".into(),
+        })),
+    });
+    let mut channel = client.clone();
+    let req = CancelMsg {
+        agent: "crab".into(),
+        sender: "user".into(),
+    };
+
+    channel.cancel(req.clone()).await.unwrap();
+    assert!(channel.cancel(req).await.is_err());
+    let Some(client_message::Msg::Cancel(msg)) = &client.sent()[0].msg else {
+        panic!("expected a cancel, got {:?}", client.sent()[0]);
+    };
+    assert_eq!((msg.agent.as_str(), msg.sender.as_str()), ("crab", "user"));
+}
@@ -0,0 +1,89 @@
+//! Tests for frame compression in the wire codec.
+
+use crabtalk_core::protocol::{
+    codec::{COMPRESS_THRESHOLD, Compression, read_message, write_message_with},
+    message::{MemoryEntryInfo, MemoryList, Ping, ServerMessage, server_message},
+};
+use prost::Message;
+
+fn memory_list(n: usize) -> ServerMessage {
+    let entries = (0..n)
+        .map(|i| MemoryEntryInfo {
+            name: format!("project-note-{i}"),
+            content: format!(
+                "The user prefers concise answers and works on the crabtalk daemon; note {i}."
+            ),
+            aliases: vec![format!("note-{i}")],
+            kind: "note".to_owned(),
+            created_at: 1_700_000_000 + i as u64,
+            access_count: 0,
+            expires_at: None,
+            pinned: false,
+        })
+        .collect();
+    ServerMessage {
+        msg: Some(server_message::Msg::MemoryList(MemoryList {
+            entries,
+            next_cursor: None,
+            total: n as u64,
+        })),
+    }
+}
+
+/// Write `msg` to a pipe and return the raw frame plus the decoded message.
+async fn roundtrip<T: Message + Default>(msg: &T, c: Option<Compression>) -> (Vec<u8>, T) {
+    let mut frame = Vec::new();
+    write_message_with(&mut frame, msg, c).await.unwrap();
+    let decoded = read_message(&mut frame.as_slice()).await.unwrap();
+    (frame, decoded)
+}
+
+#[tokio::test]
+async fn large_memory_list_compresses_well() {
+    let msg = memory_list(1000);
+    let plain = msg.encoded_len();
+    for c in Compression::ALL {
+        let (frame, decoded) = roundtrip(&msg, Some(c)).await;
+        assert_eq!(decoded, msg);
+        assert_ne!(frame[0] >> 4, 0, "{} frame not flagged", c.name());
+        assert!(
+            frame.len() * 5 < plain,
+            "{}: {} of {plain} bytes",
+            c.name(),
+            frame.len()
+        );
+    }
+}
+
+#[tokio::test]
+async fn small_frames_stay_plain() {
+    let msg = memory_list(1);
+    assert!(msg.encoded_len() <= COMPRESS_THRESHOLD);
+    let (frame, decoded) = roundtrip(&msg, Some(Compression::Zstd)).await;
+    assert_eq!(decoded, msg);
+    assert_eq!(frame[0] >> 4, 0);
+    assert_eq!(frame.len(), 4 + msg.encoded_len());
+}
+
+#[tokio::test]
+async fn no_compression_without_negotiation() {
+    let msg = memory_list(1000);
+    let (frame, _) = roundtrip(&msg, None).await;
+    assert_eq!(frame.len(), 4 + msg.encoded_len());
+}
+
+#[tokio::test]
+async fn unknown_flag_is_rejected() {
+    let mut frame = vec![0xF0, 0, 0, 0];
+    frame.extend(Ping {}.encode_to_vec());
+    let result: Result<Ping, _> = read_message(&mut frame.as_slice()).await;
+    assert!(result.unwrap_err().to_string().contains("compression flag"));
+}
+
+#[test]
+fn names_roundtrip() {
+    for c in Compression::ALL {
+        assert_eq!(Compression::from_name(c.name()), Some(c));
+    }
+    assert_eq!(Compression::from_name("brotli"), None);
+}
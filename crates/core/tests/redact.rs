@@ -0,0 +1,85 @@
+//! Tests for history redaction.
+
+use crabtalk_core::{
+    RedactionConfig,
+    model::{FunctionCall, HistoryEntry, ToolCall, ToolType},
+};
+
+#[test]
+fn default_patterns_scrub_secrets() {
+    let config = RedactionConfig::default();
+    let text = "key sk-abcdefghijklmnopqrstuvwx, mail me at luna@example.com, \
+                card 4111 1111 1111 1111, header Authorization: Bearer abc.def-ghi";
+    let out = config.redact(text);
+    assert!(!out.contains("sk-abcdef"));
+    assert!(!out.contains("luna@example.com"));
+    assert!(!out.contains("4111"));
+    assert!(!out.contains("abc.def-ghi"));
+    assert_eq!(out.matches("[REDACTED]").count(), 4);
+}
+
+#[test]
+fn ordinary_text_is_left_alone() {
+    let config = RedactionConfig::default();
+    let text = "use sk-learn-style-estimators, the bearer of bad news, \
+                icon@2x.png, sent at 1700000000000, order 1234567890123456";
+    assert_eq!(config.redact(text), text);
+}
+
+#[test]
+fn clean_text_is_borrowed() {
+    let config = RedactionConfig::default();
+    let out = config.redact("nothing to see here");
+    assert!(matches!(out, std::borrow::Cow::Borrowed(_)));
+}
+
+#[test]
+fn redacted_leaves_original_untouched() {
+    let config = RedactionConfig::default();
+    let call = ToolCall {
+        id: "call_1".into(),
+        kind: ToolType::Function,
+        function: FunctionCall {
+            name: "bash".into(),
+            arguments: r#"{"command":"curl -H 'Authorization: Bearer s3cr3t'"}"#.into(),
+        },
+        index: None,
+    };
+    let entry = HistoryEntry::assistant("email luna@example.com", None, Some(&[call]));
+
+    let scrubbed = entry.redacted(&config);
+    assert_eq!(scrubbed.text(), "email [REDACTED]");
+    assert!(
+        !scrubbed.tool_calls()[0]
+            .function
+            .arguments
+            .contains("s3cr3t")
+    );
+    assert_eq!(entry.text(), "email luna@example.com");
+    assert!(entry.tool_calls()[0].function.arguments.contains("s3cr3t"));
+}
+
+#[test]
+fn redacted_scrubs_every_text_part() {
+    let config = RedactionConfig::default();
+    let images = vec!["data:image/png;base64,AAAA".to_owned()];
+    let entry = HistoryEntry::user_with_images("mail luna@example.com", &images);
+
+    let scrubbed = entry.redacted(&config);
+    assert_eq!(scrubbed.text(), "mail [REDACTED]");
+    assert_eq!(scrubbed.images().collect::<Vec<_>>(), [images[0].as_str()]);
+    assert_eq!(entry.text(), "mail luna@example.com");
+}
+
+#[test]
+fn custom_patterns() {
+    let config = RedactionConfig::new(&["internal-[0-9]+"])
+        .unwrap()
+        .with_pattern("codename")
+        .unwrap();
+    assert_eq!(
+        config.redact("internal-42 codename luna@example.com"),
+        "[REDACTED] [REDACTED] luna@example.com"
+    );
+    assert!(RedactionConfig::new(&["("]).is_err());
+}
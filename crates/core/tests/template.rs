@@ -0,0 +1,41 @@
+//! Tests for system prompt variable substitution.
+
+use crabtalk_core::agent::template::render_prompt;
+use std::collections::BTreeMap;
+
+fn vars() -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("user_name".to_owned(), "Ada".to_owned()),
+        ("date".to_owned(), "2026-10-16".to_owned()),
+    ])
+}
+
+#[test]
+fn substitutes_known_variables() {
+    let out = render_prompt("Hi {{user_name}}, today is {{ date }}.", &vars(), true).unwrap();
+    assert_eq!(out, "Hi Ada, today is 2026-10-16.");
+}
+
+#[test]
+fn unknown_variable_renders_empty_unless_strict() {
+    assert_eq!(
+        render_prompt("[{{missing}}]", &vars(), false).unwrap(),
+        "[]"
+    );
+    assert_eq!(
+        render_prompt("[{{missing}}]", &vars(), true).unwrap_err(),
+        "missing"
+    );
+}
+
+#[test]
+fn backslash_keeps_braces_literal() {
+    let out = render_prompt(r"Write \{{user_name}} for {{user_name}}", &vars(), true).unwrap();
+    assert_eq!(out, "Write {{user_name}} for Ada");
+}
+
+#[test]
+fn unterminated_placeholder_is_left_as_written() {
+    let out = render_prompt("{{user_name}} and {{open", &vars(), true).unwrap();
+    assert_eq!(out, "Ada and {{open");
+}
@@ -0,0 +1,62 @@
+//! Tests for the structured tool output envelope.
+
+use anyhow::Context;
+use crabtalk_core::{json_output, json_tool, try_json_tool};
+use serde_json::json;
+
+#[test]
+fn success_is_wrapped_compactly() {
+    let out = json_output(Ok(json!({ "files": ["a.rs", "b.rs"], "count": 2 })));
+    assert_eq!(
+        out,
+        Ok(r#"{"ok":true,"result":{"count":2,"files":["a.rs","b.rs"]}}"#.to_owned())
+    );
+}
+
+#[test]
+fn failure_stays_err_with_message() {
+    let out = json_output(Err("no such file: \"x\"".to_owned()));
+    let err = out.unwrap_err();
+    let value: serde_json::Value = serde_json::from_str(&err).unwrap();
+    assert_eq!(
+        value,
+        json!({ "ok": false, "error": "no such file: \"x\"" })
+    );
+}
+
+#[tokio::test]
+async fn json_tool_serializes_handler_value() {
+    let out = json_tool(async { Ok(json!([1, 2, 3])) }).await;
+    assert_eq!(out, Ok(r#"{"ok":true,"result":[1,2,3]}"#.to_owned()));
+}
+
+#[derive(serde::Serialize)]
+struct Count {
+    files: Vec<&'static str>,
+    count: usize,
+}
+
+#[tokio::test]
+async fn try_json_tool_matches_json_tool_envelope() {
+    let typed = try_json_tool(async {
+        anyhow::Ok(Count {
+            files: vec!["a.rs", "b.rs"],
+            count: 2,
+        })
+    })
+    .await;
+    let untyped = json_tool(async { Ok(json!({ "files": ["a.rs", "b.rs"], "count": 2 })) }).await;
+    assert_eq!(typed, untyped);
+}
+
+#[tokio::test]
+async fn try_json_tool_keeps_the_error_chain() {
+    let out = try_json_tool(async {
+        let n: u32 = "x".parse().context("bad count")?;
+        anyhow::Ok(n)
+    })
+    .await;
+    let value: serde_json::Value = serde_json::from_str(&out.unwrap_err()).unwrap();
+    assert_eq!(value["ok"], false);
+    assert_eq!(value["error"], "bad count: invalid digit found in string");
+}
@@ -0,0 +1,51 @@
+//! Tests for token-budget truncation of history entries.
+
+use crabtalk_core::model::{HistoryEntry, TRUNCATION_MARKER, estimate_text_tokens};
+
+#[test]
+fn under_budget_is_unchanged() {
+    let entry = HistoryEntry::user("short message");
+    let out = entry.truncate_to_tokens(100);
+    assert_eq!(out.text(), "short message");
+}
+
+#[test]
+fn over_budget_is_cut_with_marker() {
+    let entry = HistoryEntry::tool("x".repeat(4000), "call_1", "read");
+    let out = entry.truncate_to_tokens(50);
+
+    assert!(out.text().ends_with(TRUNCATION_MARKER));
+    assert!(estimate_text_tokens(out.text()) <= 50);
+    assert!(out.text().len() > 150, "cut should use most of the budget");
+    assert_eq!(out.tool_call_id(), "call_1");
+}
+
+#[test]
+fn multibyte_text_is_cut_on_char_boundary() {
+    // 3-byte characters: byte budgets rarely land on a boundary.
+    let entry = HistoryEntry::user("漢".repeat(1000));
+    for max in [1, 7, 10, 33, 100] {
+        let out = entry.truncate_to_tokens(max);
+        let kept = out.text().strip_suffix(TRUNCATION_MARKER).unwrap();
+        assert!(kept.chars().all(|c| c == '漢'));
+    }
+}
+
+#[test]
+fn custom_estimator_is_honored() {
+    let words = |s: &str| s.split_whitespace().count();
+    let entry = HistoryEntry::user("one two three four five six");
+    let out = entry.truncate_to_tokens_with(3, words);
+    // The marker glues onto the last kept word, so three words fit.
+    assert_eq!(out.text(), format!("one two three{TRUNCATION_MARKER}"));
+}
+
+#[test]
+fn truncation_keeps_images() {
+    let image = "data:image/png;base64,AAAA".to_owned();
+    let entry = HistoryEntry::user_with_images("y".repeat(4000), std::slice::from_ref(&image));
+    let out = entry.truncate_to_tokens(50);
+
+    assert!(out.text().ends_with(TRUNCATION_MARKER));
+    assert_eq!(out.images().collect::<Vec<_>>(), vec![image.as_str()]);
+}
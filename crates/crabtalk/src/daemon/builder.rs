@@ -7,7 +7,7 @@ use crate::{
     hooks::{Memory, delegate},
     storage::FsStorage,
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crabllm_core::Provider;
 use crabllm_provider::{ProviderRegistry, RemoteProvider};
 use mcp::McpHandler;
@@ -16,11 +16,17 @@ use std::{
     collections::BTreeMap,
     path::{Path, PathBuf},
     sync::{Arc, OnceLock},
+    time::Duration,
 };
 use tokio::sync::{RwLock, broadcast};
-use wcore::{LlmConfig, ResolvedDirs, model::Model, resolve_dirs, storage::Storage};
+use wcore::{
+    LlmConfig, ResolvedDirs, model::Model, protocol::message::ModelInfo, resolve_dirs,
+    storage::Storage,
+};
 
-pub type DefaultProvider = crate::provider::Retrying<ProviderRegistry<RemoteProvider>>;
+pub type DefaultProvider = crate::provider::Recording<
+    crate::provider::Retrying<crate::provider::KeyPool<ProviderRegistry<RemoteProvider>>>,
+>;
 
 /// Build the LLM `Model<P>` given the daemon config and the list of models
 /// advertised by the endpoint (fetched from `/v1/models` at startup).
@@ -35,7 +41,9 @@ pub fn build_default_provider(
 }
 
 impl<P: Provider + 'static> Daemon<P> {
-    pub(crate) async fn build(
+    /// Build a daemon over `config_dir` with the provider `build_provider`
+    /// returns. [`Daemon::start`] is this with the default provider.
+    pub async fn build(
         config: &DaemonConfig,
         config_dir: &Path,
         build_provider: BuildProvider<P>,
@@ -98,7 +106,10 @@ impl<P: Provider + 'static> Daemon<P> {
                         return;
                     }
                 };
-                if let Err(e) = rt.send_to(conversation_id, &payload, &sender, None).await {
+                if let Err(e) = rt
+                    .send_to(conversation_id, &payload, &[], &sender, None, None)
+                    .await
+                {
                     tracing::warn!("event fire: send_to(agent='{target_agent}'): {e}");
                 }
             });
@@ -125,11 +136,13 @@ impl<P: Provider + 'static> Daemon<P> {
             mcp,
             os_hook,
             ask_hook,
+            max_content_bytes: config.limits.max_content_bytes,
         })
     }
 
     pub async fn reload(&self) -> Result<()> {
-        let config = DaemonConfig::load(&self.config_dir.join(wcore::paths::CONFIG_FILE))?;
+        let mut config = DaemonConfig::load(&self.config_dir.join(wcore::paths::CONFIG_FILE))?;
+        config.resolve_env()?;
         let runtime_once: Arc<OnceLock<SharedRuntime<P>>> = Arc::new(OnceLock::new());
         runtime_once
             .set(self.runtime.clone())
@@ -137,7 +150,8 @@ impl<P: Provider + 'static> Daemon<P> {
 
         let cwd = self.runtime.read().await.env.cwd.clone();
 
-        let node_hook = DaemonHook::new(self.hook.scopes.clone());
+        let mut node_hook = DaemonHook::new(self.hook.scopes.clone());
+        node_hook.confirmations = self.hook.confirmations.clone();
 
         let (mut new_runtime, _mcp, new_hook, _, _) = Self::build_all(
             &config,
@@ -187,7 +201,8 @@ impl<P: Provider + 'static> Daemon<P> {
     )> {
         let dirs = resolve_dirs(config_dir);
         let storage = Self::build_storage(config_dir, &dirs);
-        let models = fetch_models(&config.llm).await;
+        let advertised = fetch_models(&config.llm).await;
+        let models: Vec<String> = advertised.iter().map(|m| m.name.clone()).collect();
         let default_model = models.first().cloned().unwrap_or_default();
         storage.scaffold(&default_model)?;
 
@@ -203,7 +218,23 @@ impl<P: Provider + 'static> Daemon<P> {
             cwd.clone(),
             conversation_cwds.clone(),
             pending_asks,
+            config.limits.delegate_depth,
         )?;
+        {
+            let mut store = shared_memory.write();
+            store.set_capacity(
+                (config.limits.memory_entries > 0).then_some(config.limits.memory_entries),
+            );
+            if let Some(weight) = config.hooks.memory.access_weight {
+                store.set_access_weight(weight);
+            }
+        }
+        node_hook.set_confirm_tools(
+            config.tools.confirm.clone(),
+            Duration::from_secs(config.tools.confirm_timeout),
+        );
+        node_hook.set_cache_policy(config.tools.cacheable.clone(), config.tools.cache_entries);
+        node_hook.set_prompt_vars(config.prompt_vars.clone());
         let node_hook = Arc::new(node_hook);
 
         let (events_tx, _) = broadcast::channel(256);
@@ -218,8 +249,11 @@ impl<P: Provider + 'static> Daemon<P> {
         for schema in Hook::schema(node_hook.as_ref()) {
             tools.insert(schema);
         }
-        let runtime = Runtime::new(model, env, storage, shared_memory, tools);
-        runtime.set_models(models);
+        let runtime = Runtime::new(model, env, storage, shared_memory, tools)
+            .with_max_parallel_tools(config.tools.max_parallel)
+            .with_tool_timeouts(config.tools.tool_timeouts())
+            .with_max_tool_calls(config.tools.max_calls);
+        runtime.set_models(advertised);
         let mut runtime = runtime;
         Self::register_agents(&mut runtime, &dirs)?;
         Ok((runtime, mcp_handler, node_hook, os_hook, ask_hook))
@@ -250,6 +284,7 @@ impl<P: Provider + 'static> Daemon<P> {
         cwd: PathBuf,
         conversation_cwds: crate::daemon::ConversationCwds,
         pending_asks: crate::daemon::PendingAsks,
+        delegate_depth: usize,
     ) -> Result<(
         Arc<crate::hooks::os::OsHook>,
         Arc<crate::hooks::ask_user::AskUserHook>,
@@ -294,11 +329,10 @@ impl<P: Provider + 'static> Daemon<P> {
         );
         node_hook.register_hook(
             "delegate",
-            Arc::new(delegate::DelegateHook::<P>::new(
-                runtime_once,
-                conversation_cwds,
-                read_files,
-            )),
+            Arc::new(
+                delegate::DelegateHook::<P>::new(runtime_once, conversation_cwds, read_files)
+                    .with_max_depth(delegate_depth),
+            ),
         );
         let ask_hook = Arc::new(crate::hooks::ask_user::AskUserHook::new(pending_asks));
         node_hook.register_hook("ask_user", ask_hook.clone());
@@ -371,70 +405,124 @@ impl<P: Provider + 'static> Daemon<P> {
     }
 }
 
-fn build_providers(config: &DaemonConfig, models: &[String]) -> Result<Model<DefaultProvider>> {
+/// Build the provider stack for `[llm]`. Every request goes to
+/// `llm.base_url`, so tests point it at a local server replaying recorded
+/// responses.
+pub fn build_providers(config: &DaemonConfig, models: &[String]) -> Result<Model<DefaultProvider>> {
     let llm = &config.llm;
-    let provider_cfg = crabllm_core::ProviderConfig {
-        kind: crabllm_core::ProviderKind::Openai,
-        base_url: (!llm.base_url.is_empty()).then(|| llm.base_url.clone()),
-        api_key: (!llm.api_key.is_empty()).then(|| llm.api_key.clone()),
-        models: models.to_vec(),
-        ..Default::default()
-    };
-
-    let mut providers = std::collections::HashMap::new();
-    providers.insert("llm".to_owned(), provider_cfg);
-
-    let registry = ProviderRegistry::from_provider_configs(
-        &providers,
-        &std::collections::HashMap::new(),
-        |r| r,
-    )?;
-    let retrying = crate::provider::Retrying::new(registry);
+    let key_count = llm.key_pool().len();
+    // No key still needs one pool slot: an unauthenticated endpoint.
+    let mut keys: Vec<Option<wcore::ApiKey>> = llm.key_pool().into_iter().map(Some).collect();
+    if keys.is_empty() {
+        keys.push(None);
+    }
+
+    let mut pool = Vec::with_capacity(keys.len());
+    for key in &keys {
+        let provider_cfg = crabllm_core::ProviderConfig {
+            kind: crabllm_core::ProviderKind::Openai,
+            base_url: (!llm.base_url.is_empty()).then(|| llm.base_url.clone()),
+            api_key: key.as_ref().map(|k| k.key.clone()),
+            models: models.to_vec(),
+            ..Default::default()
+        };
+        let mut providers = std::collections::HashMap::new();
+        providers.insert("llm".to_owned(), provider_cfg);
+        let registry = ProviderRegistry::from_provider_configs(
+            &providers,
+            &std::collections::HashMap::new(),
+            |r| r,
+        )?;
+        pool.push((registry, key.as_ref().map_or(1, |k| k.weight)));
+    }
+    let mut retrying = crate::provider::Retrying::new(crate::provider::KeyPool::new(pool))
+        .with_endpoint(&llm.base_url);
+    if let Some(max_retries) = llm.max_retries {
+        retrying = retrying.with_max_retries(max_retries);
+    }
+    if let Some(secs) = llm.timeout {
+        retrying = retrying.with_timeout(std::time::Duration::from_secs(secs));
+    }
+    if let Some(secs) = llm.idle_timeout {
+        retrying = retrying.with_idle_timeout(std::time::Duration::from_secs(secs));
+    }
+    let recording = crate::provider::Recording::new(retrying, llm.debug);
 
     tracing::info!(
-        "llm endpoint registered — {} models from {}",
+        "llm endpoint registered — {} models from {} ({} keys)",
         models.len(),
-        llm.base_url
+        llm.base_url,
+        key_count
     );
-    Ok(Model::new(retrying))
+    Ok(Model::new(recording))
 }
 
 /// Fetch `/v1/models` from the configured LLM endpoint. Returns an empty
 /// list on failure (logged as a warning) so the daemon still starts — the
 /// next reload will retry.
-async fn fetch_models(llm: &LlmConfig) -> Vec<String> {
+async fn fetch_models(llm: &LlmConfig) -> Vec<ModelInfo> {
     if llm.base_url.is_empty() {
         tracing::warn!("no llm.base_url configured in config.toml — model list is empty");
         return Vec::new();
     }
-    let url = format!("{}/models", llm.base_url.trim_end_matches('/'));
-    let mut req = reqwest::Client::new().get(&url);
-    if !llm.api_key.is_empty() {
-        req = req.bearer_auth(&llm.api_key);
-    }
-    match fetch_models_inner(req).await {
+    match probe_models(llm).await {
         Ok(models) => models,
         Err(e) => {
-            tracing::warn!("failed to fetch {url}: {e}");
+            tracing::warn!("{e:#}");
             Vec::new()
         }
     }
 }
 
-async fn fetch_models_inner(req: reqwest::RequestBuilder) -> Result<Vec<String>> {
+/// Fetch `/v1/models`, surfacing every failure. Backs both startup and
+/// the `--check` preflight.
+pub(crate) async fn probe_models(llm: &LlmConfig) -> Result<Vec<ModelInfo>> {
+    if llm.base_url.is_empty() {
+        anyhow::bail!("no llm.base_url configured");
+    }
+    let url = format!("{}/models", llm.base_url.trim_end_matches('/'));
+    let mut req = reqwest::Client::new().get(&url);
+    if let Some(key) = llm.key_pool().first() {
+        req = req.bearer_auth(&key.key);
+    }
+    fetch_models_inner(req)
+        .await
+        .with_context(|| format!("failed to fetch {url}"))
+}
+
+async fn fetch_models_inner(req: reqwest::RequestBuilder) -> Result<Vec<ModelInfo>> {
     let body: serde_json::Value = req.send().await?.error_for_status()?.json().await?;
     Ok(body
         .get("data")
         .and_then(|d| d.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.get("id").and_then(|i| i.as_str()).map(String::from))
-                .collect()
-        })
+        .map(|arr| arr.iter().filter_map(model_info).collect())
         .unwrap_or_default())
 }
 
-fn mcp_servers(
+/// One `/v1/models` entry. Gateways that know the window report it as
+/// `context_length` (OpenRouter) or `context_window`; otherwise it stays
+/// 0 and [`Runtime::list_models`] reports the built-in estimate. Vision
+/// comes from `architecture.input_modalities` (OpenRouter) or a boolean
+/// `vision`, and stays unset otherwise.
+fn model_info(entry: &serde_json::Value) -> Option<ModelInfo> {
+    let name = entry.get("id")?.as_str()?.to_owned();
+    let context_length = ["context_length", "context_window"]
+        .iter()
+        .find_map(|key| entry.get(key).and_then(|v| v.as_u64()))
+        .unwrap_or_default();
+    let vision = entry["architecture"]["input_modalities"]
+        .as_array()
+        .map(|modalities| modalities.iter().any(|m| m == "image"))
+        .or_else(|| entry.get("vision").and_then(|v| v.as_bool()));
+    Some(ModelInfo {
+        name,
+        active: false,
+        context_length,
+        vision,
+    })
+}
+
+pub(crate) fn mcp_servers(
     config: &DaemonConfig,
     storage: &dyn Storage,
     dirs: &ResolvedDirs,
@@ -443,13 +531,14 @@ fn mcp_servers(
     for (name, mcp) in storage.list_mcps()? {
         merged.insert(name, mcp);
     }
-    Ok(merged
+    merged
         .into_values()
         .map(|mut mcp| {
+            mcp.resolve_env()?;
             for (k, v) in &config.env {
                 mcp.env.entry(k.clone()).or_insert_with(|| v.clone());
             }
-            mcp
+            Ok(mcp)
         })
-        .collect())
+        .collect()
 }
@@ -0,0 +1,218 @@
+//! Preflight self-check — validate the whole configuration without
+//! binding any listener.
+//!
+//! Each probe mirrors a step of [`Daemon::build`](super::Daemon) but
+//! surfaces the failure instead of logging a warning and carrying on.
+
+use crate::{
+    DaemonConfig,
+    daemon::builder::{mcp_servers, probe_models},
+    storage::FsStorage,
+};
+use mcp::McpHandler;
+use std::{collections::BTreeSet, fmt, path::Path};
+use wcore::{AgentConfig, resolve_dirs, storage::Storage};
+
+/// Outcome of one probe.
+#[derive(Debug, Clone)]
+pub struct CheckItem {
+    /// What was probed, e.g. `llm` or `mcp:github`.
+    pub name: String,
+    /// `Ok` carries a short summary, `Err` the reason it failed.
+    pub result: Result<String, String>,
+}
+
+/// Every probe run by [`check`], in order.
+#[derive(Debug, Clone, Default)]
+pub struct CheckReport {
+    pub items: Vec<CheckItem>,
+}
+
+impl CheckReport {
+    /// Whether every probe passed.
+    pub fn passed(&self) -> bool {
+        self.items.iter().all(|i| i.result.is_ok())
+    }
+
+    fn push(&mut self, name: impl Into<String>, result: Result<String, String>) {
+        self.items.push(CheckItem {
+            name: name.into(),
+            result,
+        });
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.items.iter().map(|i| i.name.len()).max().unwrap_or(0);
+        for item in &self.items {
+            let (status, detail) = match &item.result {
+                Ok(detail) => ("ok  ", detail),
+                Err(reason) => ("FAIL", reason),
+            };
+            writeln!(f, "{status} {:width$}  {detail}", item.name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run every preflight probe against `config_dir`: config parse, LLM
+/// endpoint, memory store, skills, MCP servers, then each agent's model
+/// and skill/MCP references. A config that fails to load stops early
+/// since nothing else can be checked without it.
+pub async fn check(config_dir: &Path) -> CheckReport {
+    let mut report = CheckReport::default();
+
+    let config_path = config_dir.join(wcore::paths::CONFIG_FILE);
+    let config = match DaemonConfig::load(&config_path).and_then(|mut c| {
+        c.resolve_env()?;
+        Ok(c)
+    }) {
+        Ok(config) => {
+            report.push("config", Ok(config_path.display().to_string()));
+            config
+        }
+        Err(e) => {
+            report.push("config", Err(format!("{e:#}")));
+            return report;
+        }
+    };
+
+    let models: Option<BTreeSet<String>> = match probe_models(&config.llm).await {
+        Ok(models) => {
+            report.push("llm", Ok(format!("{} model(s)", models.len())));
+            Some(models.into_iter().map(|m| m.name).collect())
+        }
+        Err(e) => {
+            report.push("llm", Err(format!("{e:#}")));
+            None
+        }
+    };
+
+    // The raw store, not the daemon's facade: opening that purges expired
+    // entries, and a check must not write.
+    match memory::Memory::open(config_dir.join("memory.db")) {
+        Ok(_) => report.push("memory", Ok("memory.db".to_owned())),
+        Err(e) => report.push("memory", Err(format!("{e:#}"))),
+    }
+
+    let dirs = resolve_dirs(config_dir);
+    let skills = check_skills(&dirs.skill_dirs, &mut report);
+
+    let storage = FsStorage::new(
+        config_dir.to_path_buf(),
+        config_dir.join("sessions"),
+        dirs.skill_dirs.clone(),
+    );
+    let mut servers = BTreeSet::new();
+    match mcp_servers(&config, &storage, &dirs) {
+        Ok(configs) => {
+            for server in configs {
+                let name = format!("mcp:{}", server.name);
+                match McpHandler::probe(&server).await {
+                    Ok(tools) => {
+                        servers.insert(server.name);
+                        report.push(name, Ok(format!("{tools} tool(s)")));
+                    }
+                    Err(e) => report.push(name, Err(format!("{e:#}"))),
+                }
+            }
+        }
+        Err(e) => report.push("mcp", Err(format!("{e:#}"))),
+    }
+
+    let mut agents = match storage.list_agents() {
+        Ok(agents) => agents,
+        Err(e) => {
+            report.push("agents", Err(format!("{e:#}")));
+            Vec::new()
+        }
+    };
+    let stored: BTreeSet<String> = agents.iter().map(|a| a.name.clone()).collect();
+    agents.extend(
+        dirs.plugin_agents
+            .into_values()
+            .filter(|a| !stored.contains(&a.name)),
+    );
+    for agent in &agents {
+        report.push(
+            format!("agent:{}", agent.name),
+            check_agent(agent, models.as_ref(), &skills, &servers),
+        );
+    }
+
+    report
+}
+
+/// Parse every `SKILL.md` under the skill roots, reporting each failure.
+/// Returns the names of the skills that loaded.
+fn check_skills(roots: &[std::path::PathBuf], report: &mut CheckReport) -> BTreeSet<String> {
+    let mut loaded = BTreeSet::new();
+    let mut failed = 0;
+    for root in roots {
+        let Ok(entries) = std::fs::read_dir(root) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path().join("SKILL.md");
+            let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            if name.starts_with('.') || !path.exists() || loaded.contains(&name) {
+                continue;
+            }
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|c| crate::hooks::skill::loader::parse_skill_md(&c));
+            match parsed {
+                Ok(_) => {
+                    loaded.insert(name);
+                }
+                Err(e) => {
+                    failed += 1;
+                    report.push(format!("skill:{name}"), Err(format!("{e:#}")));
+                }
+            }
+        }
+    }
+    if failed == 0 {
+        report.push("skills", Ok(format!("{} skill(s)", loaded.len())));
+    }
+    loaded
+}
+
+/// Mirror the checks `register_agents` and the scoping hooks apply:
+/// a prompt and model are set, the model is advertised, and every
+/// referenced skill and MCP server resolved.
+fn check_agent(
+    agent: &AgentConfig,
+    models: Option<&BTreeSet<String>>,
+    skills: &BTreeSet<String>,
+    servers: &BTreeSet<String>,
+) -> Result<String, String> {
+    let mut problems = Vec::new();
+    if agent.system_prompt.is_empty() {
+        problems.push("no system prompt".to_owned());
+    }
+    if agent.model.is_empty() {
+        problems.push("no model".to_owned());
+    } else if let Some(models) = models
+        && !models.contains(&agent.model)
+    {
+        problems.push(format!(
+            "model '{}' not served by the endpoint",
+            agent.model
+        ));
+    }
+    for skill in agent.skills.iter().filter(|s| !skills.contains(*s)) {
+        problems.push(format!("unknown skill '{skill}'"));
+    }
+    for mcp in agent.mcps.iter().filter(|m| !servers.contains(*m)) {
+        problems.push(format!("mcp server '{mcp}' not connected"));
+    }
+    if problems.is_empty() {
+        Ok(agent.model.clone())
+    } else {
+        Err(problems.join("; "))
+    }
+}
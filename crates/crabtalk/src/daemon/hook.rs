@@ -4,10 +4,20 @@
 //! (os, memory, skill, delegate, ask_user, mcp), the dispatch map, scope
 //! enforcement, agent descriptions, and the event sink.
 
-use parking_lot::RwLock;
-use runtime::Hook;
-use std::{collections::BTreeMap, sync::Arc};
-use wcore::{AgentConfig, AgentEvent, ToolDispatch, ToolFuture, model::HistoryEntry};
+use parking_lot::{Mutex, RwLock};
+use runtime::{Hook, Preprocessed};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::oneshot;
+use wcore::{
+    AgentConfig, AgentEvent, ApprovalFuture, ToolDispatch, ToolFuture, model::HistoryEntry,
+};
 
 /// Per-agent scope for dispatch enforcement. Empty vecs = unrestricted.
 #[derive(Default)]
@@ -20,6 +30,41 @@ pub struct AgentScope {
 /// Late-bindable sink for `agent:{name}:done` event publishes.
 pub type EventSink = Arc<dyn Fn(&str, &str) + Send + Sync>;
 
+/// Custom system prompt assembly: `(base, memory, skills) -> prompt`.
+pub type SystemAssembler = Arc<dyn Fn(&str, &str, &str) -> String + Send + Sync>;
+
+/// Cached results of pure tools, keyed by `(tool, args)`, oldest first.
+#[derive(Default)]
+struct ToolResults {
+    results: BTreeMap<(String, String), String>,
+    order: VecDeque<(String, String)>,
+}
+
+impl ToolResults {
+    /// Store `output` under `key`, dropping the oldest results past `max`.
+    fn insert(&mut self, key: (String, String), output: String, max: usize) {
+        if self.results.insert(key.clone(), output).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > max {
+            if let Some(oldest) = self.order.pop_front() {
+                self.results.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Tool calls waiting for approval, keyed by `(conversation_id, call_id)`.
+/// Shared with the protocol layer, which routes `ToolDecision` replies.
+pub type PendingConfirms = Arc<Mutex<HashMap<(u64, String), PendingConfirm>>>;
+
+/// A call announced to an interactive client. Dispatch takes `rx` and
+/// the client's decision takes `tx`, in whichever order they arrive.
+pub struct PendingConfirm {
+    tx: Option<oneshot::Sender<bool>>,
+    rx: Option<oneshot::Receiver<bool>>,
+}
+
 /// Composite hook aggregating all node sub-hooks.
 pub struct DaemonHook {
     pub scopes: Arc<RwLock<BTreeMap<String, AgentScope>>>,
@@ -27,6 +72,28 @@ pub struct DaemonHook {
     hooks: BTreeMap<String, Arc<dyn Hook>>,
     dispatch_map: BTreeMap<String, Arc<dyn Hook>>,
     event_sink: RwLock<Option<EventSink>>,
+    assembler: RwLock<Option<SystemAssembler>>,
+    /// Per-turn tool grants by conversation, cleared when the run ends.
+    grants: RwLock<BTreeMap<u64, Vec<String>>>,
+    /// One-shot instructions by conversation, taken by the next turn.
+    /// Tagged with the id of the [`TurnInstructions`] that queued them.
+    instructions: RwLock<BTreeMap<u64, (u64, String)>>,
+    /// Source of [`TurnInstructions`] ids.
+    next_instructions: AtomicU64,
+    /// Results of cacheable tools by conversation, keyed by `(tool, args)`.
+    tool_cache: RwLock<BTreeMap<u64, ToolResults>>,
+    /// Tools cacheable by config, on top of those sub-hooks mark.
+    cacheable_tools: Vec<String>,
+    /// Most cached results kept per conversation.
+    cache_entries: usize,
+    /// Tools whose calls wait for the client's approval.
+    confirm_tools: Vec<String>,
+    /// How long a call waits for the client's approval before it is denied.
+    confirm_timeout: Duration,
+    /// Calls currently waiting for approval.
+    pub confirmations: PendingConfirms,
+    /// Prompt variables from `[prompt_vars]`.
+    prompt_vars: BTreeMap<String, String>,
 }
 
 impl DaemonHook {
@@ -37,7 +104,134 @@ impl DaemonHook {
             hooks: BTreeMap::new(),
             dispatch_map: BTreeMap::new(),
             event_sink: RwLock::new(None),
+            assembler: RwLock::new(None),
+            grants: RwLock::new(BTreeMap::new()),
+            instructions: RwLock::new(BTreeMap::new()),
+            next_instructions: AtomicU64::new(0),
+            tool_cache: RwLock::new(BTreeMap::new()),
+            cacheable_tools: Vec::new(),
+            cache_entries: 128,
+            confirm_tools: Vec::new(),
+            confirm_timeout: Duration::from_secs(300),
+            confirmations: Default::default(),
+            prompt_vars: BTreeMap::new(),
+        }
+    }
+
+    /// Supply `{{name}}` prompt variables. They override sub-hook and
+    /// built-in values.
+    pub fn set_prompt_vars(&mut self, vars: BTreeMap<String, String>) {
+        self.prompt_vars = vars;
+    }
+
+    /// Cache results of `tools` as well as those sub-hooks mark
+    /// [cacheable](Hook::cacheable), keeping at most `max_entries` per
+    /// conversation. 0 turns the cache off.
+    pub fn set_cache_policy(&mut self, tools: Vec<String>, max_entries: usize) {
+        self.cacheable_tools = tools;
+        self.cache_entries = max_entries;
+    }
+
+    /// Whether results of `name`, owned by `hook`, may be reused.
+    fn cacheable(&self, hook: &dyn Hook, name: &str) -> bool {
+        self.cache_entries > 0
+            && (hook.cacheable(name) || self.cacheable_tools.iter().any(|t| t == name))
+    }
+
+    /// Require client approval before any call to one of `tools` runs,
+    /// denying calls left unanswered for `timeout`.
+    pub fn set_confirm_tools(&mut self, tools: Vec<String>, timeout: Duration) {
+        self.confirm_tools = tools;
+        self.confirm_timeout = timeout;
+    }
+
+    /// Whether calls to `tool` wait for the client's approval.
+    pub fn needs_confirmation(&self, tool: &str) -> bool {
+        self.confirm_tools.iter().any(|t| t == tool)
+    }
+
+    /// Register a call about to be shown to an interactive client. Only
+    /// registered calls wait for a verdict; any other call to a confirm
+    /// tool is denied at once. Register before the client sees the call
+    /// so its decision can never arrive first.
+    pub fn expect_confirmation(&self, conversation_id: u64, call_id: &str) {
+        let (tx, rx) = oneshot::channel();
+        self.confirmations.lock().insert(
+            (conversation_id, call_id.to_owned()),
+            PendingConfirm {
+                tx: Some(tx),
+                rx: Some(rx),
+            },
+        );
+    }
+
+    /// Drop every registered call of a conversation whose turn ended.
+    pub fn clear_confirmations(&self, conversation_id: u64) {
+        self.confirmations
+            .lock()
+            .retain(|(id, _), _| *id != conversation_id);
+    }
+
+    /// Deliver the client's verdict on a waiting call. Returns `false`
+    /// when no such call is waiting.
+    pub fn decide(&self, conversation_id: u64, call_id: &str, approve: bool) -> bool {
+        let key = (conversation_id, call_id.to_owned());
+        let mut pending = self.confirmations.lock();
+        let Some(tx) = pending.get_mut(&key).and_then(|p| p.tx.take()) else {
+            return false;
+        };
+        if pending.get(&key).is_some_and(|p| p.rx.is_none()) {
+            pending.remove(&key);
         }
+        let _ = tx.send(approve);
+        true
+    }
+
+    /// Whether the calling agent may use `name`: its scope lists it, the
+    /// scope is open, or the tool is granted for this turn.
+    fn in_scope(&self, name: &str, call: &ToolDispatch) -> bool {
+        match self.scopes.read().get(&call.agent) {
+            Some(scope) if !scope.tools.is_empty() => {
+                scope.tools.iter().any(|t| t.as_str() == name)
+                    || self.granted(call.conversation_id, name)
+            }
+            _ => true,
+        }
+    }
+
+    /// Hold the call until the client approves it; a denial, timeout or
+    /// abort returns an error to the model instead of dispatching it. A
+    /// call no interactive client was shown is denied without waiting.
+    fn confirmed<'a>(&'a self, name: &'a str, call: ToolDispatch) -> ApprovalFuture<'a> {
+        Box::pin(async move {
+            let conversation_id = call.conversation_id.ok_or_else(|| {
+                format!("tool '{name}' requires confirmation, which needs a conversation")
+            })?;
+            let key = (conversation_id, call.call_id.clone());
+            let rx = {
+                let mut pending = self.confirmations.lock();
+                let rx = pending.get_mut(&key).and_then(|p| p.rx.take());
+                if pending.get(&key).is_some_and(|p| p.tx.is_none()) {
+                    pending.remove(&key);
+                }
+                rx
+            };
+            let Some(rx) = rx else {
+                return Err(format!(
+                    "tool call not confirmed: {name} (no client attached to approve it)"
+                ));
+            };
+            let approved = tokio::select! {
+                verdict = tokio::time::timeout(self.confirm_timeout, rx) => verdict.ok().and_then(Result::ok),
+                _ = call.cancelled() => None,
+            };
+            self.confirmations.lock().remove(&key);
+            match approved {
+                Some(true) => Ok(()),
+                Some(false) => Err(format!("tool call denied by user: {name}")),
+                None => Err(format!("tool call not confirmed: {name}")),
+            }
+        })
     }
 
     /// Register a sub-hook by name.
@@ -54,6 +248,78 @@ impl DaemonHook {
         *self.event_sink.write() = Some(sink);
     }
 
+    /// Replace the default system prompt assembly order. Applies to
+    /// agents built after the call.
+    pub fn set_system_assembler(&self, assembler: SystemAssembler) {
+        *self.assembler.write() = Some(assembler);
+    }
+
+    /// Queue `instructions` for the next turn on `conversation_id`. They
+    /// are injected once, ahead of the user message, and not persisted.
+    /// Hold the returned guard until the turn ends: dropping it withdraws
+    /// the instructions if the turn never took them.
+    pub fn set_turn_instructions(
+        self: &Arc<Self>,
+        conversation_id: u64,
+        instructions: String,
+    ) -> TurnInstructions {
+        let id = self.next_instructions.fetch_add(1, Ordering::Relaxed);
+        self.instructions
+            .write()
+            .insert(conversation_id, (id, instructions));
+        TurnInstructions {
+            hook: self.clone(),
+            conversation_id,
+            id,
+        }
+    }
+
+    /// Whether a skill granted `tool` for the conversation's current turn.
+    fn granted(&self, conversation_id: Option<u64>, tool: &str) -> bool {
+        conversation_id.is_some_and(|id| {
+            self.grants
+                .read()
+                .get(&id)
+                .is_some_and(|tools| tools.iter().any(|t| t == tool))
+        })
+    }
+
+    /// Route a call to its sub-hook, reusing cached results of pure tools.
+    fn dispatch_cached<'a>(&'a self, name: &'a str, call: ToolDispatch) -> Option<ToolFuture<'a>> {
+        let hook = self.dispatch_map.get(name)?;
+        let Some(conversation_id) = call
+            .conversation_id
+            .filter(|_| self.cacheable(hook.as_ref(), name))
+        else {
+            return hook.dispatch(name, call);
+        };
+
+        // Pure tool: reuse the result for identical arguments. Errors are
+        // not cached, so a transient failure is retried next time.
+        let key = (name.to_owned(), call.args.clone());
+        let hit = self
+            .tool_cache
+            .read()
+            .get(&conversation_id)
+            .and_then(|cached| cached.results.get(&key))
+            .cloned();
+        if let Some(output) = hit {
+            return Some(Box::pin(async move { Ok(output) }));
+        }
+        let fut = hook.dispatch(name, call)?;
+        Some(Box::pin(async move {
+            let result = fut.await;
+            if let Ok(output) = &result {
+                self.tool_cache
+                    .write()
+                    .entry(conversation_id)
+                    .or_default()
+                    .insert(key, output.clone(), self.cache_entries);
+            }
+            result
+        }))
+    }
+
     /// Apply scoped tool whitelist and scope prompt for sub-agents.
     fn apply_scope(&self, config: &mut AgentConfig) {
         let has_scoping = !config.skills.is_empty() || !config.mcps.is_empty();
@@ -80,6 +346,30 @@ impl DaemonHook {
     }
 }
 
+/// Instructions queued for one turn by
+/// [`DaemonHook::set_turn_instructions`]. Dropping it withdraws them
+/// unless the turn took them, so a turn that fails before it starts
+/// leaves nothing for the next one. Instructions queued since, by
+/// another guard, are left alone.
+#[must_use = "dropping the guard withdraws the instructions"]
+pub struct TurnInstructions {
+    hook: Arc<DaemonHook>,
+    conversation_id: u64,
+    id: u64,
+}
+
+impl Drop for TurnInstructions {
+    fn drop(&mut self) {
+        let mut queued = self.hook.instructions.write();
+        if queued
+            .get(&self.conversation_id)
+            .is_some_and(|(id, _)| *id == self.id)
+        {
+            queued.remove(&self.conversation_id);
+        }
+    }
+}
+
 impl Hook for DaemonHook {
     fn schema(&self) -> Vec<crabllm_core::Tool> {
         self.hooks.values().flat_map(|h| h.schema()).collect()
@@ -99,9 +389,38 @@ impl Hook for DaemonHook {
         }
     }
 
+    fn assemble_system(&self, base: &str, memory: &str, skills: &str) -> String {
+        match self.assembler.read().as_ref() {
+            Some(assemble) => assemble(base, memory, skills),
+            None => format!("{base}{memory}{skills}"),
+        }
+    }
+
+    /// Without an assembler, every sub-hook's fragment is appended in
+    /// hook name order, as [`Hook::system_prompt`] joins them. With one,
+    /// the other fragments are folded into the base prompt and the memory
+    /// and skill fragments go through [`Hook::assemble_system`].
     fn on_build_agent(&self, mut config: AgentConfig) -> AgentConfig {
-        if let Some(ref prompt) = self.system_prompt() {
-            config.system_prompt.push_str(prompt);
+        let memory_disabled = config.hooks.memory.disabled;
+        let fragments = self
+            .hooks
+            .iter()
+            .filter(|(name, _)| !(memory_disabled && *name == "memory"))
+            .filter_map(|(name, hook)| Some((name.as_str(), hook.system_prompt()?)));
+        if self.assembler.read().is_none() {
+            for (_, fragment) in fragments {
+                config.system_prompt.push_str(&fragment);
+            }
+        } else {
+            let (mut memory, mut skills) = (String::new(), String::new());
+            for (name, fragment) in fragments {
+                match name {
+                    "memory" => memory = fragment,
+                    "skill" => skills = fragment,
+                    _ => config.system_prompt.push_str(&fragment),
+                }
+            }
+            config.system_prompt = self.assemble_system(&config.system_prompt, &memory, &skills);
         }
         self.apply_scope(&mut config);
         config
@@ -160,10 +479,62 @@ impl Hook for DaemonHook {
             injected.extend(hook.on_before_run(agent, conversation_id, history));
         }
 
+        if let Some((_, text)) = self.instructions.write().remove(&conversation_id) {
+            injected.push(
+                HistoryEntry::user(format!("<instructions>\n{text}\n</instructions>"))
+                    .auto_injected(),
+            );
+        }
+
         injected
     }
 
+    fn prompt_vars(
+        &self,
+        agent: &str,
+        conversation_id: Option<u64>,
+        sender: &str,
+    ) -> BTreeMap<String, String> {
+        let mut vars = BTreeMap::new();
+        for hook in self.hooks.values() {
+            vars.extend(hook.prompt_vars(agent, conversation_id, sender));
+        }
+        vars.extend(self.prompt_vars.clone());
+        vars
+    }
+
+    fn turn_tools(
+        &self,
+        agent: &str,
+        conversation_id: u64,
+        history: &[HistoryEntry],
+    ) -> Vec<String> {
+        let mut tools: Vec<String> = Vec::new();
+        for hook in self.hooks.values() {
+            for tool in hook.turn_tools(agent, conversation_id, history) {
+                if !tools.contains(&tool) {
+                    tools.push(tool);
+                }
+            }
+        }
+        if tools.is_empty() {
+            self.grants.write().remove(&conversation_id);
+        } else {
+            tracing::info!(
+                agent,
+                conversation_id,
+                ?tools,
+                "granting tools for this turn"
+            );
+            self.grants.write().insert(conversation_id, tools.clone());
+        }
+        tools
+    }
+
     fn on_event(&self, agent: &str, conversation_id: u64, event: &AgentEvent) {
+        if matches!(event, AgentEvent::Done(_)) {
+            self.grants.write().remove(&conversation_id);
+        }
         for hook in self.hooks.values() {
             hook.on_event(agent, conversation_id, event);
         }
@@ -177,7 +548,16 @@ impl Hook for DaemonHook {
         }
     }
 
-    fn preprocess(&self, agent: &str, content: &str) -> Option<String> {
+    fn on_close(&self, conversation_id: u64) {
+        self.grants.write().remove(&conversation_id);
+        self.instructions.write().remove(&conversation_id);
+        self.tool_cache.write().remove(&conversation_id);
+        for hook in self.hooks.values() {
+            hook.on_close(conversation_id);
+        }
+    }
+
+    fn preprocess(&self, agent: &str, content: &str) -> Option<Preprocessed> {
         for hook in self.hooks.values() {
             if let Some(result) = hook.preprocess(agent, content) {
                 return Some(result);
@@ -187,23 +567,19 @@ impl Hook for DaemonHook {
     }
 
     fn dispatch<'a>(&'a self, name: &'a str, call: ToolDispatch) -> Option<ToolFuture<'a>> {
-        // Scope enforcement.
-        {
-            let scopes = self.scopes.read();
-            if let Some(scope) = scopes.get(&call.agent)
-                && !scope.tools.is_empty()
-                && !scope.tools.iter().any(|t| t.as_str() == name)
-            {
-                return Some(Box::pin(async move {
-                    Err(format!("tool not available: {name}"))
-                }));
-            }
-        }
-
-        if let Some(hook) = self.dispatch_map.get(name) {
-            return hook.dispatch(name, call);
+        if !self.in_scope(name, &call) {
+            return Some(Box::pin(async move {
+                Err(format!("tool not available: {name}"))
+            }));
         }
+        self.dispatch_cached(name, call)
+    }
 
-        None
+    fn approve<'a>(&'a self, name: &'a str, call: ToolDispatch) -> Option<ApprovalFuture<'a>> {
+        // Out-of-scope calls are refused by `dispatch`; nobody is asked.
+        (self.needs_confirmation(name)
+            && self.dispatch_map.contains_key(name)
+            && self.in_scope(name, &call))
+        .then(|| self.confirmed(name, call))
     }
 }
@@ -86,6 +86,7 @@ impl Env for DaemonEnv {
                     structured.push(ToolCallInfo {
                         name: c.function.name.to_string(),
                         arguments: c.function.arguments.clone(),
+                        call_id: c.id.clone(),
                     });
                 }
                 Payload {
@@ -115,7 +116,7 @@ impl Env for DaemonEnv {
                 tracing::debug!(%agent, "agent tool calls complete");
                 Payload::of(AgentEventKind::ToolsComplete)
             }
-            AgentEvent::Compact { summary } => {
+            AgentEvent::Compact { summary, .. } => {
                 tracing::info!(%agent, summary_len = summary.len(), "context compacted");
                 return;
             }
@@ -170,12 +171,45 @@ impl wcore::ToolDispatcher for DaemonEnv {
     fn dispatch<'a>(
         &'a self,
         name: &'a str,
+        call_id: &'a str,
         args: &'a str,
         agent: &'a str,
         sender: &'a str,
         conversation_id: Option<u64>,
+        cancel: Option<wcore::CancellationToken>,
     ) -> wcore::ToolFuture<'a> {
-        runtime::env::dispatch_tool(self, name, args, agent, sender, conversation_id)
+        runtime::env::dispatch_tool(
+            self,
+            name,
+            call_id,
+            args,
+            agent,
+            sender,
+            conversation_id,
+            cancel,
+        )
+    }
+
+    fn approve<'a>(
+        &'a self,
+        name: &'a str,
+        call_id: &'a str,
+        args: &'a str,
+        agent: &'a str,
+        sender: &'a str,
+        conversation_id: Option<u64>,
+        cancel: Option<wcore::CancellationToken>,
+    ) -> wcore::ApprovalFuture<'a> {
+        runtime::env::approve_tool(
+            self,
+            name,
+            call_id,
+            args,
+            agent,
+            sender,
+            conversation_id,
+            cancel,
+        )
     }
 }
 
@@ -11,7 +11,10 @@ use std::{
     sync::Arc,
 };
 use tokio::sync::{Mutex, RwLock, broadcast, mpsc, oneshot};
-use wcore::protocol::{api::Server, message::ClientMessage};
+use wcore::{
+    OpenAiConfig, SessionsConfig,
+    protocol::{api::Server, message::ClientMessage},
+};
 use {
     builder::{BuildProvider, DefaultProvider, build_default_provider},
     event::EventBus,
@@ -25,6 +28,7 @@ pub type ConversationCwds = Arc<Mutex<HashMap<u64, PathBuf>>>;
 pub type PendingAsks = Arc<Mutex<HashMap<u64, oneshot::Sender<String>>>>;
 
 pub mod builder;
+pub mod check;
 pub mod event;
 pub mod hook;
 pub mod host;
@@ -57,6 +61,8 @@ pub struct Daemon<P: Provider + 'static = DefaultProvider> {
     pub(crate) os_hook: Arc<hooks::os::OsHook>,
     /// Ask-user hook — owns pending ask oneshots.
     pub(crate) ask_hook: Arc<hooks::ask_user::AskUserHook>,
+    /// `[limits] max_content_bytes`, fixed at startup.
+    pub(crate) max_content_bytes: usize,
 }
 
 impl<P: Provider + 'static> Clone for Daemon<P> {
@@ -71,6 +77,7 @@ impl<P: Provider + 'static> Clone for Daemon<P> {
             mcp: self.mcp.clone(),
             os_hook: self.os_hook.clone(),
             ask_hook: self.ask_hook.clone(),
+            max_content_bytes: self.max_content_bytes,
         }
     }
 }
@@ -78,7 +85,8 @@ impl<P: Provider + 'static> Clone for Daemon<P> {
 impl Daemon<DefaultProvider> {
     pub async fn start(config_dir: &Path) -> Result<DaemonHandle<DefaultProvider>> {
         let config_path = config_dir.join(wcore::paths::CONFIG_FILE);
-        let config = DaemonConfig::load(&config_path)?;
+        let mut config = DaemonConfig::load(&config_path)?;
+        config.resolve_env()?;
         tracing::info!("loaded configuration from {}", config_path.display());
 
         let (shutdown_tx, _) = broadcast::channel::<()>(1);
@@ -125,6 +133,8 @@ fn dispatch_callback<P: Provider + 'static>(
         tokio::spawn(async move {
             let stream = daemon.dispatch(msg);
             pin_mut!(stream);
+            // `reply` is bounded, so a slow client throttles the stream
+            // here; the transport closes a stalled one, failing the send.
             while let Some(server_msg) = stream.next().await {
                 if reply.send(server_msg).await.is_err() {
                     break;
@@ -178,6 +188,63 @@ pub fn setup_tcp<P: Provider + 'static>(
     Ok((join, addr.port()))
 }
 
+/// Serve the OpenAI-compatible API on `config.bind`. Returns `None`
+/// when the server is disabled.
+pub async fn setup_openai<P: Provider + 'static>(
+    daemon: Daemon<P>,
+    config: &OpenAiConfig,
+    shutdown_tx: &broadcast::Sender<()>,
+) -> Result<Option<tokio::task::JoinHandle<()>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    let listener = tokio::net::TcpListener::bind(&config.bind).await?;
+    tracing::info!("openai api listening on http://{}", listener.local_addr()?);
+
+    let app = crate::openai::router(daemon, config.api_key.clone());
+    let shutdown = bridge_shutdown(shutdown_tx.subscribe());
+    let join = tokio::spawn(async move {
+        let server = axum::serve(listener, app).with_graceful_shutdown(async {
+            let _ = shutdown.await;
+        });
+        if let Err(e) = server.await {
+            tracing::error!("openai api server failed: {e}");
+        }
+    });
+    Ok(Some(join))
+}
+
+/// Periodically evict idle conversations per `[sessions]`. Returns
+/// `None` when eviction is disabled.
+pub fn setup_eviction<P: Provider + 'static>(
+    daemon: Daemon<P>,
+    config: &SessionsConfig,
+    shutdown_tx: &broadcast::Sender<()>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    if config.idle_timeout == 0 {
+        return None;
+    }
+    let ttl = std::time::Duration::from_secs(config.idle_timeout);
+    let every = std::time::Duration::from_secs(config.sweep_interval.max(1));
+    let pinned = config.pinned.clone();
+    let mut shutdown = bridge_shutdown(shutdown_tx.subscribe());
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+        ticker.tick().await;
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let evicted = daemon.evict_idle(ttl, &pinned).await;
+                    if evicted > 0 {
+                        tracing::debug!(evicted, "idle sweep");
+                    }
+                }
+                _ = &mut shutdown => break,
+            }
+        }
+    }))
+}
+
 pub fn bridge_shutdown(mut rx: broadcast::Receiver<()>) -> oneshot::Receiver<()> {
     let (otx, orx) = oneshot::channel();
     tokio::spawn(async move {
@@ -2,17 +2,22 @@
 
 use crate::daemon::ConversationCwds;
 use crate::{daemon::SharedRuntime, hooks::os::ReadFiles};
+use anyhow::{Context, Result, anyhow, bail};
 use crabllm_core::Provider;
+use parking_lot::Mutex;
 use runtime::Hook;
 use serde::Deserialize;
 use std::{
+    collections::HashMap,
     path::PathBuf,
     sync::{
         Arc, OnceLock,
         atomic::{AtomicU64, Ordering},
     },
 };
-use wcore::{ToolDispatch, ToolFuture, agent::AsTool};
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+use wcore::{CancellationToken, ToolDispatch, ToolFuture, agent::AsTool, try_json_tool};
 
 /// Delegate tasks to other agents. Runs all tasks in parallel.
 ///
@@ -42,14 +47,24 @@ pub struct DelegateTask {
     pub cwd: Option<String>,
 }
 
+/// Default cap on nested delegation.
+pub const DEFAULT_MAX_DEPTH: usize = 4;
+
+/// Agents from the root conversation down to the one running under each
+/// in-flight `delegate:N` sender.
+type Chains = Arc<Mutex<HashMap<String, Vec<String>>>>;
+
 /// Delegate subsystem: dispatch tasks to other agents.
 ///
 /// Holds a late-bind runtime handle and the shared conversation CWD map
-/// for child task CWD overrides.
+/// for child task CWD overrides. Tracks the delegation chain of every
+/// running task so nesting stays bounded and cycles are refused.
 pub struct DelegateHook<P: Provider + 'static> {
     runtime: Arc<OnceLock<SharedRuntime<P>>>,
     conversation_cwds: ConversationCwds,
     read_files: ReadFiles,
+    chains: Chains,
+    max_depth: usize,
 }
 
 impl<P: Provider + 'static> DelegateHook<P> {
@@ -62,8 +77,31 @@ impl<P: Provider + 'static> DelegateHook<P> {
             runtime,
             conversation_cwds,
             read_files,
+            chains: Default::default(),
+            max_depth: DEFAULT_MAX_DEPTH,
         }
     }
+
+    /// Cap nested delegation at `depth` levels. 0 lifts the cap.
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Record `chain` as the delegation chain of the task running under
+    /// `sender`, replacing any earlier one.
+    pub fn track_chain(&self, sender: String, chain: Vec<String>) {
+        self.chains.lock().insert(sender, chain);
+    }
+
+    /// The chain a task delegated by this call would extend.
+    fn parent_chain(&self, call: &ToolDispatch) -> Vec<String> {
+        self.chains
+            .lock()
+            .get(&call.sender)
+            .cloned()
+            .unwrap_or_else(|| vec![call.agent.clone()])
+    }
 }
 
 impl<P: Provider + 'static> Hook for DelegateHook<P> {
@@ -75,28 +113,56 @@ impl<P: Provider + 'static> Hook for DelegateHook<P> {
         if name != "delegate" {
             return None;
         }
-        Some(Box::pin(async move {
-            let input: Delegate =
-                serde_json::from_str(&call.args).map_err(|e| format!("invalid arguments: {e}"))?;
+        Some(try_json_tool(async move {
+            let input: Delegate = serde_json::from_str(&call.args).context("invalid arguments")?;
             if input.tasks.is_empty() {
-                return Err("no tasks provided".to_owned());
+                bail!("no tasks provided");
             }
+            let parent = self.parent_chain(&call);
+            check_chain(&parent, &input.tasks, self.max_depth)?;
             let shared = self
                 .runtime
                 .get()
-                .ok_or_else(|| "delegate: runtime not initialized".to_owned())?;
-            dispatch_delegate(input, shared, &self.conversation_cwds, &self.read_files).await
+                .ok_or_else(|| anyhow!("delegate: runtime not initialized"))?;
+            dispatch_delegate(input, parent, call.cancel, shared, self).await
         }))
     }
 }
 
+/// Refuse a delegation that would nest deeper than `max_depth` or hand
+/// work back to an agent earlier in the chain. An agent delegating to
+/// itself is not a cycle; `max_depth` bounds that.
+fn check_chain(parent: &[String], tasks: &[DelegateTask], max_depth: usize) -> Result<()> {
+    if max_depth > 0 && parent.len() > max_depth {
+        bail!("delegation depth limit reached ({max_depth})");
+    }
+    let Some((current, earlier)) = parent.split_last() else {
+        return Ok(());
+    };
+    if let Some(task) = tasks
+        .iter()
+        .find(|t| &t.agent != current && earlier.contains(&t.agent))
+    {
+        bail!(
+            "delegation cycle: {} -> {}",
+            parent.join(" -> "),
+            task.agent
+        );
+    }
+    Ok(())
+}
+
 async fn dispatch_delegate<P: Provider + 'static>(
     input: Delegate,
+    parent: Vec<String>,
+    cancel: Option<CancellationToken>,
     shared: &SharedRuntime<P>,
-    conversation_cwds: &ConversationCwds,
-    read_files: &ReadFiles,
-) -> Result<String, String> {
-    let mut ephemeral_names = Vec::new();
+    hook: &DelegateHook<P>,
+) -> Result<serde_json::Value> {
+    let mut ephemerals = Ephemerals {
+        shared: shared.clone(),
+        names: Vec::new(),
+    };
     let mut tasks = Vec::with_capacity(input.tasks.len());
     for task in input.tasks {
         let agent_name = if let Some(prompt) = task.system_prompt {
@@ -109,21 +175,30 @@ async fn dispatch_delegate<P: Provider + 'static>(
             config.system_prompt = prompt;
             let rt = shared.read().await.clone();
             rt.add_ephemeral(config).await;
-            ephemeral_names.push(name.clone());
+            ephemerals.names.push(name.clone());
             name
         } else {
             task.agent
         };
 
         let sender = delegate_sender();
+        let mut chain = parent.clone();
+        chain.push(agent_name.clone());
+        hook.track_chain(sender.clone(), chain);
+        let cleanup = TaskCleanup {
+            shared: shared.clone(),
+            conversation_cwds: hook.conversation_cwds.clone(),
+            read_files: hook.read_files.clone(),
+            chains: hook.chains.clone(),
+            sender: sender.clone(),
+            conversation_id: None,
+        };
         let handle = spawn_agent_task(
-            shared.clone(),
-            conversation_cwds.clone(),
-            read_files.clone(),
+            cleanup,
             agent_name.clone(),
             task.message,
             task.cwd,
-            sender.clone(),
+            cancel.clone(),
         );
         tasks.push((agent_name, sender, handle));
     }
@@ -135,25 +210,26 @@ async fn dispatch_delegate<P: Provider + 'static>(
             json_results.push(serde_json::json!({ "agent": agent, "task_id": sender }));
             handles.push(handle);
         }
-        if !ephemeral_names.is_empty() {
-            let shared = shared.clone();
+        if !ephemerals.names.is_empty() {
             tokio::spawn(async move {
                 for h in handles {
                     let _ = h.await;
                 }
-                let rt = shared.read().await.clone();
-                for name in ephemeral_names {
-                    rt.remove_ephemeral(&name).await;
-                }
+                ephemerals.remove().await;
             });
         }
-        return serde_json::to_string(&json_results)
-            .map_err(|e| format!("serialization error: {e}"));
+        return Ok(json_results.into());
     }
 
+    // Dropping this call (tool timeout, aborted turn) aborts the tasks
+    // still waiting here.
+    let mut tasks: Vec<_> = tasks
+        .into_iter()
+        .map(|(agent, _sender, handle)| (agent, AbortOnDrop(handle)))
+        .collect();
     let mut results = Vec::with_capacity(tasks.len());
-    for (agent_name, _sender, handle) in tasks {
-        let (result, error) = match handle.await {
+    for (agent_name, handle) in &mut tasks {
+        let (result, error) = match (&mut handle.0).await {
             Ok((r, e)) => (r, e),
             Err(e) => (None, Some(format!("task panicked: {e}"))),
         };
@@ -164,14 +240,96 @@ async fn dispatch_delegate<P: Provider + 'static>(
         }));
     }
 
-    if !ephemeral_names.is_empty() {
-        let rt = shared.read().await.clone();
-        for name in ephemeral_names {
+    ephemerals.remove().await;
+    Ok(results.into())
+}
+
+/// Aborts the task when dropped.
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Ephemeral agents registered for one delegation. Removed once the
+/// tasks finish, or on drop if the delegation never got that far.
+struct Ephemerals<P: Provider + 'static> {
+    shared: SharedRuntime<P>,
+    names: Vec<String>,
+}
+
+impl<P: Provider + 'static> Ephemerals<P> {
+    async fn remove(mut self) {
+        let names = std::mem::take(&mut self.names);
+        if names.is_empty() {
+            return;
+        }
+        let rt = self.shared.read().await.clone();
+        for name in names {
             rt.remove_ephemeral(&name).await;
         }
     }
+}
 
-    serde_json::to_string(&results).map_err(|e| format!("serialization error: {e}"))
+impl<P: Provider + 'static> Drop for Ephemerals<P> {
+    fn drop(&mut self) {
+        let names = std::mem::take(&mut self.names);
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        if names.is_empty() {
+            return;
+        }
+        let shared = self.shared.clone();
+        handle.spawn(async move {
+            let rt = shared.read().await.clone();
+            for name in names {
+                rt.remove_ephemeral(&name).await;
+            }
+        });
+    }
+}
+
+/// State a delegated task leaves behind: its chain entry and, once
+/// created, its conversation. Released on drop, so a task that panics or
+/// is aborted cleans up like one that returns.
+struct TaskCleanup<P: Provider + 'static> {
+    shared: SharedRuntime<P>,
+    conversation_cwds: ConversationCwds,
+    read_files: ReadFiles,
+    chains: Chains,
+    sender: String,
+    conversation_id: Option<u64>,
+}
+
+impl<P: Provider + 'static> TaskCleanup<P> {
+    async fn finish(mut self) {
+        if let Some(id) = self.conversation_id.take() {
+            self.conversation_cwds.lock().await.remove(&id);
+            self.read_files.lock().remove(&id);
+            self.shared.read().await.clone().close(id).await;
+        }
+    }
+}
+
+impl<P: Provider + 'static> Drop for TaskCleanup<P> {
+    fn drop(&mut self) {
+        self.chains.lock().remove(&self.sender);
+        let Some(id) = self.conversation_id.take() else {
+            return;
+        };
+        self.read_files.lock().remove(&id);
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let (shared, cwds) = (self.shared.clone(), self.conversation_cwds.clone());
+        handle.spawn(async move {
+            cwds.lock().await.remove(&id);
+            shared.read().await.clone().close(id).await;
+        });
+    }
 }
 
 fn delegate_sender() -> String {
@@ -186,43 +344,53 @@ fn ephemeral_agent_name() -> String {
     format!("_ephemeral:{id}")
 }
 
+/// Run one delegated task on its own conversation. The turn stops when
+/// `cancel` (the delegating turn's token) fires.
 fn spawn_agent_task<P: Provider + 'static>(
-    shared: SharedRuntime<P>,
-    conversation_cwds: ConversationCwds,
-    read_files: ReadFiles,
+    mut cleanup: TaskCleanup<P>,
     agent: String,
     message: String,
     cwd: Option<String>,
-    delegate_sender: String,
-) -> tokio::task::JoinHandle<(Option<String>, Option<String>)> {
-    tokio::spawn(async move {
-        let rt = shared.read().await.clone();
-        let conversation_id = match rt
-            .get_or_create_conversation(&agent, &delegate_sender)
-            .await
-        {
-            Ok(id) => id,
-            Err(e) => return (None, Some(e.to_string())),
-        };
-        if let Some(cwd) = cwd {
-            conversation_cwds
-                .lock()
-                .await
-                .insert(conversation_id, PathBuf::from(cwd));
-        }
-
-        let (result_content, error_msg) = match rt
-            .send_to(conversation_id, &message, &delegate_sender, None)
-            .await
-        {
-            Ok(response) => (response.final_response, None),
-            Err(e) => (None, Some(e.to_string())),
-        };
+    cancel: Option<CancellationToken>,
+) -> JoinHandle<(Option<String>, Option<String>)> {
+    // Created here, inside the calling `tool` span, so the sub-agent's
+    // turn nests under the delegation in a trace.
+    let span = tracing::info_span!("delegate", agent = %agent, sender = %cleanup.sender);
+    tokio::spawn(
+        async move {
+            let rt = cleanup.shared.read().await.clone();
+            let conversation_id = match rt.get_or_create_conversation(&agent, &cleanup.sender).await
+            {
+                Ok(id) => id,
+                Err(e) => return (None, Some(e.to_string())),
+            };
+            cleanup.conversation_id = Some(conversation_id);
+            if let Some(cwd) = cwd {
+                cleanup
+                    .conversation_cwds
+                    .lock()
+                    .await
+                    .insert(conversation_id, PathBuf::from(cwd));
+            }
 
-        conversation_cwds.lock().await.remove(&conversation_id);
-        read_files.lock().remove(&conversation_id);
-        rt.close(conversation_id).await;
+            let turn = rt.send_to(conversation_id, &message, &[], &cleanup.sender, None, None);
+            let cancelled = async {
+                match &cancel {
+                    Some(token) => token.cancelled().await,
+                    None => std::future::pending().await,
+                }
+            };
+            let outcome = tokio::select! {
+                result = turn => match result {
+                    Ok(response) => (response.final_response, None),
+                    Err(e) => (None, Some(e.to_string())),
+                },
+                _ = cancelled => (None, Some("delegation cancelled".to_owned())),
+            };
 
-        (result_content, error_msg)
-    })
+            cleanup.finish().await;
+            outcome
+        }
+        .instrument(span),
+    )
 }
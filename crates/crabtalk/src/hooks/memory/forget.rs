@@ -1,6 +1,6 @@
 //! `forget` — delete a memory entry by name.
 
-use super::{Memory, MemoryHook};
+use super::{Memory, MemoryHook, Staged};
 use memory::Op;
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -13,6 +13,10 @@ pub struct Forget {
     pub name: String,
 }
 
+/// Edit distance, after normalization, within which a missed name gets
+/// a suggestion. Never acted on — deleting a guess is not recoverable.
+const FUZZY_DISTANCE: usize = 2;
+
 impl Memory {
     pub fn forget(&self, name: &str) -> String {
         let mut store = self.store_write();
@@ -20,15 +24,22 @@ impl Memory {
             name: name.to_owned(),
         }) {
             Ok(_) => format!("forgot: {name}"),
-            Err(_) => format!("no entry named: {name}"),
+            Err(_) => match store.get_fuzzy(name, FUZZY_DISTANCE) {
+                Some(near) => format!("no entry named: {name} (did you mean: {}?)", near.name),
+                None => format!("no entry named: {name}"),
+            },
         }
     }
 }
 
 impl MemoryHook {
-    pub(super) async fn handle_forget(&self, call: ToolDispatch) -> Result<String, String> {
+    pub(super) async fn handle_forget(
+        &self,
+        call: ToolDispatch,
+        transactional: bool,
+    ) -> Result<String, String> {
         let input: Forget =
             serde_json::from_str(&call.args).map_err(|e| format!("invalid arguments: {e}"))?;
-        Ok(self.memory.forget(&input.name))
+        Ok(self.write(&call, transactional, Staged::Forget { name: input.name }))
     }
 }
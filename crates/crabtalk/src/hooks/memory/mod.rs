@@ -6,13 +6,14 @@
 use anyhow::Result;
 use forget::Forget;
 use memory::Memory as Store;
-use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
 use recall::Recall;
 use remember::Remember;
-use runtime::Hook;
-use std::{path::PathBuf, sync::Arc};
+use runtime::{Hook, SharedMemory};
+use staged::Staged;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 use wcore::{
-    MemoryConfig, ToolDispatch, ToolFuture,
+    AgentConfig, AgentEvent, AgentStopReason, MemoryConfig, ToolDispatch, ToolFuture,
     agent::AsTool,
     model::{HistoryEntry, Tool},
     storage::Storage,
@@ -21,11 +22,7 @@ use wcore::{
 mod forget;
 mod recall;
 mod remember;
-
-/// Shared handle to the underlying memory store. Cloneable because the
-/// runtime needs a reference of its own for writing archives during
-/// compaction and reading them back on session resume.
-pub type SharedStore = Arc<RwLock<Store>>;
+mod staged;
 
 pub const DEFAULT_SOUL: &str = include_str!("../../../prompts/crab.md");
 
@@ -35,29 +32,39 @@ pub const DEFAULT_SOUL: &str = include_str!("../../../prompts/crab.md");
 /// per-arg description.
 const MEMORY_PROMPT: &str = include_str!("../../../prompts/memory.md");
 
+/// Memory facade. Every method takes `&self` — the store sits behind a
+/// lock — so one instance is shared across conversations behind an `Arc`.
 pub struct Memory {
-    pub(super) inner: SharedStore,
+    pub(super) inner: SharedMemory,
 }
 
 impl Memory {
-    /// Open (or create) the memory db at `db_path`.
+    /// Open (or create) the memory db at `db_path`, dropping any entries
+    /// that expired while it was closed.
     pub fn open(db_path: PathBuf) -> Result<Self> {
-        let store = Store::open(&db_path)?;
+        let mut store = Store::open(&db_path)?;
+        let purged = store.purge_expired()?;
+        if purged > 0 {
+            tracing::info!(purged, "dropped expired memory entries");
+        }
         Ok(Self {
             inner: Arc::new(RwLock::new(store)),
         })
     }
 
+    /// In-RAM memory. Nothing is persisted.
+    pub fn in_memory() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Store::new())),
+        }
+    }
+
     /// Clone the underlying store handle. Used to hand the same memory
     /// to the runtime for archive writes and resume-time reads.
-    pub fn shared(&self) -> SharedStore {
+    pub fn shared(&self) -> SharedMemory {
         self.inner.clone()
     }
 
-    pub(super) fn store_read(&self) -> RwLockReadGuard<'_, Store> {
-        self.inner.read()
-    }
-
     pub(super) fn store_write(&self) -> RwLockWriteGuard<'_, Store> {
         self.inner.write()
     }
@@ -66,28 +73,45 @@ impl Memory {
 pub struct MemoryHook {
     pub(super) memory: Arc<Memory>,
     storage: Arc<dyn Storage>,
+    /// Writes held back for the running turn, per conversation, when the
+    /// agent's memory is [`MemoryConfig::transactional`].
+    staged: Mutex<HashMap<u64, Vec<Staged>>>,
 }
 
 impl MemoryHook {
     pub fn new(memory: Arc<Memory>, storage: Arc<dyn Storage>) -> Self {
-        Self { memory, storage }
+        Self {
+            memory,
+            storage,
+            staged: Mutex::new(HashMap::new()),
+        }
     }
 
-    /// Effective recall limit for an agent. Reads
+    /// Look up an agent's memory configuration. Reads
     /// [`AgentConfig::hooks::memory`] from storage; falls back to the
     /// [`MemoryConfig`] default when the agent is not yet persisted.
     /// Storage errors are logged loudly so a transient I/O failure
     /// doesn't silently degrade recall behavior.
-    pub fn recall_limit(&self, agent: &str) -> usize {
+    fn memory_config(&self, agent: &str) -> MemoryConfig {
         match self.storage.load_agent_by_name(agent) {
-            Ok(Some(cfg)) => cfg.hooks.memory.recall_limit,
-            Ok(None) => MemoryConfig::default().recall_limit,
+            Ok(Some(cfg)) => cfg.hooks.memory,
+            Ok(None) => MemoryConfig::default(),
             Err(e) => {
                 tracing::error!(%agent, error = %e, "failed to load memory config — falling back to defaults");
-                MemoryConfig::default().recall_limit
+                MemoryConfig::default()
             }
         }
     }
+
+    /// Effective recall limit for an agent.
+    pub fn recall_limit(&self, agent: &str) -> usize {
+        self.memory_config(agent).recall_limit
+    }
+
+    /// Effective `read_only` flag for an agent.
+    pub fn read_only(&self, agent: &str) -> bool {
+        self.memory_config(agent).read_only
+    }
 }
 
 impl Hook for MemoryHook {
@@ -95,6 +119,18 @@ impl Hook for MemoryHook {
         vec![Recall::as_tool(), Remember::as_tool(), Forget::as_tool()]
     }
 
+    fn scoped_tools(&self, config: &AgentConfig) -> (Vec<String>, Option<String>) {
+        if config.hooks.memory.disabled {
+            return (Vec::new(), None);
+        }
+        let mut tools = vec![Recall::as_tool().function.name];
+        if !config.hooks.memory.read_only {
+            tools.push(Remember::as_tool().function.name);
+            tools.push(Forget::as_tool().function.name);
+        }
+        (tools, None)
+    }
+
     fn system_prompt(&self) -> Option<String> {
         Some(format!("\n\n{MEMORY_PROMPT}"))
     }
@@ -102,18 +138,92 @@ impl Hook for MemoryHook {
     fn on_before_run(
         &self,
         agent: &str,
-        _conversation_id: u64,
+        conversation_id: u64,
         history: &[HistoryEntry],
     ) -> Vec<HistoryEntry> {
-        self.memory.before_run(history, self.recall_limit(agent))
+        // Anything still staged belongs to a turn that never finished.
+        if let Some(dropped) = self.staged.lock().remove(&conversation_id) {
+            tracing::info!(
+                conversation_id,
+                writes = dropped.len(),
+                "discarding memory writes from an unfinished turn"
+            );
+        }
+        let config = self.memory_config(agent);
+        if config.disabled {
+            return Vec::new();
+        }
+        self.memory.before_run(
+            history,
+            config.recall_limit,
+            config.recall_window,
+            config.recall_max_tokens,
+        )
+    }
+
+    fn on_event(&self, agent: &str, conversation_id: u64, event: &AgentEvent) {
+        let AgentEvent::Done(response) = event else {
+            return;
+        };
+        let Some(writes) = self.staged.lock().remove(&conversation_id) else {
+            return;
+        };
+        if response.stop_reason != AgentStopReason::TextResponse {
+            tracing::info!(
+                agent,
+                conversation_id,
+                writes = writes.len(),
+                stop_reason = %response.stop_reason,
+                "turn did not complete, discarding staged memory writes"
+            );
+            return;
+        }
+        for write in writes {
+            write.commit(&self.memory);
+        }
+    }
+
+    fn on_close(&self, conversation_id: u64) {
+        if let Some(dropped) = self.staged.lock().remove(&conversation_id) {
+            tracing::info!(
+                conversation_id,
+                writes = dropped.len(),
+                "conversation closed, discarding staged memory writes"
+            );
+        }
     }
 
     fn dispatch<'a>(&'a self, name: &'a str, call: ToolDispatch) -> Option<ToolFuture<'a>> {
+        let config = self.memory_config(&call.agent);
+        if config.disabled {
+            return None;
+        }
         match name {
             "recall" => Some(Box::pin(self.handle_recall(call))),
-            "remember" => Some(Box::pin(self.handle_remember(call))),
-            "forget" => Some(Box::pin(self.handle_forget(call))),
+            "remember" if !config.read_only => {
+                Some(Box::pin(self.handle_remember(call, config.transactional)))
+            }
+            "forget" if !config.read_only => {
+                Some(Box::pin(self.handle_forget(call, config.transactional)))
+            }
             _ => None,
         }
     }
 }
+
+impl MemoryHook {
+    /// Apply a memory write, or stage it for the caller's turn when the
+    /// agent is transactional and the call belongs to a conversation.
+    /// Stateless runs have no turn end to commit at, so they always
+    /// apply immediately.
+    pub(super) fn write(&self, call: &ToolDispatch, transactional: bool, write: Staged) -> String {
+        match call.conversation_id {
+            Some(id) if transactional => {
+                let reply = write.staged_reply();
+                self.staged.lock().entry(id).or_default().push(write);
+                reply
+            }
+            _ => write.commit(&self.memory),
+        }
+    }
+}
@@ -1,13 +1,13 @@
 //! `recall` — BM25 search over memory entries. Also owns the
 //! before-run auto-recall hook, which is just a recall driven by the
-//! last user message.
+//! latest user messages.
 
 use super::{Memory, MemoryHook};
 use schemars::JsonSchema;
 use serde::Deserialize;
 use wcore::{
     ToolDispatch,
-    model::{HistoryEntry, Role},
+    model::{HistoryEntry, Role, TRUNCATION_MARKER},
 };
 
 /// Search your memory entries by keyword. Returns ranked results.
@@ -19,36 +19,76 @@ pub struct Recall {
     pub limit: Option<usize>,
 }
 
+/// Joins recalled entries.
+const SEPARATOR: &str = "\n---\n";
+
 impl Memory {
+    /// Search and count the hits as accessed, so frequently recalled
+    /// entries rank higher next time.
     pub fn recall(&self, query: &str, limit: usize) -> String {
-        let store = self.store_read();
+        self.recall_within(query, limit, 0)
+    }
+
+    /// [`Memory::recall`] held to roughly `max_tokens` (~4 chars per
+    /// token). Hits go in by rank until the budget runs out; the one
+    /// that crosses it is cut at a word boundary, and the rest are left
+    /// out and not counted as accessed. 0 means no budget.
+    pub fn recall_within(&self, query: &str, limit: usize, max_tokens: usize) -> String {
+        let mut store = self.store_write();
         let hits = store.search(query, limit);
-        if hits.is_empty() {
+        let budget = match max_tokens {
+            0 => usize::MAX,
+            n => n.saturating_mul(4),
+        };
+        let mut blocks = Vec::with_capacity(hits.len());
+        let mut ids = Vec::with_capacity(hits.len());
+        let mut used = 0;
+        for hit in &hits {
+            let block = format!("## {}\n{}", hit.entry.name, hit.entry.content);
+            let sep = if blocks.is_empty() {
+                0
+            } else {
+                SEPARATOR.len()
+            };
+            if used + sep + block.len() <= budget {
+                used += sep + block.len();
+                blocks.push(block);
+                ids.push(hit.entry.id);
+                continue;
+            }
+            let room = budget.saturating_sub(used + sep + TRUNCATION_MARKER.len());
+            let header = hit.entry.name.len() + 4;
+            if let Some(cut) = cut_at_word(&block, room).filter(|c| c.len() > header) {
+                blocks.push(format!("{cut}{TRUNCATION_MARKER}"));
+                ids.push(hit.entry.id);
+            }
+            break;
+        }
+        if blocks.is_empty() {
             return "no memories found".to_owned();
         }
-        hits.iter()
-            .map(|h| format!("## {}\n{}", h.entry.name, h.entry.content))
-            .collect::<Vec<_>>()
-            .join("\n---\n")
+        store.record_access(&ids);
+        blocks.join(SEPARATOR)
     }
 
-    /// Auto-recall: BM25-search the last user message, inject any hits
-    /// as a synthetic user turn. Caller passes the effective recall
-    /// limit so per-scope overrides resolved upstream apply.
-    pub fn before_run(&self, history: &[HistoryEntry], limit: usize) -> Vec<HistoryEntry> {
-        let last_user = history
+    /// Auto-recall: BM25-search the last `window` user messages, inject
+    /// any hits as a synthetic user turn. Each message contributes its
+    /// first eight words, newest first. Caller passes the effective
+    /// recall limit, window and token budget so per-scope overrides
+    /// resolved upstream apply.
+    pub fn before_run(
+        &self,
+        history: &[HistoryEntry],
+        limit: usize,
+        window: usize,
+        max_tokens: usize,
+    ) -> Vec<HistoryEntry> {
+        let query: String = history
             .iter()
             .rev()
-            .find(|e| *e.role() == Role::User && !e.text().is_empty());
-
-        let Some(entry) = last_user else {
-            return Vec::new();
-        };
-
-        let query: String = entry
-            .text()
-            .split_whitespace()
-            .take(8)
+            .filter(|e| *e.role() == Role::User && !e.text().is_empty())
+            .take(window.max(1))
+            .flat_map(|e| e.text().split_whitespace().take(8))
             .collect::<Vec<_>>()
             .join(" ");
 
@@ -56,7 +96,7 @@ impl Memory {
             return Vec::new();
         }
 
-        let result = self.recall(&query, limit);
+        let result = self.recall_within(&query, limit, max_tokens);
         if result == "no memories found" {
             return Vec::new();
         }
@@ -74,3 +114,22 @@ impl MemoryHook {
         Ok(self.memory.recall(&input.query, limit))
     }
 }
+
+/// Longest prefix of `text` within `max` bytes that ends on a word
+/// boundary, trailing whitespace dropped. A single word longer than
+/// `max` is cut mid-word on a char boundary.
+fn cut_at_word(text: &str, max: usize) -> Option<&str> {
+    if text.len() <= max {
+        return Some(text);
+    }
+    let end = text.floor_char_boundary(max);
+    let prefix = &text[..end];
+    let cut = if text[end..].starts_with(char::is_whitespace) {
+        prefix
+    } else {
+        prefix
+            .rfind(char::is_whitespace)
+            .map_or(prefix, |i| &prefix[..i])
+    };
+    Some(cut.trim_end()).filter(|c| !c.is_empty())
+}
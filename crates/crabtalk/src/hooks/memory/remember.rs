@@ -1,6 +1,7 @@
-//! `remember` — upsert a memory entry as an `EntryKind::Note`.
+//! `remember` — upsert a memory entry as an `EntryKind::Note`, or append
+//! to it.
 
-use super::{Memory, MemoryHook};
+use super::{Memory, MemoryHook, Staged};
 use memory::{EntryKind, Op};
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -16,39 +17,113 @@ pub struct Remember {
     /// Optional alternative search terms / related note names.
     #[serde(default)]
     pub aliases: Vec<String>,
+    /// Add the content to the end of an existing entry instead of
+    /// replacing it. Aliases are ignored when appending.
+    #[serde(default)]
+    pub append: bool,
+    /// Forget the entry this many seconds from now. Use for short-lived
+    /// facts. Omit to keep the entry's current expiry; new entries are
+    /// kept until forgotten.
+    pub expires_in: Option<u64>,
 }
 
+/// Joins appended content onto an existing entry.
+const APPEND_SEPARATOR: &str = "\n";
+
 impl Memory {
     pub fn remember(&self, name: String, content: String, aliases: Vec<String>) -> String {
         let mut store = self.store_write();
-        let exists = store.get(&name).is_some();
-        let op = if exists {
-            Op::Update {
-                name: name.clone(),
-                content,
-                aliases,
-            }
-        } else {
-            Op::Add {
-                name: name.clone(),
-                content,
-                aliases,
-                kind: EntryKind::Note,
-            }
-        };
+        let op = remember_op(&store, name.clone(), content, aliases);
         match store.apply(op) {
             Ok(_) => format!("remembered: {name}"),
             Err(e) => format!("failed to save entry: {e}"),
         }
     }
+
+    /// Append `content` to the entry, creating it if absent. Repeated
+    /// text is not deduplicated.
+    pub fn append(&self, name: String, content: String) -> String {
+        match self.store_write().apply(append_op(name.clone(), content)) {
+            Ok(_) => format!("appended to: {name}"),
+            Err(e) => format!("failed to save entry: {e}"),
+        }
+    }
+
+    /// Make the entry expire `secs` seconds from now. Expired entries
+    /// drop out of recall at once and are purged the next time the db
+    /// is opened.
+    pub fn expire(&self, name: String, secs: u64) -> String {
+        match self.store_write().apply(expire_op(name.clone(), secs)) {
+            Ok(_) => format!("{name} expires in {secs}s"),
+            Err(e) => format!("failed to set expiry: {e}"),
+        }
+    }
+}
+
+/// Update the entry if it exists, add it as a note otherwise.
+pub(super) fn remember_op(
+    store: &memory::Memory,
+    name: String,
+    content: String,
+    aliases: Vec<String>,
+) -> Op {
+    if store.get(&name).is_some() {
+        Op::Update {
+            name,
+            content,
+            aliases,
+        }
+    } else {
+        Op::Add {
+            name,
+            content,
+            aliases,
+            kind: EntryKind::Note,
+        }
+    }
+}
+
+pub(super) fn append_op(name: String, content: String) -> Op {
+    Op::Append {
+        name,
+        content,
+        separator: APPEND_SEPARATOR.to_owned(),
+    }
+}
+
+pub(super) fn expire_op(name: String, secs: u64) -> Op {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Op::Expire {
+        name,
+        expires_at: Some(now.saturating_add(secs)),
+    }
 }
 
 impl MemoryHook {
-    pub(super) async fn handle_remember(&self, call: ToolDispatch) -> Result<String, String> {
+    pub(super) async fn handle_remember(
+        &self,
+        call: ToolDispatch,
+        transactional: bool,
+    ) -> Result<String, String> {
         let input: Remember =
             serde_json::from_str(&call.args).map_err(|e| format!("invalid arguments: {e}"))?;
-        Ok(self
-            .memory
-            .remember(input.name, input.content, input.aliases))
+        let write = if input.append {
+            Staged::Append {
+                name: input.name,
+                content: input.content,
+                expires_in: input.expires_in,
+            }
+        } else {
+            Staged::Remember {
+                name: input.name,
+                content: input.content,
+                aliases: input.aliases,
+                expires_in: input.expires_in,
+            }
+        };
+        Ok(self.write(&call, transactional, write))
     }
 }
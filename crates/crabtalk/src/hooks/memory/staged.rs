@@ -0,0 +1,79 @@
+//! Memory writes held back until the turn that made them completes. See
+//! [`MemoryConfig::transactional`](wcore::MemoryConfig::transactional).
+
+use super::{
+    Memory,
+    remember::{append_op, expire_op, remember_op},
+};
+
+/// One `remember` or `forget` call, replayed against the store at commit.
+pub(crate) enum Staged {
+    Remember {
+        name: String,
+        content: String,
+        aliases: Vec<String>,
+        expires_in: Option<u64>,
+    },
+    Append {
+        name: String,
+        content: String,
+        expires_in: Option<u64>,
+    },
+    Forget {
+        name: String,
+    },
+}
+
+impl Staged {
+    /// Apply the write, returning the tool's usual reply. An expiry is
+    /// applied in the same batch as the write it belongs to, so the
+    /// entry is never saved without it.
+    pub(crate) fn commit(self, memory: &Memory) -> String {
+        let mut store = memory.store_write();
+        let (op, name, expires_in, saved) = match self {
+            Self::Remember {
+                name,
+                content,
+                aliases,
+                expires_in,
+            } => (
+                remember_op(&store, name.clone(), content, aliases),
+                name,
+                expires_in,
+                "remembered",
+            ),
+            Self::Append {
+                name,
+                content,
+                expires_in,
+            } => (
+                append_op(name.clone(), content),
+                name,
+                expires_in,
+                "appended to",
+            ),
+            Self::Forget { name } => {
+                drop(store);
+                return memory.forget(&name);
+            }
+        };
+        let expiry = expires_in.map(|secs| expire_op(name.clone(), secs));
+        match store.apply_batch(std::iter::once(op).chain(expiry)) {
+            Ok(()) => match expires_in {
+                Some(secs) => format!("{saved}: {name} ({name} expires in {secs}s)"),
+                None => format!("{saved}: {name}"),
+            },
+            Err(e) => format!("failed to save entry: {e}"),
+        }
+    }
+
+    /// Reply for a write that was staged rather than applied.
+    pub(crate) fn staged_reply(&self) -> String {
+        let (verb, name) = match self {
+            Self::Remember { name, .. } => ("remember", name),
+            Self::Append { name, .. } => ("append to", name),
+            Self::Forget { name } => ("forget", name),
+        };
+        format!("will {verb}: {name} once this turn completes")
+    }
+}
@@ -23,6 +23,7 @@ impl OsHook {
         cmd.args(["-c", &input.command])
             .envs(&input.env)
             .current_dir(&cwd)
+            .kill_on_drop(true)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
 
@@ -35,9 +36,24 @@ impl OsHook {
             .to_string()
         })?;
 
-        match tokio::time::timeout(std::time::Duration::from_secs(30), child.wait_with_output())
-            .await
-        {
+        // Dropping the wait future kills the child, so an aborted turn
+        // doesn't leave the command running.
+        let waited = tokio::select! {
+            waited = tokio::time::timeout(
+                std::time::Duration::from_secs(30),
+                child.wait_with_output(),
+            ) => waited,
+            _ = call.cancelled() => {
+                return Err(serde_json::json!({
+                    "stdout": "",
+                    "stderr": "bash cancelled",
+                    "exit_code": -1
+                })
+                .to_string());
+            }
+        };
+
+        match waited {
             Ok(Ok(output)) => {
                 let stdout = String::from_utf8_lossy(&output.stdout);
                 let stderr = String::from_utf8_lossy(&output.stderr);
@@ -1,13 +1,14 @@
 //! Skill tool — as a Hook implementation.
 //!
-//! Provides skill loading/discovery and slash-skill preprocessing.
+//! Provides skill loading/discovery and slash-skill preprocessing. A
+//! slash-invoked skill also grants its `allowed-tools` for that turn.
 
 use crate::daemon::hook::AgentScope;
 use parking_lot::RwLock;
-use runtime::Hook;
+use runtime::{Hook, Preprocessed};
 use serde::Deserialize;
 use std::{collections::BTreeMap, sync::Arc};
-use wcore::{ToolDispatch, ToolFuture, agent::AsTool, storage::Storage};
+use wcore::{ToolDispatch, ToolFuture, agent::AsTool, model::HistoryEntry, storage::Storage};
 
 /// Load a skill by name. Returns its instructions on exact match, or lists matching skills otherwise.
 #[derive(Deserialize, schemars::JsonSchema)]
@@ -30,6 +31,15 @@ impl<S: Storage> SkillHook<S> {
     pub fn new(storage: Arc<S>, scopes: Arc<RwLock<BTreeMap<String, AgentScope>>>) -> Self {
         Self { storage, scopes }
     }
+
+    /// Whether `agent`'s skill whitelist allows `name`. An agent without
+    /// a whitelist may use every skill.
+    fn in_scope(&self, agent: &str, name: &str) -> bool {
+        self.scopes
+            .read()
+            .get(agent)
+            .is_none_or(|scope| scope.skills.is_empty() || scope.skills.iter().any(|s| s == name))
+    }
 }
 
 impl<S: Storage + 'static> Hook for SkillHook<S> {
@@ -54,7 +64,7 @@ impl<S: Storage + 'static> Hook for SkillHook<S> {
         (tools, Some(line))
     }
 
-    fn preprocess(&self, agent: &str, content: &str) -> Option<String> {
+    fn preprocess(&self, agent: &str, content: &str) -> Option<Preprocessed> {
         let trimmed = content.trim_start();
         let rest = trimmed.strip_prefix('/')?;
 
@@ -68,31 +78,56 @@ impl<S: Storage + 'static> Hook for SkillHook<S> {
             return None;
         }
 
-        // Enforce skill scope.
-        {
-            let scopes = self.scopes.read();
-            if let Some(scope) = scopes.get(agent)
-                && !scope.skills.is_empty()
-                && !scope.skills.iter().any(|s| s == name)
-            {
-                return None;
-            }
+        if !self.in_scope(agent, name) {
+            return None;
         }
 
         match self.storage.load_skill(name) {
             Ok(Some(skill)) => {
                 let body = remainder.trim_start();
                 let block = format!("<skill name=\"{name}\">\n{}\n</skill>", skill.body);
-                if body.is_empty() {
-                    Some(block)
+                let content = if body.is_empty() {
+                    block
                 } else {
-                    Some(format!("{body}\n\n{block}"))
-                }
+                    format!("{body}\n\n{block}")
+                };
+                Some(Preprocessed {
+                    content,
+                    skills: vec![name.to_owned()],
+                })
             }
             _ => None,
         }
     }
 
+    /// Tools declared by the skills preprocessing loaded into the latest
+    /// user message. Tags in the message text are never consulted.
+    fn turn_tools(
+        &self,
+        agent: &str,
+        _conversation_id: u64,
+        history: &[HistoryEntry],
+    ) -> Vec<String> {
+        let Some(message) = history
+            .iter()
+            .rev()
+            .find(|e| !e.auto_injected && *e.role() == crabllm_core::Role::User)
+        else {
+            return Vec::new();
+        };
+        let mut tools: Vec<String> = Vec::new();
+        for name in message.skills.iter().filter(|s| self.in_scope(agent, s)) {
+            if let Ok(Some(skill)) = self.storage.load_skill(name) {
+                for tool in skill.allowed_tools {
+                    if !tools.contains(&tool) {
+                        tools.push(tool);
+                    }
+                }
+            }
+        }
+        tools
+    }
+
     fn dispatch<'a>(&'a self, name: &'a str, call: ToolDispatch) -> Option<ToolFuture<'a>> {
         if name != "skill" {
             return None;
@@ -102,15 +137,8 @@ impl<S: Storage + 'static> Hook for SkillHook<S> {
                 serde_json::from_str(&call.args).map_err(|e| format!("invalid arguments: {e}"))?;
             let name = &input.name;
 
-            // Enforce skill scope.
-            {
-                let scopes = self.scopes.read();
-                if let Some(scope) = scopes.get(&call.agent)
-                    && !scope.skills.is_empty()
-                    && !scope.skills.iter().any(|s| s == name)
-                {
-                    return Err(format!("skill not available: {name}"));
-                }
+            if !self.in_scope(&call.agent, name) {
+                return Err(format!("skill not available: {name}"));
             }
 
             if name.contains("..") || name.contains('/') || name.contains('\\') {
@@ -38,7 +38,12 @@ struct SkillFrontmatter {
     compatibility: Option<String>,
     #[serde(default)]
     metadata: BTreeMap<String, String>,
-    #[serde(default, rename = "allowed-tools", deserialize_with = "string_or_vec")]
+    #[serde(
+        default,
+        rename = "allowed-tools",
+        alias = "tools",
+        deserialize_with = "string_or_vec"
+    )]
     allowed_tools: Vec<String>,
 }
 
@@ -2,11 +2,13 @@
 
 pub mod daemon;
 pub mod hooks;
+pub mod openai;
 mod protocol;
 pub mod provider;
 pub mod storage;
 
+pub use daemon::check::{CheckReport, check};
 #[cfg(unix)]
 pub use daemon::setup_socket;
-pub use daemon::{Daemon, DaemonHandle, bridge_shutdown, setup_tcp};
+pub use daemon::{Daemon, DaemonHandle, bridge_shutdown, setup_eviction, setup_openai, setup_tcp};
 pub use wcore::DaemonConfig;
@@ -0,0 +1,224 @@
+//! OpenAI-compatible HTTP endpoint — `POST /v1/chat/completions`.
+//!
+//! `model` names the agent. The request's messages become a throwaway
+//! history run through [`runtime::Runtime::send_stateless`], or
+//! [`runtime::Runtime::stream_stateless`] for `stream: true`: no
+//! conversation is created and nothing is persisted. The agent uses its
+//! own tool set; client-supplied `tools` are not executed.
+
+use crate::daemon::Daemon;
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, Sse},
+    },
+    routing::post,
+};
+use crabllm_core::{
+    ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, Choice, ChunkChoice, Delta,
+    FinishReason, Message, Provider, Role, Usage,
+};
+use futures_util::StreamExt;
+use std::{convert::Infallible, sync::Arc};
+use wcore::{AgentEvent, AgentStopReason, RuntimeError, model::HistoryEntry};
+
+struct AppState<P: Provider + 'static> {
+    daemon: Daemon<P>,
+    api_key: String,
+}
+
+/// Build the router serving the OpenAI-compatible API. An empty
+/// `api_key` accepts every request.
+pub fn router<P: Provider + 'static>(daemon: Daemon<P>, api_key: String) -> Router {
+    Router::new()
+        .route("/v1/chat/completions", post(chat_completions::<P>))
+        .with_state(Arc::new(AppState { daemon, api_key }))
+}
+
+async fn chat_completions<P: Provider + 'static>(
+    State(state): State<Arc<AppState<P>>>,
+    headers: HeaderMap,
+    Json(req): Json<ChatCompletionRequest>,
+) -> Response {
+    if !authorized(&headers, &state.api_key) {
+        return error(StatusCode::UNAUTHORIZED, "invalid api key");
+    }
+
+    let rt: Arc<_> = state.daemon.runtime.read().await.clone();
+    if rt.agent(&req.model).is_none() {
+        return error(
+            StatusCode::NOT_FOUND,
+            &format!("agent '{}' not found", req.model),
+        );
+    }
+
+    let size = req.messages.iter().map(text_len).sum();
+    if let Err(e) = state.daemon.check_content_size(size) {
+        return error(StatusCode::PAYLOAD_TOO_LARGE, &e.to_string());
+    }
+
+    let history: Vec<HistoryEntry> = req
+        .messages
+        .into_iter()
+        .map(HistoryEntry::from_message)
+        .collect();
+    let id = format!("chatcmpl-{}", ulid::Ulid::new());
+    let created = chrono::Utc::now().timestamp() as u64;
+
+    if req.stream == Some(true) {
+        return stream_completion(rt, req.model, history, id, created);
+    }
+
+    let mut history = history;
+    let response = match rt.send_stateless(&req.model, &mut history).await {
+        Ok(response) => response,
+        Err(e) => {
+            let status = e
+                .downcast_ref::<RuntimeError>()
+                .and_then(|e| StatusCode::from_u16(e.code() as u16).ok())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            return error(status, &e.to_string());
+        }
+    };
+    if let AgentStopReason::Error(msg) = &response.stop_reason {
+        return error(StatusCode::BAD_GATEWAY, msg);
+    }
+
+    Json(ChatCompletionResponse {
+        id,
+        object: "chat.completion".to_owned(),
+        created,
+        model: req.model,
+        choices: vec![Choice {
+            index: 0,
+            message: Message::assistant(response.final_response.unwrap_or_default()),
+            finish_reason: Some(FinishReason::Stop),
+            logprobs: None,
+        }],
+        usage: Some(response.usage),
+        system_fingerprint: None,
+    })
+    .into_response()
+}
+
+/// Stream the agent's text and reasoning deltas as they arrive. The
+/// status is already 200 once the first byte is out, so a failed run
+/// ends with an OpenAI-shaped error event instead of a final chunk.
+fn stream_completion<P: Provider + 'static>(
+    rt: Arc<runtime::Runtime<crate::daemon::DaemonCfg<P>>>,
+    agent: String,
+    mut history: Vec<HistoryEntry>,
+    id: String,
+    created: u64,
+) -> Response {
+    let model = agent.clone();
+    let chunk = move |delta: Delta, finish_reason: Option<FinishReason>, usage: Option<Usage>| {
+        let chunk = ChatCompletionChunk {
+            id: id.clone(),
+            object: "chat.completion.chunk".to_owned(),
+            created,
+            model: model.clone(),
+            choices: vec![ChunkChoice {
+                index: 0,
+                delta,
+                finish_reason,
+                logprobs: None,
+            }],
+            usage,
+            system_fingerprint: None,
+        };
+        Event::default().data(serde_json::to_string(&chunk).unwrap_or_default())
+    };
+    let stream = async_stream::stream! {
+        yield chunk(
+            Delta {
+                role: Some(Role::Assistant),
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+        let mut events = std::pin::pin!(rt.stream_stateless(&agent, &mut history));
+        while let Some(event) = events.next().await {
+            match event {
+                AgentEvent::TextDelta(text) => {
+                    yield chunk(
+                        Delta {
+                            content: Some(text),
+                            ..Default::default()
+                        },
+                        None,
+                        None,
+                    );
+                }
+                AgentEvent::ThinkingDelta(text) => {
+                    yield chunk(
+                        Delta {
+                            reasoning_content: Some(text),
+                            ..Default::default()
+                        },
+                        None,
+                        None,
+                    );
+                }
+                AgentEvent::Done(response) => {
+                    if let AgentStopReason::Error(msg) = &response.stop_reason {
+                        yield Event::default().data(error_body(StatusCode::BAD_GATEWAY, msg).to_string());
+                    } else {
+                        yield chunk(Delta::default(), Some(FinishReason::Stop), Some(response.usage));
+                    }
+                    break;
+                }
+                _ => {}
+            }
+        }
+        yield Event::default().data("[DONE]");
+    };
+    Sse::new(stream.map(Ok::<_, Infallible>)).into_response()
+}
+
+/// Bytes of text in a message, across every text part of array-form
+/// content. Image parts do not count, as with protocol sends.
+fn text_len(message: &Message) -> usize {
+    match &message.content {
+        Some(serde_json::Value::String(text)) => text.len(),
+        Some(serde_json::Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| p["text"].as_str())
+            .map(str::len)
+            .sum(),
+        _ => 0,
+    }
+}
+
+fn authorized(headers: &HeaderMap, api_key: &str) -> bool {
+    if api_key.is_empty() {
+        return true;
+    }
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), api_key.as_bytes()))
+}
+
+/// Compare two byte strings in time that depends only on their lengths,
+/// so a wrong key reveals nothing about how much of it matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// OpenAI-shaped error response.
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(error_body(status, message))).into_response()
+}
+
+/// OpenAI-shaped error body.
+fn error_body(status: StatusCode, message: &str) -> serde_json::Value {
+    serde_json::json!({
+        "error": { "message": message, "type": status.canonical_reason().unwrap_or("error") }
+    })
+}
@@ -1,16 +1,132 @@
 //! Daemon-level administrative operations: stats, events, services.
 //! `reload` is defined alongside the daemon builder (crates/crabtalk/src/daemon/builder.rs).
 
-use crate::daemon::Daemon;
 use crate::daemon::event::EventSubscription;
+use crate::daemon::{Daemon, builder::DefaultProvider};
 use anyhow::Result;
 use crabllm_core::Provider;
 use runtime::Env;
+use std::any::Any;
 use std::collections::VecDeque;
 use std::io::{BufRead, BufReader};
 use wcore::protocol::message::*;
 
+/// Page size for `ListMemory` when the client sends 0.
+const DEFAULT_MEMORY_PAGE: usize = 100;
+
+/// Upper bound on a `ListMemory` page, whatever the client asks for.
+const MAX_MEMORY_PAGE: usize = 1000;
+
+/// Hits returned by `RecallMemory` when the client sends 0.
+const DEFAULT_RECALL_LIMIT: usize = 10;
+
 impl<P: Provider + 'static> Daemon<P> {
+    /// One page of memory entries in ID order. The cursor is the last
+    /// entry ID of the previous page.
+    pub(crate) async fn list_memory(&self, req: ListMemoryMsg) -> Result<MemoryList> {
+        let after = match req.cursor.as_deref() {
+            Some(cursor) => Some(
+                cursor
+                    .parse::<memory::EntryId>()
+                    .map_err(|_| anyhow::anyhow!("invalid memory cursor '{cursor}'"))?,
+            ),
+            None => None,
+        };
+        let limit = match req.limit as usize {
+            0 => DEFAULT_MEMORY_PAGE,
+            n => n.min(MAX_MEMORY_PAGE),
+        };
+
+        let rt = self.runtime.read().await.clone();
+        let store = rt.memory().read();
+        let page = store.page(after, limit);
+        let next_cursor = match page.last() {
+            Some(last) if store.page(Some(last.id), 1).len() == 1 => Some(last.id.to_string()),
+            _ => None,
+        };
+        let entries = page.into_iter().map(entry_info).collect();
+        Ok(MemoryList {
+            entries,
+            next_cursor,
+            total: store.len() as u64,
+        })
+    }
+
+    /// Rank entries against the query, best first. Takes a read lock
+    /// only, so access counts are left alone.
+    pub(crate) async fn recall_memory(&self, req: RecallMemoryMsg) -> Result<MemoryRecall> {
+        if req.query.trim().is_empty() {
+            anyhow::bail!("memory recall needs a query");
+        }
+        let limit = match req.limit as usize {
+            0 => DEFAULT_RECALL_LIMIT,
+            n => n.min(MAX_MEMORY_PAGE),
+        };
+
+        let rt = self.runtime.read().await.clone();
+        let store = rt.memory().read();
+        let hits = store
+            .search(&req.query, limit)
+            .into_iter()
+            .filter(|hit| hit.score >= req.threshold)
+            .map(|hit| MemoryHit {
+                entry: Some(entry_info(&hit.entry)),
+                score: hit.score,
+            })
+            .collect();
+        Ok(MemoryRecall { hits })
+    }
+
+    /// Write imported entries. Every entry is validated before the store
+    /// is touched, so a bad kind or name leaves memory unchanged.
+    pub(crate) async fn import_memory(&self, req: ImportMemoryMsg) -> Result<MemoryImported> {
+        let mut ops = Vec::with_capacity(req.entries.len());
+        for e in req.entries {
+            if e.name.trim().is_empty() {
+                anyhow::bail!("memory entry with empty name");
+            }
+            let kind = match e.kind.as_str() {
+                "" | "note" => memory::EntryKind::Note,
+                "archive" => memory::EntryKind::Archive,
+                "topic" => memory::EntryKind::Topic,
+                other => anyhow::bail!("unknown memory kind '{other}' for '{}'", e.name),
+            };
+            ops.push((e, kind));
+        }
+
+        let rt = self.runtime.read().await.clone();
+        let mut store = rt.memory().write();
+        let mut restores = Vec::with_capacity(ops.len());
+        let mut seen = std::collections::HashSet::new();
+        let mut skipped = Vec::new();
+        for (e, kind) in ops {
+            if !req.force && (store.get(&e.name).is_some() || !seen.insert(e.name.clone())) {
+                skipped.push(e.name);
+                continue;
+            }
+            let created_at = match e.created_at {
+                0 => std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+                ts => ts,
+            };
+            restores.push(memory::Op::Restore {
+                name: e.name,
+                content: e.content,
+                aliases: e.aliases,
+                kind,
+                created_at,
+                access_count: e.access_count,
+                expires_at: e.expires_at,
+                pinned: e.pinned,
+            });
+        }
+        let imported = restores.len() as u32;
+        store.apply_batch(restores)?;
+        Ok(MemoryImported { imported, skipped })
+    }
+
     pub(crate) async fn get_stats(&self) -> Result<DaemonStats> {
         let rt = self.runtime.read().await.clone();
         let active = rt.conversation_count().await;
@@ -25,6 +141,23 @@ impl<P: Provider + 'static> Daemon<P> {
         })
     }
 
+    /// The raw JSON of the latest chat call. Only the default provider
+    /// records exchanges, and only with `[llm] debug` set.
+    pub(crate) async fn get_llm_exchange(&self) -> Result<LlmExchange> {
+        let rt = self.runtime.read().await.clone();
+        let provider: &dyn Any = rt.model.provider();
+        let Some(recording) = provider.downcast_ref::<DefaultProvider>() else {
+            anyhow::bail!("this daemon's provider does not record exchanges");
+        };
+        let Some(exchange) = recording.last_exchange() else {
+            anyhow::bail!("no exchange recorded; set `debug = true` under [llm] and retry");
+        };
+        Ok(LlmExchange {
+            request: exchange.request,
+            response: exchange.response,
+        })
+    }
+
     pub(crate) fn subscribe_events(
         &self,
     ) -> impl futures_core::Stream<Item = Result<AgentEventMsg>> + Send {
@@ -165,3 +298,24 @@ impl command::service::Service for CommandService {
         &self.label
     }
 }
+
+fn kind_name(kind: memory::EntryKind) -> &'static str {
+    match kind {
+        memory::EntryKind::Note => "note",
+        memory::EntryKind::Archive => "archive",
+        memory::EntryKind::Topic => "topic",
+    }
+}
+
+fn entry_info(e: &memory::Entry) -> MemoryEntryInfo {
+    MemoryEntryInfo {
+        name: e.name.clone(),
+        content: e.content.clone(),
+        aliases: e.aliases.clone(),
+        kind: kind_name(e.kind).to_owned(),
+        created_at: e.created_at,
+        access_count: e.access_count,
+        expires_at: e.expires_at,
+        pinned: e.pinned,
+    }
+}
@@ -1,5 +1,6 @@
 //! Daemon-level conversation operations: send/stream (need os_hook cwd),
-//! kill (needs os_hook cwd cleanup), and reply_to_ask (needs ask_hook).
+//! kill and idle eviction (need os_hook cwd cleanup), reply_to_ask (needs ask_hook), and
+//! relay (stateless agent-to-agent forwarding).
 //! Pure-runtime ops live on `Runtime<C>` directly.
 
 use crate::daemon::Daemon;
@@ -7,11 +8,23 @@ use anyhow::Result;
 use crabllm_core::Provider;
 use futures_util::{StreamExt, pin_mut};
 use std::sync::Arc;
-use wcore::AgentEvent;
-use wcore::protocol::message::*;
+use wcore::protocol::{MAX_RELAY_HOPS, message::*};
+use wcore::{AgentEvent, RuntimeError, model::HistoryEntry};
 
 impl<P: Provider + 'static> Daemon<P> {
+    /// Refuse `size` bytes of message content, or of turn instructions,
+    /// over `[limits] max_content_bytes`.
+    pub(crate) fn check_content_size(&self, size: usize) -> Result<()> {
+        let limit = self.max_content_bytes;
+        if limit > 0 && size > limit {
+            return Err(RuntimeError::ContentTooLarge { size, limit }.into());
+        }
+        Ok(())
+    }
+
     pub(crate) async fn send(&self, req: SendMsg) -> Result<SendResponse> {
+        self.check_content_size(req.content.len())?;
+        self.check_content_size(req.instructions.as_ref().map_or(0, String::len))?;
         let rt: Arc<_> = self.runtime.read().await.clone();
         let sender = req.sender.as_deref().unwrap_or("");
         let created_by = if sender.is_empty() { "user" } else { sender };
@@ -26,17 +39,57 @@ impl<P: Provider + 'static> Daemon<P> {
                 .await
                 .insert(conversation_id, cwd.clone());
         }
+        let guest = req.guest.as_deref().unwrap_or_default();
+        let _instructions = req
+            .instructions
+            .filter(|s| !s.is_empty() && guest.is_empty())
+            .map(|text| self.hook.set_turn_instructions(conversation_id, text));
         let tool_choice = req
             .tool_choice
             .map(|s| wcore::model::ToolChoice::from(s.as_str()));
         let response = rt
-            .send_to(conversation_id, &req.content, sender, tool_choice)
+            .send_to(
+                conversation_id,
+                &req.content,
+                &req.images,
+                sender,
+                tool_choice,
+                req.prefill.as_deref(),
+            )
             .await?;
         Ok(SendResponse {
             agent: req.agent,
             content: response.final_response.unwrap_or_default(),
             model: response.model,
-            usage: Some(sum_usage(&response.steps)),
+            usage: Some(token_usage(&response.usage)),
+        })
+    }
+
+    /// Run `to_agent` on `from_agent`'s output without touching any
+    /// conversation. The content is framed like a guest message so the
+    /// target knows who is speaking.
+    pub(crate) async fn relay(&self, req: RelayMsg) -> Result<SendResponse> {
+        if req.hops >= MAX_RELAY_HOPS {
+            return Err(RuntimeError::RelayHopsExceeded(req.hops).into());
+        }
+        self.check_content_size(req.content.len())?;
+        let rt: Arc<_> = self.runtime.read().await.clone();
+        let content = if req.from_agent.is_empty() {
+            req.content
+        } else {
+            format!(
+                "<from agent=\"{}\">\n{}\n</from>",
+                escape_attr(&req.from_agent),
+                req.content
+            )
+        };
+        let mut history = vec![HistoryEntry::user(content)];
+        let response = rt.send_stateless(&req.to_agent, &mut history).await?;
+        Ok(SendResponse {
+            agent: req.to_agent,
+            content: response.final_response.unwrap_or_default(),
+            model: response.model,
+            usage: Some(token_usage(&response.usage)),
         })
     }
 
@@ -45,28 +98,39 @@ impl<P: Provider + 'static> Daemon<P> {
         req: StreamMsg,
     ) -> impl futures_core::Stream<Item = Result<StreamEvent>> + Send + 'a {
         let runtime = self.runtime.clone();
+        let hook = self.hook.clone();
         let conversation_cwds = self.os_hook.conversation_cwds().clone();
         let agent = req.agent;
         let content = req.content;
+        let images = req.images;
+        let interactive = req.interactive;
         let sender = req.sender.unwrap_or_default();
         let cwd = req.cwd.map(std::path::PathBuf::from);
         let guest = req.guest.unwrap_or_default();
+        let instructions = req.instructions.filter(|s| !s.is_empty());
         let tool_choice = req
             .tool_choice
             .map(|s| wcore::model::ToolChoice::from(s.as_str()));
+        let too_large = self
+            .check_content_size(content.len())
+            .and_then(|_| self.check_content_size(instructions.as_ref().map_or(0, String::len)));
         async_stream::try_stream! {
+            too_large?;
             let rt: Arc<_> = runtime.read().await.clone();
             let created_by = if sender.is_empty() { "user".into() } else { sender.clone() };
             let conversation_id = rt.get_or_create_conversation(&agent, created_by.as_str()).await?;
             if let Some(ref cwd) = cwd {
                 conversation_cwds.lock().await.insert(conversation_id, cwd.clone());
             }
+            let _instructions = instructions
+                .filter(|_| guest.is_empty())
+                .map(|text| hook.set_turn_instructions(conversation_id, text));
 
             let responding_agent = if guest.is_empty() { agent.clone() } else { guest.clone() };
             yield StreamEvent { event: Some(stream_event::Event::Start(StreamStart { agent: responding_agent.clone() })) };
 
             let stream: std::pin::Pin<Box<dyn futures_core::Stream<Item = wcore::AgentEvent> + Send + '_>> = if guest.is_empty() {
-                Box::pin(rt.stream_to(conversation_id, &content, &sender, tool_choice))
+                Box::pin(rt.stream_to(conversation_id, &content, &images, &sender, tool_choice))
             } else {
                 Box::pin(rt.guest_stream_to(conversation_id, &content, &sender, &guest))
             };
@@ -96,6 +160,7 @@ impl<P: Provider + 'static> Daemon<P> {
                             calls: calls.into_iter().map(|c| ToolCallInfo {
                                 name: c.function.name.to_string(),
                                 arguments: String::new(),
+                                call_id: c.id,
                             }).collect(),
                         })) };
                     }
@@ -119,16 +184,31 @@ impl<P: Provider + 'static> Daemon<P> {
                             })
                             .collect();
 
+                        let confirms: Vec<ConfirmToolEvent> = calls
+                            .iter()
+                            .filter(|c| interactive && rt.env.hook.needs_confirmation(&c.function.name))
+                            .map(|c| ConfirmToolEvent {
+                                call_id: c.id.clone(),
+                                name: c.function.name.to_string(),
+                                arguments: c.function.arguments.clone(),
+                            })
+                            .collect();
+
                         yield StreamEvent { event: Some(stream_event::Event::ToolStart(ToolStartEvent {
                             calls: calls.into_iter().map(|c| ToolCallInfo {
                                 name: c.function.name.to_string(),
                                 arguments: c.function.arguments,
+                                call_id: c.id,
                             }).collect(),
                         })) };
 
                         if !ask_questions.is_empty() {
                             yield StreamEvent { event: Some(stream_event::Event::AskUser(AskUserEvent { questions: ask_questions })) };
                         }
+                        for confirm in confirms {
+                            rt.env.hook.expect_confirmation(conversation_id, &confirm.call_id);
+                            yield StreamEvent { event: Some(stream_event::Event::ConfirmTool(confirm)) };
+                        }
                     }
                     AgentEvent::ToolResult { call_id, output, duration_ms } => {
                         let is_error = output.is_err();
@@ -148,16 +228,18 @@ impl<P: Provider + 'static> Daemon<P> {
                         } else {
                             String::new()
                         };
+                        rt.env.hook.clear_confirmations(conversation_id);
                         yield StreamEvent { event: Some(stream_event::Event::End(StreamEnd {
                             agent: responding_agent.clone(),
                             error,
                             model: resp.model,
-                            usage: Some(sum_usage(&resp.steps)),
+                            usage: Some(token_usage(&resp.usage)),
                         })) };
                         return;
                     }
                 }
             }
+            rt.env.hook.clear_confirmations(conversation_id);
             yield StreamEvent { event: Some(stream_event::Event::End(StreamEnd {
                 agent: responding_agent.clone(),
                 error: String::new(),
@@ -172,12 +254,34 @@ impl<P: Provider + 'static> Daemon<P> {
         let Some(conversation_id) = rt.conversation_id(agent, sender).await else {
             return Ok(false);
         };
+        Ok(self.close_conversation(&rt, conversation_id).await)
+    }
+
+    /// Drop a conversation from the runtime along with the daemon-side
+    /// state keyed by its id.
+    async fn close_conversation(
+        &self,
+        rt: &runtime::Runtime<crate::daemon::DaemonCfg<P>>,
+        conversation_id: u64,
+    ) -> bool {
         self.os_hook
             .conversation_cwds()
             .lock()
             .await
             .remove(&conversation_id);
-        Ok(rt.close(conversation_id).await)
+        rt.close(conversation_id).await
+    }
+
+    /// Close every conversation idle for at least `ttl` whose agent is
+    /// not pinned. Returns how many were evicted.
+    pub(crate) async fn evict_idle(&self, ttl: std::time::Duration, pinned: &[String]) -> usize {
+        let rt = self.runtime.read().await.clone();
+        let evicted = rt.evict_idle(ttl, pinned).await;
+        for &id in &evicted {
+            self.os_hook.conversation_cwds().lock().await.remove(&id);
+            tracing::info!(conversation_id = id, "evicted idle conversation");
+        }
+        evicted.len()
     }
 
     pub(crate) async fn reply_to_ask(
@@ -213,46 +317,51 @@ impl<P: Provider + 'static> Daemon<P> {
         }
         anyhow::bail!("no pending ask_user for agent='{agent}' sender='{sender}'")
     }
-}
-
-pub(super) fn sum_usage(steps: &[wcore::AgentStep]) -> TokenUsage {
-    let mut prompt = 0u32;
-    let mut completion = 0u32;
-    let mut total = 0u32;
-    let mut cache_hit = 0u32;
-    let mut cache_miss = 0u32;
-    let mut reasoning = 0u32;
-    let mut has_cache_hit = false;
-    let mut has_cache_miss = false;
-    let mut has_reasoning = false;
 
-    for step in steps {
-        let u = &step.usage;
-        prompt += u.prompt_tokens;
-        completion += u.completion_tokens;
-        total += u.total_tokens;
-        if let Some(v) = u.prompt_cache_hit_tokens {
-            cache_hit += v;
-            has_cache_hit = true;
-        }
-        if let Some(v) = u.prompt_cache_miss_tokens {
-            cache_miss += v;
-            has_cache_miss = true;
-        }
-        if let Some(ref d) = u.completion_tokens_details
-            && let Some(v) = d.reasoning_tokens
-        {
-            reasoning += v;
-            has_reasoning = true;
+    /// Approve or deny a tool call waiting on confirmation in the
+    /// `(agent, sender)` conversation.
+    pub(crate) async fn tool_decision(
+        &self,
+        agent: &str,
+        sender: &str,
+        call_id: &str,
+        approve: bool,
+    ) -> Result<()> {
+        let rt = self.runtime.read().await.clone();
+        let conversation_id = rt.require_conversation_id(agent, sender).await?;
+        if self.hook.decide(conversation_id, call_id, approve) {
+            return Ok(());
         }
+        Err(RuntimeError::NoPendingToolCall(call_id.to_owned()).into())
     }
+}
 
+pub(super) fn token_usage(u: &crabllm_core::Usage) -> TokenUsage {
     TokenUsage {
-        prompt_tokens: prompt,
-        completion_tokens: completion,
-        total_tokens: total,
-        cache_hit_tokens: has_cache_hit.then_some(cache_hit),
-        cache_miss_tokens: has_cache_miss.then_some(cache_miss),
-        reasoning_tokens: has_reasoning.then_some(reasoning),
+        prompt_tokens: u.prompt_tokens,
+        completion_tokens: u.completion_tokens,
+        total_tokens: u.total_tokens,
+        cache_hit_tokens: u.prompt_cache_hit_tokens,
+        cache_miss_tokens: u.prompt_cache_miss_tokens,
+        reasoning_tokens: u
+            .completion_tokens_details
+            .as_ref()
+            .and_then(|d| d.reasoning_tokens),
+    }
+}
+
+/// Escape `value` for a double-quoted tag attribute, so a crafted name
+/// cannot close the tag and speak for the sender.
+fn escape_attr(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            c => out.push(c),
+        }
     }
+    out
 }
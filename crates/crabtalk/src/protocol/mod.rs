@@ -40,9 +40,16 @@ impl<P: Provider + 'static> Server for Daemon<P> {
         self.stream(req)
     }
 
+    async fn relay(&self, req: RelayMsg) -> Result<SendResponse> {
+        self.relay(req).await
+    }
+
     async fn compact_conversation(&self, agent: String, sender: String) -> Result<String> {
         let rt = self.runtime.read().await.clone();
-        rt.compact_conversation(&agent, &sender).await
+        Ok(rt
+            .compact_conversation(&agent, &sender)
+            .await?
+            .unwrap_or_default())
     }
 
     async fn ping(&self) -> Result<()> {
@@ -70,6 +77,10 @@ impl<P: Provider + 'static> Server for Daemon<P> {
         self.get_stats().await
     }
 
+    async fn get_llm_exchange(&self) -> Result<LlmExchange> {
+        self.get_llm_exchange().await
+    }
+
     async fn subscribe_event(&self, req: SubscribeEventMsg) -> Result<SubscriptionInfo> {
         self.subscribe_event(req).await
     }
@@ -91,7 +102,13 @@ impl<P: Provider + 'static> Server for Daemon<P> {
         self.reply_to_ask(&agent, &sender, content).await
     }
 
+    async fn tool_decision(&self, req: ToolDecisionMsg) -> Result<()> {
+        self.tool_decision(&req.agent, &req.sender, &req.call_id, req.approve)
+            .await
+    }
+
     async fn steer_session(&self, req: SteerSessionMsg) -> Result<()> {
+        self.check_content_size(req.content.len())?;
         let rt = self.runtime.read().await.clone();
         let sender = if req.sender.is_empty() {
             "user".to_owned()
@@ -102,6 +119,16 @@ impl<P: Provider + 'static> Server for Daemon<P> {
             .await
     }
 
+    async fn cancel_turn(&self, req: CancelMsg) -> Result<()> {
+        let rt = self.runtime.read().await.clone();
+        let sender = if req.sender.is_empty() {
+            "user".to_owned()
+        } else {
+            req.sender
+        };
+        rt.cancel_conversation(&req.agent, &sender).await
+    }
+
     async fn list_agents(&self) -> Result<Vec<AgentInfo>> {
         let rt = self.runtime.read().await.clone();
         Ok(rt.agents().iter().map(AgentInfo::from).collect())
@@ -204,6 +231,18 @@ impl<P: Provider + 'static> Server for Daemon<P> {
         Ok(self.list_skills())
     }
 
+    async fn list_memory(&self, req: ListMemoryMsg) -> Result<MemoryList> {
+        self.list_memory(req).await
+    }
+
+    async fn import_memory(&self, req: ImportMemoryMsg) -> Result<MemoryImported> {
+        self.import_memory(req).await
+    }
+
+    async fn recall_memory(&self, req: RecallMemoryMsg) -> Result<MemoryRecall> {
+        self.recall_memory(req).await
+    }
+
     async fn list_models(&self) -> Result<Vec<ModelInfo>> {
         let rt = self.runtime.read().await.clone();
         Ok(rt.list_models())
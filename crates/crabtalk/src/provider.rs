@@ -1,5 +1,9 @@
 //! `Retrying<P>` — a `Provider` wrapper that adds exponential-backoff retry
-//! and per-call timeout on top of any inner provider.
+//! and per-call timeout on top of any inner provider. `KeyPool<P>` spreads
+//! calls across several keys for the same endpoint; it sits under
+//! `Retrying` so a rate-limited attempt is retried on the next key.
+//! `Recording<P>` sits on top of both and, when `[llm] debug` is set,
+//! keeps the wire JSON of the most recent exchange for inspection.
 //!
 //! This restores the retry/timeout semantics that lived in the old
 //! `crates/model::Provider` wrapper before the trait migration. It is a
@@ -7,79 +11,129 @@
 //! not retry, since not every consumer (e.g. an in-process MLX provider)
 //! wants the same retry policy.
 //!
-//! Per-provider retry config (`max_retries` / `timeout` on individual
-//! `ProviderDef` entries) is not threaded through yet; the wrapper applies
-//! a single set of defaults to every dispatch. Restoring per-provider
-//! config is a follow-up — see TODO below.
+//! There is one endpoint, so there is one retry policy: `[llm]
+//! max_retries` sets the attempt cap for every dispatch.
 
 use crabllm_core::{
     AudioSpeechRequest, BoxStream, ChatCompletionChunk, ChatCompletionRequest,
     ChatCompletionResponse, EmbeddingRequest, EmbeddingResponse, Error, ImageRequest,
     MultipartField, Provider,
 };
+use futures_util::StreamExt;
+use parking_lot::Mutex;
 use rand::Rng;
-use std::time::Duration;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
-/// Default values matching the old `crates/model::Provider` defaults.
 const DEFAULT_MAX_RETRIES: u32 = 2;
-const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Bound on a whole chat call, or on opening a stream.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+/// Longest gap between chunks before an open stream is abandoned.
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
 const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// How long a key sits out after a 429.
+const DEFAULT_KEY_COOLDOWN: Duration = Duration::from_secs(60);
 
 /// A `Provider` wrapper that retries transient failures with exponential
 /// backoff and full jitter, and bounds each attempt with a per-call timeout.
 ///
-/// **Scope:** the retry policy applies to `chat_completion` only. Streaming
-/// (`chat_completion_stream`) skips retry — the connection is already
-/// established and clients consuming chunk-by-chunk handle their own
-/// resumption — but still bounds the connection-establishment phase with
-/// the same timeout. The non-chat methods (`embedding`, `image_generation`,
-/// `audio_speech`, `audio_transcription`) are bare pass-throughs without
-/// retry or timeout, because the daemon's current protocol doesn't expose
-/// these endpoints. If a future daemon feature needs them, extend this
-/// wrapper's scope at that point.
+/// Only transient errors (429, 5xx, timeouts, connection failures) are
+/// retried; other 4xx such as 400, 401 and 403 fail on the first attempt.
+///
+/// **Scope:** the retry policy applies to `chat_completion` and to opening
+/// a `chat_completion_stream`. A stream that failed to open has yielded
+/// nothing, so retrying it loses nothing; once a stream is open, errors
+/// mid-stream are passed through, since chunks already consumed can't be
+/// replayed. The timeout bounds stream opening; an open stream is instead
+/// bounded by an idle timeout between chunks. The non-chat methods
+/// (`embedding`, `image_generation`, `audio_speech`, `audio_transcription`)
+/// are bare pass-throughs without retry or timeout, because the daemon's
+/// current protocol doesn't expose these endpoints. If a future daemon
+/// feature needs them, extend this wrapper's scope at that point.
 #[derive(Debug, Clone)]
 pub struct Retrying<P: Provider> {
     inner: P,
     max_retries: u32,
     timeout: Duration,
+    idle_timeout: Duration,
+    endpoint: String,
 }
 
 impl<P: Provider> Retrying<P> {
-    /// Wrap a provider with the default retry policy
-    /// (2 retries, 30s timeout, 100ms initial backoff).
+    /// Wrap a provider with the default retry policy (2 retries, 120s
+    /// timeout, 60s stream idle timeout, 100ms initial backoff).
     pub fn new(inner: P) -> Self {
         Self {
             inner,
             max_retries: DEFAULT_MAX_RETRIES,
             timeout: DEFAULT_TIMEOUT,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            endpoint: String::new(),
         }
     }
 
-    // TODO: per-provider retry config. The old crates/model wrapper read
-    // `ProviderDef.max_retries` and `ProviderDef.timeout` per provider.
-    // Restoring that requires either a config-aware wrapper at the
-    // Deployment level (in crabllm) or a per-call config struct.
-}
+    /// Bound each chat call and stream opening; zero disables it.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
 
-impl<P: Provider> Provider for Retrying<P> {
-    async fn chat_completion(
-        &self,
-        request: &ChatCompletionRequest,
-    ) -> Result<ChatCompletionResponse, Error> {
+    /// Bound the gap between stream chunks; zero disables it.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Name the endpoint in timeout errors.
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    fn timed_out(&self, what: &str, after: Duration) -> Error {
+        let endpoint = if self.endpoint.is_empty() {
+            "llm endpoint"
+        } else {
+            &self.endpoint
+        };
+        Error::Internal(format!(
+            "{endpoint} timed out: {what} for {}s",
+            after.as_secs()
+        ))
+    }
+
+    /// Retry a transient failure up to `max_retries` times; 0 disables
+    /// retry.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Run `call` under the timeout, retrying transient errors with
+    /// jittered exponential backoff.
+    async fn retry<T, F, Fut>(&self, mut call: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, Error>>,
+    {
         let mut backoff = INITIAL_BACKOFF;
         let mut last_err = None;
         for _ in 0..=self.max_retries {
             let result = if self.timeout.is_zero() {
-                self.inner.chat_completion(request).await
+                call().await
             } else {
-                match tokio::time::timeout(self.timeout, self.inner.chat_completion(request)).await
-                {
+                match tokio::time::timeout(self.timeout, call()).await {
                     Ok(r) => r,
-                    Err(_) => Err(Error::Timeout),
+                    Err(_) => Err(self.timed_out("no response", self.timeout)),
                 }
             };
             match result {
-                Ok(resp) => return Ok(resp),
+                Ok(value) => return Ok(value),
                 Err(e) if e.is_transient() => {
                     last_err = Some(e);
                     tokio::time::sleep(jittered(backoff)).await;
@@ -90,25 +144,311 @@ impl<P: Provider> Provider for Retrying<P> {
         }
         Err(last_err.expect("retry loop exited without producing an error"))
     }
+}
+
+impl<P: Provider> Provider for Retrying<P> {
+    async fn chat_completion(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, Error> {
+        self.retry(|| self.inner.chat_completion(request)).await
+    }
 
     async fn chat_completion_stream(
         &self,
         request: &ChatCompletionRequest,
     ) -> Result<BoxStream<'static, Result<ChatCompletionChunk, Error>>, Error> {
-        // Streaming does not retry — connection establishment is the only
-        // failure mode we could meaningfully retry, and chunks already
-        // streaming would be lost on retry. Apply the timeout to stream
-        // open only.
-        if self.timeout.is_zero() {
-            self.inner.chat_completion_stream(request).await
-        } else {
-            match tokio::time::timeout(self.timeout, self.inner.chat_completion_stream(request))
-                .await
-            {
-                Ok(r) => r,
-                Err(_) => Err(Error::Timeout),
+        let stream = self
+            .retry(|| self.inner.chat_completion_stream(request))
+            .await?;
+        if self.idle_timeout.is_zero() {
+            return Ok(stream);
+        }
+        let idle = self.idle_timeout;
+        let stalled = self.timed_out("no stream chunk", idle);
+        Ok(Box::pin(async_stream::stream! {
+            let mut stream = stream;
+            loop {
+                match tokio::time::timeout(idle, stream.next()).await {
+                    Ok(Some(item)) => yield item,
+                    Ok(None) => break,
+                    Err(_) => {
+                        yield Err(stalled);
+                        break;
+                    }
+                }
             }
+        }))
+    }
+
+    async fn embedding(&self, request: &EmbeddingRequest) -> Result<EmbeddingResponse, Error> {
+        self.inner.embedding(request).await
+    }
+
+    async fn image_generation(
+        &self,
+        request: &ImageRequest,
+    ) -> Result<(bytes::Bytes, String), Error> {
+        self.inner.image_generation(request).await
+    }
+
+    async fn audio_speech(
+        &self,
+        request: &AudioSpeechRequest,
+    ) -> Result<(bytes::Bytes, String), Error> {
+        self.inner.audio_speech(request).await
+    }
+
+    async fn audio_transcription(
+        &self,
+        model: &str,
+        fields: &[MultipartField],
+    ) -> Result<(bytes::Bytes, String), Error> {
+        self.inner.audio_transcription(model, fields).await
+    }
+}
+
+/// A `Provider` that rotates across one inner provider per API key by
+/// smooth weighted round-robin. A key that answers 429 is benched for a
+/// cooldown; if every key is benched, the one that frees up first is
+/// used anyway rather than failing locally.
+pub struct KeyPool<P: Provider> {
+    keys: Vec<PooledKey<P>>,
+    cooldown: Duration,
+    state: Mutex<Vec<PoolState>>,
+}
+
+struct PooledKey<P> {
+    provider: P,
+    weight: i64,
+}
+
+#[derive(Default, Clone, Copy)]
+struct PoolState {
+    current: i64,
+    benched_until: Option<Instant>,
+}
+
+impl<P: Provider> KeyPool<P> {
+    /// Build a pool from `(provider, weight)` pairs. Zero weights are
+    /// treated as 1. Panics on an empty pool.
+    pub fn new(keys: Vec<(P, u32)>) -> Self {
+        assert!(!keys.is_empty(), "key pool needs at least one key");
+        let state = vec![PoolState::default(); keys.len()];
+        Self {
+            keys: keys
+                .into_iter()
+                .map(|(provider, weight)| PooledKey {
+                    provider,
+                    weight: weight.max(1) as i64,
+                })
+                .collect(),
+            cooldown: DEFAULT_KEY_COOLDOWN,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Override the 429 cooldown (default 60s).
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Pick the next key index.
+    fn pick(&self) -> usize {
+        let now = Instant::now();
+        let mut state = self.state.lock();
+        let available: Vec<usize> = (0..self.keys.len())
+            .filter(|&i| state[i].benched_until.is_none_or(|t| t <= now))
+            .collect();
+        if available.is_empty() {
+            return (0..self.keys.len())
+                .min_by_key(|&i| state[i].benched_until)
+                .expect("pool is non-empty");
+        }
+        let total: i64 = available.iter().map(|&i| self.keys[i].weight).sum();
+        let mut best = available[0];
+        for &i in &available {
+            state[i].current += self.keys[i].weight;
+            if state[i].current > state[best].current {
+                best = i;
+            }
+        }
+        state[best].current -= total;
+        best
+    }
+
+    /// Bench the key on a rate-limit error; pass the result through.
+    fn observe<T>(&self, index: usize, result: Result<T, Error>) -> Result<T, Error> {
+        if let Err(Error::Provider { status: 429, .. }) = &result {
+            tracing::warn!(key = index, "llm key rate limited, cooling down");
+            self.state.lock()[index].benched_until = Some(Instant::now() + self.cooldown);
         }
+        result
+    }
+}
+
+impl<P: Provider> Provider for KeyPool<P> {
+    async fn chat_completion(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, Error> {
+        let i = self.pick();
+        let result = self.keys[i].provider.chat_completion(request).await;
+        self.observe(i, result)
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<BoxStream<'static, Result<ChatCompletionChunk, Error>>, Error> {
+        let i = self.pick();
+        let result = self.keys[i].provider.chat_completion_stream(request).await;
+        self.observe(i, result)
+    }
+
+    async fn embedding(&self, request: &EmbeddingRequest) -> Result<EmbeddingResponse, Error> {
+        let i = self.pick();
+        let result = self.keys[i].provider.embedding(request).await;
+        self.observe(i, result)
+    }
+
+    async fn image_generation(
+        &self,
+        request: &ImageRequest,
+    ) -> Result<(bytes::Bytes, String), Error> {
+        let i = self.pick();
+        let result = self.keys[i].provider.image_generation(request).await;
+        self.observe(i, result)
+    }
+
+    async fn audio_speech(
+        &self,
+        request: &AudioSpeechRequest,
+    ) -> Result<(bytes::Bytes, String), Error> {
+        let i = self.pick();
+        let result = self.keys[i].provider.audio_speech(request).await;
+        self.observe(i, result)
+    }
+
+    async fn audio_transcription(
+        &self,
+        model: &str,
+        fields: &[MultipartField],
+    ) -> Result<(bytes::Bytes, String), Error> {
+        let i = self.pick();
+        let result = self.keys[i]
+            .provider
+            .audio_transcription(model, fields)
+            .await;
+        self.observe(i, result)
+    }
+}
+
+/// The raw JSON of one provider call, as captured by [`Recording`].
+#[derive(Debug, Clone, Default)]
+pub struct Exchange {
+    /// Serialized request body.
+    pub request: String,
+    /// Response body, or the concatenated SSE `data:` lines for a stream.
+    /// An error is recorded as its message.
+    pub response: String,
+}
+
+/// A `Provider` wrapper that captures the request and response of the
+/// most recent chat call when enabled. Disabled, it is a bare
+/// pass-through and serializes nothing — prompts and replies can hold
+/// private data, so capture is opt-in.
+///
+/// Only `chat_completion` and `chat_completion_stream` are captured.
+/// A stream's response grows as chunks are consumed; a later call
+/// replaces the capture and detaches the earlier stream from it.
+pub struct Recording<P: Provider> {
+    inner: P,
+    enabled: bool,
+    last: Arc<Mutex<Option<(u64, Exchange)>>>,
+    calls: AtomicU64,
+}
+
+impl<P: Provider> Recording<P> {
+    /// Wrap `inner`; nothing is captured unless `enabled`.
+    pub fn new(inner: P, enabled: bool) -> Self {
+        Self {
+            inner,
+            enabled,
+            last: Arc::new(Mutex::new(None)),
+            calls: Default::default(),
+        }
+    }
+
+    /// The most recent exchange, if capture is enabled and a call was made.
+    pub fn last_exchange(&self) -> Option<Exchange> {
+        self.last.lock().as_ref().map(|(_, ex)| ex.clone())
+    }
+
+    /// Start a capture for `request`, returning its call id.
+    fn begin(&self, request: &ChatCompletionRequest) -> u64 {
+        let id = self.calls.fetch_add(1, Ordering::Relaxed);
+        let exchange = Exchange {
+            request: serde_json::to_string(request).unwrap_or_default(),
+            response: String::new(),
+        };
+        *self.last.lock() = Some((id, exchange));
+        id
+    }
+}
+
+/// Append to the capture for call `id`, unless a later call replaced it.
+fn record(last: &Mutex<Option<(u64, Exchange)>>, id: u64, text: &str) {
+    if let Some((current, exchange)) = last.lock().as_mut()
+        && *current == id
+    {
+        exchange.response.push_str(text);
+    }
+}
+
+impl<P: Provider> Provider for Recording<P> {
+    async fn chat_completion(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, Error> {
+        if !self.enabled {
+            return self.inner.chat_completion(request).await;
+        }
+        let id = self.begin(request);
+        let result = self.inner.chat_completion(request).await;
+        let text = match &result {
+            Ok(resp) => serde_json::to_string(resp).unwrap_or_default(),
+            Err(e) => e.to_string(),
+        };
+        record(&self.last, id, &text);
+        result
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<BoxStream<'static, Result<ChatCompletionChunk, Error>>, Error> {
+        if !self.enabled {
+            return self.inner.chat_completion_stream(request).await;
+        }
+        let id = self.begin(request);
+        let stream = match self.inner.chat_completion_stream(request).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                record(&self.last, id, &e.to_string());
+                return Err(e);
+            }
+        };
+        let last = self.last.clone();
+        Ok(Box::pin(stream.map(move |chunk| {
+            let text = match &chunk {
+                Ok(c) => format!("data: {}\n\n", serde_json::to_string(c).unwrap_or_default()),
+                Err(e) => e.to_string(),
+            };
+            record(&last, id, &text);
+            chunk
+        })))
     }
 
     async fn embedding(&self, request: &EmbeddingRequest) -> Result<EmbeddingResponse, Error> {
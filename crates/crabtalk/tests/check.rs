@@ -0,0 +1,65 @@
+//! Preflight self-check — crabtalk::check reports every probe.
+
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn check_reports_each_failing_probe() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join(wcore::paths::CONFIG_FILE), "").unwrap();
+    let broken = dir.path().join(wcore::paths::SKILLS_DIR).join("broken");
+    std::fs::create_dir_all(&broken).unwrap();
+    std::fs::write(broken.join("SKILL.md"), "no frontmatter here").unwrap();
+
+    let report = crabtalk::check(dir.path()).await;
+    assert!(!report.passed());
+
+    let result = |name: &str| {
+        report
+            .items
+            .iter()
+            .find(|i| i.name == name)
+            .unwrap_or_else(|| panic!("no '{name}' probe in:\n{report}"))
+            .result
+            .clone()
+    };
+    assert!(result("config").is_ok());
+    assert!(result("memory").is_ok());
+    assert!(result("llm").unwrap_err().contains("base_url"));
+    assert!(result("skill:broken").is_err());
+}
+
+#[tokio::test]
+async fn check_leaves_expired_memories_alone() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join(wcore::paths::CONFIG_FILE), "").unwrap();
+    let db = dir.path().join("memory.db");
+    let mut store = memory::Memory::open(&db).unwrap();
+    store
+        .apply(memory::Op::Restore {
+            name: "stale".to_owned(),
+            content: "long gone".to_owned(),
+            aliases: Vec::new(),
+            kind: memory::EntryKind::Note,
+            created_at: 1,
+            access_count: 0,
+            expires_at: Some(1),
+            pinned: false,
+        })
+        .unwrap();
+    let before = std::fs::read(&db).unwrap();
+
+    let report = crabtalk::check(dir.path()).await;
+    assert!(report.items.iter().any(|i| i.name == "memory"));
+    assert_eq!(std::fs::read(&db).unwrap(), before, "check wrote memory.db");
+}
+
+#[tokio::test]
+async fn check_stops_on_unreadable_config() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join(wcore::paths::CONFIG_FILE), "[llm\n").unwrap();
+
+    let report = crabtalk::check(dir.path()).await;
+    assert_eq!(report.items.len(), 1);
+    assert_eq!(report.items[0].name, "config");
+    assert!(!report.passed());
+}
@@ -79,3 +79,152 @@ description = "A helper agent"
 "#;
     DaemonConfig::from_toml(toml).unwrap();
 }
+
+#[test]
+fn openai_defaults() {
+    let config = DaemonConfig::from_toml("").unwrap();
+    assert!(!config.openai.enabled);
+    assert_eq!(config.openai.bind, "127.0.0.1:6688");
+    assert!(config.openai.api_key.is_empty());
+}
+
+#[test]
+fn openai_section_parsed() {
+    let toml = r#"
+[openai]
+enabled = true
+api_key = "secret"
+"#;
+    let config = DaemonConfig::from_toml(toml).unwrap();
+    assert!(config.openai.enabled);
+    assert_eq!(config.openai.bind, "127.0.0.1:6688");
+    assert_eq!(config.openai.api_key, "secret");
+}
+
+#[test]
+fn llm_key_pool_parsed() {
+    let toml = r#"
+[llm]
+base_url = "https://api.openai.com/v1"
+api_key = "sk-single"
+
+[[llm.keys]]
+key = "sk-one"
+weight = 3
+
+[[llm.keys]]
+key = "sk-two"
+"#;
+    let config = DaemonConfig::from_toml(toml).unwrap();
+    let pool = config.llm.key_pool();
+    assert_eq!(pool.len(), 2);
+    assert_eq!(pool[0].key, "sk-one");
+    assert_eq!(pool[0].weight, 3);
+    assert_eq!(pool[1].weight, 1);
+}
+
+#[test]
+fn llm_single_key_is_a_pool_of_one() {
+    let toml = r#"
+[llm]
+api_key = "sk-single"
+"#;
+    let config = DaemonConfig::from_toml(toml).unwrap();
+    let pool = config.llm.key_pool();
+    assert_eq!(pool.len(), 1);
+    assert_eq!(pool[0].key, "sk-single");
+}
+
+#[test]
+fn resolve_env_interpolates_secrets() {
+    // SAFETY: the variable name is unique to this test.
+    unsafe { std::env::set_var("CRABTALK_TEST_LLM_KEY", "sk-from-env") };
+    let toml = r#"
+[llm]
+base_url = "https://api.openai.com/v1"
+api_key = "${CRABTALK_TEST_LLM_KEY}"
+
+[env]
+TOKEN = "prefix-${CRABTALK_TEST_LLM_KEY}"
+"#;
+    let mut config = DaemonConfig::from_toml(toml).unwrap();
+    assert_eq!(config.llm.api_key, "${CRABTALK_TEST_LLM_KEY}");
+    config.resolve_env().unwrap();
+    assert_eq!(config.llm.api_key, "sk-from-env");
+    assert_eq!(config.env["TOKEN"], "prefix-sk-from-env");
+    assert_eq!(config.llm.base_url, "https://api.openai.com/v1");
+}
+
+#[test]
+fn resolve_env_names_missing_var() {
+    let toml = r#"
+[[llm.keys]]
+key = "${CRABTALK_TEST_UNSET_VAR}"
+"#;
+    let mut config = DaemonConfig::from_toml(toml).unwrap();
+    let err = config.resolve_env().unwrap_err().to_string();
+    assert!(err.contains("llm.keys[0].key"), "{err}");
+    assert!(err.contains("CRABTALK_TEST_UNSET_VAR"), "{err}");
+}
+
+#[test]
+fn limits_default_and_override() {
+    let config = DaemonConfig::from_toml("").unwrap();
+    assert_eq!(config.limits.max_content_bytes, 256 * 1024);
+
+    let config = DaemonConfig::from_toml("[limits]\nmax_content_bytes = 0\n").unwrap();
+    assert_eq!(config.limits.max_content_bytes, 0);
+}
+
+#[test]
+fn sessions_default_and_override() {
+    let config = DaemonConfig::from_toml("").unwrap();
+    assert_eq!(config.sessions.idle_timeout, 3600);
+    assert_eq!(config.sessions.sweep_interval, 60);
+    assert!(config.sessions.pinned.is_empty());
+
+    let config =
+        DaemonConfig::from_toml("[sessions]\nidle_timeout = 0\npinned = [\"crab\"]\n").unwrap();
+    assert_eq!(config.sessions.idle_timeout, 0);
+    assert_eq!(config.sessions.pinned, vec!["crab".to_owned()]);
+}
+
+#[test]
+fn tools_max_parallel_default_and_override() {
+    let config = DaemonConfig::from_toml("").unwrap();
+    assert_eq!(config.tools.max_parallel, 8);
+
+    let config = DaemonConfig::from_toml("[tools]\nmax_parallel = 2\n").unwrap();
+    assert_eq!(config.tools.max_parallel, 2);
+    assert!(config.tools.confirm.is_empty());
+}
+
+#[test]
+fn tools_timeouts_default_and_override() {
+    use std::time::Duration;
+
+    let timeouts = DaemonConfig::from_toml("").unwrap().tools.tool_timeouts();
+    assert_eq!(timeouts.for_tool("bash"), None);
+
+    let toml = "[tools]\ntimeout = 30\n\n[tools.timeouts]\nbash = 600\nread = 0\n";
+    let timeouts = DaemonConfig::from_toml(toml).unwrap().tools.tool_timeouts();
+    assert_eq!(timeouts.for_tool("edit"), Some(Duration::from_secs(30)));
+    assert_eq!(timeouts.for_tool("bash"), Some(Duration::from_secs(600)));
+    assert_eq!(timeouts.for_tool("read"), None);
+}
+
+#[test]
+fn tools_max_calls_default_and_override() {
+    assert_eq!(DaemonConfig::from_toml("").unwrap().tools.max_calls, 0);
+    let config = DaemonConfig::from_toml("[tools]\nmax_calls = 40\n").unwrap();
+    assert_eq!(config.tools.max_calls, 40);
+}
+
+#[test]
+fn memory_access_weight_default_and_override() {
+    let config = DaemonConfig::from_toml("").unwrap();
+    assert_eq!(config.hooks.memory.access_weight, None);
+
+    let config = DaemonConfig::from_toml("[hooks.memory]\naccess_weight = 0.0\n").unwrap();
+    assert_eq!(config.hooks.memory.access_weight, Some(0.0));
+}
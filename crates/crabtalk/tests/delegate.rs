@@ -0,0 +1,178 @@
+//! Tests for the delegate hook's nesting guard and task cleanup.
+
+use crabllm_core::{
+    BoxStream, ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, Error, Provider,
+};
+use crabtalk::{Daemon, daemon::builder::BuildProvider, hooks::delegate::DelegateHook};
+use runtime::{Env, Hook};
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use wcore::{
+    AgentConfig, CancellationToken, DaemonConfig, ToolDispatch,
+    model::Model,
+    testing::{provider::TestProvider, test_dispatch},
+};
+
+fn hook() -> DelegateHook<TestProvider> {
+    DelegateHook::new(
+        Arc::new(OnceLock::new()),
+        Default::default(),
+        Default::default(),
+    )
+}
+
+async fn delegate(hook: &DelegateHook<TestProvider>, agent: &str, target: &str) -> String {
+    delegate_from(hook, agent, "", target).await
+}
+
+/// Delegate from the task running under `sender`.
+async fn delegate_from(
+    hook: &DelegateHook<TestProvider>,
+    agent: &str,
+    sender: &str,
+    target: &str,
+) -> String {
+    let args = serde_json::json!({ "tasks": [{ "agent": target, "message": "go" }] });
+    let call = ToolDispatch {
+        sender: sender.to_owned(),
+        ..test_dispatch(agent, args.to_string())
+    };
+    let out = hook
+        .dispatch("delegate", call)
+        .expect("delegate is handled");
+    out.await.unwrap_or_else(|e| e)
+}
+
+#[tokio::test]
+async fn delegating_to_self_passes_the_guard() {
+    let out = delegate(&hook(), "crab", "crab").await;
+    assert!(!out.contains("delegation"), "{out}");
+    assert!(out.contains("runtime not initialized"), "{out}");
+}
+
+#[tokio::test]
+async fn delegating_back_up_the_chain_is_a_cycle() {
+    let hook = hook();
+    hook.track_chain(
+        "delegate:1".to_owned(),
+        vec!["crab".to_owned(), "lobster".to_owned()],
+    );
+    let out = delegate_from(&hook, "lobster", "delegate:1", "crab").await;
+    assert!(
+        out.contains("delegation cycle: crab -> lobster -> crab"),
+        "{out}"
+    );
+}
+
+#[tokio::test]
+async fn nesting_past_the_depth_limit_is_refused() {
+    let hook = hook().with_max_depth(2);
+    let chain = ["crab", "crab", "crab"].map(str::to_owned).to_vec();
+    hook.track_chain("delegate:1".to_owned(), chain);
+    let out = delegate_from(&hook, "crab", "delegate:1", "crab").await;
+    assert!(out.contains("delegation depth limit reached (2)"), "{out}");
+
+    hook.track_chain("delegate:2".to_owned(), vec!["crab".to_owned(); 2]);
+    let out = delegate_from(&hook, "crab", "delegate:2", "crab").await;
+    assert!(out.contains("runtime not initialized"), "{out}");
+}
+
+#[tokio::test]
+async fn delegating_to_another_agent_passes_the_guard() {
+    let out = delegate(&hook(), "crab", "lobster").await;
+    assert!(!out.contains("delegation"), "{out}");
+    assert!(out.contains("runtime not initialized"), "{out}");
+}
+
+/// A model that never answers, so delegated turns stay in flight.
+#[derive(Clone)]
+struct Hang;
+
+impl Provider for Hang {
+    async fn chat_completion(
+        &self,
+        _request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, Error> {
+        std::future::pending().await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        _request: &ChatCompletionRequest,
+    ) -> Result<BoxStream<'static, Result<ChatCompletionChunk, Error>>, Error> {
+        std::future::pending().await
+    }
+}
+
+/// A daemon with a `worker` agent whose turns hang.
+async fn hanging_daemon() -> (Daemon<Hang>, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let build: BuildProvider<Hang> =
+        Arc::new(|_: &DaemonConfig, _: &[String]| Ok(Model::new(Hang)));
+    let daemon = Daemon::build(&DaemonConfig::default(), dir.path(), build)
+        .await
+        .unwrap();
+    let rt = daemon.runtime.read().await.clone();
+    rt.create_agent(AgentConfig::new("worker").model("test-model"), "Work.")
+        .unwrap();
+    (daemon, dir)
+}
+
+fn delegate_call(cancel: Option<CancellationToken>) -> ToolDispatch {
+    let args = serde_json::json!({ "tasks": [{ "agent": "worker", "message": "go" }] });
+    ToolDispatch {
+        cancel,
+        ..test_dispatch("crab", args.to_string())
+    }
+}
+
+/// Wait for the delegated conversations to be closed.
+async fn settled(daemon: &Daemon<Hang>) -> usize {
+    let rt = daemon.runtime.read().await.clone();
+    for _ in 0..50 {
+        if rt.conversation_count().await == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    rt.conversation_count().await
+}
+
+#[tokio::test]
+async fn dropped_delegation_stops_its_tasks() {
+    let (daemon, _dir) = hanging_daemon().await;
+    let rt = daemon.runtime.read().await.clone();
+    let call = rt
+        .env
+        .hook()
+        .dispatch("delegate", delegate_call(None))
+        .unwrap();
+
+    // What a tool timeout does: give up on the call and drop it.
+    let timed_out = tokio::time::timeout(Duration::from_millis(100), call).await;
+    assert!(timed_out.is_err());
+    assert_eq!(settled(&daemon).await, 0, "sub-agent conversation leaked");
+}
+
+#[tokio::test]
+async fn cancelled_delegation_stops_its_tasks() {
+    let (daemon, _dir) = hanging_daemon().await;
+    let rt = daemon.runtime.read().await.clone();
+    let token = CancellationToken::new();
+    let call = rt
+        .env
+        .hook()
+        .dispatch("delegate", delegate_call(Some(token.clone())))
+        .unwrap();
+
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        cancel.cancel();
+    });
+    let out = call.await.unwrap();
+    assert!(out.contains("delegation cancelled"), "{out}");
+    assert_eq!(settled(&daemon).await, 0, "sub-agent conversation leaked");
+}
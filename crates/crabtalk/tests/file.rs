@@ -4,7 +4,7 @@ use crabtalk::{hooks::os::OsHook, storage::FsStorage};
 use runtime::Hook;
 use std::{collections::HashMap, path::PathBuf, sync::Arc};
 use tokio::sync::Mutex;
-use wcore::{ToolDispatch, storage::Storage};
+use wcore::{ToolDispatch, storage::Storage, testing::test_dispatch};
 
 fn hook(cwd: PathBuf) -> OsHook {
     let cwds = Arc::new(Mutex::new(HashMap::new()));
@@ -20,10 +20,8 @@ fn hook(cwd: PathBuf) -> OsHook {
 
 fn dispatch(args: &str) -> ToolDispatch {
     ToolDispatch {
-        args: args.to_owned(),
-        agent: "agent".into(),
-        sender: String::new(),
         conversation_id: Some(1),
+        ..test_dispatch("agent", args)
     }
 }
 
@@ -243,10 +241,8 @@ async fn read_allowed_for_gateway_sender() {
 
     let h = hook(dir.path().to_path_buf());
     let call = ToolDispatch {
-        args: format!(r#"{{"path":"{}"}}"#, file.display()),
-        agent: "agent".into(),
         sender: "gateway:telegram".into(),
-        conversation_id: None,
+        ..test_dispatch("agent", format!(r#"{{"path":"{}"}}"#, file.display()))
     };
     let result = h
         .dispatch("read", call)
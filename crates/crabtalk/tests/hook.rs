@@ -0,0 +1,285 @@
+//! Tests for the composite daemon hook.
+
+use crabtalk::daemon::hook::DaemonHook;
+use runtime::Hook;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use wcore::{
+    AgentConfig, AgentEvent, AgentResponse, ToolDispatch, ToolFuture, agent::AsTool,
+    model::HistoryEntry, testing::test_dispatch,
+};
+
+struct Fragment(&'static str);
+
+impl Hook for Fragment {
+    fn system_prompt(&self) -> Option<String> {
+        Some(self.0.to_owned())
+    }
+}
+
+fn daemon_hook() -> DaemonHook {
+    let mut hook = DaemonHook::new(Default::default());
+    hook.register_hook("memory", Arc::new(Fragment("<memory>")));
+    hook.register_hook("os", Arc::new(Fragment("<os>")));
+    hook.register_hook("skill", Arc::new(Fragment("<skills>")));
+    hook
+}
+
+#[test]
+fn default_assembly_keeps_hook_order() {
+    let hook = daemon_hook();
+    let config = hook.on_build_agent(AgentConfig::new("crab").system_prompt("base"));
+    assert_eq!(config.system_prompt, "base<memory><os><skills>");
+}
+
+#[test]
+fn memory_disabled_agent_gets_no_memory_prompt() {
+    let hook = daemon_hook();
+    let mut config = AgentConfig::new("translator").system_prompt("base");
+    config.hooks.memory.disabled = true;
+    let config = hook.on_build_agent(config);
+    assert_eq!(config.system_prompt, "base<os><skills>");
+}
+
+#[test]
+fn custom_assembler_controls_order() {
+    let hook = daemon_hook();
+    hook.set_system_assembler(Arc::new(|base, _memory, skills| {
+        format!("{base}{skills}\ntoday")
+    }));
+    let config = hook.on_build_agent(AgentConfig::new("crab").system_prompt("base"));
+    assert_eq!(config.system_prompt, "base<os><skills>\ntoday");
+}
+
+/// Grants `bash` whenever the latest message mentions it.
+struct Grant;
+
+impl Hook for Grant {
+    fn turn_tools(&self, _agent: &str, _id: u64, history: &[HistoryEntry]) -> Vec<String> {
+        match history.last() {
+            Some(e) if e.text().contains("bash") => vec!["bash".to_owned()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Whether scope enforcement refuses `tool`. No hook owns the tool, so
+/// an allowed call falls through as `None`.
+fn refused(hook: &DaemonHook, tool: &str, conversation_id: u64) -> bool {
+    let call = ToolDispatch {
+        conversation_id: Some(conversation_id),
+        ..test_dispatch("crab", "{}")
+    };
+    hook.dispatch(tool, call).is_some()
+}
+
+#[test]
+fn turn_grant_lasts_one_run() {
+    let mut hook = DaemonHook::new(Default::default());
+    hook.register_hook("grant", Arc::new(Grant));
+    let mut config = AgentConfig::new("crab");
+    config.tools = vec!["read".to_owned()];
+    hook.on_register_agent("crab", &config);
+    assert!(refused(&hook, "bash", 1));
+
+    let granted = hook.turn_tools("crab", 1, &[HistoryEntry::user("/deploy with bash")]);
+    assert_eq!(granted, vec!["bash"]);
+    assert!(!refused(&hook, "bash", 1));
+    assert!(refused(&hook, "bash", 2), "grants are per conversation");
+
+    hook.on_event("crab", 1, &AgentEvent::Done(AgentResponse::error("end")));
+    assert!(refused(&hook, "bash", 1));
+}
+
+#[test]
+fn turn_instructions_inject_once() {
+    let hook = Arc::new(DaemonHook::new(Default::default()));
+    let _turn = hook.set_turn_instructions(7, "Answer in French.".to_owned());
+
+    let other = hook.on_before_run("crab", 8, &[]);
+    assert!(other.is_empty());
+
+    let injected = hook.on_before_run("crab", 7, &[]);
+    assert_eq!(injected.len(), 1);
+    assert!(injected[0].auto_injected);
+    assert_eq!(
+        injected[0].text(),
+        "<instructions>\nAnswer in French.\n</instructions>"
+    );
+
+    assert!(hook.on_before_run("crab", 7, &[]).is_empty());
+}
+
+#[test]
+fn untaken_turn_instructions_are_withdrawn() {
+    let hook = Arc::new(DaemonHook::new(Default::default()));
+    drop(hook.set_turn_instructions(7, "Answer in French.".to_owned()));
+    assert!(hook.on_before_run("crab", 7, &[]).is_empty());
+
+    let failed = hook.set_turn_instructions(7, "Answer in French.".to_owned());
+    let _next = hook.set_turn_instructions(7, "Answer in German.".to_owned());
+    drop(failed);
+    let injected = hook.on_before_run("crab", 7, &[]);
+    assert_eq!(
+        injected[0].text(),
+        "<instructions>\nAnswer in German.\n</instructions>"
+    );
+}
+
+/// Convert a quantity between units.
+#[derive(schemars::JsonSchema)]
+#[allow(dead_code)]
+struct Convert {
+    value: f64,
+}
+
+/// A pure tool that counts how often it actually runs.
+#[derive(Default)]
+struct Pure(AtomicUsize);
+
+impl Hook for Pure {
+    fn schema(&self) -> Vec<crabllm_core::Tool> {
+        vec![Convert::as_tool()]
+    }
+
+    fn cacheable(&self, name: &str) -> bool {
+        name == "convert"
+    }
+
+    fn dispatch<'a>(&'a self, _name: &'a str, call: ToolDispatch) -> Option<ToolFuture<'a>> {
+        let run = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+        Some(Box::pin(
+            async move { Ok(format!("{} run {run}", call.args)) },
+        ))
+    }
+}
+
+async fn convert(hook: &DaemonHook, args: &str, conversation_id: Option<u64>) -> String {
+    let call = ToolDispatch {
+        conversation_id,
+        ..test_dispatch("crab", args)
+    };
+    hook.dispatch("convert", call).unwrap().await.unwrap()
+}
+
+#[tokio::test]
+async fn cacheable_results_are_reused_per_conversation() {
+    let mut hook = DaemonHook::new(Default::default());
+    hook.register_hook("pure", Arc::new(Pure::default()));
+
+    assert_eq!(convert(&hook, "1", Some(1)).await, "1 run 1");
+    assert_eq!(convert(&hook, "1", Some(1)).await, "1 run 1");
+    assert_eq!(convert(&hook, "2", Some(1)).await, "2 run 2");
+    assert_eq!(convert(&hook, "1", Some(2)).await, "1 run 3");
+    assert_eq!(convert(&hook, "1", None).await, "1 run 4");
+
+    hook.on_close(1);
+    assert_eq!(convert(&hook, "1", Some(1)).await, "1 run 5");
+}
+
+/// An impure tool the config marks cacheable.
+#[derive(Default)]
+struct Listed(AtomicUsize);
+
+impl Hook for Listed {
+    fn schema(&self) -> Vec<crabllm_core::Tool> {
+        vec![Convert::as_tool()]
+    }
+
+    fn dispatch<'a>(&'a self, _name: &'a str, call: ToolDispatch) -> Option<ToolFuture<'a>> {
+        let run = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+        Some(Box::pin(
+            async move { Ok(format!("{} run {run}", call.args)) },
+        ))
+    }
+}
+
+#[tokio::test]
+async fn config_listed_tools_are_cached_up_to_the_limit() {
+    let mut hook = DaemonHook::new(Default::default());
+    hook.register_hook("listed", Arc::new(Listed::default()));
+    assert_eq!(convert(&hook, "1", Some(1)).await, "1 run 1");
+    assert_eq!(convert(&hook, "1", Some(1)).await, "1 run 2");
+
+    hook.set_cache_policy(vec!["convert".to_owned()], 2);
+    assert_eq!(convert(&hook, "1", Some(1)).await, "1 run 3");
+    assert_eq!(convert(&hook, "2", Some(1)).await, "2 run 4");
+    assert_eq!(convert(&hook, "1", Some(1)).await, "1 run 3");
+    assert_eq!(convert(&hook, "3", Some(1)).await, "3 run 5");
+    // "1" was the oldest entry, so the third one pushed it out.
+    assert_eq!(convert(&hook, "1", Some(1)).await, "1 run 6");
+    assert_eq!(convert(&hook, "3", Some(1)).await, "3 run 5");
+
+    hook.set_cache_policy(vec!["convert".to_owned()], 0);
+    assert_eq!(convert(&hook, "3", Some(1)).await, "3 run 7");
+}
+
+fn confirm_call(call_id: &str, conversation_id: Option<u64>) -> ToolDispatch {
+    ToolDispatch {
+        call_id: call_id.to_owned(),
+        conversation_id,
+        ..test_dispatch("crab", "1")
+    }
+}
+
+#[tokio::test]
+async fn confirmed_tools_wait_for_a_decision() {
+    let mut hook = DaemonHook::new(Default::default());
+    hook.register_hook("pure", Arc::new(Pure::default()));
+    hook.set_confirm_tools(vec!["convert".to_owned()], Duration::from_secs(300));
+    assert!(hook.needs_confirmation("convert"));
+
+    hook.expect_confirmation(1, "a");
+    let denied = hook.approve("convert", confirm_call("a", Some(1))).unwrap();
+    assert!(hook.decide(1, "a", false));
+    assert_eq!(
+        denied.await.unwrap_err(),
+        "tool call denied by user: convert"
+    );
+
+    // A decision that beats the approval is kept for it.
+    hook.expect_confirmation(1, "b");
+    assert!(hook.decide(1, "b", true));
+    let approved = hook.approve("convert", confirm_call("b", Some(1))).unwrap();
+    assert_eq!(approved.await, Ok(()));
+
+    assert!(!hook.decide(1, "b", true), "decisions are consumed");
+    let stateless = hook.approve("convert", confirm_call("c", None)).unwrap();
+    assert!(stateless.await.is_err());
+    assert!(hook.approve("other", confirm_call("d", Some(1))).is_none());
+}
+
+#[tokio::test]
+async fn unanswered_confirm_calls_time_out() {
+    let mut hook = DaemonHook::new(Default::default());
+    hook.register_hook("pure", Arc::new(Pure::default()));
+    hook.set_confirm_tools(vec!["convert".to_owned()], Duration::from_millis(10));
+
+    hook.expect_confirmation(1, "a");
+    let call = hook.approve("convert", confirm_call("a", Some(1))).unwrap();
+    assert_eq!(call.await.unwrap_err(), "tool call not confirmed: convert");
+    assert!(
+        !hook.decide(1, "a", true),
+        "the timed-out call is forgotten"
+    );
+}
+
+#[tokio::test]
+async fn unannounced_confirm_calls_are_denied_at_once() {
+    let mut hook = DaemonHook::new(Default::default());
+    hook.register_hook("pure", Arc::new(Pure::default()));
+    hook.set_confirm_tools(vec!["convert".to_owned()], Duration::from_secs(300));
+
+    let call = hook.approve("convert", confirm_call("a", Some(1))).unwrap();
+    let err = call.await.unwrap_err();
+    assert!(err.contains("no client attached"), "{err}");
+
+    hook.expect_confirmation(1, "b");
+    hook.clear_confirmations(1);
+    assert!(!hook.decide(1, "b", true));
+}
@@ -1,7 +1,9 @@
 //! Integration tests for the hook-level memory facade.
 
 use crabtalk::hooks::Memory;
+use std::sync::Arc;
 use tempfile::tempdir;
+use wcore::model::HistoryEntry;
 
 fn test_memory() -> Memory {
     let dir = tempdir().unwrap();
@@ -77,6 +79,16 @@ fn forget_nonexistent_returns_error() {
     assert!(result.contains("no entry named"));
 }
 
+#[test]
+fn forget_near_miss_suggests_without_deleting() {
+    let mem = test_memory();
+    mem.remember("user_name".to_owned(), "Ada".to_owned(), vec![]);
+
+    let result = mem.forget("username");
+    assert!(result.contains("did you mean: user_name?"), "got: {result}");
+    assert!(mem.recall("Ada", 5).contains("user_name"));
+}
+
 #[test]
 fn remember_updates_existing() {
     let mem = test_memory();
@@ -137,3 +149,245 @@ fn aliases_boost_search() {
     let result = mem.recall("ship", 5);
     assert!(result.contains("deploy"));
 }
+
+#[test]
+fn append_keeps_earlier_details() {
+    let mem = test_memory();
+
+    mem.remember(
+        "user-prefs".to_owned(),
+        "Prefers dark mode.".to_owned(),
+        vec![],
+    );
+    mem.append("user-prefs".to_owned(), "Writes Rust.".to_owned());
+
+    let result = mem.recall("dark mode rust", 5);
+    assert!(result.contains("Prefers dark mode."));
+    assert!(result.contains("Writes Rust."));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn remember_from_two_tasks() {
+    let mem = Arc::new(Memory::in_memory());
+    let tasks = ["alpha", "beta"].map(|prefix| {
+        let mem = mem.clone();
+        tokio::spawn(async move {
+            for i in 0..50 {
+                mem.remember(
+                    format!("{prefix}-{i}"),
+                    format!("{prefix} fact {i}"),
+                    vec![],
+                );
+            }
+        })
+    });
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let store = mem.shared();
+    let store = store.read();
+    assert_eq!(store.list().count(), 100);
+    assert!(store.get("alpha-49").is_some());
+    assert!(store.get("beta-0").is_some());
+}
+
+#[tokio::test]
+async fn read_only_agent_cannot_write() {
+    use crabtalk::hooks::memory::MemoryHook;
+    use runtime::Hook;
+    use wcore::{
+        AgentConfig, AgentId,
+        storage::Storage,
+        testing::{InMemoryStorage, test_dispatch},
+    };
+
+    let mut config = AgentConfig::new("reader");
+    config.id = AgentId::new();
+    config.hooks.memory.read_only = true;
+    let storage = Arc::new(InMemoryStorage::new());
+    storage.upsert_agent(&config, "").unwrap();
+    let hook = MemoryHook::new(Arc::new(Memory::in_memory()), storage);
+
+    let call = |agent: &str| test_dispatch(agent, r#"{"name":"note","content":"hi"}"#);
+    assert!(hook.dispatch("remember", call("reader")).is_none());
+    assert!(hook.dispatch("forget", call("reader")).is_none());
+    assert!(hook.dispatch("recall", call("reader")).is_some());
+    assert!(hook.dispatch("remember", call("writer")).is_some());
+
+    let (tools, _) = hook.scoped_tools(&config);
+    assert_eq!(tools, ["recall"]);
+}
+
+#[test]
+fn memory_disabled_agent_skips_recall_and_tools() {
+    use crabtalk::hooks::memory::MemoryHook;
+    use runtime::Hook;
+    use wcore::{
+        AgentConfig, AgentId,
+        storage::Storage,
+        testing::{InMemoryStorage, test_dispatch},
+    };
+
+    let mem = Arc::new(test_memory());
+    mem.remember(
+        "translator-tone".into(),
+        "translate formally".into(),
+        vec![],
+    );
+    let mut config = AgentConfig::new("translator");
+    config.id = AgentId::new();
+    config.hooks.memory.disabled = true;
+    let storage = Arc::new(InMemoryStorage::new());
+    storage.upsert_agent(&config, "").unwrap();
+    let hook = MemoryHook::new(mem, storage);
+
+    let history = [HistoryEntry::user("translate this formally")];
+    assert!(hook.on_before_run("translator", 1, &history).is_empty());
+    assert_eq!(hook.on_before_run("other", 1, &history).len(), 1);
+
+    let call = test_dispatch("translator", r#"{"query":"tone"}"#);
+    assert!(hook.dispatch("recall", call).is_none());
+    assert!(hook.scoped_tools(&config).0.is_empty());
+}
+
+#[test]
+fn auto_recall_window_reaches_earlier_messages() {
+    let mem = test_memory();
+    mem.remember(
+        "deploy-steps".into(),
+        "kubernetes rollout via helm".into(),
+        vec![],
+    );
+    let history = vec![
+        HistoryEntry::user("how do we roll out kubernetes changes?"),
+        HistoryEntry::assistant("Want the steps?", None, None),
+        HistoryEntry::user("yes please"),
+    ];
+
+    assert!(mem.before_run(&history, 5, 1, 0).is_empty());
+    let injected = mem.before_run(&history, 5, 2, 0);
+    assert_eq!(injected.len(), 1);
+    assert!(injected[0].text().contains("deploy-steps"));
+}
+
+#[tokio::test]
+async fn transactional_writes_wait_for_the_turn() {
+    use crabtalk::hooks::memory::MemoryHook;
+    use runtime::Hook;
+    use wcore::{
+        AgentConfig, AgentEvent, AgentId, AgentResponse, AgentStopReason, ToolDispatch,
+        storage::Storage,
+        testing::{InMemoryStorage, test_dispatch},
+    };
+
+    let mut config = AgentConfig::new("careful");
+    config.id = AgentId::new();
+    config.hooks.memory.transactional = true;
+    let storage = Arc::new(InMemoryStorage::new());
+    storage.upsert_agent(&config, "").unwrap();
+    let mem = Arc::new(test_memory());
+    let hook = MemoryHook::new(mem.clone(), storage);
+
+    let remember = |name: &str, conversation_id| ToolDispatch {
+        conversation_id,
+        ..test_dispatch(
+            "careful",
+            format!(r#"{{"name":"{name}","content":"kept only on success"}}"#),
+        )
+    };
+    let done = |stop_reason| {
+        let mut response = AgentResponse::error("");
+        response.stop_reason = stop_reason;
+        AgentEvent::Done(response)
+    };
+    let stored = |name: &str| mem.shared().read().get(name).is_some();
+
+    // Failed turn: the write is dropped.
+    let reply = hook
+        .dispatch("remember", remember("failed", Some(1)))
+        .unwrap();
+    assert!(reply.await.unwrap().contains("once this turn completes"));
+    assert!(!stored("failed"));
+    hook.on_event("careful", 1, &done(AgentStopReason::Error("boom".into())));
+    assert!(!stored("failed"));
+
+    // Completed turn: the write lands.
+    hook.dispatch("remember", remember("kept", Some(1)))
+        .unwrap()
+        .await
+        .unwrap();
+    hook.on_event("careful", 1, &done(AgentStopReason::TextResponse));
+    assert!(stored("kept"));
+
+    // A killed turn never sends Done; the next turn starts clean.
+    hook.dispatch("remember", remember("killed", Some(1)))
+        .unwrap()
+        .await
+        .unwrap();
+    hook.on_before_run("careful", 1, &[HistoryEntry::user("again")]);
+    hook.on_event("careful", 1, &done(AgentStopReason::TextResponse));
+    assert!(!stored("killed"));
+
+    // Closing the conversation drops what its turn staged.
+    hook.dispatch("remember", remember("closed", Some(2)))
+        .unwrap()
+        .await
+        .unwrap();
+    hook.on_close(2);
+    hook.on_event("careful", 2, &done(AgentStopReason::TextResponse));
+    assert!(!stored("closed"));
+
+    // Stateless calls have no turn to wait for.
+    hook.dispatch("remember", remember("stateless", None))
+        .unwrap()
+        .await
+        .unwrap();
+    assert!(stored("stateless"));
+}
+
+#[test]
+fn expired_entry_drops_out_of_recall() {
+    let mem = test_memory();
+    mem.remember(
+        "today".to_owned(),
+        "User is debugging the frame codec today.".to_owned(),
+        vec![],
+    );
+    assert!(mem.recall("frame codec", 5).contains("today"));
+
+    mem.expire("today".to_owned(), 0);
+    assert_eq!(mem.recall("frame codec", 5), "no memories found");
+}
+
+#[test]
+fn auto_recall_stays_within_token_budget() {
+    let mem = test_memory();
+    mem.remember(
+        "deploy-steps".into(),
+        "kubernetes rollout via helm, then watch the canary dashboards for ten minutes".into(),
+        vec![],
+    );
+    mem.remember(
+        "deploy-owner".into(),
+        "kubernetes deploys are owned by the platform team".into(),
+        vec![],
+    );
+    let history = [HistoryEntry::user("kubernetes deploy")];
+
+    let full = mem.before_run(&history, 5, 1, 0);
+    assert!(full[0].text().contains("deploy-steps"));
+    assert!(full[0].text().contains("deploy-owner"));
+
+    // ~30 tokens: the first hit fits, the second is cut on a word.
+    let budgeted = mem.recall_within("kubernetes deploy", 5, 30);
+    assert!(budgeted.len() <= 120, "over budget: {budgeted}");
+    assert!(budgeted.ends_with(wcore::model::TRUNCATION_MARKER));
+    let kept = budgeted.trim_end_matches(wcore::model::TRUNCATION_MARKER);
+    assert!(!kept.ends_with(char::is_whitespace));
+    assert!(
+        full[0]
+            .text()
+            .contains(kept.rsplit("---\n").next().unwrap())
+    );
+}
@@ -0,0 +1,146 @@
+//! Tests for the daemon's memory operations over the protocol.
+
+use crabtalk::{Daemon, daemon::builder::BuildProvider};
+use memory::{EntryKind, Op};
+use std::sync::Arc;
+use wcore::{
+    DaemonConfig,
+    model::Model,
+    protocol::{
+        api::Server,
+        message::{ImportMemoryMsg, ListMemoryMsg, MemoryEntryInfo, RecallMemoryMsg},
+    },
+    testing::provider::TestProvider,
+};
+
+async fn daemon(config: &DaemonConfig) -> (Daemon<TestProvider>, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let build: BuildProvider<TestProvider> = Arc::new(|_: &DaemonConfig, _: &[String]| {
+        Ok(Model::new(TestProvider::with_chunks(Vec::new())))
+    });
+    let daemon = Daemon::build(config, dir.path(), build).await.unwrap();
+    (daemon, dir)
+}
+
+/// Score of the one entry `query` matches, after seeding it with
+/// `access_count` recalls.
+async fn score(daemon: &Daemon<TestProvider>, access_count: u32) -> f64 {
+    let rt = daemon.runtime.read().await.clone();
+    rt.memory()
+        .write()
+        .apply(Op::Restore {
+            name: "editor".to_owned(),
+            content: "prefers helix".to_owned(),
+            aliases: Vec::new(),
+            kind: EntryKind::Note,
+            created_at: 1,
+            access_count,
+            expires_at: None,
+            pinned: false,
+        })
+        .unwrap();
+    let recall = Server::recall_memory(
+        daemon,
+        RecallMemoryMsg {
+            query: "helix".to_owned(),
+            limit: 0,
+            threshold: 0.0,
+        },
+    )
+    .await
+    .unwrap();
+    recall.hits[0].score
+}
+
+#[tokio::test]
+async fn access_weight_comes_from_config() {
+    let (boosted, _dir) = daemon(&DaemonConfig::default()).await;
+    let mut config = DaemonConfig::default();
+    config.hooks.memory.access_weight = Some(0.0);
+    let (flat, _flat_dir) = daemon(&config).await;
+
+    assert_eq!(score(&flat, 0).await, score(&flat, 50).await);
+    assert!(score(&boosted, 50).await > score(&flat, 50).await);
+}
+
+fn entry(name: &str, content: &str, kind: &str) -> MemoryEntryInfo {
+    MemoryEntryInfo {
+        name: name.to_owned(),
+        content: content.to_owned(),
+        kind: kind.to_owned(),
+        created_at: 1,
+        ..Default::default()
+    }
+}
+
+async fn import(
+    daemon: &Daemon<TestProvider>,
+    entries: Vec<MemoryEntryInfo>,
+    force: bool,
+) -> anyhow::Result<wcore::protocol::message::MemoryImported> {
+    Server::import_memory(daemon, ImportMemoryMsg { entries, force }).await
+}
+
+/// Every entry's `(name, content, kind)`, in ID order.
+async fn contents(daemon: &Daemon<TestProvider>) -> Vec<(String, String, String)> {
+    let list = Server::list_memory(
+        daemon,
+        ListMemoryMsg {
+            cursor: None,
+            limit: 0,
+        },
+    )
+    .await
+    .unwrap();
+    list.entries
+        .into_iter()
+        .map(|e| (e.name, e.content, e.kind))
+        .collect()
+}
+
+#[tokio::test]
+async fn import_skips_existing_names_unless_forced() {
+    let (daemon, _dir) = daemon(&DaemonConfig::default()).await;
+    let first = import(&daemon, vec![entry("editor", "helix", "note")], false)
+        .await
+        .unwrap();
+    assert_eq!(first.imported, 1);
+
+    let entries = vec![
+        entry("editor", "vim", "note"),
+        entry("shell", "fish", ""),
+        entry("shell", "zsh", "note"),
+    ];
+    let skipped = import(&daemon, entries.clone(), false).await.unwrap();
+    assert_eq!(skipped.imported, 1);
+    assert_eq!(skipped.skipped, ["editor", "shell"]);
+    assert_eq!(
+        contents(&daemon).await,
+        [
+            ("editor".into(), "helix".into(), "note".into()),
+            ("shell".into(), "fish".into(), "note".into()),
+        ]
+    );
+
+    let forced = import(&daemon, entries, true).await.unwrap();
+    assert_eq!(forced.imported, 3);
+    assert!(forced.skipped.is_empty());
+    let after = contents(&daemon).await;
+    assert!(after.contains(&("editor".into(), "vim".into(), "note".into())));
+    assert!(after.contains(&("shell".into(), "zsh".into(), "note".into())));
+}
+
+#[tokio::test]
+async fn import_with_a_bad_kind_writes_nothing() {
+    let (daemon, _dir) = daemon(&DaemonConfig::default()).await;
+    let entries = vec![
+        entry("editor", "helix", "note"),
+        entry("mood", "sunny", "feeling"),
+    ];
+    let err = import(&daemon, entries, false).await.unwrap_err();
+    assert!(
+        err.to_string().contains("unknown memory kind 'feeling'"),
+        "{err}"
+    );
+    assert!(contents(&daemon).await.is_empty());
+}
@@ -0,0 +1,134 @@
+//! Tests for the OpenAI-compatible endpoint.
+
+use crabllm_core::FinishReason;
+use crabtalk::{Daemon, daemon::builder::BuildProvider};
+use std::sync::Arc;
+use wcore::{
+    AgentConfig, DaemonConfig,
+    model::Model,
+    testing::provider::{TestProvider, finish_chunk, text_chunk},
+};
+
+/// Serve the endpoint for a daemon whose model streams `chunks`, and
+/// return its chat completions URL.
+async fn serve(chunks: Vec<&str>) -> (String, tempfile::TempDir) {
+    serve_with(&DaemonConfig::default(), chunks).await
+}
+
+async fn serve_with(config: &DaemonConfig, chunks: Vec<&str>) -> (String, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut batch: Vec<_> = chunks.into_iter().map(text_chunk).collect();
+    batch.push(finish_chunk(FinishReason::Stop));
+    let provider = TestProvider::with_chunks(vec![batch]);
+    let build: BuildProvider<TestProvider> =
+        Arc::new(move |_: &DaemonConfig, _: &[String]| Ok(Model::new(provider.clone())));
+    let daemon = Daemon::build(config, dir.path(), build).await.unwrap();
+    let rt = daemon.runtime.read().await.clone();
+    rt.create_agent(AgentConfig::new("echo").model("test-model"), "Echo.")
+        .unwrap();
+
+    let app = crabtalk::openai::router(daemon, String::new());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    (format!("http://{addr}/v1/chat/completions"), dir)
+}
+
+fn request(stream: bool) -> serde_json::Value {
+    serde_json::json!({
+        "model": "echo",
+        "messages": [{ "role": "user", "content": "hi" }],
+        "stream": stream,
+    })
+}
+
+#[tokio::test]
+async fn completion_returns_final_text() {
+    let (url, _dir) = serve(vec!["hello ", "there"]).await;
+    let body: serde_json::Value = reqwest::Client::new()
+        .post(url)
+        .json(&request(false))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["object"], "chat.completion", "{body}");
+    assert_eq!(body["choices"][0]["message"]["content"], "hello there");
+    assert_eq!(body["choices"][0]["finish_reason"], "stop");
+}
+
+#[tokio::test]
+async fn stream_sends_each_delta() {
+    let (url, _dir) = serve(vec!["hello ", "there"]).await;
+    let body = reqwest::Client::new()
+        .post(url)
+        .json(&request(true))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let events: Vec<&str> = body
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .collect();
+    assert_eq!(events.last(), Some(&"[DONE]"));
+
+    let chunks: Vec<serde_json::Value> = events[..events.len() - 1]
+        .iter()
+        .map(|e| serde_json::from_str(e).unwrap())
+        .collect();
+    assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+    let deltas: Vec<&str> = chunks
+        .iter()
+        .filter_map(|c| c["choices"][0]["delta"]["content"].as_str())
+        .collect();
+    assert_eq!(deltas, vec!["hello ", "there"]);
+    let last = chunks.last().unwrap();
+    assert_eq!(last["choices"][0]["finish_reason"], "stop");
+}
+
+#[tokio::test]
+async fn oversized_message_is_refused() {
+    let mut config = DaemonConfig::default();
+    config.limits.max_content_bytes = 8;
+    let (url, _dir) = serve_with(&config, vec!["unused"]).await;
+    let parts = serde_json::json!([
+        { "type": "text", "text": "five " },
+        { "type": "text", "text": "more" },
+    ]);
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&serde_json::json!({
+            "model": "echo",
+            "messages": [{ "role": "user", "content": parts }],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn many_small_messages_count_toward_the_limit() {
+    let mut config = DaemonConfig::default();
+    config.limits.max_content_bytes = 8;
+    let (url, _dir) = serve_with(&config, vec!["unused"]).await;
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&serde_json::json!({
+            "model": "echo",
+            "messages": [
+                { "role": "user", "content": "four" },
+                { "role": "assistant", "content": "four" },
+                { "role": "user", "content": "four" },
+            ],
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+}
@@ -0,0 +1,46 @@
+//! Tests for system prompt variables in the daemon.
+
+use crabtalk::{Daemon, daemon::builder::BuildProvider};
+use std::sync::Arc;
+use wcore::{
+    AgentConfig, DaemonConfig, PromptVars,
+    model::Model,
+    testing::provider::{TestProvider, text_chunks},
+};
+
+#[tokio::test]
+async fn config_vars_fill_the_base_prompt_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let skill = dir.path().join(wcore::paths::SKILLS_DIR).join("braces");
+    std::fs::create_dir_all(&skill).unwrap();
+    std::fs::write(
+        skill.join("SKILL.md"),
+        "---\nname: braces\ndescription: write {{literally}}\n---\nbody\n",
+    )
+    .unwrap();
+
+    let provider = TestProvider::with_chunks(vec![text_chunks("ok")]);
+    let model = provider.clone();
+    let build: BuildProvider<TestProvider> =
+        Arc::new(move |_: &DaemonConfig, _: &[String]| Ok(Model::new(model.clone())));
+    let mut config = DaemonConfig::default();
+    config
+        .prompt_vars
+        .insert("team".to_owned(), "platform".to_owned());
+    let daemon = Daemon::build(&config, dir.path(), build).await.unwrap();
+
+    let rt = daemon.runtime.read().await.clone();
+    let mut agent = AgentConfig::new("echo").model("test-model");
+    agent.prompt_vars = PromptVars::Strict;
+    rt.create_agent(agent, "Team {{team}}.").unwrap();
+    let id = rt.get_or_create_conversation("echo", "user").await.unwrap();
+    rt.send_to(id, "hi", &[], "", None, None).await.unwrap();
+
+    let system = provider.requests()[0].messages[0].clone();
+    let system = system.content.as_ref().and_then(|v| v.as_str()).unwrap();
+    assert!(system.starts_with("Team platform."), "{system}");
+    assert!(
+        system.contains("braces: write {{literally}}"),
+        "skill fragment is sent as written: {system}"
+    );
+}
@@ -0,0 +1,260 @@
+//! Tests for the API key pool, retry and recording providers.
+
+use crabllm_core::{
+    BoxStream, ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, Error, Provider,
+};
+use crabtalk::provider::{KeyPool, Recording, Retrying};
+use futures_util::StreamExt;
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU32, Ordering},
+};
+use std::time::Duration;
+
+/// Records which key served each call; optionally answers 429.
+struct Key {
+    name: &'static str,
+    rate_limited: bool,
+    calls: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Provider for Key {
+    async fn chat_completion(
+        &self,
+        _request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, Error> {
+        self.calls.lock().unwrap().push(self.name);
+        if self.rate_limited {
+            return Err(Error::Provider {
+                status: 429,
+                body: "slow down".to_owned(),
+            });
+        }
+        Ok(ChatCompletionResponse::default())
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        _request: &ChatCompletionRequest,
+    ) -> Result<BoxStream<'static, Result<ChatCompletionChunk, Error>>, Error> {
+        let chunks = (0..2).map(|_| Ok(ChatCompletionChunk::default()));
+        Ok(Box::pin(futures_util::stream::iter(chunks)))
+    }
+}
+
+fn request() -> ChatCompletionRequest {
+    serde_json::from_value(serde_json::json!({ "model": "m", "messages": [] })).unwrap()
+}
+
+fn pool(keys: &[(&'static str, u32, bool)]) -> (KeyPool<Key>, Arc<Mutex<Vec<&'static str>>>) {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let keys = keys
+        .iter()
+        .map(|&(name, weight, rate_limited)| {
+            let key = Key {
+                name,
+                rate_limited,
+                calls: calls.clone(),
+            };
+            (key, weight)
+        })
+        .collect();
+    (KeyPool::new(keys), calls)
+}
+
+#[tokio::test]
+async fn spreads_calls_by_weight() {
+    let (pool, calls) = pool(&[("a", 2, false), ("b", 1, false)]);
+    for _ in 0..6 {
+        pool.chat_completion(&request()).await.unwrap();
+    }
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.iter().filter(|k| **k == "a").count(), 4);
+    assert_eq!(calls.iter().filter(|k| **k == "b").count(), 2);
+    // Smooth round-robin interleaves rather than bursting.
+    assert_eq!(&calls[..3], &["a", "b", "a"]);
+}
+
+#[tokio::test]
+async fn rate_limited_key_cools_down() {
+    let (pool, calls) = pool(&[("a", 1, true), ("b", 1, false)]);
+    let err = pool.chat_completion(&request()).await.unwrap_err();
+    assert!(matches!(err, Error::Provider { status: 429, .. }));
+    for _ in 0..3 {
+        pool.chat_completion(&request()).await.unwrap();
+    }
+    assert_eq!(*calls.lock().unwrap(), vec!["a", "b", "b", "b"]);
+}
+
+#[tokio::test]
+async fn all_benched_falls_back_to_a_key() {
+    let (pool, calls) = pool(&[("a", 1, true)]);
+    assert!(pool.chat_completion(&request()).await.is_err());
+    assert!(pool.chat_completion(&request()).await.is_err());
+    assert_eq!(calls.lock().unwrap().len(), 2);
+}
+
+fn key(rate_limited: bool) -> Key {
+    Key {
+        name: "a",
+        rate_limited,
+        calls: Arc::new(Mutex::new(Vec::new())),
+    }
+}
+
+#[tokio::test]
+async fn recording_is_off_by_default() {
+    let provider = Recording::new(key(false), false);
+    provider.chat_completion(&request()).await.unwrap();
+    assert!(provider.last_exchange().is_none());
+}
+
+#[tokio::test]
+async fn recording_captures_last_call() {
+    let provider = Recording::new(key(false), true);
+    provider.chat_completion(&request()).await.unwrap();
+    let exchange = provider.last_exchange().unwrap();
+    let sent: serde_json::Value = serde_json::from_str(&exchange.request).unwrap();
+    assert_eq!(sent["model"], "m");
+    let received: ChatCompletionResponse = serde_json::from_str(&exchange.response).unwrap();
+    assert!(received.choices.is_empty());
+
+    let provider = Recording::new(key(true), true);
+    assert!(provider.chat_completion(&request()).await.is_err());
+    assert!(
+        provider
+            .last_exchange()
+            .unwrap()
+            .response
+            .contains("slow down")
+    );
+}
+
+#[tokio::test]
+async fn recording_concatenates_stream() {
+    let provider = Recording::new(key(false), true);
+    let stream = provider.chat_completion_stream(&request()).await.unwrap();
+    assert_eq!(stream.collect::<Vec<_>>().await.len(), 2);
+    let response = provider.last_exchange().unwrap().response;
+    assert_eq!(response.matches("data: {").count(), 2);
+    assert!(response.ends_with("\n\n"));
+}
+
+/// Fails its first `failures` calls with `status`, then succeeds.
+struct Flaky {
+    status: u16,
+    failures: u32,
+    calls: Arc<AtomicU32>,
+}
+
+impl Flaky {
+    fn new(status: u16, failures: u32) -> Self {
+        Self {
+            status,
+            failures,
+            calls: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    fn fail(&self) -> Option<Error> {
+        let n = self.calls.fetch_add(1, Ordering::SeqCst);
+        (n < self.failures).then(|| Error::Provider {
+            status: self.status,
+            body: "try later".to_owned(),
+        })
+    }
+}
+
+impl Provider for Flaky {
+    async fn chat_completion(
+        &self,
+        _request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, Error> {
+        match self.fail() {
+            Some(e) => Err(e),
+            None => Ok(ChatCompletionResponse::default()),
+        }
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        _request: &ChatCompletionRequest,
+    ) -> Result<BoxStream<'static, Result<ChatCompletionChunk, Error>>, Error> {
+        match self.fail() {
+            Some(e) => Err(e),
+            None => Ok(Box::pin(futures_util::stream::empty())),
+        }
+    }
+}
+
+#[tokio::test]
+async fn retries_transient_errors() {
+    let flaky = Flaky::new(429, 2);
+    let calls = flaky.calls.clone();
+    Retrying::new(flaky)
+        .chat_completion(&request())
+        .await
+        .unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    let provider = Retrying::new(Flaky::new(503, 5)).with_max_retries(1);
+    let err = provider.chat_completion(&request()).await.unwrap_err();
+    assert!(matches!(err, Error::Provider { status: 503, .. }));
+}
+
+#[tokio::test]
+async fn client_errors_fail_fast() {
+    let flaky = Flaky::new(401, 1);
+    let calls = flaky.calls.clone();
+    let err = Retrying::new(flaky)
+        .chat_completion(&request())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Provider { status: 401, .. }));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn retries_stream_open() {
+    let provider = Retrying::new(Flaky::new(502, 1));
+    assert!(provider.chat_completion_stream(&request()).await.is_ok());
+}
+
+/// Opens a stream that yields one chunk and then stalls.
+struct Stalls;
+
+impl Provider for Stalls {
+    async fn chat_completion(
+        &self,
+        _request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, Error> {
+        std::future::pending().await
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        _request: &ChatCompletionRequest,
+    ) -> Result<BoxStream<'static, Result<ChatCompletionChunk, Error>>, Error> {
+        let first = futures_util::stream::once(async { Ok(ChatCompletionChunk::default()) });
+        Ok(Box::pin(first.chain(futures_util::stream::pending())))
+    }
+}
+
+#[tokio::test]
+async fn timeouts_name_the_endpoint() {
+    let provider = Retrying::new(Stalls)
+        .with_max_retries(0)
+        .with_timeout(Duration::from_millis(20))
+        .with_idle_timeout(Duration::from_millis(20))
+        .with_endpoint("http://llm.test/v1");
+
+    let err = provider.chat_completion(&request()).await.unwrap_err();
+    assert!(err.to_string().contains("http://llm.test/v1 timed out"));
+
+    let stream = provider.chat_completion_stream(&request()).await.unwrap();
+    let items = stream.collect::<Vec<_>>().await;
+    assert_eq!(items.len(), 2);
+    assert!(items[0].is_ok());
+    let stalled = items[1].as_ref().unwrap_err().to_string();
+    assert!(stalled.contains("no stream chunk"));
+}
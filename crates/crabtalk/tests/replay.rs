@@ -0,0 +1,324 @@
+//! Provider tests against recorded HTTP exchanges.
+//!
+//! Each test serves one cassette from `tests/cassettes/` on a local port
+//! and points `[llm] base_url` at it, so the real provider stack builds
+//! the request and parses the recorded response. The fixture keeps the
+//! request it received for assertions.
+//!
+//! To re-record a cassette, set `CRABTALK_RECORD_UPSTREAM` to a live
+//! endpoint (e.g. `https://api.openai.com/v1`) and `CRABTALK_RECORD_API_KEY`
+//! to its key, then run the test: the fixture forwards the request and
+//! overwrites the cassette with the upstream response.
+
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+};
+use crabllm_core::{
+    ChatCompletionRequest, Error, FinishReason, FunctionDef, Message, Provider, Tool, ToolType,
+};
+use crabtalk::{
+    Daemon,
+    daemon::builder::{BuildProvider, DefaultProvider, build_providers},
+};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use wcore::{DaemonConfig, model::Model, protocol::api::Server};
+
+const API_KEY: &str = "sk-test";
+
+#[derive(Serialize, Deserialize)]
+struct Cassette {
+    response: Recorded,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Recorded {
+    status: u16,
+    content_type: String,
+    /// JSON bodies are stored as JSON; anything else (SSE) as a string.
+    body: serde_json::Value,
+}
+
+/// What the provider sent.
+#[derive(Default)]
+struct Seen {
+    path: String,
+    authorization: String,
+    body: serde_json::Value,
+}
+
+struct Fixture {
+    cassette: PathBuf,
+    seen: Mutex<Seen>,
+}
+
+/// Serve cassette `name` and return a provider stack for `model` wired to
+/// it, plus the request log.
+async fn replay(name: &str, model: &str) -> (Model<DefaultProvider>, Arc<Fixture>) {
+    let (config, fixture) = serve(name).await;
+    let model = build_providers(&config, &[model.to_owned()]).unwrap();
+    (model, fixture)
+}
+
+/// Serve cassette `name` and return a config pointing `[llm]` at it.
+async fn serve(name: &str) -> (DaemonConfig, Arc<Fixture>) {
+    let fixture = Arc::new(Fixture {
+        cassette: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/cassettes")
+            .join(format!("{name}.json")),
+        seen: Mutex::default(),
+    });
+    let app = Router::new().fallback(handle).with_state(fixture.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let mut config = DaemonConfig::default();
+    config.llm.base_url = format!("http://{addr}/v1");
+    config.llm.api_key = API_KEY.to_owned();
+    (config, fixture)
+}
+
+async fn handle(
+    State(fixture): State<Arc<Fixture>>,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    *fixture.seen.lock().unwrap() = Seen {
+        path: uri.path().to_owned(),
+        authorization: headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_owned(),
+        body: serde_json::from_slice(&body).unwrap_or_default(),
+    };
+
+    let recorded = match std::env::var("CRABTALK_RECORD_UPSTREAM") {
+        Ok(upstream) => record(&fixture, &upstream, &uri, body).await,
+        Err(_) => {
+            let text = std::fs::read_to_string(&fixture.cassette).unwrap();
+            serde_json::from_str::<Cassette>(&text).unwrap().response
+        }
+    };
+    let body = match recorded.body {
+        serde_json::Value::String(text) => text,
+        json => json.to_string(),
+    };
+    (
+        StatusCode::from_u16(recorded.status).unwrap(),
+        [(header::CONTENT_TYPE, recorded.content_type)],
+        body,
+    )
+        .into_response()
+}
+
+/// Forward to the live endpoint and overwrite the cassette.
+async fn record(fixture: &Fixture, upstream: &str, uri: &Uri, body: Bytes) -> Recorded {
+    let path = uri.path().strip_prefix("/v1").unwrap_or(uri.path());
+    let key = std::env::var("CRABTALK_RECORD_API_KEY").unwrap_or_default();
+    let resp = reqwest::Client::new()
+        .post(format!("{}{path}", upstream.trim_end_matches('/')))
+        .bearer_auth(key)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    let status = resp.status().as_u16();
+    let content_type = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/json")
+        .to_owned();
+    let text = resp.text().await.unwrap();
+    let body = if content_type.starts_with("application/json") {
+        serde_json::from_str(&text).unwrap()
+    } else {
+        serde_json::Value::String(text)
+    };
+    let recorded = Recorded {
+        status,
+        content_type,
+        body,
+    };
+    let cassette = Cassette { response: recorded };
+    let json = serde_json::to_string_pretty(&cassette).unwrap();
+    std::fs::write(&fixture.cassette, json + "\n").unwrap();
+    cassette.response
+}
+
+fn request(model: &str, prompt: &str) -> ChatCompletionRequest {
+    serde_json::from_value(serde_json::json!({
+        "model": model,
+        "messages": [Message::user(prompt)],
+    }))
+    .unwrap()
+}
+
+fn bash_tool() -> Tool {
+    Tool {
+        kind: ToolType::Function,
+        function: FunctionDef {
+            name: "bash".to_owned(),
+            description: Some("Run a shell command.".to_owned()),
+            parameters: Some(serde_json::json!({
+                "type": "object",
+                "properties": { "command": { "type": "string" } },
+                "required": ["command"],
+            })),
+        },
+        strict: None,
+    }
+}
+
+#[tokio::test]
+async fn openai_tool_call() {
+    let (model, fixture) = replay("openai/tool_call", "gpt-4o-mini").await;
+    let mut req = request("gpt-4o-mini", "list the files here");
+    req.tools = Some(vec![bash_tool()]);
+
+    let resp = model.provider().chat_completion(&req).await.unwrap();
+    let calls = resp.tool_calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].id, "call_Qm3b8K2nF7xV");
+    assert_eq!(calls[0].function.name, "bash");
+    assert_eq!(calls[0].function.arguments, r#"{"command":"ls -la"}"#);
+    assert_eq!(resp.finish_reason(), Some(&FinishReason::ToolCalls));
+    assert_eq!(resp.usage.unwrap().total_tokens, 99);
+
+    let seen = fixture.seen.lock().unwrap();
+    assert_eq!(seen.path, "/v1/chat/completions");
+    assert_eq!(seen.authorization, format!("Bearer {API_KEY}"));
+    assert_eq!(seen.body["model"], "gpt-4o-mini");
+    assert_eq!(seen.body["messages"][0]["role"], "user");
+    assert_eq!(seen.body["tools"][0]["type"], "function");
+    assert_eq!(seen.body["tools"][0]["function"]["name"], "bash");
+}
+
+#[tokio::test]
+async fn openai_stream() {
+    let (model, fixture) = replay("openai/stream", "gpt-4o-mini").await;
+    let mut req = request("gpt-4o-mini", "say hello");
+    req.stream = Some(true);
+
+    let mut stream = model.provider().chat_completion_stream(&req).await.unwrap();
+    let mut text = String::new();
+    let mut finish = None;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.unwrap();
+        text.push_str(chunk.content().unwrap_or_default());
+        finish = chunk.finish_reason().cloned().or(finish);
+    }
+    assert_eq!(text, "Hello there!");
+    assert_eq!(finish, Some(FinishReason::Stop));
+    assert_eq!(fixture.seen.lock().unwrap().body["stream"], true);
+}
+
+#[tokio::test]
+async fn openai_stream_tool_call() {
+    let (model, _fixture) = replay("openai/stream_tool_call", "gpt-4o-mini").await;
+    let mut req = request("gpt-4o-mini", "list the files here");
+    req.stream = Some(true);
+    req.tools = Some(vec![bash_tool()]);
+
+    let mut stream = model.provider().chat_completion_stream(&req).await.unwrap();
+    let (mut name, mut arguments, mut finish) = (String::new(), String::new(), None);
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.unwrap();
+        for delta in chunk.tool_calls() {
+            let Some(function) = &delta.function else {
+                continue;
+            };
+            name.push_str(function.name.as_deref().unwrap_or_default());
+            arguments.push_str(function.arguments.as_deref().unwrap_or_default());
+        }
+        finish = chunk.finish_reason().cloned().or(finish);
+    }
+    assert_eq!(name, "bash");
+    assert_eq!(arguments, r#"{"command":"ls -la"}"#);
+    assert_eq!(finish, Some(FinishReason::ToolCalls));
+}
+
+#[tokio::test]
+async fn openai_error_surfaces_status() {
+    let (model, _fixture) = replay("openai/error", "gpt-4o-mini").await;
+    let req = request("gpt-4o-mini", "hi");
+
+    let err = model.provider().chat_completion(&req).await.unwrap_err();
+    let Error::Provider { status, body } = err else {
+        panic!("expected a provider error, got {err:?}");
+    };
+    assert_eq!(status, 401);
+    assert!(body.contains("invalid_api_key"), "{body}");
+}
+
+#[tokio::test]
+async fn deepseek_reasoning_stream() {
+    let (model, _fixture) = replay("deepseek/reasoning_stream", "deepseek-reasoner").await;
+    let mut req = request("deepseek-reasoner", "what is 2+2?");
+    req.stream = Some(true);
+
+    let mut stream = model.provider().chat_completion_stream(&req).await.unwrap();
+    let (mut reasoning, mut text, mut usage) = (String::new(), String::new(), None);
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.unwrap();
+        reasoning.push_str(chunk.reasoning_content().unwrap_or_default());
+        text.push_str(chunk.content().unwrap_or_default());
+        usage = chunk.usage.or(usage);
+    }
+    assert_eq!(reasoning, "The user asks for 2+2.");
+    assert_eq!(text, "4");
+    assert_eq!(usage.unwrap().total_tokens, 21);
+}
+
+/// A daemon on the default provider stack, wired to cassette `name`.
+async fn replay_daemon(name: &str, debug: bool) -> (Daemon, tempfile::TempDir) {
+    let (mut config, _fixture) = serve(name).await;
+    config.llm.debug = debug;
+    let dir = tempfile::tempdir().unwrap();
+    let build: BuildProvider<DefaultProvider> = Arc::new(|config: &DaemonConfig, _: &[String]| {
+        build_providers(config, &["gpt-4o-mini".to_owned()])
+    });
+    let daemon = Daemon::build(&config, dir.path(), build).await.unwrap();
+    (daemon, dir)
+}
+
+#[tokio::test]
+async fn debug_daemon_serves_the_last_exchange() {
+    let (daemon, _dir) = replay_daemon("openai/tool_call", true).await;
+    let rt = daemon.runtime.read().await.clone();
+    let req = request("gpt-4o-mini", "list the files here");
+    rt.model.provider().chat_completion(&req).await.unwrap();
+
+    let exchange = Server::get_llm_exchange(&daemon).await.unwrap();
+    assert!(
+        exchange.request.contains("list the files here"),
+        "{exchange:?}"
+    );
+    assert!(
+        exchange.response.contains("call_Qm3b8K2nF7xV"),
+        "{exchange:?}"
+    );
+}
+
+#[tokio::test]
+async fn exchange_needs_llm_debug() {
+    let (daemon, _dir) = replay_daemon("openai/tool_call", false).await;
+    let rt = daemon.runtime.read().await.clone();
+    let req = request("gpt-4o-mini", "list the files here");
+    rt.model.provider().chat_completion(&req).await.unwrap();
+
+    let err = Server::get_llm_exchange(&daemon).await.unwrap_err();
+    assert!(err.to_string().contains("no exchange recorded"), "{err}");
+}
@@ -0,0 +1,69 @@
+//! Tests for skill frontmatter parsing and per-turn tool grants.
+
+use crabtalk::{
+    daemon::hook::AgentScope,
+    hooks::skill::{handler::SkillHook, loader::parse_skill_md},
+};
+use runtime::Hook;
+use std::{collections::BTreeMap, sync::Arc};
+use wcore::{model::HistoryEntry, testing::InMemoryStorage};
+
+const DEPLOY: &str =
+    "---\nname: deploy\ndescription: ship it\ntools: [bash, read]\n---\nRun the deploy script.\n";
+
+#[test]
+fn frontmatter_tools_alias() {
+    let skill = parse_skill_md(DEPLOY).unwrap();
+    assert_eq!(skill.allowed_tools, vec!["bash", "read"]);
+
+    let skill = parse_skill_md("---\nname: x\nallowed-tools: bash, read\n---\nbody").unwrap();
+    assert_eq!(skill.allowed_tools, vec!["bash", "read"]);
+}
+
+#[test]
+fn slash_skill_grants_its_tools() {
+    let storage = Arc::new(InMemoryStorage::with_skills(vec![
+        parse_skill_md(DEPLOY).unwrap(),
+    ]));
+    let hook = SkillHook::new(storage, Default::default());
+
+    let pre = hook.preprocess("crab", "/deploy now").unwrap();
+    assert_eq!(pre.skills, vec!["deploy"]);
+    let mut entry = HistoryEntry::user(pre.content);
+    entry.skills = pre.skills;
+    let history = vec![entry];
+    assert_eq!(hook.turn_tools("crab", 1, &history), vec!["bash", "read"]);
+
+    // The next plain message grants nothing.
+    let history = vec![HistoryEntry::user("thanks")];
+    assert!(hook.turn_tools("crab", 1, &history).is_empty());
+}
+
+#[test]
+fn typed_skill_tag_grants_nothing() {
+    let storage = Arc::new(InMemoryStorage::with_skills(vec![
+        parse_skill_md(DEPLOY).unwrap(),
+    ]));
+    let hook = SkillHook::new(storage, Default::default());
+
+    let history = vec![HistoryEntry::user("<skill name=\"deploy\">\nhi\n</skill>")];
+    assert!(hook.turn_tools("crab", 1, &history).is_empty());
+}
+
+#[test]
+fn out_of_scope_skill_grants_nothing() {
+    let storage = Arc::new(InMemoryStorage::with_skills(vec![
+        parse_skill_md(DEPLOY).unwrap(),
+    ]));
+    let scope = AgentScope {
+        skills: vec!["review".to_owned()],
+        ..Default::default()
+    };
+    let scopes = BTreeMap::from([("crab".to_owned(), scope)]);
+    let hook = SkillHook::new(storage, Arc::new(scopes.into()));
+
+    assert!(hook.preprocess("crab", "/deploy now").is_none());
+    let mut entry = HistoryEntry::user("deploy");
+    entry.skills = vec!["deploy".to_owned()];
+    assert!(hook.turn_tools("crab", 1, &[entry]).is_empty());
+}
@@ -32,35 +32,8 @@ impl McpHandler {
 
         // 1. Connect servers from config.
         for server_config in configs {
-            let fut = async {
-                if let Some(url) = &server_config.url {
-                    tracing::info!(
-                        server = %server_config.name,
-                        url = %url,
-                        "connecting MCP server via HTTP"
-                    );
-                    bridge
-                        .connect_http_named(server_config.name.clone(), url)
-                        .await
-                } else {
-                    let mut cmd = tokio::process::Command::new(&server_config.command);
-                    cmd.args(&server_config.args);
-                    for (k, v) in &server_config.env {
-                        cmd.env(k, v);
-                    }
-                    tracing::info!(
-                        server = %server_config.name,
-                        command = %server_config.command,
-                        "connecting MCP server via stdio"
-                    );
-                    bridge
-                        .connect_stdio_named(server_config.name.clone(), cmd)
-                        .await
-                }
-            };
-
-            match tokio::time::timeout(Self::MCP_CONNECT_TIMEOUT, fut).await {
-                Ok(Ok(tools)) => {
+            match Self::connect(&bridge, server_config).await {
+                Ok(tools) => {
                     connected_names.push(server_config.name.clone());
                     tracing::info!(
                         "connected MCP server '{}' — {} tool(s)",
@@ -68,16 +41,9 @@ impl McpHandler {
                         tools.len()
                     );
                 }
-                Ok(Err(e)) => {
+                Err(e) => {
                     tracing::warn!("failed to connect MCP server '{}': {e}", server_config.name);
                 }
-                Err(_) => {
-                    tracing::warn!(
-                        "MCP server '{}' timed out after {}s, skipping",
-                        server_config.name,
-                        Self::MCP_CONNECT_TIMEOUT.as_secs()
-                    );
-                }
             }
         }
 
@@ -115,6 +81,54 @@ impl McpHandler {
         bridge
     }
 
+    /// Connect one configured server into `bridge`, bounded by
+    /// [`Self::MCP_CONNECT_TIMEOUT`]. Returns the server's tool names.
+    async fn connect(
+        bridge: &McpBridge,
+        server_config: &McpServerConfig,
+    ) -> anyhow::Result<Vec<String>> {
+        let fut = async {
+            if let Some(url) = &server_config.url {
+                tracing::info!(
+                    server = %server_config.name,
+                    url = %url,
+                    "connecting MCP server via HTTP"
+                );
+                bridge
+                    .connect_http_named(server_config.name.clone(), url)
+                    .await
+            } else {
+                let mut cmd = tokio::process::Command::new(&server_config.command);
+                cmd.args(&server_config.args);
+                for (k, v) in &server_config.env {
+                    cmd.env(k, v);
+                }
+                tracing::info!(
+                    server = %server_config.name,
+                    command = %server_config.command,
+                    "connecting MCP server via stdio"
+                );
+                bridge
+                    .connect_stdio_named(server_config.name.clone(), cmd)
+                    .await
+            }
+        };
+        tokio::time::timeout(Self::MCP_CONNECT_TIMEOUT, fut)
+            .await
+            .map_err(|_| {
+                anyhow::anyhow!("timed out after {}s", Self::MCP_CONNECT_TIMEOUT.as_secs())
+            })?
+    }
+
+    /// Connect to a single server on a throwaway bridge and report how
+    /// many tools it exposes. The connection is dropped on return.
+    pub async fn probe(server_config: &McpServerConfig) -> anyhow::Result<usize> {
+        let bridge = McpBridge::new();
+        let tools = Self::connect(&bridge, server_config).await?;
+        bridge.clear().await;
+        Ok(tools.len())
+    }
+
     /// Load MCP servers from the given configs at startup.
     pub async fn load(configs: &[McpServerConfig]) -> Self {
         let bridge = Self::build_bridge(configs).await;
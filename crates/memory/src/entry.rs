@@ -15,4 +15,21 @@ pub struct Entry {
     pub aliases: Vec<String>,
     pub created_at: u64,
     pub kind: EntryKind,
+    /// How many times recall has surfaced this entry. Feeds the access
+    /// boost in search ranking.
+    pub access_count: u32,
+    /// Unix time after which the entry is gone: skipped by lookups and
+    /// search, and dropped by [`Memory::purge_expired`](crate::Memory::purge_expired).
+    /// `None` never expires.
+    pub expires_at: Option<u64>,
+    /// Exempt from the note capacity: eviction never drops a pinned
+    /// entry. Set with [`Op::Pin`](crate::Op::Pin).
+    pub pinned: bool,
+}
+
+impl Entry {
+    /// Whether the entry has expired as of unix time `now`.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|t| t <= now)
+    }
 }
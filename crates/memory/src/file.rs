@@ -1,4 +1,4 @@
-//! Binary file format v1.
+//! Binary file format v4.
 //!
 //! Layout:
 //! ```text
@@ -18,6 +18,9 @@
 //!   id          u64 LE
 //!   created_at  u64 LE
 //!   kind        u32 LE    (0 = Note, 1 = Archive, 2 = Topic)
+//!   access_cnt  u32 LE    (v2+; absent in v1, read as 0)
+//!   expires_at  u64 LE    (v3+; 0 = never; absent before v3)
+//!   flags       u32 LE    (v4+; bit 0 = pinned; absent before v4)
 //!   name        u32 len LE + utf8 bytes
 //!   content     u32 len LE + utf8 bytes
 //!   alias_cnt   u32 LE
@@ -27,6 +30,8 @@
 //! `kind` is u32 rather than u8 so the fixed entry prefix stays 4-byte
 //! aligned — cheap hygiene for any future on-disk index work.
 //!
+//! v1 to v3 files are still read; the next write upgrades them to v4.
+//!
 //! The inverted index is not persisted; it is rebuilt from entries on
 //! load. Keeps the file small and the format boring.
 
@@ -44,11 +49,15 @@ use std::{
 const MAGIC: &[u8; 6] = b"CRMEM\0";
 // Bump when a new kind ships post-1.0 so older binaries refuse files
 // they can't interpret instead of crashing on "unknown entry kind".
-const VERSION: u32 = 1;
+const VERSION: u32 = 4;
+const VERSION_V1: u32 = 1;
+const VERSION_V2: u32 = 2;
+const VERSION_V3: u32 = 3;
 const HEADER_LEN: usize = 16;
 const KIND_NOTE: u32 = 0;
 const KIND_ARCHIVE: u32 = 1;
 const KIND_TOPIC: u32 = 2;
+const FLAG_PINNED: u32 = 1;
 
 pub(crate) struct Snapshot {
     pub(crate) next_id: EntryId,
@@ -69,7 +78,7 @@ pub(crate) fn read(path: &Path) -> Result<Option<Snapshot>> {
         return Err(Error::BadFormat("invalid magic"));
     }
     let version = u32::from_le_bytes(bytes[6..10].try_into().unwrap());
-    if version != VERSION {
+    if !matches!(version, VERSION | VERSION_V1 | VERSION_V2 | VERSION_V3) {
         return Err(Error::BadFormat("unsupported version"));
     }
     let flags = u16::from_le_bytes(bytes[10..12].try_into().unwrap());
@@ -82,7 +91,7 @@ pub(crate) fn read(path: &Path) -> Result<Option<Snapshot>> {
     let count = cur.read_u32()? as usize;
     let mut entries = Vec::with_capacity(count);
     for _ in 0..count {
-        entries.push(cur.read_entry()?);
+        entries.push(cur.read_entry(version)?);
     }
     Ok(Some(Snapshot { next_id, entries }))
 }
@@ -136,6 +145,10 @@ fn encode_entry(buf: &mut Vec<u8>, e: &Entry) -> Result<()> {
         EntryKind::Topic => KIND_TOPIC,
     };
     buf.extend_from_slice(&kind.to_le_bytes());
+    buf.extend_from_slice(&e.access_count.to_le_bytes());
+    buf.extend_from_slice(&e.expires_at.unwrap_or(0).to_le_bytes());
+    let flags = if e.pinned { FLAG_PINNED } else { 0 };
+    buf.extend_from_slice(&flags.to_le_bytes());
     encode_string(buf, &e.name)?;
     encode_string(buf, &e.content)?;
     let alias_cnt = u32_from_len(e.aliases.len(), "too many aliases")?;
@@ -190,7 +203,7 @@ impl<'a> Cursor<'a> {
         String::from_utf8(bytes).map_err(|_| Error::BadFormat("invalid utf8"))
     }
 
-    fn read_entry(&mut self) -> Result<Entry> {
+    fn read_entry(&mut self, version: u32) -> Result<Entry> {
         let id = self.read_u64()?;
         let created_at = self.read_u64()?;
         let kind = match self.read_u32()? {
@@ -199,6 +212,24 @@ impl<'a> Cursor<'a> {
             KIND_TOPIC => EntryKind::Topic,
             _ => return Err(Error::BadFormat("unknown entry kind")),
         };
+        let access_count = if version == VERSION_V1 {
+            0
+        } else {
+            self.read_u32()?
+        };
+        let expires_at = if version >= VERSION_V3 {
+            Some(self.read_u64()?).filter(|&t| t != 0)
+        } else {
+            None
+        };
+        let flags = if version == VERSION {
+            self.read_u32()?
+        } else {
+            0
+        };
+        if flags & !FLAG_PINNED != 0 {
+            return Err(Error::BadFormat("unknown entry flags"));
+        }
         let name = self.read_string()?;
         let content = self.read_string()?;
         let alias_cnt = self.read_u32()? as usize;
@@ -213,6 +244,9 @@ impl<'a> Cursor<'a> {
             name,
             content,
             aliases,
+            access_count,
+            expires_at,
+            pinned: flags & FLAG_PINNED != 0,
         })
     }
 }
@@ -1,8 +1,9 @@
 //! Standalone single-file memory system.
 //!
 //! `Memory` is a connection to a single db file (like SQLite): entries,
-//! aliases, and an inverted index all live in one place. v1 is in-RAM only;
-//! persistence and dump/load land in later phases.
+//! aliases, and an inverted index all live in one place. `Memory::open`
+//! persists every write to its file; `Memory::new` is in-RAM and can be
+//! written out with `Memory::save_as`.
 
 pub mod bm25;
 mod dump;
@@ -15,6 +16,6 @@ mod op;
 pub use crate::{
     entry::{Entry, EntryId, EntryKind},
     error::{Error, Result},
-    memory::{Memory, SearchHit},
+    memory::{DEFAULT_ACCESS_WEIGHT, Memory, SearchHit},
     op::Op,
 };
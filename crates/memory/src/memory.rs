@@ -13,6 +13,11 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
+/// Default weight of the access boost in search ranking. Small enough
+/// that relevance still dominates: an entry recalled 20 times scores
+/// about 1.3x its raw BM25.
+pub const DEFAULT_ACCESS_WEIGHT: f64 = 0.1;
+
 /// Memory connection. `open(path)` is persistent (auto-flushes every
 /// `apply` via atomic write); `new()` is in-RAM only.
 pub struct Memory {
@@ -21,6 +26,8 @@ pub struct Memory {
     by_name: HashMap<String, EntryId>,
     index: Index<EntryId>,
     next_id: EntryId,
+    access_weight: f64,
+    capacity: Option<usize>,
 }
 
 #[derive(Clone, Debug)]
@@ -44,6 +51,8 @@ impl Memory {
             by_name: HashMap::new(),
             index: Index::<EntryId>::new(),
             next_id: 1,
+            access_weight: DEFAULT_ACCESS_WEIGHT,
+            capacity: None,
         }
     }
 
@@ -58,6 +67,8 @@ impl Memory {
             by_name: HashMap::new(),
             index: Index::<EntryId>::new(),
             next_id: 1,
+            access_weight: DEFAULT_ACCESS_WEIGHT,
+            capacity: None,
         };
         if let Some(snap) = file::read(&path)? {
             mem.next_id = snap.next_id;
@@ -75,6 +86,25 @@ impl Memory {
     /// op (or the next `open`, which re-reads the file). WAL will close
     /// this window in v2.
     pub fn apply(&mut self, op: Op) -> Result<()> {
+        self.apply_in_ram(op)?;
+        self.flush()
+    }
+
+    /// Apply `ops` in order and persist once at the end, rather than
+    /// rewriting the file after every op. Stops at the first op that
+    /// fails: the ops before it stay applied and are flushed, then the
+    /// error is returned.
+    pub fn apply_batch(&mut self, ops: impl IntoIterator<Item = Op>) -> Result<()> {
+        let applied = ops.into_iter().try_for_each(|op| self.apply_in_ram(op));
+        self.flush()?;
+        applied
+    }
+
+    fn apply_in_ram(&mut self, op: Op) -> Result<()> {
+        let written = match &op {
+            Op::Add { name, .. } | Op::Append { name, .. } => Some(name.clone()),
+            _ => None,
+        };
         match op {
             Op::Add {
                 name,
@@ -87,57 +117,250 @@ impl Memory {
                 content,
                 aliases,
             } => self.update(&name, content, aliases)?,
+            Op::Append {
+                name,
+                content,
+                separator,
+            } => self.append(name, content, &separator)?,
             Op::Alias { name, aliases } => self.set_aliases(&name, aliases)?,
             Op::Remove { name } => self.remove(&name)?,
+            Op::Expire { name, expires_at } => self.set_expiry(&name, expires_at)?,
+            Op::Pin { name, pinned } => self.set_pinned(&name, pinned)?,
+            Op::Restore {
+                name,
+                content,
+                aliases,
+                kind,
+                created_at,
+                access_count,
+                expires_at,
+                pinned,
+            } => self.restore(Entry {
+                id: 0,
+                name,
+                content,
+                aliases,
+                created_at,
+                kind,
+                access_count,
+                expires_at,
+                pinned,
+            }),
         }
-        self.flush()
+        if let Some(name) = written {
+            self.evict_over_capacity(&name);
+        }
+        Ok(())
     }
 
+    /// Look up an entry by name. Expired entries are not returned.
     pub fn get(&self, name: &str) -> Option<&Entry> {
-        self.by_name.get(name).and_then(|id| self.entries.get(id))
+        self.by_name
+            .get(name)
+            .and_then(|id| self.entries.get(id))
+            .filter(|e| !e.is_expired(now_unix()))
     }
 
+    /// Resolve a near-miss `name`. An exact match wins; otherwise names
+    /// are compared after normalizing (lowercased, `_`/`-`/`.`/
+    /// whitespace stripped) by Levenshtein distance, and the closest
+    /// within `max_distance` is returned — so `user_name`, `UserName`
+    /// and `username` all find each other at distance 0.
+    ///
+    /// Ties go to the most accessed entry, then the oldest (lowest ID),
+    /// so the answer is stable across calls. Callers that must not act
+    /// on a guess should compare the returned name to the one asked for.
+    pub fn get_fuzzy(&self, name: &str, max_distance: usize) -> Option<&Entry> {
+        if let Some(entry) = self.get(name) {
+            return Some(entry);
+        }
+        let wanted = normalize_name(name);
+        let now = now_unix();
+        self.entries
+            .values()
+            .filter(|e| !e.is_expired(now))
+            .filter_map(|e| {
+                let distance = levenshtein(&wanted, &normalize_name(&e.name));
+                (distance <= max_distance).then_some((distance, e))
+            })
+            .min_by(|(da, a), (db, b)| {
+                da.cmp(db)
+                    .then(b.access_count.cmp(&a.access_count))
+                    .then(a.id.cmp(&b.id))
+            })
+            .map(|(_, e)| e)
+    }
+
+    /// Every entry that has not expired.
     pub fn list(&self) -> impl Iterator<Item = &Entry> {
-        self.entries.values()
+        let now = now_unix();
+        self.entries.values().filter(move |e| !e.is_expired(now))
+    }
+
+    /// Number of stored entries. Expired entries count until
+    /// [`Memory::purge_expired`] drops them.
+    pub fn len(&self) -> usize {
+        self.entries.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Up to `limit` entries with IDs above `after`, in ID order. Pass
+    /// the last returned ID as `after` to fetch the next page; IDs are
+    /// never reused, so pages stay stable across concurrent writes.
+    /// Expired entries are skipped.
+    pub fn page(&self, after: Option<EntryId>, limit: usize) -> Vec<&Entry> {
+        let after = after.unwrap_or(0);
+        let mut entries: Vec<&Entry> = self.list().filter(|e| e.id > after).collect();
+        entries.sort_by_key(|e| e.id);
+        entries.truncate(limit);
+        entries
+    }
+
+    /// BM25 search, scaled by each entry's access boost:
+    /// `score * (1 + ln(1 + access_count) * access_weight)`. Expired
+    /// entries never match.
     pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
-        self.index
-            .search(query, limit)
-            .into_iter()
-            .filter_map(|(id, score)| {
-                self.entries.get(&id).map(|e| SearchHit {
-                    entry: e.clone(),
-                    score,
-                })
-            })
-            .collect()
+        self.ranked(query, limit, |_| true)
     }
 
-    /// BM25 search restricted to a single `EntryKind`. The inner search
-    /// runs unbounded so the kind filter can't truncate matches mid-list;
-    /// we clone only the survivors that fit inside `limit`.
+    /// [`Memory::search`] restricted to a single `EntryKind`.
     pub fn search_kind(&self, query: &str, limit: usize, kind: EntryKind) -> Vec<SearchHit> {
+        self.ranked(query, limit, |e| e.kind == kind)
+    }
+
+    /// Set the weight of the access boost. `0.0` ranks on BM25 alone.
+    pub fn set_access_weight(&mut self, weight: f64) {
+        self.access_weight = weight;
+    }
+
+    /// Cap the number of `Note` entries; `None` lifts the cap. An add or
+    /// append that takes the count past it drops notes until it fits:
+    /// expired ones first, then the least recalled, oldest first among
+    /// equals. The entry just written is never the one dropped, and
+    /// pinned notes, archives and topics are never dropped, so the count
+    /// can stay over the cap. `Restore` never evicts, so an import lands
+    /// whole; a lowered cap, like an import past it, applies at the
+    /// next add or append.
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+    }
+
+    /// Bump the access count of each entry in `ids`. RAM only — counts
+    /// reach disk with the next write, so recall stays cheap and a crash
+    /// loses at most the counts since the last flush.
+    pub fn record_access(&mut self, ids: &[EntryId]) {
+        for id in ids {
+            if let Some(entry) = self.entries.get_mut(id) {
+                entry.access_count = entry.access_count.saturating_add(1);
+            }
+        }
+    }
+
+    /// The inner search runs unbounded so neither the filter nor the
+    /// boost can truncate matches mid-list; we clone only the survivors
+    /// that fit inside `limit`.
+    fn ranked(&self, query: &str, limit: usize, keep: impl Fn(&Entry) -> bool) -> Vec<SearchHit> {
         if limit == 0 {
             return Vec::new();
         }
-        self.index
+        let now = now_unix();
+        let mut scored: Vec<(&Entry, f64)> = self
+            .index
             .search(query, usize::MAX)
             .into_iter()
             .filter_map(|(id, score)| {
-                let entry = self.entries.get(&id)?;
-                if entry.kind != kind {
-                    return None;
-                }
-                Some(SearchHit {
-                    entry: entry.clone(),
-                    score,
-                })
+                let entry = self
+                    .entries
+                    .get(&id)
+                    .filter(|e| !e.is_expired(now) && keep(e))?;
+                Some((entry, score * self.access_boost(entry)))
             })
+            .collect();
+        // Stable, so equal scores keep their BM25 order.
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored
+            .into_iter()
             .take(limit)
+            .map(|(entry, score)| SearchHit {
+                entry: entry.clone(),
+                score,
+            })
             .collect()
     }
 
+    fn access_boost(&self, entry: &Entry) -> f64 {
+        1.0 + (entry.access_count as f64).ln_1p() * self.access_weight
+    }
+
+    /// Drop every expired entry, index terms included, and persist.
+    /// Returns how many were dropped; with none, nothing is written.
+    pub fn purge_expired(&mut self) -> Result<usize> {
+        let now = now_unix();
+        let expired: Vec<String> = self
+            .entries
+            .values()
+            .filter(|e| e.is_expired(now))
+            .map(|e| e.name.clone())
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+        for name in &expired {
+            self.remove(name)?;
+        }
+        self.flush()?;
+        Ok(expired.len())
+    }
+
+    /// Drop notes past the capacity, sparing `keep` and pinned notes.
+    fn evict_over_capacity(&mut self, keep: &str) {
+        let Some(capacity) = self.capacity else {
+            return;
+        };
+        let now = now_unix();
+        let mut notes: Vec<&Entry> = self
+            .entries
+            .values()
+            .filter(|e| e.kind == EntryKind::Note)
+            .collect();
+        let Some(excess) = notes.len().checked_sub(capacity).filter(|&n| n > 0) else {
+            return;
+        };
+        notes.retain(|e| e.name != keep && !e.pinned);
+        notes.sort_by_key(|e| (!e.is_expired(now), e.access_count, e.created_at, e.id));
+        let evicted: Vec<String> = notes
+            .into_iter()
+            .take(excess)
+            .map(|e| e.name.clone())
+            .collect();
+        for name in evicted {
+            let _ = self.remove(&name);
+        }
+    }
+
+    /// ID of the named entry, or `NotFound` when it is absent or expired.
+    fn live_id(&self, name: &str) -> Result<EntryId> {
+        self.get(name)
+            .map(|e| e.id)
+            .ok_or_else(|| Error::NotFound(name.to_owned()))
+    }
+
+    /// Remove the named entry if it has expired, so its name can be
+    /// reused as if it were new.
+    fn drop_if_expired(&mut self, name: &str) {
+        let expired = self
+            .by_name
+            .get(name)
+            .and_then(|id| self.entries.get(id))
+            .is_some_and(|e| e.is_expired(now_unix()));
+        if expired {
+            let _ = self.remove(name);
+        }
+    }
+
     fn add(
         &mut self,
         name: String,
@@ -145,6 +368,7 @@ impl Memory {
         aliases: Vec<String>,
         kind: EntryKind,
     ) -> Result<()> {
+        self.drop_if_expired(&name);
         if self.by_name.contains_key(&name) {
             return Err(Error::Duplicate(name));
         }
@@ -157,6 +381,9 @@ impl Memory {
             aliases,
             created_at: now_unix(),
             kind,
+            access_count: 0,
+            expires_at: None,
+            pinned: false,
         };
         self.reindex(&entry);
         self.by_name.insert(name, id);
@@ -165,10 +392,7 @@ impl Memory {
     }
 
     fn update(&mut self, name: &str, content: String, aliases: Vec<String>) -> Result<()> {
-        let id = *self
-            .by_name
-            .get(name)
-            .ok_or_else(|| Error::NotFound(name.to_owned()))?;
+        let id = self.live_id(name)?;
         let entry = self.entries.get_mut(&id).expect("entry id out of sync");
         entry.content = content;
         entry.aliases = aliases;
@@ -177,11 +401,23 @@ impl Memory {
         Ok(())
     }
 
+    fn append(&mut self, name: String, content: String, separator: &str) -> Result<()> {
+        self.drop_if_expired(&name);
+        let Some(&id) = self.by_name.get(&name) else {
+            return self.add(name, content, Vec::new(), EntryKind::Note);
+        };
+        let entry = self.entries.get_mut(&id).expect("entry id out of sync");
+        if !entry.content.is_empty() {
+            entry.content.push_str(separator);
+        }
+        entry.content.push_str(&content);
+        let snapshot = entry.clone();
+        self.reindex(&snapshot);
+        Ok(())
+    }
+
     fn set_aliases(&mut self, name: &str, aliases: Vec<String>) -> Result<()> {
-        let id = *self
-            .by_name
-            .get(name)
-            .ok_or_else(|| Error::NotFound(name.to_owned()))?;
+        let id = self.live_id(name)?;
         let entry = self.entries.get_mut(&id).expect("entry id out of sync");
         entry.aliases = aliases;
         let snapshot = entry.clone();
@@ -189,6 +425,38 @@ impl Memory {
         Ok(())
     }
 
+    fn set_expiry(&mut self, name: &str, expires_at: Option<u64>) -> Result<()> {
+        let id = self.live_id(name)?;
+        let entry = self.entries.get_mut(&id).expect("entry id out of sync");
+        entry.expires_at = expires_at;
+        Ok(())
+    }
+
+    fn set_pinned(&mut self, name: &str, pinned: bool) -> Result<()> {
+        let id = self.live_id(name)?;
+        let entry = self.entries.get_mut(&id).expect("entry id out of sync");
+        entry.pinned = pinned;
+        Ok(())
+    }
+
+    /// Upsert keeping the entry's id when the name already exists, so
+    /// list cursors stay valid across a forced import. The id `entry`
+    /// carries is ignored.
+    fn restore(&mut self, mut entry: Entry) {
+        entry.id = match self.by_name.get(&entry.name) {
+            Some(&id) => id,
+            None => {
+                let id = self.next_id;
+                self.next_id += 1;
+                self.by_name.insert(entry.name.clone(), id);
+                id
+            }
+        };
+        let id = entry.id;
+        self.reindex(&entry);
+        self.entries.insert(id, entry);
+    }
+
     fn remove(&mut self, name: &str) -> Result<()> {
         let id = self
             .by_name
@@ -208,12 +476,10 @@ impl Memory {
     }
 
     fn flush(&self) -> Result<()> {
-        let Some(path) = &self.path else {
-            return Ok(());
-        };
-        let mut entries: Vec<&Entry> = self.entries.values().collect();
-        entries.sort_by_key(|e| e.id);
-        file::write(path, self.next_id, &entries)
+        match &self.path {
+            Some(path) => self.save_as(path),
+            None => Ok(()),
+        }
     }
 
     /// Force a write of the current state to disk, whether or not any
@@ -224,6 +490,16 @@ impl Memory {
         self.flush()
     }
 
+    /// Write the db, in the same binary format, to `path` — a backup of
+    /// a file-backed db, or the way to keep an in-RAM one. `Memory::open`
+    /// reads it back. The db stays bound to its own path (or none);
+    /// later writes do not touch `path`.
+    pub fn save_as(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut entries: Vec<&Entry> = self.entries.values().collect();
+        entries.sort_by_key(|e| e.id);
+        file::write(path.as_ref(), self.next_id, &entries)
+    }
+
     /// Materialize the db as a markdown tree at `dir`. Each kind's
     /// subdirectory is cleared before writing so renames and deletes
     /// don't leave orphan files behind. Anything else in `dir` (e.g. a
@@ -231,7 +507,7 @@ impl Memory {
     pub fn dump(&self, dir: impl AsRef<Path>) -> Result<()> {
         let dir = dir.as_ref();
         let mut by_kind: HashMap<EntryKind, Vec<&Entry>> = HashMap::new();
-        for e in self.entries.values() {
+        for e in self.list() {
             dump::validate_name(&e.name)?;
             by_kind.entry(e.kind).or_default().push(e);
         }
@@ -288,6 +564,9 @@ impl Memory {
                 aliases: item.aliases,
                 created_at: item.created_at.unwrap_or_else(now_unix),
                 kind: item.kind,
+                access_count: 0,
+                expires_at: None,
+                pinned: false,
             };
             let mut terms = tokenize(&entry.content);
             for alias in &entry.aliases {
@@ -312,3 +591,28 @@ fn now_unix() -> u64 {
         .map(|d| d.as_secs())
         .unwrap_or(0)
 }
+
+/// Fold a name to the form [`Memory::get_fuzzy`] compares: lowercase
+/// with separators (`_`, `-`, `.`, whitespace) removed.
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-' | '.') && !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Edit distance over chars, two-row.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
@@ -2,7 +2,14 @@ use crate::entry::EntryKind;
 
 /// Write operations. `Update` rewrites content and aliases but preserves
 /// `kind` — an archive stays an archive for life. Use `Remove` + `Add` to
-/// change kind.
+/// change kind. `Append` adds to an entry's content instead of replacing
+/// it, creating a `Note` when the name is new. `Restore` writes an entry
+/// as-is — kind, `created_at`, access count, expiry and pin included —
+/// replacing any entry of the same name; it is the import path for
+/// exported entries. `Expire` sets or clears the unix time after which
+/// an entry is gone. New entries never expire; `Update`, `Append` and
+/// `Alias` keep the entry's expiry. `Pin` sets or clears the flag that
+/// exempts an entry from the note capacity; new entries are unpinned.
 #[derive(Clone, Debug)]
 pub enum Op {
     Add {
@@ -16,6 +23,13 @@ pub enum Op {
         content: String,
         aliases: Vec<String>,
     },
+    /// Concatenate `content` onto the entry, joined by `separator`.
+    /// No dedup: appending the same text twice stores it twice.
+    Append {
+        name: String,
+        content: String,
+        separator: String,
+    },
     Alias {
         name: String,
         aliases: Vec<String>,
@@ -23,4 +37,22 @@ pub enum Op {
     Remove {
         name: String,
     },
+    Expire {
+        name: String,
+        expires_at: Option<u64>,
+    },
+    Pin {
+        name: String,
+        pinned: bool,
+    },
+    Restore {
+        name: String,
+        content: String,
+        aliases: Vec<String>,
+        kind: EntryKind,
+        created_at: u64,
+        access_count: u32,
+        expires_at: Option<u64>,
+        pinned: bool,
+    },
 }
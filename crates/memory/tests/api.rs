@@ -50,6 +50,29 @@ fn update_replaces_content_and_reindexes() {
     assert_eq!(mem.search("cherry", 10).len(), 1);
 }
 
+#[test]
+fn append_accumulates_content() {
+    let mut mem = Memory::new();
+    let append = |mem: &mut Memory, content: &str| {
+        mem.apply(Op::Append {
+            name: "prefs".into(),
+            content: content.into(),
+            separator: "\n".into(),
+        })
+        .unwrap();
+    };
+    append(&mut mem, "likes tea");
+    assert_eq!(mem.get("prefs").unwrap().kind, EntryKind::Note);
+    append(&mut mem, "hates mornings");
+    append(&mut mem, "likes tea");
+
+    assert_eq!(
+        mem.get("prefs").unwrap().content,
+        "likes tea\nhates mornings\nlikes tea"
+    );
+    assert_eq!(mem.search("mornings", 10).len(), 1);
+}
+
 #[test]
 fn remove_drops_entry_and_index() {
     let mut mem = Memory::new();
@@ -129,3 +152,299 @@ fn archive_kind_is_preserved() {
 
     assert_eq!(mem.get("archive-1").unwrap().kind, EntryKind::Archive);
 }
+
+#[test]
+fn frequently_accessed_entry_outranks_equal_relevance() {
+    let mut mem = Memory::new();
+    add(&mut mem, "cold", "deploy checklist", &[]);
+    add(&mut mem, "warm", "deploy checklist", &[]);
+    let warm = mem.get("warm").unwrap().id;
+    for _ in 0..10 {
+        mem.record_access(&[warm]);
+    }
+
+    let hits = mem.search("deploy", 10);
+    assert_eq!(hits[0].entry.name, "warm");
+    assert_eq!(hits[0].entry.access_count, 10);
+    assert!(hits[0].score > hits[1].score);
+}
+
+#[test]
+fn zero_access_weight_ranks_on_bm25_alone() {
+    let mut mem = Memory::new();
+    add(&mut mem, "cold", "deploy checklist", &[]);
+    add(&mut mem, "warm", "deploy checklist", &[]);
+    let warm = mem.get("warm").unwrap().id;
+    mem.record_access(&[warm]);
+    mem.set_access_weight(0.0);
+
+    let hits = mem.search("deploy", 10);
+    assert_eq!(hits[0].score, hits[1].score);
+}
+
+#[test]
+fn page_walks_entries_in_id_order() {
+    let mut mem = Memory::new();
+    for name in ["a", "b", "c", "d", "e"] {
+        add(&mut mem, name, "x", &[]);
+    }
+    mem.apply(Op::Remove { name: "b".into() }).unwrap();
+    assert_eq!(mem.len(), 4);
+
+    let first: Vec<_> = mem.page(None, 2).iter().map(|e| e.name.clone()).collect();
+    assert_eq!(first, ["a", "c"]);
+    let after = mem.get("c").unwrap().id;
+    let second: Vec<_> = mem
+        .page(Some(after), 2)
+        .iter()
+        .map(|e| e.name.clone())
+        .collect();
+    assert_eq!(second, ["d", "e"]);
+    let after = mem.get("e").unwrap().id;
+    assert!(mem.page(Some(after), 2).is_empty());
+}
+
+#[test]
+fn restore_keeps_timestamp_and_replaces_in_place() {
+    let mut mem = Memory::new();
+    add(&mut mem, "a", "apple", &[]);
+    let id = mem.get("a").unwrap().id;
+
+    mem.apply(Op::Restore {
+        name: "a".into(),
+        content: "banana".into(),
+        aliases: vec![],
+        kind: EntryKind::Archive,
+        created_at: 42,
+        access_count: 0,
+        expires_at: None,
+        pinned: false,
+    })
+    .unwrap();
+    mem.apply(Op::Restore {
+        name: "b".into(),
+        content: "cherry".into(),
+        aliases: vec![],
+        kind: EntryKind::Note,
+        created_at: 7,
+        access_count: 0,
+        expires_at: None,
+        pinned: false,
+    })
+    .unwrap();
+
+    let a = mem.get("a").unwrap();
+    assert_eq!((a.id, a.created_at, a.kind), (id, 42, EntryKind::Archive));
+    assert!(mem.search("apple", 5).is_empty());
+    assert_eq!(mem.search("banana", 5)[0].entry.name, "a");
+    assert_eq!(mem.get("b").unwrap().created_at, 7);
+    assert_eq!(mem.len(), 2);
+}
+
+#[test]
+fn get_fuzzy_resolves_near_miss_names() {
+    let mut mem = Memory::new();
+    add(&mut mem, "user_name", "Ada", &[]);
+    add(&mut mem, "favorite-color", "teal", &[]);
+
+    assert!(mem.get("username").is_none());
+    assert_eq!(mem.get_fuzzy("username", 0).unwrap().name, "user_name");
+    assert_eq!(mem.get_fuzzy("User Name", 0).unwrap().name, "user_name");
+    assert_eq!(
+        mem.get_fuzzy("favourite_color", 1).unwrap().name,
+        "favorite-color"
+    );
+    assert!(mem.get_fuzzy("favourite_color", 0).is_none());
+    assert!(mem.get_fuzzy("unrelated", 2).is_none());
+}
+
+#[test]
+fn get_fuzzy_prefers_exact_then_most_accessed() {
+    let mut mem = Memory::new();
+    add(&mut mem, "user-name", "old", &[]);
+    add(&mut mem, "user_name", "new", &[]);
+    add(&mut mem, "UserName", "exact", &[]);
+
+    assert_eq!(mem.get_fuzzy("UserName", 2).unwrap().content, "exact");
+    // Three-way tie at distance 0: oldest wins until another is accessed.
+    assert_eq!(mem.get_fuzzy("username", 0).unwrap().content, "old");
+    let id = mem.get("user_name").unwrap().id;
+    mem.record_access(&[id]);
+    assert_eq!(mem.get_fuzzy("username", 0).unwrap().content, "new");
+}
+
+#[test]
+fn expired_entries_are_hidden_then_purged() {
+    let mut mem = Memory::new();
+    add(&mut mem, "debugging", "user is debugging the codec", &[]);
+    add(&mut mem, "lasting", "user writes rust", &[]);
+    let expire = |mem: &mut Memory, expires_at| {
+        mem.apply(Op::Expire {
+            name: "debugging".into(),
+            expires_at,
+        })
+    };
+
+    expire(&mut mem, Some(u64::MAX)).unwrap();
+    assert!(mem.get("debugging").is_some());
+
+    expire(&mut mem, Some(1)).unwrap();
+    assert!(mem.get("debugging").is_none());
+    assert!(mem.search("debugging codec", 5).is_empty());
+    assert_eq!(mem.list().count(), 1);
+    assert!(expire(&mut mem, None).is_err());
+    assert_eq!(mem.len(), 2);
+
+    assert_eq!(mem.purge_expired().unwrap(), 1);
+    assert_eq!(mem.purge_expired().unwrap(), 0);
+    assert_eq!(mem.len(), 1);
+    assert!(mem.get("lasting").is_some());
+}
+
+#[test]
+fn add_reuses_an_expired_name() {
+    let mut mem = Memory::new();
+    add(&mut mem, "status", "on call", &[]);
+    mem.apply(Op::Expire {
+        name: "status".into(),
+        expires_at: Some(1),
+    })
+    .unwrap();
+
+    add(&mut mem, "status", "on holiday", &[]);
+    let e = mem.get("status").unwrap();
+    assert_eq!(e.content, "on holiday");
+    assert_eq!(e.expires_at, None);
+    assert!(mem.search("call", 5).is_empty());
+}
+
+#[test]
+fn apply_batch_indexes_every_entry() {
+    let mut mem = Memory::new();
+    let ops = (0..200).map(|i| Op::Add {
+        name: format!("fact-{i}"),
+        content: format!("token{i} shared"),
+        aliases: vec![],
+        kind: EntryKind::Note,
+    });
+    mem.apply_batch(ops).unwrap();
+
+    assert_eq!(mem.len(), 200);
+    assert_eq!(mem.search("shared", 500).len(), 200);
+    assert_eq!(mem.search("token137", 5)[0].entry.name, "fact-137");
+}
+
+#[test]
+fn apply_batch_stops_at_first_error() {
+    let mut mem = Memory::new();
+    add(&mut mem, "b", "existing", &[]);
+    let note = |name: &str| Op::Add {
+        name: name.into(),
+        content: "new".into(),
+        aliases: vec![],
+        kind: EntryKind::Note,
+    };
+
+    let result = mem.apply_batch([note("a"), note("b"), note("c")]);
+    assert!(result.is_err());
+    assert!(mem.get("a").is_some());
+    assert_eq!(mem.get("b").unwrap().content, "existing");
+    assert!(mem.get("c").is_none());
+}
+
+#[test]
+fn restore_carries_access_count_and_expiry() {
+    let mut mem = Memory::new();
+    mem.apply(Op::Restore {
+        name: "popular".into(),
+        content: "shared term".into(),
+        aliases: vec![],
+        kind: EntryKind::Note,
+        created_at: 1,
+        access_count: 20,
+        expires_at: Some(u64::MAX),
+        pinned: false,
+    })
+    .unwrap();
+    mem.apply(Op::Restore {
+        name: "plain".into(),
+        content: "shared term".into(),
+        aliases: vec![],
+        kind: EntryKind::Note,
+        created_at: 2,
+        access_count: 0,
+        expires_at: None,
+        pinned: false,
+    })
+    .unwrap();
+
+    let e = mem.get("popular").unwrap();
+    assert_eq!((e.access_count, e.expires_at), (20, Some(u64::MAX)));
+    assert_eq!(mem.search("shared", 5)[0].entry.name, "popular");
+}
+
+#[test]
+fn capacity_evicts_least_recalled_notes() {
+    let mut mem = Memory::new();
+    mem.set_capacity(Some(2));
+    add(&mut mem, "old", "first", &[]);
+    add(&mut mem, "popular", "second", &[]);
+    let popular = mem.get("popular").unwrap().id;
+    mem.record_access(&[popular]);
+    mem.apply(Op::Add {
+        name: "log".into(),
+        content: "compaction".into(),
+        aliases: vec![],
+        kind: EntryKind::Archive,
+    })
+    .unwrap();
+    assert_eq!(mem.len(), 3);
+
+    add(&mut mem, "new", "third", &[]);
+    assert!(mem.get("old").is_none());
+    assert!(mem.get("popular").is_some());
+    assert!(mem.get("new").is_some());
+    assert!(mem.get("log").is_some());
+    assert!(mem.search("first", 5).is_empty());
+}
+
+#[test]
+fn capacity_spares_pinned_notes() {
+    let mut mem = Memory::new();
+    mem.set_capacity(Some(1));
+    add(&mut mem, "persona", "terse and direct", &[]);
+    mem.apply(Op::Pin {
+        name: "persona".into(),
+        pinned: true,
+    })
+    .unwrap();
+    add(&mut mem, "first", "one", &[]);
+    add(&mut mem, "second", "two", &[]);
+
+    assert!(mem.get("persona").unwrap().pinned);
+    assert!(mem.get("first").is_none());
+    assert!(mem.get("second").is_some());
+}
+
+#[test]
+fn restore_never_evicts() {
+    let mut mem = Memory::new();
+    mem.set_capacity(Some(1));
+    let restore = |name: &str| Op::Restore {
+        name: name.into(),
+        content: "imported".into(),
+        aliases: vec![],
+        kind: EntryKind::Note,
+        created_at: 1,
+        access_count: 0,
+        expires_at: None,
+        pinned: false,
+    };
+    mem.apply_batch([restore("a"), restore("b"), restore("c")])
+        .unwrap();
+    assert_eq!(mem.len(), 3);
+
+    add(&mut mem, "d", "fresh", &[]);
+    assert_eq!(mem.len(), 1);
+    assert!(mem.get("d").is_some());
+}
@@ -217,6 +217,95 @@ fn empty_content_round_trips() {
     assert_eq!(mem.get("blank").unwrap().content, "");
 }
 
+#[test]
+fn access_count_survives_round_trip() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("mem.db");
+    {
+        let mut mem = Memory::open(&path).unwrap();
+        add(&mut mem, "a", "alpha", &[], EntryKind::Note);
+        let id = mem.get("a").unwrap().id;
+        mem.record_access(&[id]);
+        mem.record_access(&[id]);
+        mem.checkpoint().unwrap();
+    }
+    let mem = Memory::open(&path).unwrap();
+    assert_eq!(mem.get("a").unwrap().access_count, 2);
+}
+
+#[test]
+fn expiry_survives_round_trip() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("mem.db");
+    {
+        let mut mem = Memory::open(&path).unwrap();
+        add(&mut mem, "a", "alpha", &[], EntryKind::Note);
+        add(&mut mem, "b", "beta", &[], EntryKind::Note);
+        mem.apply(Op::Expire {
+            name: "a".into(),
+            expires_at: Some(u64::MAX),
+        })
+        .unwrap();
+    }
+    let mem = Memory::open(&path).unwrap();
+    assert_eq!(mem.get("a").unwrap().expires_at, Some(u64::MAX));
+    assert_eq!(mem.get("b").unwrap().expires_at, None);
+}
+
+#[test]
+fn pin_survives_round_trip() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("mem.db");
+    {
+        let mut mem = Memory::open(&path).unwrap();
+        add(&mut mem, "a", "alpha", &[], EntryKind::Note);
+        add(&mut mem, "b", "beta", &[], EntryKind::Note);
+        mem.apply(Op::Pin {
+            name: "a".into(),
+            pinned: true,
+        })
+        .unwrap();
+    }
+    let mem = Memory::open(&path).unwrap();
+    assert!(mem.get("a").unwrap().pinned);
+    assert!(!mem.get("b").unwrap().pinned);
+}
+
+#[test]
+fn apply_batch_persists() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("mem.db");
+    {
+        let mut mem = Memory::open(&path).unwrap();
+        let ops = ["a", "b", "c"].map(|name| Op::Add {
+            name: name.into(),
+            content: format!("{name} content"),
+            aliases: vec![],
+            kind: EntryKind::Note,
+        });
+        mem.apply_batch(ops).unwrap();
+    }
+    let mem = Memory::open(&path).unwrap();
+    assert_eq!(mem.len(), 3);
+    assert_eq!(mem.search("content", 5).len(), 3);
+}
+
+#[test]
+fn save_as_writes_an_in_ram_db() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("saved.db");
+    let mut mem = Memory::new();
+    add(&mut mem, "a", "alpha", &["first"], EntryKind::Note);
+    mem.save_as(&path).unwrap();
+
+    // Still in-RAM: later writes do not reach the saved file.
+    add(&mut mem, "b", "beta", &[], EntryKind::Note);
+    let saved = Memory::open(&path).unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved.get("a").unwrap().aliases, vec!["first"]);
+    assert_eq!(saved.search("alpha", 5).len(), 1);
+}
+
 /// Byte-for-byte fixture. Regression guard against silent format drift.
 /// A single entry: id=1, created_at=0x1122334455667788, kind=Archive,
 /// name="hi", content="yo", one alias "hey". next_id=2.
@@ -253,4 +342,5 @@ fn known_good_fixture() {
     assert_eq!(e.kind, EntryKind::Archive);
     assert_eq!(e.content, "yo");
     assert_eq!(e.aliases, vec!["hey"]);
+    assert_eq!(e.access_count, 0);
 }
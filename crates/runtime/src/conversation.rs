@@ -19,6 +19,9 @@ pub struct Conversation {
     /// When this conversation was loaded/created in this process.
     /// Process-local — resets across restarts.
     pub created_at: Instant,
+    /// When the last turn on this conversation finished. Drives idle
+    /// eviction. Process-local, like `created_at`.
+    pub last_active: Instant,
     /// Persisted RFC3339 creation timestamp. Populated at construction
     /// and overwritten on resume from `ConversationMeta.created_at`;
     /// never bumped after that.
@@ -40,6 +43,7 @@ impl Conversation {
             history: Vec::new(),
             title: String::new(),
             created_at: Instant::now(),
+            last_active: Instant::now(),
             created_at_iso: chrono::Utc::now().to_rfc3339(),
             summary: None,
             handle: None,
@@ -3,8 +3,15 @@
 use super::Runtime;
 use crate::{Config, Env, Hook};
 use anyhow::Result;
-use std::sync::Arc;
-use wcore::{Agent, AgentBuilder, AgentConfig, AgentId, ToolDispatcher, paths, storage::Storage};
+use chrono::SecondsFormat;
+use std::{borrow::Cow, collections::BTreeMap, sync::Arc};
+use wcore::{
+    Agent, AgentBuilder, AgentConfig, AgentId, PromptVars, RuntimeError, ToolDispatcher,
+    agent::template::render_prompt,
+    model::{HistoryEntry, estimate_text_tokens},
+    paths,
+    storage::Storage,
+};
 
 impl<C: Config> Runtime<C> {
     pub fn add_agent(&self, config: AgentConfig) {
@@ -14,6 +21,9 @@ impl<C: Config> Runtime<C> {
     pub fn upsert_agent(&self, config: AgentConfig) -> AgentConfig {
         let (name, agent) = self.build_agent(config);
         let registered = agent.config.clone();
+        if let Err(e) = self.check_prompt_budget(&registered) {
+            tracing::error!("{e}");
+        }
         // Fire the hook before insert so the invariant "visible via .agent()
         // ⇒ tracked by hooks" holds. Same rationale in reverse for remove_agent.
         self.env.hook().on_register_agent(&name, &registered);
@@ -29,7 +39,10 @@ impl<C: Config> Runtime<C> {
         removed
     }
 
-    fn build_agent(&self, config: AgentConfig) -> (String, Agent<C::Provider>) {
+    fn build_agent(&self, mut config: AgentConfig) -> (String, Agent<C::Provider>) {
+        if config.prompt_vars != PromptVars::Off {
+            config.prompt_template = config.system_prompt.clone();
+        }
         let config = self.env.hook().on_build_agent(config);
         let name = config.name.clone();
         let tools = self.tools.filtered_snapshot(&config.tools);
@@ -38,6 +51,9 @@ impl<C: Config> Runtime<C> {
             .config(config)
             .tools(tools)
             .dispatcher(dispatcher)
+            .max_parallel_tools(self.max_parallel_tools)
+            .tool_timeouts(self.tool_timeouts.clone())
+            .max_tool_calls(self.max_tool_calls)
             .build();
         (name, agent)
     }
@@ -73,6 +89,185 @@ impl<C: Config> Runtime<C> {
         self.ephemeral_agents.read().await.get(name).cloned()
     }
 
+    /// Widen a resolved agent with the tools [`Hook::turn_tools`] grants
+    /// for this turn. Agents without a tool whitelist already see every
+    /// tool and are returned as-is.
+    pub(crate) fn grant_turn_tools(
+        &self,
+        mut agent: Agent<C::Provider>,
+        conversation_id: u64,
+        history: &[HistoryEntry],
+    ) -> Agent<C::Provider> {
+        let granted = self
+            .env
+            .hook()
+            .turn_tools(&agent.config.name, conversation_id, history);
+        if granted.is_empty() || agent.config.tools.is_empty() {
+            return agent;
+        }
+        agent.extend_tools(self.tools.filtered_snapshot(&granted));
+        agent
+    }
+
+    /// Fill in a resolved agent's system prompt placeholders for this turn
+    /// when its [`PromptVars`] mode asks for it. Built-ins are `agent`,
+    /// `sender`, `date`, `datetime`, and `conversation_id` outside
+    /// stateless runs; [`Hook::prompt_vars`] entries override them. Only
+    /// the agent's own prompt is rendered: it replaces the template in the
+    /// prompt hooks assembled when the agent was built, so fragments such
+    /// as memory and skills stay as written and the build hook is not run
+    /// again.
+    pub(crate) fn resolve_prompt_vars(
+        &self,
+        mut agent: Agent<C::Provider>,
+        conversation_id: Option<u64>,
+        sender: &str,
+    ) -> Result<Agent<C::Provider>, RuntimeError> {
+        let strict = match agent.config.prompt_vars {
+            PromptVars::Off => return Ok(agent),
+            PromptVars::Lenient => false,
+            PromptVars::Strict => true,
+        };
+        let name = agent.config.name.clone();
+        let now = chrono::Local::now();
+        let mut vars = BTreeMap::from([
+            ("agent".to_owned(), name.clone()),
+            ("sender".to_owned(), sender.to_owned()),
+            ("date".to_owned(), now.format("%Y-%m-%d").to_string()),
+            (
+                "datetime".to_owned(),
+                now.to_rfc3339_opts(SecondsFormat::Secs, false),
+            ),
+        ]);
+        if let Some(id) = conversation_id {
+            vars.insert("conversation_id".to_owned(), id.to_string());
+        }
+        vars.extend(self.env.hook().prompt_vars(&name, conversation_id, sender));
+        let rendered =
+            render_prompt(&agent.config.prompt_template, &vars, strict).map_err(|var| {
+                RuntimeError::MissingPromptVar {
+                    agent: name,
+                    name: var,
+                }
+            })?;
+        agent.config.system_prompt =
+            agent
+                .config
+                .system_prompt
+                .replacen(&agent.config.prompt_template, &rendered, 1);
+        Ok(agent)
+    }
+
+    /// Check that `name`'s assembled system prompt — base prompt plus
+    /// injected memory and skill blocks — fits its model's context
+    /// window. Warns when it takes over half; errors when it overflows
+    /// a window the endpoint advertised, and only warns otherwise.
+    pub fn validate_agent(&self, name: &str) -> Result<()> {
+        let config = self
+            .agent(name)
+            .ok_or_else(|| RuntimeError::AgentNotRegistered(name.to_owned()))?;
+        self.check_prompt_budget(&config)?;
+        Ok(())
+    }
+
+    /// The model an agent's turns run on: its own, else the active one.
+    fn agent_model(&self, config: &AgentConfig) -> String {
+        if config.model.is_empty() {
+            self.active_model()
+        } else {
+            config.model.clone()
+        }
+    }
+
+    /// `(prompt tokens, context window, advertised)` for an agent whose
+    /// model is known. `advertised` is false when the window is only the
+    /// built-in estimate for the model family.
+    fn prompt_budget(&self, config: &AgentConfig) -> Option<(usize, usize, bool)> {
+        let model = self.agent_model(config);
+        if model.is_empty() {
+            return None;
+        }
+        let tokens = estimate_text_tokens(&config.system_prompt);
+        Some(match self.advertised_context_limit(&model) {
+            Some(limit) => (tokens, limit, true),
+            None => (tokens, self.context_limit(&model), false),
+        })
+    }
+
+    fn check_prompt_budget(&self, config: &AgentConfig) -> Result<(), RuntimeError> {
+        self.ensure_prompt_fits(config)?;
+        if let Some((tokens, limit, _)) = self.prompt_budget(config)
+            && tokens > limit / 2
+            && tokens <= limit
+        {
+            tracing::warn!(
+                agent = %config.name,
+                tokens,
+                limit,
+                "system prompt takes over half the context window"
+            );
+        }
+        Ok(())
+    }
+
+    /// Refuse a turn whose system prompt alone overflows the advertised
+    /// context window — the provider would reject every request anyway.
+    /// Against a built-in estimate the window may be wrong, so an
+    /// overflow only warns.
+    pub(crate) fn ensure_prompt_fits(&self, config: &AgentConfig) -> Result<(), RuntimeError> {
+        match self.prompt_budget(config) {
+            Some((tokens, limit, true)) if tokens > limit => Err(RuntimeError::PromptTooLarge {
+                agent: config.name.clone(),
+                tokens,
+                limit,
+            }),
+            Some((tokens, limit, false)) if tokens > limit => {
+                tracing::warn!(
+                    agent = %config.name,
+                    tokens,
+                    limit,
+                    "system prompt may overflow the estimated context window"
+                );
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The text and images a turn hands the model. Images for a model
+    /// the endpoint says has no vision are dropped with a warning, and
+    /// the text notes how many were left out.
+    pub(crate) fn visible_images<'a>(
+        &self,
+        config: &AgentConfig,
+        content: &'a str,
+        images: &'a [String],
+    ) -> (Cow<'a, str>, &'a [String]) {
+        if images.is_empty() {
+            return (content.into(), images);
+        }
+        let model = self.agent_model(config);
+        if self.supports_vision(&model) != Some(false) {
+            return (content.into(), images);
+        }
+        tracing::warn!(
+            agent = %config.name,
+            model,
+            dropped = images.len(),
+            "model has no vision support, dropping images"
+        );
+        let note = format!(
+            "[{} image(s) omitted: {model} cannot see images]",
+            images.len()
+        );
+        let content = if content.is_empty() {
+            note
+        } else {
+            format!("{content}\n\n{note}")
+        };
+        (content.into(), &[])
+    }
+
     pub(crate) async fn has_agent(&self, name: &str) -> bool {
         let has_persistent = self.agents.read().contains_key(name);
         if has_persistent {
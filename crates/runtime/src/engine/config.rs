@@ -1,8 +1,14 @@
-//! Storage-backed configuration queries — active model, model listing.
+//! Storage-backed configuration queries — active model, model listing,
+//! context windows — and provider swapping.
 
 use super::Runtime;
 use crate::Config;
-use wcore::{paths, protocol::message::ModelInfo, storage::Storage};
+use wcore::{
+    model::{Model, default_context_limit},
+    paths,
+    protocol::message::ModelInfo,
+    storage::Storage,
+};
 
 impl<C: Config> Runtime<C> {
     /// The active model — defined as the default agent's `model` field.
@@ -18,21 +24,74 @@ impl<C: Config> Runtime<C> {
 
     /// Set the cached model list — called by the daemon builder after
     /// fetching `/v1/models` from the LLM endpoint at startup / reload.
-    pub fn set_models(&self, names: Vec<String>) {
-        *self.models.write() = names;
+    /// `active` is ignored; [`Runtime::list_models`] computes it. A
+    /// `context_length` of 0 means the endpoint did not advertise one.
+    pub fn set_models(&self, models: Vec<ModelInfo>) {
+        *self.models.write() = models;
     }
 
     /// List models advertised by the configured LLM endpoint at startup
-    /// (or last reload). Flags the currently active model.
+    /// (or last reload). Flags the currently active model and fills in
+    /// the estimated window where none was advertised.
     pub fn list_models(&self) -> Vec<ModelInfo> {
         let active_model = self.active_model();
         self.models
             .read()
             .iter()
-            .map(|name| ModelInfo {
-                name: name.clone(),
-                active: *name == active_model,
+            .map(|model| ModelInfo {
+                active: model.name == active_model,
+                context_length: match model.context_length {
+                    0 => default_context_limit(&model.name) as u64,
+                    advertised => advertised,
+                },
+                ..model.clone()
             })
             .collect()
     }
+
+    /// Context window of `model` in tokens: as advertised by the
+    /// endpoint, else the built-in estimate for the model family.
+    pub fn context_limit(&self, model: &str) -> usize {
+        self.advertised_context_limit(model)
+            .unwrap_or_else(|| default_context_limit(model))
+    }
+
+    /// Context window of `model` in tokens, only if the endpoint
+    /// advertised one.
+    pub fn advertised_context_limit(&self, model: &str) -> Option<usize> {
+        self.models
+            .read()
+            .iter()
+            .find(|m| m.name == model && m.context_length > 0)
+            .map(|m| m.context_length as usize)
+    }
+
+    /// Whether `model` takes image input, if the endpoint said.
+    pub fn supports_vision(&self, model: &str) -> Option<bool> {
+        self.models
+            .read()
+            .iter()
+            .find(|m| m.name == model)
+            .and_then(|m| m.vision)
+    }
+
+    /// Replace the LLM provider — e.g. fail over from a local model to a
+    /// remote one — keeping agents, conversations and memory. Every
+    /// registered and ephemeral agent is pointed at the new provider in
+    /// place; no hook runs and agent configs are untouched.
+    ///
+    /// Takes `&mut self`: a runtime reachable only through a shared
+    /// reference, such as an `Arc`, cannot be swapped, and there is no
+    /// interior locking around the provider. Turns already running hold
+    /// their own agent clone and finish on the old provider; only turns
+    /// started after the swap use the new one.
+    pub fn set_provider(&mut self, provider: C::Provider) {
+        self.model = Model::new(provider);
+        for agent in self.agents.get_mut().values_mut() {
+            agent.set_model(self.model.clone());
+        }
+        for agent in self.ephemeral_agents.get_mut().values_mut() {
+            agent.set_model(self.model.clone());
+        }
+    }
 }
@@ -1,13 +1,16 @@
 //! Conversation management — lifecycle, persistence, and title generation.
 
 use super::{ConvSlot, Runtime};
-use crate::{Config, Conversation, ConversationHandle};
-use anyhow::{Result, bail};
+use crate::{Config, Conversation, ConversationHandle, Env, Hook};
+use anyhow::Result;
 use crabllm_core::{ChatCompletionRequest, Message, Role};
 use memory::{EntryKind, Op};
-use std::sync::{Arc, atomic::Ordering};
+use std::{
+    sync::{Arc, atomic::Ordering},
+    time::{Duration, Instant},
+};
 use tokio::sync::Mutex;
-use wcore::{model::HistoryEntry, storage::Storage};
+use wcore::{RuntimeError, model::HistoryEntry, storage::Storage};
 
 impl<C: Config> Runtime<C> {
     pub(super) fn new_slot(id: u64, agent: &str, created_by: &str) -> ConvSlot {
@@ -20,10 +23,11 @@ impl<C: Config> Runtime<C> {
 
     /// Get or create a conversation for the given (agent, created_by)
     /// identity. Returns the existing conversation for this pair if one
-    /// is active, otherwise allocates a fresh id and persists.
+    /// is active, resumes its session if it was evicted for idleness,
+    /// and otherwise allocates a fresh id.
     pub async fn get_or_create_conversation(&self, agent: &str, created_by: &str) -> Result<u64> {
         if !self.has_agent(agent).await {
-            bail!("agent '{agent}' not registered");
+            return Err(RuntimeError::AgentNotRegistered(agent.to_owned()).into());
         }
 
         // Read-first: a single active conversation per (agent, sender)
@@ -33,6 +37,17 @@ impl<C: Config> Runtime<C> {
             return Ok(id);
         }
 
+        let evicted = self
+            .evicted
+            .lock()
+            .remove(&(agent.to_owned(), created_by.to_owned()));
+        if let Some(handle) = evicted {
+            match self.load(handle).await {
+                Ok(id) => return Ok(id),
+                Err(e) => tracing::warn!("resume after eviction failed, starting fresh: {e}"),
+            }
+        }
+
         let id = self.next_conversation_id.fetch_add(1, Ordering::Relaxed);
         let slot = Self::new_slot(id, agent, created_by);
         self.conversations.write().await.insert(id, slot);
@@ -44,9 +59,9 @@ impl<C: Config> Runtime<C> {
         let storage = self.storage();
         let snapshot = storage
             .load_session(&handle)?
-            .ok_or_else(|| anyhow::anyhow!("conversation '{}' not found", handle.as_str()))?;
+            .ok_or_else(|| RuntimeError::ConversationNotFound(format!("'{}'", handle.as_str())))?;
         if !self.has_agent(&snapshot.meta.agent).await {
-            bail!("agent '{}' not registered", snapshot.meta.agent);
+            return Err(RuntimeError::AgentNotRegistered(snapshot.meta.agent).into());
         }
         let id = self.next_conversation_id.fetch_add(1, Ordering::Relaxed);
         let slot = Self::new_slot(id, &snapshot.meta.agent, &snapshot.meta.created_by);
@@ -65,6 +80,39 @@ impl<C: Config> Runtime<C> {
         Ok(id)
     }
 
+    /// Fork conversation `id` into a new conversation for the same
+    /// agent, addressed by `created_by`. The fork starts from a copy of
+    /// the history, title and summary, is persisted as its own session,
+    /// and evolves independently from then on. A turn running on the
+    /// source finishes before the copy is taken.
+    pub async fn fork(&self, id: u64, created_by: &str) -> Result<u64> {
+        let (agent, _, source) = self
+            .acquire_slot(id)
+            .await
+            .ok_or_else(|| RuntimeError::ConversationNotFound(id.to_string()))?;
+        if self.conversation_id(&agent, created_by).await.is_some() {
+            anyhow::bail!("conversation already exists for agent='{agent}' sender='{created_by}'");
+        }
+
+        let new_id = self.next_conversation_id.fetch_add(1, Ordering::Relaxed);
+        let slot = Self::new_slot(new_id, &agent, created_by);
+        {
+            let source = source.lock().await;
+            let mut fork = slot.inner.lock().await;
+            fork.history = source
+                .history
+                .iter()
+                .filter(|e| !e.auto_injected)
+                .cloned()
+                .collect();
+            fork.title = source.title.clone();
+            fork.summary = source.summary.clone();
+            self.persist_messages(&mut fork, &agent, created_by, 0, None, &[]);
+        }
+        self.conversations.write().await.insert(new_id, slot);
+        Ok(new_id)
+    }
+
     pub async fn list_active(&self) -> Vec<wcore::protocol::message::ActiveConversationInfo> {
         // Snapshot the slot metadata and mutex handles first so the
         // outer read guard isn't held across per-conversation locks —
@@ -91,16 +139,38 @@ impl<C: Config> Runtime<C> {
         infos
     }
 
+    /// Drop a conversation. A turn still running on it is aborted: its
+    /// tool handlers see their cancellation token fire.
     pub async fn close(&self, id: u64) -> bool {
         self.steering.write().await.remove(&id);
-        self.conversations.write().await.remove(&id).is_some()
+        if let Some(cancel) = self.cancellations.write().await.remove(&id) {
+            cancel.cancel();
+        }
+        let closed = self.conversations.write().await.remove(&id).is_some();
+        if closed {
+            self.env.hook().on_close(id);
+        }
+        closed
+    }
+
+    /// Abort the turn running on a conversation, keeping the conversation.
+    /// The model stream and any running tools are dropped; the history the
+    /// turn built so far is persisted as usual and the turn ends with
+    /// [`AgentStopReason::Cancelled`](wcore::AgentStopReason::Cancelled).
+    pub async fn cancel(&self, conversation_id: u64) -> Result<()> {
+        let cancellations = self.cancellations.read().await;
+        let token = cancellations
+            .get(&conversation_id)
+            .ok_or(RuntimeError::NoActiveStream(conversation_id))?;
+        token.cancel();
+        Ok(())
     }
 
     pub async fn steer(&self, conversation_id: u64, content: String) -> Result<()> {
         let senders = self.steering.read().await;
-        let tx = senders.get(&conversation_id).ok_or_else(|| {
-            anyhow::anyhow!("no active stream for conversation {conversation_id}")
-        })?;
+        let tx = senders
+            .get(&conversation_id)
+            .ok_or(RuntimeError::NoActiveStream(conversation_id))?;
         tx.send(Some(content))
             .map_err(|_| anyhow::anyhow!("steering channel closed"))?;
         Ok(())
@@ -138,6 +208,37 @@ impl<C: Config> Runtime<C> {
             .collect()
     }
 
+    /// Drop every conversation with no finished turn for at least
+    /// `ttl`, skipping any whose agent is in `pinned`, and return their
+    /// ids. A conversation some caller holds a handle to (a turn running
+    /// or about to start, title generation in flight) is never idle.
+    /// Each evicted pair's session is resumed by its next
+    /// [`Runtime::get_or_create_conversation`].
+    pub async fn evict_idle(&self, ttl: Duration, pinned: &[String]) -> Vec<u64> {
+        // The write lock stops `acquire_slot` from handing out new
+        // handles, so an unshared, idle slot stays idle until removed.
+        let mut conversations = self.conversations.write().await;
+        let idle: Vec<(u64, Option<ConversationHandle>)> = conversations
+            .iter()
+            .filter(|(_, s)| !pinned.contains(&s.agent) && Arc::strong_count(&s.inner) == 1)
+            .filter_map(|(id, s)| {
+                let c = s.inner.try_lock().ok()?;
+                (c.last_active.elapsed() >= ttl).then(|| (*id, c.handle.clone()))
+            })
+            .collect();
+        let mut evicted = self.evicted.lock();
+        for (id, handle) in &idle {
+            let Some(slot) = conversations.remove(id) else {
+                continue;
+            };
+            if let Some(handle) = handle.clone() {
+                evicted.insert((slot.agent, slot.created_by), handle);
+            }
+            self.env.hook().on_close(*id);
+        }
+        idle.into_iter().map(|(id, _)| id).collect()
+    }
+
     pub async fn conversation_count(&self) -> usize {
         self.conversations.read().await.len()
     }
@@ -157,18 +258,22 @@ impl<C: Config> Runtime<C> {
     /// by conversation identity.
     pub async fn require_conversation_id(&self, agent: &str, sender: &str) -> Result<u64> {
         self.conversation_id(agent, sender).await.ok_or_else(|| {
-            anyhow::anyhow!("conversation not found for agent='{agent}' sender='{sender}'")
+            RuntimeError::ConversationNotFound(format!("for agent='{agent}' sender='{sender}'"))
+                .into()
         })
     }
 
-    /// Compact the conversation identified by (agent, sender). Errors if
-    /// no conversation exists for that pair or if compaction fails (empty
-    /// history, agent gone, etc.).
-    pub async fn compact_conversation(&self, agent: &str, sender: &str) -> Result<String> {
-        let id = self.require_conversation_id(agent, sender).await?;
-        self.compact(id)
-            .await
-            .ok_or_else(|| anyhow::anyhow!("compact failed for agent='{agent}' sender='{sender}'"))
+    /// Compact the conversation identified by (agent, sender), returning
+    /// the summary. With no conversation for that pair there is nothing
+    /// to compact and `None` comes back. Errors if compaction fails
+    /// (empty history, agent gone, etc.).
+    pub async fn compact_conversation(&self, agent: &str, sender: &str) -> Result<Option<String>> {
+        let Some(id) = self.conversation_id(agent, sender).await else {
+            return Ok(None);
+        };
+        self.compact(id).await.map(Some).ok_or_else(|| {
+            RuntimeError::Compaction(format!("agent='{agent}' sender='{sender}'")).into()
+        })
     }
 
     /// Steer the conversation identified by (agent, sender) with the given
@@ -183,26 +288,60 @@ impl<C: Config> Runtime<C> {
         self.steer(id, content).await
     }
 
+    /// Cancel the turn running on the conversation identified by (agent,
+    /// sender). Errors if no conversation exists for that pair or no turn
+    /// is running on it.
+    pub async fn cancel_conversation(&self, agent: &str, sender: &str) -> Result<()> {
+        let id = self.require_conversation_id(agent, sender).await?;
+        self.cancel(id).await
+    }
+
+    /// Compact a conversation now, whatever its size: summarize the
+    /// history, replace it with the summary, and persist the result the
+    /// way a mid-turn compaction is. Waits for a running turn to finish
+    /// first. Returns the summary, or `None` if there was nothing to
+    /// compact or the summary call failed.
     pub async fn compact(&self, conversation_id: u64) -> Option<String> {
-        // Release the conversations read lock before the per-conversation
-        // mutex await — otherwise readers queue behind a potentially
-        // contended inner lock.
-        let (agent_name, conversation_mutex) = {
-            let conversations = self.conversations.read().await;
-            let slot = conversations.get(&conversation_id)?;
-            (slot.agent.clone(), slot.inner.clone())
-        };
-        let history = {
-            let conversation = conversation_mutex.lock().await;
-            if conversation.history.is_empty() {
-                return None;
-            }
-            conversation.history.clone()
-        };
-        self.resolve_agent(&agent_name)
-            .await?
-            .compact(&history)
-            .await
+        let (agent_name, created_by, conversation_mutex) =
+            self.acquire_slot(conversation_id).await?;
+        let agent = self.resolve_agent(&agent_name).await?;
+        let mut conversation = conversation_mutex.lock().await;
+        let history: Vec<_> = conversation
+            .history
+            .iter()
+            .filter(|e| !e.auto_injected)
+            .cloned()
+            .collect();
+        if history.is_empty() {
+            return None;
+        }
+        let summary = agent.compact(&history).await?;
+        conversation.history = vec![HistoryEntry::user(&summary)];
+        self.persist_messages(
+            &mut conversation,
+            &agent_name,
+            &created_by,
+            0,
+            Some(summary.clone()),
+            &[],
+        );
+        self.compacted(&agent_name, conversation_id, history.len(), 1);
+        Some(summary)
+    }
+
+    /// Report a finished compaction to the hook. Shared by mid-turn and
+    /// manual compaction.
+    pub(crate) fn compacted(
+        &self,
+        agent: &str,
+        conversation_id: u64,
+        old_len: usize,
+        new_len: usize,
+    ) {
+        tracing::debug!(%agent, conversation_id, old_len, new_len, "conversation compacted");
+        self.env
+            .hook()
+            .on_compacted(agent, conversation_id, old_len, new_len);
     }
 
     pub async fn transfer_to<C2: Config>(&self, dest: &mut Runtime<C2>) {
@@ -213,6 +352,12 @@ impl<C: Config> Runtime<C> {
         }
         let next = self.next_conversation_id.load(Ordering::Relaxed);
         dest.next_conversation_id.store(next, Ordering::Relaxed);
+        dest.evicted.get_mut().extend(
+            self.evicted
+                .lock()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
     }
 
     /// Build the conversation's replay history from storage's post-compact
@@ -312,6 +457,12 @@ impl<C: Config> Runtime<C> {
         compact_summary: Option<String>,
         event_trace: &[wcore::EventLine],
     ) {
+        conversation.last_active = Instant::now();
+        // Inline images were seen this turn; keep their bytes out of
+        // memory and storage from here on.
+        for entry in &mut conversation.history {
+            entry.strip_inline_images();
+        }
         self.persist_messages(
             conversation,
             agent,
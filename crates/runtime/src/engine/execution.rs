@@ -1,14 +1,17 @@
 //! Execution — message sending and streaming through agents.
 
 use super::Runtime;
-use crate::{Config, Conversation, Env, Hook};
+use crate::{Config, Conversation, Env, Hook, Preprocessed};
 use anyhow::Result;
 use async_stream::stream;
-use crabllm_core::{ChatCompletionRequest, Message, ToolChoice};
+use crabllm_core::{ChatCompletionRequest, Message, Role, ToolChoice, Usage};
 use futures_core::Stream;
 use futures_util::StreamExt;
 use tokio::sync::{mpsc, watch};
-use wcore::{AgentEvent, AgentResponse, AgentStopReason, model::HistoryEntry};
+use wcore::{
+    AgentEvent, AgentResponse, AgentStopReason, CancellationToken, RuntimeError,
+    model::HistoryEntry, trace::in_span,
+};
 
 impl<C: Config> Runtime<C> {
     fn prepare_history(
@@ -16,20 +19,21 @@ impl<C: Config> Runtime<C> {
         conversation: &mut Conversation,
         agent: &str,
         content: &str,
+        images: &[String],
         sender: &str,
     ) {
-        let content = self
+        let pre = self
             .env
             .hook()
             .preprocess(agent, content)
-            .unwrap_or_else(|| content.to_owned());
-        if sender.is_empty() {
-            conversation.history.push(HistoryEntry::user(&content));
-        } else {
-            conversation
-                .history
-                .push(HistoryEntry::user_with_sender(&content, sender));
-        }
+            .unwrap_or_else(|| Preprocessed {
+                content: content.to_owned(),
+                skills: Vec::new(),
+            });
+        let mut entry = HistoryEntry::user_with_images(pre.content, images);
+        entry.sender = sender.to_owned();
+        entry.skills = pre.skills;
+        conversation.history.push(entry);
 
         conversation.history.retain(|e| !e.auto_injected);
 
@@ -66,35 +70,93 @@ impl<C: Config> Runtime<C> {
         }
     }
 
+    /// Trim trailing whitespace off user content, refusing a message that
+    /// has nothing left and no images. Runs before any conversation state
+    /// is touched.
+    fn user_content<'a>(content: &'a str, images: &[String]) -> Result<&'a str, RuntimeError> {
+        let content = content.trim_end();
+        if content.is_empty() && images.is_empty() {
+            return Err(RuntimeError::EmptyMessage);
+        }
+        Ok(content)
+    }
+
+    /// Register a fresh cancellation token for the turn starting on
+    /// `conversation_id`. [`Runtime::close`] cancels it.
+    async fn begin_turn(&self, conversation_id: u64) -> CancellationToken {
+        let cancel = CancellationToken::new();
+        self.cancellations
+            .write()
+            .await
+            .insert(conversation_id, cancel.clone());
+        cancel
+    }
+
+    /// Run one turn on a conversation. A non-empty `prefill` seeds the
+    /// start of the reply; the model continues from it.
+    #[tracing::instrument(
+        name = "turn",
+        skip_all,
+        fields(conversation_id, sender, agent = tracing::field::Empty)
+    )]
     pub async fn send_to(
         &self,
         conversation_id: u64,
         content: &str,
+        images: &[String],
         sender: &str,
         tool_choice: Option<ToolChoice>,
+        prefill: Option<&str>,
     ) -> Result<AgentResponse> {
+        let content = Self::user_content(content, images)?;
         let (agent_name, created_by, conversation_mutex) = self
             .acquire_slot(conversation_id)
             .await
-            .ok_or_else(|| anyhow::anyhow!("conversation {conversation_id} not found"))?;
+            .ok_or_else(|| RuntimeError::ConversationNotFound(conversation_id.to_string()))?;
+        tracing::Span::current().record("agent", agent_name.as_str());
 
         let mut conversation = conversation_mutex.lock().await;
         let pre_run_len = conversation.history.len();
-        self.prepare_history(&mut conversation, &agent_name, content, sender);
+        // Refusals happen before the user entry lands in history.
         let agent = self
             .resolve_agent(&agent_name)
             .await
-            .ok_or_else(|| anyhow::anyhow!("agent '{}' not registered", agent_name))?;
+            .ok_or_else(|| RuntimeError::AgentNotRegistered(agent_name.clone()))?;
+        let agent = self.resolve_prompt_vars(agent, Some(conversation_id), sender)?;
+        self.ensure_prompt_fits(&agent.config)?;
+        let (content, images) = self.visible_images(&agent.config, content, images);
+        self.prepare_history(&mut conversation, &agent_name, &content, images, sender);
+        let agent = self.grant_turn_tools(agent, conversation_id, &conversation.history);
+        if let Some(prefill) = prefill.filter(|p| !p.is_empty()) {
+            conversation
+                .history
+                .push(HistoryEntry::assistant(prefill, None, None));
+        }
 
+        let cancel = self.begin_turn(conversation_id).await;
+        let abort_on_drop = cancel.clone().drop_guard();
         let (tx, mut rx) = mpsc::unbounded_channel();
         let response = agent
-            .run(&mut conversation.history, tx, None, tool_choice)
+            .run(
+                &mut conversation.history,
+                tx,
+                None,
+                tool_choice,
+                Some(cancel),
+            )
             .await;
+        self.cancellations.write().await.remove(&conversation_id);
+        abort_on_drop.disarm();
 
         let mut compact_summary: Option<String> = None;
         while let Ok(event) = rx.try_recv() {
-            if let AgentEvent::Compact { ref summary } = event {
+            if let AgentEvent::Compact {
+                ref summary,
+                replaced,
+            } = event
+            {
                 compact_summary = Some(summary.clone());
+                self.compacted(&agent_name, conversation_id, replaced, 1);
             }
             self.env
                 .hook()
@@ -116,73 +178,183 @@ impl<C: Config> Runtime<C> {
         Ok(response)
     }
 
+    /// Run `agent` over a caller-owned history without touching any
+    /// conversation. Nothing is persisted and no hook events fire — the
+    /// caller keeps the history and decides what to do with it. End the
+    /// history with an assistant entry to prefill the reply.
+    #[tracing::instrument(name = "turn", skip_all, fields(agent))]
+    pub async fn send_stateless(
+        &self,
+        agent: &str,
+        history: &mut Vec<HistoryEntry>,
+    ) -> Result<AgentResponse> {
+        if history.last().is_none_or(is_blank_user) {
+            return Err(RuntimeError::EmptyMessage.into());
+        }
+        let agent = self
+            .resolve_agent(agent)
+            .await
+            .ok_or_else(|| RuntimeError::AgentNotRegistered(agent.to_owned()))?;
+        let agent = self.resolve_prompt_vars(agent, None, "")?;
+        self.ensure_prompt_fits(&agent.config)?;
+        let (tx, _rx) = mpsc::unbounded_channel();
+        Ok(agent.run(history, tx, None, None, None).await)
+    }
+
+    /// Streaming counterpart of [`Runtime::send_stateless`]: the same
+    /// tool loop over a caller-owned history, with no conversation,
+    /// persistence, compaction or hook events.
+    ///
+    /// The stream holds `history` mutably borrowed until it is dropped.
+    /// Entries are appended as the run goes, so once the stream ends the
+    /// history holds the assistant reply and every tool call and result.
+    /// Dropping the stream early stops the run and leaves the history
+    /// with whatever the finished steps appended. Errors arrive as a
+    /// single [`AgentEvent::Done`] carrying an error stop reason.
+    pub fn stream_stateless<'a>(
+        &'a self,
+        agent: &'a str,
+        history: &'a mut Vec<HistoryEntry>,
+    ) -> impl Stream<Item = AgentEvent> + 'a {
+        let span = tracing::info_span!("turn", agent);
+        in_span(
+            span,
+            stream! {
+                if history.last().is_none_or(is_blank_user) {
+                    yield AgentEvent::Done(AgentResponse::error(RuntimeError::EmptyMessage.to_string()));
+                    return;
+                }
+                let Some(agent) = self.resolve_agent(agent).await else {
+                    yield AgentEvent::Done(AgentResponse::error(
+                        RuntimeError::AgentNotRegistered(agent.to_owned()).to_string(),
+                    ));
+                    return;
+                };
+                let agent = match self.resolve_prompt_vars(agent, None, "") {
+                    Ok(agent) => agent,
+                    Err(e) => {
+                        yield AgentEvent::Done(AgentResponse::error(e.to_string()));
+                        return;
+                    }
+                };
+                if let Err(e) = self.ensure_prompt_fits(&agent.config) {
+                    yield AgentEvent::Done(AgentResponse::error(e.to_string()));
+                    return;
+                }
+                let mut events = std::pin::pin!(agent.run_stream(history, None, None, None, None));
+                while let Some(event) = events.next().await {
+                    yield event;
+                }
+            },
+        )
+    }
+
     pub fn stream_to(
         &self,
         conversation_id: u64,
         content: &str,
+        images: &[String],
         sender: &str,
         tool_choice: Option<ToolChoice>,
     ) -> impl Stream<Item = AgentEvent> + '_ {
-        let content = content.to_owned();
+        let content = Self::user_content(content, images).map(str::to_owned);
+        let images = images.to_vec();
         let sender = sender.to_owned();
-        stream! {
-            let Some((agent_name, created_by, conversation_mutex)) =
-                self.acquire_slot(conversation_id).await
-            else {
-                yield AgentEvent::Done(AgentResponse::error(
-                    format!("conversation {conversation_id} not found"),
-                ));
-                return;
-            };
-
-            let mut conversation = conversation_mutex.lock().await;
-            let pre_run_len = conversation.history.len();
-            self.prepare_history(&mut conversation, &agent_name, &content, &sender);
-            let Some(agent) = self.resolve_agent(&agent_name).await else {
-                yield AgentEvent::Done(AgentResponse::error(
-                    format!("agent '{}' not registered", agent_name),
-                ));
-                return;
-            };
-
-            let (steer_tx, steer_rx) = watch::channel(None::<String>);
-            self.steering.write().await.insert(conversation_id, steer_tx);
-            let mut compact_summary: Option<String> = None;
-            let mut done_event: Option<AgentEvent> = None;
-            let mut event_trace: Vec<wcore::EventLine> = Vec::new();
-            {
-                let mut event_stream = std::pin::pin!(agent.run_stream(&mut conversation.history, Some(conversation_id), Some(steer_rx), tool_choice));
-                while let Some(event) = event_stream.next().await {
-                    if let AgentEvent::Compact { ref summary } = event {
-                        compact_summary = Some(summary.clone());
+        let span = tracing::info_span!(
+            "turn",
+            conversation_id,
+            sender,
+            agent = tracing::field::Empty
+        );
+        in_span(
+            span,
+            stream! {
+                let content = match content {
+                    Ok(content) => content,
+                    Err(e) => {
+                        yield AgentEvent::Done(AgentResponse::error(e.to_string()));
+                        return;
                     }
-                    self.env.hook().on_event(&agent_name, conversation_id, &event);
-                    self.env.on_agent_event(&agent_name, conversation_id, &event);
-                    if let Some(line) = wcore::EventLine::from_agent_event(&event) {
-                        event_trace.push(line);
+                };
+                let Some((agent_name, created_by, conversation_mutex)) =
+                    self.acquire_slot(conversation_id).await
+                else {
+                    yield AgentEvent::Done(AgentResponse::error(
+                        RuntimeError::ConversationNotFound(conversation_id.to_string()).to_string(),
+                    ));
+                    return;
+                };
+                tracing::Span::current().record("agent", agent_name.as_str());
+
+                let mut conversation = conversation_mutex.lock().await;
+                let pre_run_len = conversation.history.len();
+                // Refusals happen before the user entry lands in history.
+                let Some(agent) = self.resolve_agent(&agent_name).await else {
+                    yield AgentEvent::Done(AgentResponse::error(
+                        RuntimeError::AgentNotRegistered(agent_name.clone()).to_string(),
+                    ));
+                    return;
+                };
+                let agent = match self.resolve_prompt_vars(agent, Some(conversation_id), &sender) {
+                    Ok(agent) => agent,
+                    Err(e) => {
+                        yield AgentEvent::Done(AgentResponse::error(e.to_string()));
+                        return;
                     }
-                    if matches!(event, AgentEvent::Done(_)) {
-                        done_event = Some(event);
-                    } else {
-                        yield event;
+                };
+                if let Err(e) = self.ensure_prompt_fits(&agent.config) {
+                    yield AgentEvent::Done(AgentResponse::error(e.to_string()));
+                    return;
+                }
+                let (content, images) = self.visible_images(&agent.config, &content, &images);
+                self.prepare_history(&mut conversation, &agent_name, &content, images, &sender);
+                let agent = self.grant_turn_tools(agent, conversation_id, &conversation.history);
+
+                let (steer_tx, steer_rx) = watch::channel(None::<String>);
+                self.steering.write().await.insert(conversation_id, steer_tx);
+                let cancel = self.begin_turn(conversation_id).await;
+                // A dropped stream (client gone) aborts the turn's tools too.
+                let abort_on_drop = cancel.clone().drop_guard();
+                let mut compact_summary: Option<String> = None;
+                let mut done_event: Option<AgentEvent> = None;
+                let mut event_trace: Vec<wcore::EventLine> = Vec::new();
+                {
+                    let mut event_stream = std::pin::pin!(agent.run_stream(&mut conversation.history, Some(conversation_id), Some(steer_rx), tool_choice, Some(cancel)));
+                    while let Some(event) = event_stream.next().await {
+                        if let AgentEvent::Compact { ref summary, replaced } = event {
+                            compact_summary = Some(summary.clone());
+                            self.compacted(&agent_name, conversation_id, replaced, 1);
+                        }
+                        self.env.hook().on_event(&agent_name, conversation_id, &event);
+                        self.env.on_agent_event(&agent_name, conversation_id, &event);
+                        if let Some(line) = wcore::EventLine::from_agent_event(&event) {
+                            event_trace.push(line);
+                        }
+                        if matches!(event, AgentEvent::Done(_)) {
+                            done_event = Some(event);
+                        } else {
+                            yield event;
+                        }
                     }
                 }
-            }
-            self.steering.write().await.remove(&conversation_id);
-            self.finalize_run(
-                conversation_id,
-                &mut conversation,
-                conversation_mutex.clone(),
-                &agent_name,
-                &created_by,
-                pre_run_len,
-                compact_summary,
-                &event_trace,
-            );
-            if let Some(event) = done_event {
-                yield event;
-            }
-        }
+                self.steering.write().await.remove(&conversation_id);
+                self.cancellations.write().await.remove(&conversation_id);
+                abort_on_drop.disarm();
+                self.finalize_run(
+                    conversation_id,
+                    &mut conversation,
+                    conversation_mutex.clone(),
+                    &agent_name,
+                    &created_by,
+                    pre_run_len,
+                    compact_summary,
+                    &event_trace,
+                );
+                if let Some(event) = done_event {
+                    yield event;
+                }
+            },
+        )
     }
 
     pub fn guest_stream_to(
@@ -192,10 +364,17 @@ impl<C: Config> Runtime<C> {
         sender: &str,
         guest: &str,
     ) -> impl Stream<Item = AgentEvent> + '_ {
-        let content = content.to_owned();
+        let content = Self::user_content(content, &[]).map(str::to_owned);
         let sender = sender.to_owned();
         let guest = guest.to_owned();
         stream! {
+            let content = match content {
+                Ok(content) => content,
+                Err(e) => {
+                    yield AgentEvent::Done(AgentResponse::error(e.to_string()));
+                    return;
+                }
+            };
             let Some(guest_agent) = self.resolve_agent(&guest).await else {
                 yield AgentEvent::Done(AgentResponse::error(
                     format!("guest agent '{guest}' not registered"),
@@ -207,7 +386,7 @@ impl<C: Config> Runtime<C> {
                 self.acquire_slot(conversation_id).await
             else {
                 yield AgentEvent::Done(AgentResponse::error(
-                    format!("conversation {conversation_id} not found"),
+                    RuntimeError::ConversationNotFound(conversation_id.to_string()).to_string(),
                 ));
                 return;
             };
@@ -215,18 +394,17 @@ impl<C: Config> Runtime<C> {
             let mut conversation = conversation_mutex.lock().await;
             let pre_run_len = conversation.history.len();
 
-            let content = self
+            let pre = self
                 .env
                 .hook()
                 .preprocess(&agent_name, &content)
-                .unwrap_or_else(|| content.clone());
-            if sender.is_empty() {
-                conversation.history.push(HistoryEntry::user(&content));
-            } else {
-                conversation
-                    .history
-                    .push(HistoryEntry::user_with_sender(&content, &sender));
-            }
+                .unwrap_or_else(|| Preprocessed {
+                    content: content.clone(),
+                    skills: Vec::new(),
+                });
+            let mut entry = HistoryEntry::user_with_sender(pre.content, &sender);
+            entry.skills = pre.skills;
+            conversation.history.push(entry);
 
             conversation.history.retain(|e| !e.auto_injected);
 
@@ -252,15 +430,15 @@ impl<C: Config> Runtime<C> {
                 model: model_name.clone(),
                 messages,
                 temperature: None,
-                top_p: None,
+                top_p: guest_agent.config.top_p,
                 max_tokens: None,
                 stream: None,
-                stop: None,
+                stop: guest_agent.config.stop_sequences(),
                 tools: None,
                 tool_choice: None,
-                frequency_penalty: None,
-                presence_penalty: None,
-                seed: None,
+                frequency_penalty: guest_agent.config.frequency_penalty,
+                presence_penalty: guest_agent.config.presence_penalty,
+                seed: guest_agent.config.seed,
                 user: None,
                 reasoning_effort: if guest_agent.config.thinking {
                     Some("high".to_string())
@@ -274,6 +452,7 @@ impl<C: Config> Runtime<C> {
 
             let mut response_text = String::new();
             let mut reasoning = String::new();
+            let mut usage = Usage::default();
             {
                 let mut stream = std::pin::pin!(self.model.stream_ct(request));
                 while let Some(result) = stream.next().await {
@@ -287,6 +466,9 @@ impl<C: Config> Runtime<C> {
                                 reasoning.push_str(text);
                                 yield AgentEvent::ThinkingDelta(text.to_string());
                             }
+                            if let Some(u) = chunk.usage {
+                                usage = u;
+                            }
                         }
                         Err(e) => {
                             yield AgentEvent::Done(AgentResponse {
@@ -295,6 +477,7 @@ impl<C: Config> Runtime<C> {
                                 stop_reason: AgentStopReason::Error(e.to_string()),
                                 steps: vec![],
                                 model: model_name.clone(),
+                                usage,
                             });
                             return;
                         }
@@ -328,7 +511,19 @@ impl<C: Config> Runtime<C> {
                 stop_reason: AgentStopReason::TextResponse,
                 steps: vec![],
                 model: model_name,
+                usage,
             });
         }
     }
 }
+
+/// A user entry with no content or only whitespace. Non-string content
+/// (image parts) counts as content.
+fn is_blank_user(entry: &HistoryEntry) -> bool {
+    *entry.role() == Role::User
+        && match &entry.message.content {
+            None => true,
+            Some(serde_json::Value::String(text)) => text.trim().is_empty(),
+            Some(_) => false,
+        }
+}
@@ -3,6 +3,7 @@
 use super::Runtime;
 use crate::Config;
 use anyhow::Result;
+use wcore::RuntimeError;
 use wcore::protocol::message::{ConversationHistory, ConversationInfo, ConversationMessage};
 use wcore::storage::{SessionHandle, Storage};
 
@@ -20,7 +21,7 @@ impl<C: Config> Runtime<C> {
         let snapshot = self
             .storage()
             .load_session(&handle)?
-            .ok_or_else(|| anyhow::anyhow!("conversation not found: {slug}"))?;
+            .ok_or_else(|| RuntimeError::ConversationNotFound(slug.to_owned()))?;
         let meta = snapshot.meta;
         let mut messages = snapshot.history;
         if let Some(name) = snapshot.archive {
@@ -56,7 +57,7 @@ impl<C: Config> Runtime<C> {
         let handle = SessionHandle::new(slug);
         let deleted = self.storage().delete_session(&handle)?;
         if !deleted {
-            anyhow::bail!("conversation not found: {slug}");
+            return Err(RuntimeError::ConversationNotFound(slug.to_owned()).into());
         }
         Ok(())
     }
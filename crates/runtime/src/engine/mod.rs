@@ -6,14 +6,17 @@
 //! (`send_to`, `stream_to`) take a conversation ID, lock the conversation,
 //! clone the agent, and run with the conversation's history.
 
-use crate::{Config, Conversation, sessions::SessionIndex};
+use crate::{Config, Conversation, ConversationHandle, sessions::SessionIndex};
 use memory::Memory;
 use std::{
     collections::BTreeMap,
     sync::{Arc, atomic::AtomicU64},
 };
 use tokio::sync::{Mutex, RwLock, watch};
-use wcore::{Agent, ToolRegistry, model::Model};
+use wcore::{
+    Agent, CancellationToken, ToolRegistry, ToolTimeouts, agent::DEFAULT_MAX_PARALLEL_TOOLS,
+    model::Model, protocol::message::ModelInfo,
+};
 
 mod agents;
 mod config;
@@ -53,13 +56,27 @@ pub struct Runtime<C: Config> {
     agents: parking_lot::RwLock<BTreeMap<String, Agent<C::Provider>>>,
     ephemeral_agents: RwLock<BTreeMap<String, Agent<C::Provider>>>,
     conversations: RwLock<BTreeMap<u64, ConvSlot>>,
+    /// Persisted session of each `(agent, sender)` pair evicted for
+    /// idleness, resumed on the pair's next conversation.
+    evicted: parking_lot::Mutex<BTreeMap<(String, String), ConversationHandle>>,
     pub(super) session_index: parking_lot::RwLock<SessionIndex>,
     next_conversation_id: AtomicU64,
     pub tools: ToolRegistry,
     steering: RwLock<BTreeMap<u64, watch::Sender<Option<String>>>>,
+    /// Cancellation token of each conversation's running turn, handed to
+    /// tool handlers and cancelled when the turn is aborted.
+    cancellations: RwLock<BTreeMap<u64, CancellationToken>>,
     /// Model names advertised by the LLM endpoint — populated by the
     /// daemon builder from a `/v1/models` fetch at startup / reload.
-    pub(super) models: parking_lot::RwLock<Vec<String>>,
+    pub(super) models: parking_lot::RwLock<Vec<ModelInfo>>,
+    /// Cap on concurrent tool calls per model round, handed to every
+    /// agent this runtime builds.
+    max_parallel_tools: usize,
+    /// Per-call tool time limits, shared by every agent this runtime builds.
+    tool_timeouts: Arc<ToolTimeouts>,
+    /// Cap on tool calls per turn, handed to every agent this runtime
+    /// builds. 0 = no cap.
+    max_tool_calls: usize,
 }
 
 impl<C: Config> Runtime<C> {
@@ -79,14 +96,45 @@ impl<C: Config> Runtime<C> {
             agents: parking_lot::RwLock::new(BTreeMap::new()),
             ephemeral_agents: RwLock::new(BTreeMap::new()),
             conversations: RwLock::new(BTreeMap::new()),
+            evicted: parking_lot::Mutex::new(BTreeMap::new()),
             session_index: parking_lot::RwLock::new(SessionIndex::new()),
             next_conversation_id: AtomicU64::new(1),
             tools,
             steering: RwLock::new(BTreeMap::new()),
+            cancellations: RwLock::new(BTreeMap::new()),
             models: parking_lot::RwLock::new(Vec::new()),
+            max_parallel_tools: DEFAULT_MAX_PARALLEL_TOOLS,
+            tool_timeouts: Arc::default(),
+            max_tool_calls: 0,
         }
     }
 
+    /// Cap how many tool calls from one model round run at once (default
+    /// [`DEFAULT_MAX_PARALLEL_TOOLS`]). 0 = no cap. Applies to agents
+    /// added afterwards, so set it before registering any.
+    pub fn with_max_parallel_tools(mut self, max: usize) -> Self {
+        self.max_parallel_tools = max;
+        self
+    }
+
+    /// Limit how long each tool call may run, globally and per tool. A
+    /// call past its limit is dropped and the model gets a timeout error
+    /// instead. Like [`Runtime::with_max_parallel_tools`], set it before
+    /// registering agents.
+    pub fn with_tool_timeouts(mut self, timeouts: ToolTimeouts) -> Self {
+        self.tool_timeouts = Arc::new(timeouts);
+        self
+    }
+
+    /// Cap the tool calls one turn may make (0, the default, = no cap).
+    /// A turn whose next round would go past it ends with an error naming
+    /// the count and the last tool requested. Set it before registering
+    /// agents.
+    pub fn with_max_tool_calls(mut self, max: usize) -> Self {
+        self.max_tool_calls = max;
+        self
+    }
+
     /// Access the persistence backend.
     pub fn storage(&self) -> &Arc<C::Storage> {
         &self.storage
@@ -7,7 +7,9 @@
 use crate::Hook;
 use std::path::{Path, PathBuf};
 use tokio::sync::broadcast;
-use wcore::{AgentEvent, ToolDispatch, ToolFuture, protocol::message};
+use wcore::{
+    AgentEvent, ApprovalFuture, CancellationToken, ToolDispatch, ToolFuture, protocol::message,
+};
 
 /// The runtime environment — combines server capabilities with tool dispatch.
 ///
@@ -45,19 +47,24 @@ pub trait Env: Send + Sync + 'static {
 
 /// Dispatch a tool call through an Env's hook. Utility for Env
 /// implementors building their ToolDispatcher impl.
+#[allow(clippy::too_many_arguments)]
 pub fn dispatch_tool<'a, E: Env>(
     env: &'a E,
     name: &'a str,
+    call_id: &'a str,
     args: &'a str,
     agent: &'a str,
     sender: &'a str,
     conversation_id: Option<u64>,
+    cancel: Option<CancellationToken>,
 ) -> ToolFuture<'a> {
     let call = ToolDispatch {
+        call_id: call_id.to_owned(),
         args: args.to_owned(),
         agent: agent.to_owned(),
         sender: sender.to_owned(),
         conversation_id,
+        cancel,
     };
 
     match env.hook().dispatch(name, call) {
@@ -66,6 +73,33 @@ pub fn dispatch_tool<'a, E: Env>(
     }
 }
 
+/// Ask an Env's hook whether a tool call may run. The counterpart of
+/// [`dispatch_tool`] for `ToolDispatcher::approve`.
+#[allow(clippy::too_many_arguments)]
+pub fn approve_tool<'a, E: Env>(
+    env: &'a E,
+    name: &'a str,
+    call_id: &'a str,
+    args: &'a str,
+    agent: &'a str,
+    sender: &'a str,
+    conversation_id: Option<u64>,
+    cancel: Option<CancellationToken>,
+) -> ApprovalFuture<'a> {
+    let call = ToolDispatch {
+        call_id: call_id.to_owned(),
+        args: args.to_owned(),
+        agent: agent.to_owned(),
+        sender: sender.to_owned(),
+        conversation_id,
+        cancel,
+    };
+
+    env.hook()
+        .approve(name, call)
+        .unwrap_or_else(|| Box::pin(std::future::ready(Ok(()))))
+}
+
 impl Env for () {
     type Hook = ();
 
@@ -5,7 +5,20 @@
 //! events, preprocess messages, and dispatch tool calls.
 
 use crabllm_core::Tool;
-use wcore::{AgentConfig, AgentEvent, ToolDispatch, ToolFuture, model::HistoryEntry};
+use std::collections::BTreeMap;
+use wcore::{
+    AgentConfig, AgentEvent, ApprovalFuture, ToolDispatch, ToolFuture, model::HistoryEntry,
+};
+
+/// User content rewritten by [`Hook::preprocess`].
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessed {
+    /// The message text the model sees.
+    pub content: String,
+    /// Skills loaded into `content`, recorded on the entry so only they
+    /// can grant tools.
+    pub skills: Vec<String>,
+}
 
 /// A pluggable subsystem that participates in the agent lifecycle.
 ///
@@ -22,6 +35,13 @@ pub trait Hook: Send + Sync {
         None
     }
 
+    /// Compose an agent's system prompt from its base prompt and the
+    /// memory and skill fragments. Override to reorder, drop, or extend
+    /// the pieces. Default: base → memory → skills.
+    fn assemble_system(&self, base: &str, memory: &str, skills: &str) -> String {
+        format!("{base}{memory}{skills}")
+    }
+
     /// Called by `Runtime::add_agent()` before building the `Agent`.
     fn on_build_agent(&self, config: AgentConfig) -> AgentConfig {
         config
@@ -50,12 +70,47 @@ pub trait Hook: Send + Sync {
         Vec::new()
     }
 
+    /// Extra tools to enable for this turn on top of the agent's own
+    /// whitelist, given the history after preprocessing. The grant lasts
+    /// for the one run; the registered agent is never changed.
+    fn turn_tools(
+        &self,
+        _agent: &str,
+        _conversation_id: u64,
+        _history: &[HistoryEntry],
+    ) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Variables for an agent's system prompt placeholders this turn, on
+    /// top of the runtime's built-ins. Only consulted for agents with
+    /// [`PromptVars`](wcore::PromptVars) turned on. `conversation_id` is
+    /// `None` for stateless runs.
+    fn prompt_vars(
+        &self,
+        _agent: &str,
+        _conversation_id: Option<u64>,
+        _sender: &str,
+    ) -> BTreeMap<String, String> {
+        BTreeMap::new()
+    }
+
     /// Called by Runtime after each agent step during execution.
     fn on_event(&self, _agent: &str, _conversation_id: u64, _event: &AgentEvent) {}
 
+    /// Called after a conversation's history is compacted, automatically
+    /// mid-turn or through [`Runtime::compact`](crate::Runtime::compact).
+    /// `old_len` entries were replaced by `new_len`.
+    fn on_compacted(&self, _agent: &str, _conversation_id: u64, _old_len: usize, _new_len: usize) {}
+
+    /// Called after a conversation is dropped from the runtime, closed or
+    /// evicted as idle. Hooks should drop any per-conversation state
+    /// they own.
+    fn on_close(&self, _conversation_id: u64) {}
+
     /// Preprocess user content before it becomes a message.
     /// Return `Some(modified)` to transform, `None` to pass through.
-    fn preprocess(&self, _agent: &str, _content: &str) -> Option<String> {
+    fn preprocess(&self, _agent: &str, _content: &str) -> Option<Preprocessed> {
         None
     }
 
@@ -73,11 +128,25 @@ pub trait Hook: Send + Sync {
         (tools, None)
     }
 
+    /// Whether `name` is pure: its result depends only on its arguments.
+    /// Successful results of pure tools may be reused for the same
+    /// arguments within a conversation. Default: `false` — anything with
+    /// side effects or time-dependent output must stay uncached.
+    fn cacheable(&self, _name: &str) -> bool {
+        false
+    }
+
     /// Dispatch a tool call by name. Return `None` if this hook doesn't
     /// own the tool — Env will try the next hook or the legacy entries.
     fn dispatch<'a>(&'a self, _name: &'a str, _call: ToolDispatch) -> Option<ToolFuture<'a>> {
         None
     }
+
+    /// Hold a call until it may run. Return `None` to let it run at once.
+    /// Awaited before [`Hook::dispatch`], outside the tool timeout.
+    fn approve<'a>(&'a self, _name: &'a str, _call: ToolDispatch) -> Option<ApprovalFuture<'a>> {
+        None
+    }
 }
 
 /// No-op Hook for tests.
@@ -7,8 +7,8 @@ pub mod sessions;
 pub use conversation::Conversation;
 pub use engine::{Runtime, SharedMemory};
 pub use env::Env;
-pub use hook::Hook;
-pub use wcore::{MemoryConfig, TasksConfig};
+pub use hook::{Hook, Preprocessed};
+pub use wcore::{MemoryConfig, RuntimeError, TasksConfig};
 
 /// Opaque persistent handle to a conversation. Re-exported from the
 /// storage trait so runtime callers don't need to speak the storage
@@ -3,8 +3,9 @@
 #[tokio::test]
 async fn unknown_tool_rejected() {
     let env = ();
-    let err = wcore::ToolDispatcher::dispatch(&env, "nonexistent", "{}", "agent", "", None)
-        .await
-        .unwrap_err();
+    let err =
+        wcore::ToolDispatcher::dispatch(&env, "nonexistent", "", "{}", "agent", "", None, None)
+            .await
+            .unwrap_err();
     assert!(err.contains("tool not registered"));
 }
@@ -3,15 +3,17 @@
 //! Uses `Env<()>` with InMemoryStorage. Every test gets its own
 //! in-memory storage — no shared global state, no filesystem I/O, no node.
 
-use crabtalk_runtime::{Config, Runtime, sessions::SearchOptions};
+use crabllm_core::{FunctionCall, ToolCall};
+use crabtalk_runtime::{Config, Env, Hook, Runtime, RuntimeError, sessions::SearchOptions};
 use futures_util::StreamExt;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use wcore::{
-    AgentConfig, AgentEvent, AgentStopReason,
-    model::Model,
+    AgentConfig, AgentEvent, AgentStopReason, CancellationToken, PromptVars, ToolDispatcher,
+    ToolFuture,
+    model::{HistoryEntry, Model},
     testing::{
         InMemoryStorage,
-        provider::{TestProvider, text_chunks},
+        provider::{TestProvider, text_chunks, text_response, tool_chunks},
     },
 };
 
@@ -35,6 +37,82 @@ fn runtime(provider: TestProvider) -> Runtime<TestCfg> {
     )
 }
 
+/// Env whose hook records the conversation lifecycle calls it sees.
+#[derive(Default)]
+struct Recorder {
+    closed: parking_lot::Mutex<Vec<u64>>,
+    compacted: parking_lot::Mutex<Vec<(usize, usize)>>,
+    built: parking_lot::Mutex<Vec<String>>,
+}
+
+impl Hook for Recorder {
+    fn on_build_agent(&self, mut config: AgentConfig) -> AgentConfig {
+        self.built.lock().push(config.name.clone());
+        config.system_prompt.push_str("\n<fragment>");
+        config
+    }
+
+    fn on_compacted(&self, _agent: &str, _conversation_id: u64, old_len: usize, new_len: usize) {
+        self.compacted.lock().push((old_len, new_len));
+    }
+
+    fn on_close(&self, conversation_id: u64) {
+        self.closed.lock().push(conversation_id);
+    }
+}
+
+impl Env for Recorder {
+    type Hook = Self;
+
+    fn hook(&self) -> &Self {
+        self
+    }
+}
+
+impl ToolDispatcher for Recorder {
+    fn dispatch<'a>(
+        &'a self,
+        name: &'a str,
+        call_id: &'a str,
+        args: &'a str,
+        agent: &'a str,
+        sender: &'a str,
+        conversation_id: Option<u64>,
+        cancel: Option<CancellationToken>,
+    ) -> ToolFuture<'a> {
+        crabtalk_runtime::env::dispatch_tool(
+            self,
+            name,
+            call_id,
+            args,
+            agent,
+            sender,
+            conversation_id,
+            cancel,
+        )
+    }
+}
+
+struct RecordCfg;
+
+impl Config for RecordCfg {
+    type Storage = InMemoryStorage;
+    type Provider = TestProvider;
+    type Env = Recorder;
+}
+
+fn recorded(provider: TestProvider) -> (Runtime<RecordCfg>, Arc<Recorder>) {
+    let recorder = Arc::new(Recorder::default());
+    let runtime = Runtime::new(
+        Model::new(provider),
+        recorder.clone(),
+        Arc::new(InMemoryStorage::new()),
+        Arc::new(parking_lot::RwLock::new(memory::Memory::new())),
+        wcore::ToolRegistry::new(),
+    );
+    (runtime, recorder)
+}
+
 // --- Agent registry ---
 
 #[tokio::test]
@@ -127,6 +205,85 @@ async fn create_and_close() {
     assert!(!runtime.close(id).await);
 }
 
+#[tokio::test]
+async fn evict_idle_skips_pinned_busy_and_recent() {
+    let runtime = runtime(TestProvider::with_chunks(vec![]));
+    runtime.add_agent(AgentConfig::new("crab"));
+    runtime.add_agent(AgentConfig::new("pinned"));
+
+    let idle = runtime
+        .get_or_create_conversation("crab", "idle")
+        .await
+        .unwrap();
+    let busy = runtime
+        .get_or_create_conversation("crab", "busy")
+        .await
+        .unwrap();
+    runtime
+        .get_or_create_conversation("pinned", "user")
+        .await
+        .unwrap();
+    let pinned = vec!["pinned".to_owned()];
+
+    assert!(
+        runtime
+            .evict_idle(Duration::from_secs(3600), &pinned)
+            .await
+            .is_empty()
+    );
+
+    // A held handle marks a turn about to start, even before it locks.
+    let _busy = runtime.conversation(busy).await.unwrap();
+    assert_eq!(
+        runtime.evict_idle(Duration::ZERO, &pinned).await,
+        vec![idle]
+    );
+    assert_eq!(runtime.conversation_count().await, 2);
+}
+
+#[tokio::test]
+async fn close_and_eviction_notify_the_hook() {
+    let (runtime, recorder) = recorded(TestProvider::with_chunks(vec![]));
+    runtime.add_agent(AgentConfig::new("crab"));
+    let closed = runtime
+        .get_or_create_conversation("crab", "closed")
+        .await
+        .unwrap();
+    let idle = runtime
+        .get_or_create_conversation("crab", "idle")
+        .await
+        .unwrap();
+
+    assert!(runtime.close(closed).await);
+    assert!(!runtime.close(closed).await);
+    runtime.evict_idle(Duration::ZERO, &[]).await;
+    assert_eq!(*recorder.closed.lock(), vec![closed, idle]);
+}
+
+#[tokio::test]
+async fn evicted_conversation_resumes_its_history() {
+    let runtime = runtime(TestProvider::with_chunks(vec![text_chunks("hello")]));
+    runtime.add_agent(AgentConfig::new("crab"));
+    let id = runtime
+        .get_or_create_conversation("crab", "user")
+        .await
+        .unwrap();
+    runtime
+        .send_to(id, "remember me", &[], "user", None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(runtime.evict_idle(Duration::ZERO, &[]).await, vec![id]);
+    let resumed = runtime
+        .get_or_create_conversation("crab", "user")
+        .await
+        .unwrap();
+    assert_ne!(resumed, id);
+    let conversation = runtime.conversation(resumed).await.unwrap();
+    let history = &conversation.lock().await.history;
+    assert!(history.iter().any(|e| e.text() == "remember me"));
+}
+
 #[tokio::test]
 async fn conversations_lists_all() {
     let runtime = runtime(TestProvider::with_chunks(vec![]));
@@ -202,7 +359,7 @@ async fn send_to_returns_response() {
         .await
         .unwrap();
     let response = runtime
-        .send_to(conversation_id, "hi", "", None)
+        .send_to(conversation_id, "hi", &[], "", None, None)
         .await
         .unwrap();
 
@@ -213,10 +370,178 @@ async fn send_to_returns_response() {
 #[tokio::test]
 async fn send_to_nonexistent_conversation_errors() {
     let runtime = runtime(TestProvider::with_chunks(vec![]));
-    let err = runtime.send_to(999, "hi", "", None).await.unwrap_err();
+    let err = runtime
+        .send_to(999, "hi", &[], "", None, None)
+        .await
+        .unwrap_err();
     assert!(err.to_string().contains("not found"));
 }
 
+#[tokio::test]
+async fn runtime_errors_are_typed() {
+    let runtime = runtime(TestProvider::with_chunks(vec![]));
+
+    let err = runtime
+        .send_to(999, "hi", &[], "", None, None)
+        .await
+        .unwrap_err();
+    let typed = err.downcast_ref::<RuntimeError>().unwrap();
+    assert!(matches!(typed, RuntimeError::ConversationNotFound(_)));
+    assert_eq!(typed.code(), 404);
+
+    let err = runtime
+        .get_or_create_conversation("ghost", "user")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<RuntimeError>(),
+        Some(RuntimeError::AgentNotRegistered(name)) if name == "ghost"
+    ));
+
+    let err = runtime.steer(999, "hi".to_owned()).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<RuntimeError>().unwrap().code(), 409);
+
+    let err = runtime.cancel(999).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<RuntimeError>().unwrap().code(), 409);
+}
+
+#[tokio::test]
+async fn blank_messages_are_refused() {
+    let runtime = runtime(TestProvider::with_chunks(vec![text_chunks("unused")]));
+    runtime.add_agent(AgentConfig::new("crab"));
+    let conversation_id = runtime
+        .get_or_create_conversation("crab", "user")
+        .await
+        .unwrap();
+
+    let err = runtime
+        .send_to(conversation_id, " \n\t", &[], "", None, None)
+        .await
+        .unwrap_err();
+    let typed = err.downcast_ref::<RuntimeError>().unwrap();
+    assert!(matches!(typed, RuntimeError::EmptyMessage));
+    assert_eq!(typed.code(), 400);
+
+    let events: Vec<_> = runtime
+        .stream_to(conversation_id, "", &[], "", None)
+        .collect()
+        .await;
+    assert!(matches!(
+        events.as_slice(),
+        [AgentEvent::Done(r)] if matches!(&r.stop_reason, AgentStopReason::Error(m) if m == "message is empty")
+    ));
+
+    let mut history = vec![HistoryEntry::user("   ")];
+    assert!(runtime.send_stateless("crab", &mut history).await.is_err());
+
+    let conversation = runtime.conversation(conversation_id).await.unwrap();
+    assert!(conversation.lock().await.history.is_empty());
+}
+
+#[tokio::test]
+async fn send_to_attaches_images() {
+    let runtime = runtime(TestProvider::with_chunks(vec![text_chunks("a crab")]));
+    runtime.add_agent(AgentConfig::new("crab"));
+    let conversation_id = runtime
+        .get_or_create_conversation("crab", "user")
+        .await
+        .unwrap();
+    let images = vec!["https://example.com/crab.png".to_owned()];
+    runtime
+        .send_to(conversation_id, "", &images, "", None, None)
+        .await
+        .unwrap();
+
+    let conversation = runtime.conversation(conversation_id).await.unwrap();
+    let history = &conversation.lock().await.history;
+    assert_eq!(history[0].text(), "");
+    assert_eq!(
+        history[0].images().collect::<Vec<_>>(),
+        vec!["https://example.com/crab.png"]
+    );
+}
+
+#[tokio::test]
+async fn inline_images_reach_the_model_but_not_history() {
+    let provider = TestProvider::with_chunks(vec![text_chunks("a crab")]);
+    let runtime = runtime(provider.clone());
+    runtime.add_agent(AgentConfig::new("crab"));
+    let conversation_id = runtime
+        .get_or_create_conversation("crab", "user")
+        .await
+        .unwrap();
+    let image = "data:image/png;base64,AAAA".to_owned();
+    runtime
+        .send_to(
+            conversation_id,
+            "look",
+            std::slice::from_ref(&image),
+            "",
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+    let sent = serde_json::to_string(&provider.requests()[0].messages).unwrap();
+    assert!(sent.contains(&image), "the turn sees the image");
+    let conversation = runtime.conversation(conversation_id).await.unwrap();
+    let history = &conversation.lock().await.history;
+    assert_eq!(history[0].text(), "look");
+    assert_eq!(history[0].images().count(), 0);
+    let kept = serde_json::to_string(&history[0].message).unwrap();
+    assert!(!kept.contains("base64"), "{kept}");
+    assert!(kept.contains(wcore::model::INLINE_IMAGE_PLACEHOLDER));
+}
+
+#[tokio::test]
+async fn images_are_dropped_for_models_without_vision() {
+    let provider = TestProvider::with_chunks(vec![text_chunks("ok")]);
+    let runtime = runtime(provider.clone());
+    runtime.set_models(vec![wcore::protocol::message::ModelInfo {
+        name: "blind".to_owned(),
+        vision: Some(false),
+        ..Default::default()
+    }]);
+    runtime.add_agent(AgentConfig::new("crab").model("blind"));
+    let conversation_id = runtime
+        .get_or_create_conversation("crab", "user")
+        .await
+        .unwrap();
+    let images = vec!["https://example.com/crab.png".to_owned()];
+    runtime
+        .send_to(conversation_id, "look", &images, "", None, None)
+        .await
+        .unwrap();
+
+    let sent = serde_json::to_string(&provider.requests()[0].messages).unwrap();
+    assert!(!sent.contains("crab.png"), "{sent}");
+    let conversation = runtime.conversation(conversation_id).await.unwrap();
+    let history = &conversation.lock().await.history;
+    assert_eq!(history[0].images().count(), 0);
+    assert_eq!(
+        history[0].text(),
+        "look\n\n[1 image(s) omitted: blind cannot see images]"
+    );
+}
+
+#[tokio::test]
+async fn send_to_trims_trailing_whitespace() {
+    let runtime = runtime(TestProvider::with_chunks(vec![text_chunks("ok")]));
+    runtime.add_agent(AgentConfig::new("crab"));
+    let conversation_id = runtime
+        .get_or_create_conversation("crab", "user")
+        .await
+        .unwrap();
+    runtime
+        .send_to(conversation_id, "hello \n\n", &[], "", None, None)
+        .await
+        .unwrap();
+
+    let conversation = runtime.conversation(conversation_id).await.unwrap();
+    assert_eq!(conversation.lock().await.history[0].text(), "hello");
+}
+
 #[tokio::test]
 async fn send_to_appends_to_history() {
     let provider = TestProvider::with_chunks(vec![
@@ -231,11 +556,11 @@ async fn send_to_appends_to_history() {
         .await
         .unwrap();
     runtime
-        .send_to(conversation_id, "hello", "", None)
+        .send_to(conversation_id, "hello", &[], "", None, None)
         .await
         .unwrap();
     runtime
-        .send_to(conversation_id, "again", "", None)
+        .send_to(conversation_id, "again", &[], "", None, None)
         .await
         .unwrap();
 
@@ -245,6 +570,440 @@ async fn send_to_appends_to_history() {
     assert_eq!(conversation.history.len(), 4);
 }
 
+#[tokio::test]
+async fn compact_replaces_history_with_summary() {
+    let provider = TestProvider::with_both(
+        vec![
+            text_response("the summary"),
+            text_response("mid-turn summary"),
+        ],
+        vec![
+            text_chunks("ok"),
+            text_chunks("ok again"),
+            text_chunks("short"),
+            text_chunks("done"),
+        ],
+    );
+    let (runtime, recorder) = recorded(provider);
+    runtime.add_agent(AgentConfig::new("crab"));
+
+    let conversation_id = runtime
+        .get_or_create_conversation("crab", "test-compact")
+        .await
+        .unwrap();
+    for content in ["hello", "more"] {
+        runtime
+            .send_to(conversation_id, content, &[], "", None, None)
+            .await
+            .unwrap();
+    }
+
+    let summary = runtime.compact_conversation("crab", "test-compact").await;
+    assert_eq!(summary.unwrap().as_deref(), Some("the summary"));
+    let conversation_mutex = runtime.conversation(conversation_id).await.unwrap();
+    let conversation = conversation_mutex.lock().await;
+    assert_eq!(conversation.history.len(), 1);
+    assert_eq!(conversation.history[0].text(), "the summary");
+    drop(conversation);
+    assert_eq!(*recorder.compacted.lock(), [(4, 1)]);
+
+    // No conversation for this sender: nothing to compact, not an error.
+    let none = runtime
+        .compact_conversation("crab", "nobody")
+        .await
+        .unwrap();
+    assert!(none.is_none());
+
+    // Mid-turn: the first step crosses the threshold and is compacted.
+    // The second crosses it too, but its summary call finds the script
+    // empty, so the turn ends uncompacted.
+    let mut config = AgentConfig::new("tight");
+    config.compact_threshold = Some(1);
+    runtime.add_agent(config);
+    let tight = runtime
+        .get_or_create_conversation("tight", "test-compact")
+        .await
+        .unwrap();
+    runtime
+        .send_to(tight, "hello", &[], "", None, None)
+        .await
+        .unwrap();
+    assert_eq!(*recorder.compacted.lock(), [(4, 1), (2, 1)]);
+    let conversation_mutex = runtime.conversation(tight).await.unwrap();
+    let conversation = conversation_mutex.lock().await;
+    assert_eq!(conversation.history[0].text(), "mid-turn summary");
+}
+
+#[tokio::test]
+async fn forked_branches_evolve_independently() {
+    let provider = TestProvider::with_chunks(vec![
+        text_chunks("first reply"),
+        text_chunks("branch reply"),
+        text_chunks("main reply"),
+    ]);
+    let runtime = runtime(provider);
+    runtime.add_agent(AgentConfig::new("crab"));
+
+    let main = runtime
+        .get_or_create_conversation("crab", "user")
+        .await
+        .unwrap();
+    runtime
+        .send_to(main, "hello", &[], "", None, None)
+        .await
+        .unwrap();
+
+    let branch = runtime.fork(main, "user:branch").await.unwrap();
+    assert_ne!(branch, main);
+    assert!(runtime.fork(main, "user:branch").await.is_err());
+    assert_eq!(
+        runtime.conversation_id("crab", "user:branch").await,
+        Some(branch)
+    );
+
+    runtime
+        .send_to(branch, "try this instead", &[], "", None, None)
+        .await
+        .unwrap();
+    runtime
+        .send_to(main, "carry on", &[], "", None, None)
+        .await
+        .unwrap();
+
+    let texts = |history: &[HistoryEntry]| -> Vec<String> {
+        history.iter().map(|e| e.text().to_owned()).collect()
+    };
+    let main = runtime.conversation(main).await.unwrap();
+    let main = main.lock().await;
+    let branch = runtime.conversation(branch).await.unwrap();
+    let branch = branch.lock().await;
+    assert_eq!(
+        texts(&main.history),
+        ["hello", "first reply", "carry on", "main reply"]
+    );
+    assert_eq!(
+        texts(&branch.history),
+        ["hello", "first reply", "try this instead", "branch reply"]
+    );
+    assert!(branch.handle.is_some());
+    assert_ne!(branch.handle, main.handle, "each branch is its own session");
+}
+
+#[tokio::test]
+async fn send_to_prefill_is_continued_and_merged() {
+    let provider = TestProvider::with_chunks(vec![text_chunks("\"ok\"}")]);
+    let runtime = runtime(provider.clone());
+    runtime.add_agent(AgentConfig::new("crab"));
+
+    let conversation_id = runtime
+        .get_or_create_conversation("crab", "test-prefill")
+        .await
+        .unwrap();
+    let response = runtime
+        .send_to(
+            conversation_id,
+            "status?",
+            &[],
+            "",
+            None,
+            Some("{\"status\": "),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        response.final_response.as_deref(),
+        Some("{\"status\": \"ok\"}")
+    );
+
+    let sent = &provider.requests()[0].messages;
+    let last = sent.last().unwrap();
+    assert_eq!(last.role, crabllm_core::Role::Assistant);
+    assert_eq!(
+        last.content.as_ref().and_then(|v| v.as_str()),
+        Some("{\"status\": ")
+    );
+
+    let conversation_mutex = runtime.conversation(conversation_id).await.unwrap();
+    let conversation = conversation_mutex.lock().await;
+    assert_eq!(conversation.history.len(), 2);
+    assert_eq!(conversation.history[1].text(), "{\"status\": \"ok\"}");
+}
+
+#[tokio::test]
+async fn send_stateless_leaves_conversations_untouched() {
+    let provider = TestProvider::with_chunks(vec![text_chunks("stateless reply")]);
+    let runtime = runtime(provider);
+    runtime.add_agent(AgentConfig::new("crab"));
+
+    let mut history = vec![HistoryEntry::user("hi")];
+    let response = runtime.send_stateless("crab", &mut history).await.unwrap();
+
+    assert_eq!(response.final_response.as_deref(), Some("stateless reply"));
+    assert_eq!(history.len(), 2);
+    assert_eq!(runtime.conversation_count().await, 0);
+}
+
+#[tokio::test]
+async fn prompt_vars_fill_system_prompt_per_turn() {
+    let provider = TestProvider::with_chunks(vec![text_chunks("ok")]);
+    let runtime = runtime(provider.clone());
+    let mut config = AgentConfig::new("crab")
+        .system_prompt("You are {{agent}}, talking to {{ sender }}. Keep \\{{date}}.{{nope}}");
+    config.prompt_vars = PromptVars::Lenient;
+    runtime.add_agent(config);
+
+    let id = runtime
+        .get_or_create_conversation("crab", "alice")
+        .await
+        .unwrap();
+    runtime
+        .send_to(id, "hi", &[], "alice", None, None)
+        .await
+        .unwrap();
+
+    let system = provider.requests()[0].messages[0].clone();
+    assert_eq!(
+        system.content.as_ref().and_then(|v| v.as_str()),
+        Some("You are crab, talking to alice. Keep {{date}}.")
+    );
+    // The registered prompt keeps its placeholders for the next turn.
+    assert!(
+        runtime
+            .agent("crab")
+            .unwrap()
+            .system_prompt
+            .contains("{{agent}}")
+    );
+}
+
+#[tokio::test]
+async fn prompt_vars_do_not_rebuild_the_agent() {
+    let provider = TestProvider::with_chunks(vec![text_chunks("one"), text_chunks("two")]);
+    let (runtime, recorder) = recorded(provider.clone());
+    let mut config = AgentConfig::new("crab").system_prompt("Hi {{sender}}");
+    config.prompt_vars = PromptVars::Lenient;
+    runtime.add_agent(config);
+
+    let id = runtime
+        .get_or_create_conversation("crab", "alice")
+        .await
+        .unwrap();
+    for content in ["hi", "again"] {
+        runtime
+            .send_to(id, content, &[], "alice", None, None)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(*recorder.built.lock(), ["crab"]);
+    for request in provider.requests() {
+        let system = &request.messages[0];
+        assert_eq!(
+            system.content.as_ref().and_then(|v| v.as_str()),
+            Some("Hi alice\n<fragment>")
+        );
+    }
+}
+
+#[tokio::test]
+async fn strict_prompt_vars_refuse_unknown_variable() {
+    let provider = TestProvider::with_chunks(vec![text_chunks("ok")]);
+    let runtime = runtime(provider.clone());
+    let mut config = AgentConfig::new("crab").system_prompt("Hello {{user_name}}");
+    config.prompt_vars = PromptVars::Strict;
+    runtime.add_agent(config);
+
+    let id = runtime
+        .get_or_create_conversation("crab", "alice")
+        .await
+        .unwrap();
+    let err = runtime
+        .send_to(id, "hi", &[], "alice", None, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<RuntimeError>(),
+        Some(RuntimeError::MissingPromptVar { name, .. }) if name == "user_name"
+    ));
+    assert!(provider.requests().is_empty());
+}
+
+fn advertise(runtime: &Runtime<TestCfg>, model: &str, context_length: u64) {
+    runtime.set_models(vec![wcore::protocol::message::ModelInfo {
+        name: model.to_owned(),
+        context_length,
+        ..Default::default()
+    }]);
+}
+
+#[tokio::test]
+async fn oversized_system_prompt_is_refused() {
+    let runtime = runtime(TestProvider::with_chunks(vec![]));
+    advertise(&runtime, "gpt-4", 8_192);
+    runtime.add_agent(
+        AgentConfig::new("crab")
+            .model("gpt-4")
+            .system_prompt("x".repeat(40_000)),
+    );
+    runtime.add_agent(
+        AgentConfig::new("half")
+            .model("gpt-4")
+            .system_prompt("x".repeat(20_000)),
+    );
+
+    let err = runtime.validate_agent("crab").unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<RuntimeError>(),
+        Some(RuntimeError::PromptTooLarge {
+            tokens: 10_000,
+            limit: 8_192,
+            ..
+        })
+    ));
+    let mut history = vec![HistoryEntry::user("hi")];
+    let err = runtime
+        .send_stateless("crab", &mut history)
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<RuntimeError>().unwrap().code(), 413);
+    assert!(
+        runtime.validate_agent("half").is_ok(),
+        "over half only warns"
+    );
+
+    advertise(&runtime, "gpt-4", 128_000);
+    assert!(
+        runtime.validate_agent("crab").is_ok(),
+        "advertised window wins"
+    );
+}
+
+#[tokio::test]
+async fn estimated_window_only_warns() {
+    let runtime = runtime(TestProvider::with_chunks(vec![]));
+    // gpt-4.1 is not advertised, so its window is the 8192-token guess.
+    runtime.add_agent(
+        AgentConfig::new("crab")
+            .model("gpt-4.1")
+            .system_prompt("x".repeat(40_000)),
+    );
+    assert!(runtime.validate_agent("crab").is_ok());
+}
+
+#[tokio::test]
+async fn refused_turn_leaves_no_user_message() {
+    let provider = TestProvider::with_chunks(vec![text_chunks("ok")]);
+    let runtime = runtime(provider.clone());
+    advertise(&runtime, "gpt-4", 8_192);
+    runtime.add_agent(
+        AgentConfig::new("crab")
+            .model("gpt-4")
+            .system_prompt("x".repeat(40_000)),
+    );
+    let id = runtime
+        .get_or_create_conversation("crab", "user")
+        .await
+        .unwrap();
+
+    let err = runtime
+        .send_to(id, "refused", &[], "", None, None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<RuntimeError>().unwrap().code(), 413);
+
+    advertise(&runtime, "gpt-4", 128_000);
+    runtime
+        .send_to(id, "accepted", &[], "", None, None)
+        .await
+        .unwrap();
+    let sent = serde_json::to_string(&provider.requests()[0].messages).unwrap();
+    assert!(sent.contains("accepted"));
+    assert!(!sent.contains("refused"), "{sent}");
+}
+
+#[tokio::test]
+async fn send_stateless_unknown_agent_errors() {
+    let runtime = runtime(TestProvider::with_chunks(vec![]));
+    let mut history = vec![HistoryEntry::user("hi")];
+    let err = runtime
+        .send_stateless("ghost", &mut history)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not registered"));
+}
+
+#[tokio::test]
+async fn stream_stateless_runs_tools_into_callers_history() {
+    let provider = TestProvider::with_chunks(vec![
+        tool_chunks(vec![ToolCall {
+            index: Some(0),
+            id: "call_1".into(),
+            function: FunctionCall {
+                name: "lookup".into(),
+                arguments: "{}".into(),
+            },
+            ..Default::default()
+        }]),
+        text_chunks("streamed reply"),
+    ]);
+    let runtime = runtime(provider);
+    runtime.add_agent(AgentConfig::new("crab"));
+
+    let mut history = vec![HistoryEntry::user("hi")];
+    let events: Vec<_> = runtime
+        .stream_stateless("crab", &mut history)
+        .collect()
+        .await;
+
+    let text: String = events
+        .iter()
+        .filter_map(|e| match e {
+            AgentEvent::TextDelta(t) => Some(t.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(text, "streamed reply");
+    assert!(
+        matches!(events.last(), Some(AgentEvent::Done(r)) if r.final_response.as_deref() == Some("streamed reply"))
+    );
+    // user, assistant tool call, tool result, assistant reply
+    assert_eq!(history.len(), 4);
+    assert_eq!(history[3].text(), "streamed reply");
+    assert_eq!(runtime.conversation_count().await, 0);
+
+    let mut blank = vec![HistoryEntry::user(" ")];
+    let events: Vec<_> = runtime.stream_stateless("crab", &mut blank).collect().await;
+    assert!(
+        matches!(events.as_slice(), [AgentEvent::Done(r)] if matches!(r.stop_reason, AgentStopReason::Error(_)))
+    );
+}
+
+#[tokio::test]
+async fn set_provider_keeps_conversations_and_agents() {
+    let mut runtime = runtime(TestProvider::with_chunks(vec![text_chunks("local")]));
+    runtime.add_agent(AgentConfig::new("crab"));
+    let id = runtime
+        .get_or_create_conversation("crab", "user")
+        .await
+        .unwrap();
+    let reply = runtime
+        .send_to(id, "hi", &[], "", None, None)
+        .await
+        .unwrap();
+    assert_eq!(reply.final_response.as_deref(), Some("local"));
+
+    runtime.set_provider(TestProvider::with_chunks(vec![text_chunks("remote")]));
+    assert!(runtime.agent("crab").is_some());
+    let reply = runtime
+        .send_to(id, "again", &[], "", None, None)
+        .await
+        .unwrap();
+    assert_eq!(reply.final_response.as_deref(), Some("remote"));
+
+    let conversation = runtime.conversation(id).await.unwrap();
+    assert_eq!(conversation.lock().await.history.len(), 4);
+}
+
 #[tokio::test]
 async fn stream_to_yields_correct_content() {
     let provider = TestProvider::with_chunks(vec![text_chunks("streamed")]);
@@ -257,7 +1016,7 @@ async fn stream_to_yields_correct_content() {
         .unwrap();
 
     let mut events = Vec::new();
-    let mut stream = std::pin::pin!(runtime.stream_to(conversation_id, "hi", "", None));
+    let mut stream = std::pin::pin!(runtime.stream_to(conversation_id, "hi", &[], "", None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -291,7 +1050,7 @@ async fn stream_to_nonexistent_conversation_yields_error() {
     let runtime = runtime(TestProvider::with_chunks(vec![]));
 
     let mut events = Vec::new();
-    let mut stream = std::pin::pin!(runtime.stream_to(999, "hi", "", None));
+    let mut stream = std::pin::pin!(runtime.stream_to(999, "hi", &[], "", None));
     while let Some(event) = stream.next().await {
         events.push(event);
     }
@@ -320,7 +1079,14 @@ async fn send_to_indexes_messages_for_search() {
         .await
         .unwrap();
     runtime
-        .send_to(conversation_id, "why is the deploy stuck", "", None)
+        .send_to(
+            conversation_id,
+            "why is the deploy stuck",
+            &[],
+            "",
+            None,
+            None,
+        )
         .await
         .unwrap();
 
@@ -333,3 +1099,35 @@ async fn send_to_indexes_messages_for_search() {
         "window must surface the matched message snippet"
     );
 }
+
+#[tokio::test]
+async fn list_models_flags_active_and_keeps_context_length() {
+    use wcore::protocol::message::ModelInfo;
+
+    let runtime = runtime(TestProvider::with_chunks(vec![]));
+    runtime.set_models(vec![
+        ModelInfo {
+            name: "gpt-4o".to_owned(),
+            active: false,
+            context_length: 128_000,
+            vision: None,
+        },
+        ModelInfo {
+            name: "mystery".to_owned(),
+            active: false,
+            context_length: 0,
+            vision: None,
+        },
+    ]);
+
+    let models = runtime.list_models();
+    assert_eq!(models.len(), 2);
+    assert_eq!(models[0].context_length, 128_000);
+    assert!(!models[0].active, "no default agent, so nothing is active");
+    assert_eq!(
+        models[1].context_length,
+        wcore::model::default_context_limit("mystery") as u64,
+        "unadvertised windows are listed as the estimate"
+    );
+    assert_eq!(runtime.advertised_context_limit("mystery"), None);
+}
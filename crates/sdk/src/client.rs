@@ -1,12 +1,13 @@
 //! Client for connecting to the crabtalk daemon.
 //!
 //! Creates a fresh connection per message to support concurrent sends
-//! from platform adapters (Telegram).
+//! from platform adapters (Telegram). Adapters take any [`Connect`], so
+//! tests can swap the daemon for a scripted client.
 
 use futures_util::StreamExt;
 use std::net::{Ipv4Addr, SocketAddr};
 use tokio::sync::mpsc;
-use transport::tcp::TcpConnection;
+use transport::tcp::{TcpClient, TcpClientConfig};
 use wcore::protocol::{
     api::Client,
     message::{ClientMessage, ServerMessage},
@@ -23,11 +24,12 @@ pub enum NodeTransport {
 
 /// Client that sends `ClientMessage`s to the daemon.
 ///
-/// Each call to [`send`] opens a new connection, sends the message, and
-/// returns a receiver that streams back `ServerMessage` responses until
-/// the daemon closes the connection.
+/// Each call to [`Connect::send`] opens a new connection, sends the
+/// message, and returns a receiver that streams back `ServerMessage`
+/// responses until the daemon closes the connection.
 pub struct NodeClient {
     transport: NodeTransport,
+    compression: bool,
 }
 
 impl NodeClient {
@@ -50,6 +52,7 @@ impl NodeClient {
     pub fn tcp(port: u16) -> Self {
         Self {
             transport: NodeTransport::Tcp(port),
+            compression: false,
         }
     }
 
@@ -58,40 +61,36 @@ impl NodeClient {
     pub fn uds(socket_path: &std::path::Path) -> Self {
         Self {
             transport: NodeTransport::Uds(socket_path.to_path_buf()),
+            compression: false,
         }
     }
 
-    /// Send a message to the daemon and return a receiver for streamed replies.
-    ///
-    /// Opens a fresh connection per call so multiple platform loops can send
-    /// concurrently without blocking.
-    pub async fn send(&self, msg: ClientMessage) -> mpsc::UnboundedReceiver<ServerMessage> {
-        let (tx, rx) = mpsc::unbounded_channel();
+    /// Negotiate frame compression on each TCP connection. Off by
+    /// default; UDS connections never compress.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+}
 
-        macro_rules! spawn_stream {
-            ($conn:expr, $msg:expr, $tx:expr) => {{
-                let mut conn = $conn;
-                let msg = $msg;
-                let tx = $tx;
-                tokio::spawn(async move {
-                    let mut stream = std::pin::pin!(conn.request_stream(msg));
-                    while let Some(result) = stream.next().await {
-                        match result {
-                            Ok(server_msg) => {
-                                if tx.send(server_msg).is_err() {
-                                    break;
-                                }
-                            }
-                            Err(e) => {
-                                tracing::warn!("daemon stream error: {e}");
-                                break;
-                            }
-                        }
-                    }
-                });
-            }};
-        }
+/// A source of daemon connections for platform loops.
+///
+/// Each [`send`](Connect::send) runs on its own connection, so loops can
+/// send concurrently without blocking. [`NodeClient`] dials the daemon;
+/// any cloneable [`Client`] sends on a fresh clone, which lets tests drive
+/// a gateway with `wcore::testing::TestClient` instead of a daemon.
+pub trait Connect: Send + Sync + 'static {
+    /// Send a message and return a receiver for the streamed replies. The
+    /// receiver closes when the daemon ends the stream.
+    fn send(
+        &self,
+        msg: ClientMessage,
+    ) -> impl Future<Output = mpsc::UnboundedReceiver<ServerMessage>> + Send;
+}
 
+impl Connect for NodeClient {
+    async fn send(&self, msg: ClientMessage) -> mpsc::UnboundedReceiver<ServerMessage> {
+        let (tx, rx) = mpsc::unbounded_channel();
         match &self.transport {
             #[cfg(unix)]
             NodeTransport::Uds(socket_path) => {
@@ -101,14 +100,17 @@ impl NodeClient {
                 .connect()
                 .await
                 {
-                    Ok(conn) => spawn_stream!(conn, msg, tx),
+                    Ok(conn) => forward(conn, msg, tx),
                     Err(e) => tracing::error!("failed to connect to daemon: {e}"),
                 }
             }
             NodeTransport::Tcp(port) => {
-                let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, *port));
-                match TcpConnection::connect(addr).await {
-                    Ok(conn) => spawn_stream!(conn, msg, tx),
+                let client = TcpClient::new(TcpClientConfig {
+                    addr: SocketAddr::from((Ipv4Addr::LOCALHOST, *port)),
+                    compression: self.compression,
+                });
+                match client.connect().await {
+                    Ok(conn) => forward(conn, msg, tx),
                     Err(e) => tracing::error!("failed to connect to daemon: {e}"),
                 }
             }
@@ -116,3 +118,35 @@ impl NodeClient {
         rx
     }
 }
+
+impl<C: Client + Clone + Sync + 'static> Connect for C {
+    async fn send(&self, msg: ClientMessage) -> mpsc::UnboundedReceiver<ServerMessage> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        forward(self.clone(), msg, tx);
+        rx
+    }
+}
+
+/// Stream the replies to `msg` on `conn` into `tx` from a spawned task.
+fn forward<C: Client + 'static>(
+    mut conn: C,
+    msg: ClientMessage,
+    tx: mpsc::UnboundedSender<ServerMessage>,
+) {
+    tokio::spawn(async move {
+        let mut stream = std::pin::pin!(conn.request_stream(msg));
+        while let Some(result) = stream.next().await {
+            match result {
+                Ok(server_msg) => {
+                    if tx.send(server_msg).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("daemon stream error: {e}");
+                    break;
+                }
+            }
+        }
+    });
+}
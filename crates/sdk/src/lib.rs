@@ -9,9 +9,11 @@ pub mod command;
 pub mod message;
 pub mod stream;
 
-pub use client::NodeClient;
+pub use client::{Connect, NodeClient};
 pub use command::{BotCommand, COMMAND_HINT, parse_command};
-pub use message::{Attachment, AttachmentKind, GatewayMessage, attachment_summary};
+pub use message::{
+    Attachment, AttachmentKind, Channel, GatewayMessage, attachment_summary, split_message,
+};
 pub use stream::StreamAccumulator;
 
 /// Shared set of sender IDs belonging to sibling Crabtalk bots.
@@ -15,12 +15,15 @@ pub struct GatewayMessage {
     pub is_bot: bool,
     /// Whether this message is from a group chat (vs DM).
     pub is_group: bool,
+    /// Whether the message is directed at the bot. Always true in DMs; in
+    /// groups, true when the bot is mentioned or replied to.
+    pub addressed: bool,
     /// Message text content.
     pub content: String,
     /// Attached files or media.
     pub attachments: Vec<Attachment>,
     /// Message ID being replied to, if any.
-    pub reply_to: Option<i32>,
+    pub reply_to: Option<i64>,
     /// Unix timestamp when the message was created.
     pub timestamp: u64,
 }
@@ -92,3 +95,65 @@ pub fn attachment_summary(attachments: &[Attachment]) -> Option<String> {
     }
     Some(format!("[Attachments: {}]", parts.join(", ")))
 }
+
+/// An outbound chat adapter, as far as message limits go.
+///
+/// Each adapter reports the longest message its platform accepts and
+/// sends what [`Channel::split`] returns.
+pub trait Channel {
+    /// Longest message the platform accepts, in characters. `None` when
+    /// it has no limit.
+    fn max_message_len(&self) -> Option<usize>;
+
+    /// Split outbound `text` into messages this channel accepts.
+    fn split(&self, text: &str) -> Vec<String> {
+        match self.max_message_len() {
+            Some(max) => split_message(text, max),
+            None => vec![text.to_owned()],
+        }
+    }
+}
+
+/// Split outbound text into chunks of at most `max` characters.
+///
+/// Each platform caps message length (Telegram at 4096, Discord at 2000),
+/// so adapters declare their limit through [`Channel`] and call this
+/// before sending. Splits
+/// prefer paragraph boundaries, then line breaks, then whitespace; a run
+/// with no boundary inside the window is hard-split at `max`. The
+/// boundary itself is dropped. Always returns at least one chunk.
+pub fn split_message(text: &str, max: usize) -> Vec<String> {
+    let max = max.max(1);
+    let mut chunks = Vec::new();
+    let mut rest = text;
+
+    loop {
+        let Some((cut, _)) = rest.char_indices().nth(max) else {
+            chunks.push(rest.to_owned());
+            break;
+        };
+
+        let window = &rest[..cut];
+        let (end, next) = match window.rfind("\n\n").filter(|&i| i > 0) {
+            Some(i) => (i, i + 2),
+            None => match window.rfind('\n').filter(|&i| i > 0) {
+                Some(i) => (i, i + 1),
+                None => match window.rfind(char::is_whitespace).filter(|&i| i > 0) {
+                    Some(i) => {
+                        let ws = window[i..].chars().next().map_or(1, char::len_utf8);
+                        (i, i + ws)
+                    }
+                    None => (cut, cut),
+                },
+            },
+        };
+
+        chunks.push(rest[..end].to_owned());
+        rest = &rest[next..];
+        if rest.is_empty() {
+            break;
+        }
+    }
+
+    chunks
+}
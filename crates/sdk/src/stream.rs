@@ -11,6 +11,9 @@ pub struct StreamAccumulator {
     text: String,
     /// Current tool call status line (e.g., "[calling bash, read...]").
     tool_line: Option<String>,
+    /// Calls from the latest `ToolStart` without a result yet, as
+    /// `(call_id, name)`.
+    running: Vec<(String, String)>,
     /// Agent name from StreamStart.
     pub agent: Option<String>,
     /// Captured error, if any.
@@ -32,6 +35,7 @@ impl StreamAccumulator {
         Self {
             text: String::new(),
             tool_line: None,
+            running: Vec::new(),
             agent: None,
             error: None,
             done: false,
@@ -52,11 +56,19 @@ impl StreamAccumulator {
                 // Thinking content not shown in gateway messages.
             }
             Some(stream_event::Event::ToolStart(ts)) => {
-                let names: Vec<&str> = ts.calls.iter().map(|c| c.name.as_str()).collect();
-                self.tool_line = Some(format!("[calling {}...]", names.join(", ")));
+                self.running = ts
+                    .calls
+                    .iter()
+                    .map(|c| (c.call_id.clone(), c.name.clone()))
+                    .collect();
+                self.update_tool_line();
+            }
+            Some(stream_event::Event::ToolResult(tr)) => {
+                self.running.retain(|(id, _)| *id != tr.call_id);
+                self.update_tool_line();
             }
-            Some(stream_event::Event::ToolResult(_)) => {}
             Some(stream_event::Event::ToolsComplete(_)) => {
+                self.running.clear();
                 self.tool_line = None;
             }
             Some(stream_event::Event::End(end)) => {
@@ -70,6 +82,9 @@ impl StreamAccumulator {
                 self.tool_line = Some(format!("[question: {}]", headers.join(", ")));
                 self.pending_questions = Some(ask.questions.clone());
             }
+            Some(stream_event::Event::ConfirmTool(confirm)) => {
+                self.tool_line = Some(format!("[awaiting approval: {}]", confirm.name));
+            }
             Some(stream_event::Event::UserSteered(_)) => {}
             Some(
                 stream_event::Event::TextStart(_)
@@ -81,6 +96,16 @@ impl StreamAccumulator {
         }
     }
 
+    /// Show the tools still running, or nothing once all have finished.
+    fn update_tool_line(&mut self) {
+        if self.running.is_empty() {
+            self.tool_line = None;
+            return;
+        }
+        let names: Vec<&str> = self.running.iter().map(|(_, n)| n.as_str()).collect();
+        self.tool_line = Some(format!("[calling {}...]", names.join(", ")));
+    }
+
     /// Set a captured error message.
     pub fn set_error(&mut self, msg: String) {
         self.error = Some(msg);
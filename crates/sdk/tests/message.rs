@@ -0,0 +1,59 @@
+//! Tests for outbound message splitting.
+
+use crabtalk_sdk::{Channel, split_message};
+
+#[test]
+fn short_text_is_single_chunk() {
+    assert_eq!(split_message("hello", 10), vec!["hello"]);
+    assert_eq!(split_message("", 10), vec![""]);
+}
+
+#[test]
+fn splits_on_paragraph_boundary() {
+    let text = "first paragraph\nstill first\n\nsecond paragraph";
+    let chunks = split_message(text, 32);
+    assert_eq!(
+        chunks,
+        vec!["first paragraph\nstill first", "second paragraph"]
+    );
+}
+
+#[test]
+fn falls_back_to_line_then_whitespace() {
+    let chunks = split_message("aaaa\nbbbb cccc", 10);
+    assert_eq!(chunks, vec!["aaaa", "bbbb cccc"]);
+
+    let chunks = split_message("aaaa bbbb cccc", 10);
+    assert_eq!(chunks, vec!["aaaa bbbb", "cccc"]);
+}
+
+#[test]
+fn hard_splits_overlong_line() {
+    let text = "x".repeat(25);
+    let chunks = split_message(&text, 10);
+    assert_eq!(chunks, vec!["x".repeat(10), "x".repeat(10), "x".repeat(5)]);
+}
+
+#[test]
+fn counts_characters_not_bytes() {
+    let text = "é".repeat(12);
+    let chunks = split_message(&text, 5);
+    assert_eq!(chunks.len(), 3);
+    assert!(chunks.iter().all(|c| c.chars().count() <= 5));
+    assert_eq!(chunks.concat(), text);
+}
+
+struct Limited(Option<usize>);
+
+impl Channel for Limited {
+    fn max_message_len(&self) -> Option<usize> {
+        self.0
+    }
+}
+
+#[test]
+fn channel_splits_at_its_own_limit() {
+    let text = "one two three";
+    assert_eq!(Limited(Some(8)).split(text), vec!["one two", "three"]);
+    assert_eq!(Limited(None).split(text), vec![text]);
+}
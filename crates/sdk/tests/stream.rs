@@ -0,0 +1,50 @@
+//! Tests for the gateway stream accumulator.
+
+use crabtalk_sdk::StreamAccumulator;
+use wcore::protocol::message::{
+    StreamChunk, StreamEvent, ToolCallInfo, ToolResultEvent, ToolStartEvent, ToolsCompleteEvent,
+    stream_event::Event,
+};
+
+fn event(event: Event) -> StreamEvent {
+    StreamEvent { event: Some(event) }
+}
+
+fn call(call_id: &str, name: &str) -> ToolCallInfo {
+    ToolCallInfo {
+        name: name.into(),
+        arguments: String::new(),
+        call_id: call_id.into(),
+    }
+}
+
+fn result(call_id: &str) -> StreamEvent {
+    event(Event::ToolResult(ToolResultEvent {
+        call_id: call_id.into(),
+        output: "ok".into(),
+        duration_ms: 1,
+        is_error: false,
+    }))
+}
+
+#[test]
+fn tool_line_tracks_running_calls() {
+    let mut acc = StreamAccumulator::new();
+    acc.push(&event(Event::Chunk(StreamChunk {
+        content: "looking".into(),
+    })));
+    acc.push(&event(Event::ToolStart(ToolStartEvent {
+        calls: vec![call("c1", "bash"), call("c2", "read")],
+    })));
+    assert_eq!(acc.render(), "looking\n[calling bash, read...]");
+
+    // Results arrive in completion order, not call order.
+    acc.push(&result("c2"));
+    assert_eq!(acc.render(), "looking\n[calling bash...]");
+
+    acc.push(&result("c1"));
+    assert_eq!(acc.render(), "looking");
+
+    acc.push(&event(Event::ToolsComplete(ToolsCompleteEvent {})));
+    assert_eq!(acc.render(), "looking");
+}
@@ -10,11 +10,23 @@
 /// of buffer before backpressure stalls the producer.
 pub const REPLY_CHANNEL_CAPACITY: usize = 256;
 
+/// How long a single reply frame may take to write before the client is
+/// treated as stuck.
+///
+/// A full reply channel makes producers await, which throttles a lazy
+/// agent stream at its source. A client that stops reading entirely
+/// would hold that stall forever, so once one frame sits unwritten this
+/// long the writer closes the connection; pending sends then fail and
+/// the producer's stream is dropped.
+pub const WRITE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
 use anyhow::Result;
 use futures_core::Stream;
+use tokio::{io::AsyncWrite, sync::mpsc};
 use wcore::protocol::{
     api::Client,
-    message::{ClientMessage, ServerMessage},
+    codec::{self, Compression},
+    message::{ClientMessage, HelloReply, ServerMessage, client_message, server_message},
 };
 
 pub mod tcp;
@@ -63,3 +75,57 @@ impl Client for Transport {
         }
     }
 }
+
+/// Answer a `Hello` handshake on the connection's reply channel with the
+/// first offered compression this build supports. Returns `false` for any
+/// other message, which then goes to the daemon as usual.
+pub(crate) async fn answer_hello(msg: &ClientMessage, tx: &mpsc::Sender<ServerMessage>) -> bool {
+    let Some(client_message::Msg::Hello(hello)) = &msg.msg else {
+        return false;
+    };
+    let chosen = hello
+        .compression
+        .iter()
+        .find_map(|name| Compression::from_name(name));
+    let reply = ServerMessage {
+        msg: Some(server_message::Msg::Hello(HelloReply {
+            compression: chosen.map(|c| c.name().to_owned()).unwrap_or_default(),
+        })),
+    };
+    let _ = tx.send(reply).await;
+    true
+}
+
+/// Write queued replies to `writer` until the channel closes, a write
+/// fails, or one write exceeds [`WRITE_TIMEOUT`]. Returning drops both
+/// the writer and the receiver, so a stalled client is disconnected and
+/// every producer still sending to it sees an error.
+///
+/// Frames are plain until a `HelloReply` goes out naming a compression;
+/// every frame after it may be compressed.
+pub(crate) async fn drain_replies<W: AsyncWrite + Unpin>(
+    mut rx: mpsc::Receiver<ServerMessage>,
+    mut writer: W,
+) {
+    let mut compression = None;
+    while let Some(msg) = rx.recv().await {
+        let write = codec::write_message_with(&mut writer, &msg, compression);
+        match tokio::time::timeout(WRITE_TIMEOUT, write).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                tracing::error!("failed to write message: {e}");
+                break;
+            }
+            Err(_) => {
+                tracing::warn!(
+                    "client stopped reading for {}s, closing connection",
+                    WRITE_TIMEOUT.as_secs()
+                );
+                break;
+            }
+        }
+        if let Some(server_message::Msg::Hello(reply)) = &msg.msg {
+            compression = Compression::from_name(&reply.compression);
+        }
+    }
+}
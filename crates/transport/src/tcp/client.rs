@@ -6,8 +6,8 @@ use std::net::SocketAddr;
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use wcore::protocol::{
     api::Client,
-    codec,
-    message::{ClientMessage, ErrorMsg, ServerMessage, server_message},
+    codec::{self, Compression},
+    message::{ClientMessage, ErrorMsg, Hello, ServerMessage, client_message, server_message},
 };
 
 /// Client configuration for connecting to a crabtalk daemon over TCP.
@@ -15,6 +15,9 @@ use wcore::protocol::{
 pub struct TcpClientConfig {
     /// Daemon TCP address.
     pub addr: SocketAddr,
+    /// Negotiate frame compression on connect. Worth it over a remote
+    /// link; pointless on localhost.
+    pub compression: bool,
 }
 
 /// TCP client for the crabtalk daemon.
@@ -32,7 +35,11 @@ impl TcpClient {
 
     /// Connect to the daemon and return a [`TcpConnection`].
     pub async fn connect(&self) -> Result<TcpConnection> {
-        TcpConnection::connect(self.config.addr).await
+        let mut conn = TcpConnection::connect(self.config.addr).await?;
+        if self.config.compression {
+            conn.negotiate_compression().await?;
+        }
+        Ok(conn)
     }
 }
 
@@ -43,6 +50,7 @@ impl TcpClient {
 pub struct TcpConnection {
     reader: OwnedReadHalf,
     writer: OwnedWriteHalf,
+    compression: Option<Compression>,
 }
 
 impl TcpConnection {
@@ -52,13 +60,40 @@ impl TcpConnection {
         stream.set_nodelay(true)?;
         tracing::debug!("connected to {addr}");
         let (reader, writer) = stream.into_split();
-        Ok(Self { reader, writer })
+        Ok(Self {
+            reader,
+            writer,
+            compression: None,
+        })
+    }
+
+    /// Offer every supported compression in a `Hello` handshake and use
+    /// whichever the daemon picks for large frames from now on. Returns
+    /// the agreed algorithm, `None` if the daemon supports none of them.
+    pub async fn negotiate_compression(&mut self) -> Result<Option<Compression>> {
+        let hello = ClientMessage {
+            msg: Some(client_message::Msg::Hello(Hello {
+                compression: Compression::ALL
+                    .iter()
+                    .map(|c| c.name().to_owned())
+                    .collect(),
+            })),
+        };
+        match self.request(hello).await? {
+            ServerMessage {
+                msg: Some(server_message::Msg::Hello(reply)),
+            } => {
+                self.compression = Compression::from_name(&reply.compression);
+                Ok(self.compression)
+            }
+            other => anyhow::bail!("unexpected hello reply: {other:?}"),
+        }
     }
 }
 
 impl Client for TcpConnection {
     async fn request(&mut self, msg: ClientMessage) -> Result<ServerMessage> {
-        codec::write_message(&mut self.writer, &msg).await?;
+        codec::write_message_with(&mut self.writer, &msg, self.compression).await?;
         Ok(codec::read_message(&mut self.reader).await?)
     }
 
@@ -67,7 +102,7 @@ impl Client for TcpConnection {
         msg: ClientMessage,
     ) -> impl Stream<Item = Result<ServerMessage>> + Send + '_ {
         async_stream::try_stream! {
-            codec::write_message(&mut self.writer, &msg).await?;
+            codec::write_message_with(&mut self.writer, &msg, self.compression).await?;
 
             loop {
                 let server_msg: ServerMessage = codec::read_message(&mut self.reader).await?;
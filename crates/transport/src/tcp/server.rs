@@ -1,6 +1,6 @@
 //! TCP server — accept loop and per-connection message handler.
 
-use crate::REPLY_CHANNEL_CAPACITY;
+use crate::{REPLY_CHANNEL_CAPACITY, answer_hello, drain_replies};
 use std::{
     net::{Ipv4Addr, SocketAddr},
     time::Duration,
@@ -63,16 +63,9 @@ pub async fn accept_loop<F>(
                         tracing::debug!("tcp connection from {addr}");
                         let cb = on_message.clone();
                         tokio::spawn(async move {
-                            let (mut reader, mut writer) = stream.into_split();
-                            let (tx, mut rx) = mpsc::channel::<ServerMessage>(REPLY_CHANNEL_CAPACITY);
-                            let send_task = tokio::spawn(async move {
-                                while let Some(msg) = rx.recv().await {
-                                    if let Err(e) = codec::write_message(&mut writer, &msg).await {
-                                        tracing::error!("failed to write message: {e}");
-                                        break;
-                                    }
-                                }
-                            });
+                            let (mut reader, writer) = stream.into_split();
+                            let (tx, rx) = mpsc::channel::<ServerMessage>(REPLY_CHANNEL_CAPACITY);
+                            let send_task = tokio::spawn(drain_replies(rx, writer));
 
                             loop {
                                 let client_msg: ClientMessage = match codec::read_message(&mut reader).await {
@@ -80,6 +73,9 @@ pub async fn accept_loop<F>(
                                     Err(codec::FrameError::ConnectionClosed) => break,
                                     Err(e) => { tracing::debug!("read error: {e}"); break; }
                                 };
+                                if answer_hello(&client_msg, &tx).await {
+                                    continue;
+                                }
                                 cb(client_msg, tx.clone());
                             }
 
@@ -0,0 +1,71 @@
+//! Tests for the TCP transport's compression handshake.
+
+use crabtalk_transport::tcp::{TcpClient, TcpClientConfig, TcpConnection, accept_loop};
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::{
+    net::TcpListener,
+    sync::{mpsc, oneshot},
+};
+use wcore::protocol::{
+    api::Client,
+    codec::Compression,
+    message::{ClientMessage, ErrorMsg, Ping, ServerMessage, client_message, server_message},
+};
+
+/// Serve a daemon stand-in that answers every message with a large,
+/// compressible error frame.
+async fn serve() -> (SocketAddr, oneshot::Sender<()>) {
+    let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    let on_message = |_msg: ClientMessage, tx: mpsc::Sender<ServerMessage>| {
+        tokio::spawn(async move {
+            let reply = ServerMessage {
+                msg: Some(server_message::Msg::Error(ErrorMsg {
+                    code: 200,
+                    message: "crab ".repeat(4096),
+                })),
+            };
+            let _ = tx.send(reply).await;
+        });
+    };
+    tokio::spawn(accept_loop(listener, on_message, shutdown_rx));
+    (addr, shutdown_tx)
+}
+
+fn ping() -> ClientMessage {
+    ClientMessage {
+        msg: Some(client_message::Msg::Ping(Ping {})),
+    }
+}
+
+fn error_text(reply: ServerMessage) -> String {
+    match reply.msg {
+        Some(server_message::Msg::Error(e)) => e.message,
+        other => panic!("unexpected reply: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn handshake_agrees_on_the_preferred_compression() {
+    let (addr, _shutdown) = serve().await;
+    let mut conn = TcpConnection::connect(addr).await.unwrap();
+    let chosen = conn.negotiate_compression().await.unwrap();
+    assert_eq!(chosen, Some(Compression::ALL[0]));
+
+    let reply = conn.request(ping()).await.unwrap();
+    assert_eq!(error_text(reply), "crab ".repeat(4096));
+}
+
+#[tokio::test]
+async fn client_config_negotiates_on_connect() {
+    let (addr, _shutdown) = serve().await;
+    for compression in [true, false] {
+        let client = TcpClient::new(TcpClientConfig { addr, compression });
+        let mut conn = client.connect().await.unwrap();
+        let reply = conn.request(ping()).await.unwrap();
+        assert_eq!(error_text(reply), "crab ".repeat(4096), "{compression}");
+    }
+}
@@ -0,0 +1,79 @@
+//! Reply delivery — routes a fired entry's final reply to a gateway chat.
+//!
+//! Cron doesn't talk to the gateway processes. It posts straight to the
+//! platform API, reading credentials from the gateway's own config file,
+//! which lives next to `crons.toml`. A platform without a usable config is
+//! treated as not connected: the delivery is logged and skipped.
+
+use crate::entry::{DeliverTo, Platform};
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+/// The slice of `telegram.toml` cron needs.
+#[derive(Deserialize)]
+struct TelegramCredentials {
+    #[serde(default)]
+    token: String,
+}
+
+pub struct Deliverer {
+    http: reqwest::Client,
+    config_dir: PathBuf,
+}
+
+impl Deliverer {
+    /// `config_dir` is where the gateway config files (`telegram.toml`)
+    /// are looked up on each delivery, so token edits apply without a
+    /// restart.
+    pub fn new(config_dir: PathBuf) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            config_dir,
+        }
+    }
+
+    /// Send `text` to the target chat. Returns `Ok(())` without sending
+    /// when the platform isn't configured.
+    pub async fn deliver(&self, target: &DeliverTo, text: &str) -> Result<()> {
+        match target.platform {
+            Platform::Telegram => self.deliver_telegram(target.chat_id, text).await,
+        }
+    }
+
+    async fn deliver_telegram(&self, chat_id: i64, text: &str) -> Result<()> {
+        let path = self.config_dir.join("telegram.toml");
+        let token = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|c| toml::from_str::<TelegramCredentials>(&c).ok())
+            .map(|c| c.token)
+            .unwrap_or_default();
+        if token.is_empty() {
+            tracing::warn!(
+                chat_id,
+                "telegram not connected ({} has no token), skipping delivery",
+                path.display()
+            );
+            return Ok(());
+        }
+
+        let url = format!("https://api.telegram.org/bot{token}/sendMessage");
+        for chunk in sdk::split_message(text, telegram::MAX_MESSAGE_LEN) {
+            let resp = self
+                .http
+                .post(&url)
+                .json(&serde_json::json!({ "chat_id": chat_id, "text": chunk }))
+                .send()
+                .await
+                // The URL holds the bot token; keep it out of the error.
+                .map_err(reqwest::Error::without_url)
+                .context("telegram sendMessage failed")?;
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                bail!("telegram sendMessage returned {status}: {body}");
+            }
+        }
+        Ok(())
+    }
+}
@@ -6,7 +6,8 @@ use std::str::FromStr;
 /// A single scheduled trigger.
 ///
 /// Fires `/{skill}` into `agent` as `sender` on the cron `schedule`, skipping
-/// fire times that fall inside the optional quiet window.
+/// fire times that fall inside the optional quiet window. When `deliver_to`
+/// is set, the agent's final reply is also sent to that chat.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CronEntry {
     pub id: u64,
@@ -20,6 +21,22 @@ pub struct CronEntry {
     pub quiet_end: Option<String>,
     #[serde(default)]
     pub once: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deliver_to: Option<DeliverTo>,
+}
+
+/// Target chat for a fired entry's reply.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeliverTo {
+    pub platform: Platform,
+    pub chat_id: i64,
+}
+
+/// Gateway platform a reply can be delivered through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Telegram,
 }
 
 /// Parse a cron expression, returning a human-readable error on failure.
@@ -1,10 +1,11 @@
 //! Scheduler loop — spawns one timer task per schedule, polls the store
 //! file for external edits, and fires `/{skill}` into the daemon on cue.
 
+use crate::deliver::Deliverer;
 use crate::entry::{CronEntry, is_quiet};
 use crate::store::Store;
 use anyhow::Result;
-use sdk::NodeClient;
+use sdk::{Connect, StreamAccumulator};
 use std::{
     collections::{HashMap, HashSet},
     path::{Path, PathBuf},
@@ -16,7 +17,7 @@ use tokio::{
     sync::{Mutex, broadcast},
     task::JoinHandle,
 };
-use wcore::protocol::message::{ClientMessage, StreamMsg};
+use wcore::protocol::message::{ClientMessage, StreamMsg, server_message};
 
 const FILE_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
@@ -24,14 +25,20 @@ const FILE_POLL_INTERVAL: Duration = Duration::from_secs(2);
 ///
 /// `schedule_path` is the TOML file owned by this process. `client` is the
 /// daemon connection — constructed by the caller so tests and alternate
-/// assemblies can inject their own.
-pub async fn run(schedule_path: PathBuf, client: NodeClient) -> Result<()> {
+/// assemblies can inject their own. Gateway configs used for `deliver_to`
+/// are read from the schedule file's directory.
+pub async fn run(schedule_path: PathBuf, client: impl Connect) -> Result<()> {
     let store = Arc::new(Mutex::new(Store::load(schedule_path.clone())?));
     let client = Arc::new(client);
+    let config_dir = schedule_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let deliverer = Arc::new(Deliverer::new(config_dir));
     let (shutdown_tx, _) = broadcast::channel::<()>(1);
 
     let mut timers: HashMap<u64, JoinHandle<()>> = HashMap::new();
-    reconcile(&store, &client, &shutdown_tx, &mut timers).await;
+    reconcile(&store, &client, &deliverer, &shutdown_tx, &mut timers).await;
     tracing::info!(
         "cron started — {} schedule(s) loaded from {}",
         timers.len(),
@@ -54,7 +61,7 @@ pub async fn run(schedule_path: PathBuf, client: NodeClient) -> Result<()> {
                     match Store::load(schedule_path.clone()) {
                         Ok(fresh) => {
                             *store.lock().await = fresh;
-                            reconcile(&store, &client, &shutdown_tx, &mut timers).await;
+                            reconcile(&store, &client, &deliverer, &shutdown_tx, &mut timers).await;
                         }
                         Err(e) => tracing::warn!("reload failed: {e}"),
                     }
@@ -76,9 +83,10 @@ pub async fn run(schedule_path: PathBuf, client: NodeClient) -> Result<()> {
 
 /// Start timers for entries that don't have one, abort timers whose entry
 /// is gone. Assumes the store holds the fresh state.
-async fn reconcile(
+async fn reconcile<C: Connect>(
     store: &Arc<Mutex<Store>>,
-    client: &Arc<NodeClient>,
+    client: &Arc<C>,
+    deliverer: &Arc<Deliverer>,
     shutdown_tx: &broadcast::Sender<()>,
     timers: &mut HashMap<u64, JoinHandle<()>>,
 ) {
@@ -101,6 +109,7 @@ async fn reconcile(
         let handle = spawn_timer(
             entry.clone(),
             client.clone(),
+            deliverer.clone(),
             store.clone(),
             shutdown_tx.subscribe(),
         );
@@ -108,9 +117,10 @@ async fn reconcile(
     }
 }
 
-fn spawn_timer(
+fn spawn_timer<C: Connect>(
     entry: CronEntry,
-    client: Arc<NodeClient>,
+    client: Arc<C>,
+    deliverer: Arc<Deliverer>,
     store: Arc<Mutex<Store>>,
     mut shutdown_rx: broadcast::Receiver<()>,
 ) -> JoinHandle<()> {
@@ -167,7 +177,17 @@ fn spawn_timer(
                 entry.sender,
             );
 
-            fire(&client, &entry).await;
+            let reply = fire(client.as_ref(), &entry).await;
+            if let Some(target) = &entry.deliver_to {
+                match reply {
+                    Some(text) => {
+                        if let Err(e) = deliverer.deliver(target, &text).await {
+                            tracing::warn!("cron {}: delivery failed: {e}", entry.id);
+                        }
+                    }
+                    None => tracing::warn!("cron {}: no reply to deliver", entry.id),
+                }
+            }
 
             if entry.once {
                 if let Err(e) = store.lock().await.delete(entry.id) {
@@ -182,8 +202,11 @@ fn spawn_timer(
 
 /// Open a connection, fire a single StreamMsg, and drain the reply stream.
 /// Errors inside the daemon surface as ErrorMsg in the stream and are logged
-/// by NodeClient — the schedule continues on the next tick regardless.
-async fn fire(client: &NodeClient, entry: &CronEntry) {
+/// by the client — the schedule continues on the next tick regardless.
+///
+/// Returns the final reply text, or `None` if the stream errored or the
+/// agent produced no text.
+pub async fn fire(client: &impl Connect, entry: &CronEntry) -> Option<String> {
     let msg = ClientMessage::from(StreamMsg {
         agent: entry.agent.clone(),
         content: format!("/{}", entry.skill),
@@ -191,9 +214,23 @@ async fn fire(client: &NodeClient, entry: &CronEntry) {
         cwd: None,
         guest: None,
         tool_choice: None,
+        instructions: None,
+        images: Vec::new(),
+        interactive: false,
     });
     let mut rx = client.send(msg).await;
-    while rx.recv().await.is_some() {}
+    let mut acc = StreamAccumulator::new();
+    while let Some(reply) = rx.recv().await {
+        match reply.msg {
+            Some(server_message::Msg::Stream(event)) => acc.push(&event),
+            Some(server_message::Msg::Error(err)) => acc.set_error(err.message),
+            _ => {}
+        }
+    }
+    if acc.error().is_some() {
+        return None;
+    }
+    Some(acc.render()).filter(|text| !text.is_empty())
 }
 
 fn mtime(path: &Path) -> Option<SystemTime> {
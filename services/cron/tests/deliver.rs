@@ -0,0 +1,64 @@
+//! Tests for `deliver_to` parsing and delivery to unconfigured platforms.
+
+use crabtalk_cron::{DeliverTo, Platform, Store, deliver::Deliverer};
+use tempfile::tempdir;
+
+const CRONS: &str = r#"
+[[cron]]
+id = 1
+schedule = "0 0 9 * * *"
+skill = "daily-summary"
+agent = "crab"
+sender = "cron"
+deliver_to = { platform = "telegram", chat_id = 42 }
+
+[[cron]]
+id = 2
+schedule = "0 */5 * * * *"
+skill = "check-feeds"
+agent = "crab"
+sender = "cron"
+"#;
+
+#[test]
+fn deliver_to_parses_and_defaults_to_none() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("crons.toml");
+    std::fs::write(&path, CRONS).unwrap();
+
+    let store = Store::load(path).unwrap();
+    let entries = store.list();
+    assert_eq!(
+        entries[0].deliver_to,
+        Some(DeliverTo {
+            platform: Platform::Telegram,
+            chat_id: 42,
+        })
+    );
+    assert_eq!(entries[1].deliver_to, None);
+}
+
+#[test]
+fn deliver_to_survives_rewrite() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("crons.toml");
+    std::fs::write(&path, CRONS).unwrap();
+
+    let mut store = Store::load(path.clone()).unwrap();
+    assert!(store.delete(2).unwrap());
+
+    let reloaded = Store::load(path).unwrap();
+    assert_eq!(reloaded.list().len(), 1);
+    assert!(reloaded.list()[0].deliver_to.is_some());
+}
+
+#[tokio::test]
+async fn unconfigured_platform_skips_delivery() {
+    let dir = tempdir().unwrap();
+    let deliverer = Deliverer::new(dir.path().to_path_buf());
+    let target = DeliverTo {
+        platform: Platform::Telegram,
+        chat_id: 42,
+    };
+    deliverer.deliver(&target, "hello").await.unwrap();
+}
@@ -0,0 +1,51 @@
+//! Tests for firing a schedule into the daemon, against a scripted client.
+
+use crabtalk_cron::{CronEntry, fire};
+use wcore::{
+    protocol::message::{ErrorMsg, ServerMessage, client_message, server_message},
+    testing::client::{TestClient, text_stream},
+};
+
+fn entry() -> CronEntry {
+    CronEntry {
+        id: 1,
+        schedule: "0 0 9 * * *".to_owned(),
+        skill: "daily-summary".to_owned(),
+        agent: "crab".to_owned(),
+        sender: "cron".to_owned(),
+        quiet_start: None,
+        quiet_end: None,
+        once: false,
+        deliver_to: None,
+    }
+}
+
+#[tokio::test]
+async fn fire_sends_the_skill_and_returns_the_reply() {
+    let client = TestClient::new();
+    client.push_stream(text_stream("crab", &["all ", "quiet"]));
+
+    let reply = fire(&client, &entry()).await;
+    assert_eq!(reply.as_deref(), Some("all quiet"));
+
+    let sent = client.sent();
+    let Some(client_message::Msg::Stream(msg)) = &sent[0].msg else {
+        panic!("expected a stream message, got {sent:?}");
+    };
+    assert_eq!(msg.agent, "crab");
+    assert_eq!(msg.content, "/daily-summary");
+    assert_eq!(msg.sender.as_deref(), Some("cron"));
+}
+
+#[tokio::test]
+async fn fire_drops_a_failed_reply() {
+    let client = TestClient::new();
+    client.push_stream(vec![ServerMessage {
+        msg: Some(server_message::Msg::Error(ErrorMsg {
+            code: 500,
+            message: "boom".to_owned(),
+        })),
+    }]);
+
+    assert_eq!(fire(&client, &entry()).await, None);
+}